@@ -0,0 +1,139 @@
+//! At-rest encryption for OAuth access/refresh tokens.
+//!
+//! `oauth_connections.access_token`/`refresh_token` hold third-party
+//! bearer credentials, not something we ever need to query on, so unlike
+//! passwords (hashed, one-way) or other tokens (`hash_token`, also
+//! one-way) these need to be *recoverable* - we have to hand the plaintext
+//! back to Google/GitHub on refresh. That rules out hashing and calls for
+//! symmetric encryption instead: ChaCha20-Poly1305, keyed from
+//! `OAUTH_TOKEN_KEY`, with a random 12-byte nonce prepended to the
+//! ciphertext and the whole thing hex-encoded for storage in the existing
+//! `TEXT` columns (same hex-for-storage convention as `password::hash_token`).
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Nonce,
+};
+use rand::RngCore;
+
+use super::AuthError;
+
+const NONCE_LEN: usize = 12;
+
+fn cipher() -> Result<ChaCha20Poly1305, AuthError> {
+    let key_hex = std::env::var("OAUTH_TOKEN_KEY").map_err(|_| {
+        AuthError::Internal("OAUTH_TOKEN_KEY is not set".to_string())
+    })?;
+    let key_bytes = hex::decode(&key_hex)
+        .map_err(|e| AuthError::Internal(format!("OAUTH_TOKEN_KEY is not valid hex: {}", e)))?;
+    if key_bytes.len() != 32 {
+        return Err(AuthError::Internal(
+            "OAUTH_TOKEN_KEY must decode to 32 bytes".to_string(),
+        ));
+    }
+    ChaCha20Poly1305::new_from_slice(&key_bytes)
+        .map_err(|e| AuthError::Internal(format!("Invalid OAUTH_TOKEN_KEY: {}", e)))
+}
+
+/// Encrypt `plaintext` (an OAuth access or refresh token) for storage.
+pub fn encrypt_token(plaintext: &str) -> Result<String, AuthError> {
+    let cipher = cipher()?;
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext.as_bytes())
+        .map_err(|e| AuthError::Internal(format!("Failed to encrypt OAuth token: {}", e)))?;
+
+    let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    Ok(hex::encode(out))
+}
+
+/// Decrypt a token previously produced by [`encrypt_token`].
+///
+/// `oauth_connections` rows written before this encryption landed still hold
+/// their access/refresh tokens as plaintext, and nothing migrated them. Rather
+/// than hard-erroring (and silently breaking that user's OAuth session on the
+/// next refresh), treat anything that isn't even shaped like our
+/// hex-nonce+ciphertext format (not valid hex, or too short to contain a
+/// nonce) as a legacy plaintext token and hand it back as-is - callers that
+/// write a token back after reading it (e.g. `refresh_oauth_tokens_and_profile`)
+/// re-encrypt through [`encrypt_token`], so the row upgrades itself the next
+/// time its token is refreshed. A value that IS shaped like our format but
+/// fails to authenticate is a real error (wrong/rotated `OAUTH_TOKEN_KEY`, or
+/// a tampered/corrupted row) and still surfaces as one, since silently
+/// treating its ciphertext as a usable plaintext token would hide exactly
+/// that kind of incident.
+pub fn decrypt_token(stored: &str) -> Result<String, AuthError> {
+    let cipher = cipher()?;
+
+    let Ok(raw) = hex::decode(stored) else {
+        return Ok(stored.to_string());
+    };
+    if raw.len() < NONCE_LEN {
+        return Ok(stored.to_string());
+    }
+    let (nonce_bytes, ciphertext) = raw.split_at(NONCE_LEN);
+    let nonce = Nonce::from_slice(nonce_bytes);
+
+    let plaintext = cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|e| AuthError::Internal(format!("Failed to decrypt OAuth token: {}", e)))?;
+
+    String::from_utf8(plaintext)
+        .map_err(|e| AuthError::Internal(format!("Decrypted OAuth token is not valid UTF-8: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_test_key() {
+        std::env::set_var(
+            "OAUTH_TOKEN_KEY",
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+        );
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        set_test_key();
+
+        let token = "gho_supersecretgithubtoken";
+        let encrypted = encrypt_token(token).expect("should encrypt");
+        assert_ne!(encrypted, token);
+
+        let decrypted = decrypt_token(&encrypted).expect("should decrypt");
+        assert_eq!(decrypted, token);
+    }
+
+    #[test]
+    fn test_decrypt_legacy_plaintext_token_passes_through() {
+        set_test_key();
+
+        // A pre-chunk12-5 row: never encrypted, so it's not hex-nonce+ciphertext.
+        let legacy_plaintext = "gho_supersecretgithubtoken";
+        let decrypted = decrypt_token(legacy_plaintext).expect("should pass through as-is");
+        assert_eq!(decrypted, legacy_plaintext);
+    }
+
+    #[test]
+    fn test_decrypt_corrupted_ciphertext_still_errors() {
+        set_test_key();
+
+        let encrypted = encrypt_token("gho_supersecretgithubtoken").expect("should encrypt");
+        let mut raw = hex::decode(&encrypted).unwrap();
+        let last = raw.len() - 1;
+        raw[last] ^= 0xff; // flip a byte inside the ciphertext/auth tag
+        let corrupted = hex::encode(raw);
+
+        assert!(
+            decrypt_token(&corrupted).is_err(),
+            "a value shaped like our format but failing to authenticate must error, not pass through"
+        );
+    }
+}
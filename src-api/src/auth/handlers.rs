@@ -3,26 +3,45 @@
 //! Implements register, login, logout, me, email verification, password reset, and OAuth.
 
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     http::{header, HeaderMap},
     response::{Json, Redirect},
 };
-use chrono::{Duration, Utc};
-use serde::Deserialize;
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::{Pool, Postgres};
 use std::sync::Arc;
+use uuid::Uuid;
 
-use crate::db::models::{NewEmailToken, NewOAuthConnection, NewUser};
+use crate::db::models::{
+    ExpiringOAuthConnection, NewAuthSession, NewEmailToken, NewInvite, NewOAuthConnection,
+    NewRefreshToken, NewSaveSlotGrant, NewUser, NewWebAuthnCredential, SharedSaveSlot,
+};
 use crate::db::operations;
 use crate::email::OptionalEmailService;
 
-use super::jwt::{create_short_token, create_token, extract_bearer_token, verify_token};
-use super::oauth::{GitHubOAuth, GoogleOAuth, OAuthState, OAuthUserInfo};
-use super::password::{hash_password, hash_token, verify_password};
+use super::crypto;
+use super::invite;
+use super::jwt::{
+    create_short_token, create_token, ensure_not_banned, extract_bearer_token, verify_token,
+    verify_token_with_status,
+};
+use super::oauth::{OAuthState, OAuthUserInfo, PkceStore, ProviderRegistry};
+use super::password::{
+    generate_secure_token, generate_security_stamp, hash_password, hash_token, verify_password,
+    verify_password_checked,
+};
+use super::totp;
+use super::webauthn::SharedWebAuthnService;
 use super::{
-    AuthError, AuthResponse, LoginRequest, RegisterRequest, RequestResetRequest,
-    ResendVerifyRequest, ResetPasswordRequest, UserResponse, VerifyEmailRequest,
+    AuthError, AuthResponse, ChangeEmailRequest, ConfirmChangeEmailRequest, ConfirmDeleteRequest,
+    CreateInviteCodeRequest, CreateInviteRequest, InviteTrustedContactRequest, LoginRequest,
+    RefreshRequest, RegisterRequest, RequestDeleteRequest, RequestResetRequest,
+    ResendVerifyRequest, ResetPasswordRequest, TotpConfirmRequest, TotpDisableRequest,
+    UserResponse, VerifyEmailRequest,
 };
 
 /// Application state for auth handlers
@@ -30,27 +49,93 @@ pub struct AuthState {
     pub db: Pool<Postgres>,
     /// Email service for verification/reset emails (optional - works without in dev)
     pub email: OptionalEmailService,
-    /// Google OAuth client (optional - requires env vars)
-    pub google_oauth: Option<GoogleOAuth>,
-    /// GitHub OAuth client (optional - requires env vars)
-    pub github_oauth: Option<GitHubOAuth>,
+    /// Provider-agnostic OAuth registry (GitHub, Google, GitLab, Discord) used
+    /// by the generic `/oauth/{provider}/*` routes
+    pub oauth_providers: ProviderRegistry,
+    /// PKCE verifiers for OAuth flows in flight, keyed by `OAuthState::nonce`
+    /// - see [`PkceStore`] for why the verifier isn't carried in the `state`
+    /// query parameter itself.
+    pub oauth_pkce: PkceStore,
+    /// WebAuthn/passkey service (optional - requires RP env vars)
+    pub webauthn: Option<SharedWebAuthnService>,
     /// Frontend URL for email links and OAuth redirects
     pub frontend_url: String,
+    /// Caches each user's current `security_stamp` so `extract_and_verify_token`
+    /// doesn't hit the DB on every authenticated request; an entry is
+    /// dropped/overwritten the moment that user's stamp rotates.
+    pub security_stamps: DashMap<Uuid, String>,
+    /// When set, `register` requires a valid `invite_token` for an invite
+    /// created via `POST /api/auth/invite`.
+    pub require_invite: bool,
+}
+
+/// A single logged-in device/browser, as returned by `GET /api/auth/sessions`
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthSessionResponse {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    /// Whether this is the session the request was authenticated with
+    pub current: bool,
+}
+
+/// Name of the HttpOnly cookie mirroring the bearer access token, for the
+/// browser front-end (see `auth_middleware::jwt_auth_middleware`'s cookie
+/// fallback). Native Tauri/API clients ignore it and keep using `token` from
+/// the JSON body as a bearer header instead.
+const ACCESS_TOKEN_COOKIE: &str = "access_token";
+
+/// Build the `Set-Cookie` entry for a freshly-minted access `token`.
+/// `HttpOnly` keeps it out of reach of any injected script, `Secure` +
+/// `SameSite=Strict` keep it from leaking over plain HTTP or cross-site
+/// requests; there's no `Max-Age` so it expires with the browser session,
+/// while the JWT inside it stops verifying after 15 minutes regardless.
+fn access_token_cookie(token: &str) -> Cookie<'static> {
+    Cookie::build((ACCESS_TOKEN_COOKIE, token.to_string()))
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .path("/")
+        .build()
 }
 
 /// POST /api/auth/register
+#[utoipa::path(
+    post,
+    path = "/api/auth/register",
+    request_body = RegisterRequest,
+    responses(
+        (status = 200, description = "Account created", body = AuthResponse),
+        (status = 400, description = "Invalid email, password too weak, or invite token missing or invalid"),
+        (status = 403, description = "Invite code invalid, expired, or exhausted"),
+        (status = 409, description = "Email or username already taken"),
+    ),
+    tag = "auth",
+)]
 pub async fn register(
     State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
     Json(payload): Json<RegisterRequest>,
-) -> Result<Json<AuthResponse>, AuthError> {
+) -> Result<(CookieJar, Json<AuthResponse>), AuthError> {
     // Validate email format
     if !is_valid_email(&payload.email) {
         return Err(AuthError::InvalidEmail);
     }
-    
+
     // Normalize email to lowercase
     let email = payload.email.to_lowercase();
-    
+
+    // On invite-only deployments, validate the invite up front so a rejected
+    // one doesn't leave behind a half-registered user; it's only marked
+    // consumed once the user row actually gets created below.
+    let invite = if state.require_invite {
+        Some(validate_invite(&state, &email, payload.invite_token.as_deref()).await?)
+    } else {
+        None
+    };
+
     // Check if email already exists (using db::operations)
     if operations::get_user_by_email(&state.db, &email)
         .await
@@ -79,14 +164,43 @@ pub async fn register(
         email,
         username: payload.username.clone(),
         password_hash,
+        security_stamp: generate_security_stamp(),
     };
     
-    let user = operations::create_user(&state.db, &new_user)
+    // A beta invite code, if supplied, is redeemed atomically with the user
+    // row itself (same transaction) so a code that turns out to be
+    // exhausted/expired can't leave behind a user it didn't actually gate.
+    let user = if let Some(code) = payload.invite_code.as_deref() {
+        let mut tx = state
+            .db
+            .begin()
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+        let user = operations::create_user(&mut *tx, &new_user).await?;
+        super::invite::redeem_invite_code(&mut *tx, code, &user.email, user.id).await?;
+        tx.commit()
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+        user
+    } else {
+        operations::create_user(&state.db, &new_user).await?
+    };
+
+    if let Some(invite) = invite {
+        operations::mark_invite_used(&state.db, invite.id)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+    }
+
+    // Auto-accept any trusted-contact grants that were waiting on this email
+    // to register (see `accept_trusted_contact_grants`), so a grantee who
+    // doesn't have an account yet doesn't need a separate accept step.
+    operations::accept_trusted_contact_grants(&state.db, user.id, &user.email)
         .await
         .map_err(|e| AuthError::Database(e.to_string()))?;
-    
+
     // Create verification token (valid for 24 hours)
-    let verify_token_str = create_short_token(user.id, &user.email, 24)?;
+    let verify_token_str = create_short_token(user.id, &user.email, &user.security_stamp, &user.role, 24)?;
     let token_hash = hash_token(&verify_token_str);
     
     // Store verification token using db::operations
@@ -117,26 +231,44 @@ pub async fn register(
     }
     
     // Create session token
-    let token = create_token(user.id, &user.email, user.total_xp as u32)?;
-    
-    Ok(Json(AuthResponse {
-        token,
-        user: UserResponse {
-            id: user.id,
-            email: user.email,
-            username: user.username,
-            email_verified: user.email_verified,
-            total_xp: user.total_xp as u32,
-            created_at: user.created_at,
-        },
-    }))
+    let (token, refresh_token) = start_session(&state, &user, &headers).await?;
+    let jar = CookieJar::new().add(access_token_cookie(&token));
+
+    Ok((
+        jar,
+        Json(AuthResponse {
+            token,
+            refresh_token,
+            user: UserResponse {
+                id: user.id,
+                email: user.email,
+                username: user.username,
+                email_verified: user.email_verified,
+                total_xp: user.total_xp as u32,
+                created_at: user.created_at,
+                role: user.role,
+            },
+        }),
+    ))
 }
 
 /// POST /api/auth/login
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Authenticated", body = AuthResponse),
+        (status = 401, description = "Invalid credentials, or a TOTP code is required/incorrect"),
+        (status = 403, description = "Account is suspended or blacklisted"),
+    ),
+    tag = "auth",
+)]
 pub async fn login(
     State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, AuthError> {
+) -> Result<(CookieJar, Json<AuthResponse>), AuthError> {
     let email = payload.email.to_lowercase();
     
     // Fetch user by email using db::operations
@@ -149,53 +281,276 @@ pub async fn login(
     let password_hash = user.password_hash.as_ref().ok_or(AuthError::InvalidCredentials)?;
     
     // Verify password
-    if !verify_password(&payload.password, password_hash)? {
+    let verification = verify_password_checked(&payload.password, password_hash)?;
+    if !verification.valid {
         return Err(AuthError::InvalidCredentials);
     }
-    
-    // Check account status
-    if user.is_blacklisted {
-        return Err(AuthError::UserBlacklisted);
-    }
-    if user.is_suspended {
-        return Err(AuthError::UserSuspended);
+
+    // Transparently migrate this user's hash onto the current Argon2Policy.
+    // Not the credential the user asked to change, so it doesn't rotate
+    // their security stamp or otherwise affect this login.
+    if verification.needs_rehash {
+        match hash_password(&payload.password) {
+            Ok(new_hash) => {
+                if let Err(e) = operations::update_password_hash(&state.db, user.id, &new_hash).await {
+                    tracing::warn!("failed to persist upgraded password hash: {e}");
+                }
+            }
+            Err(e) => tracing::warn!("failed to compute upgraded password hash: {e}"),
+        }
     }
-    
+
+    ensure_not_banned(&state.db, user.id).await?;
+
+    verify_totp_for_login(&state, user.id, payload.totp_code.as_deref()).await?;
+
     // Update last login using db::operations
     operations::update_last_login(&state.db, user.id)
         .await
         .map_err(|e| AuthError::Database(e.to_string()))?;
-    
+
+    // Lazily refresh any near-expiry OAuth connections for this user, off
+    // the request's critical path - same fire-and-forget shape as
+    // `spawn_oauth_token_refresh`'s periodic sweep, but triggered by the
+    // user actually logging in instead of waiting for the next tick.
+    {
+        let state = state.clone();
+        let user_id = user.id;
+        tokio::spawn(async move {
+            let connections = match operations::get_oauth_connections_for_user(&state.db, user_id)
+                .await
+            {
+                Ok(connections) => connections,
+                Err(e) => {
+                    tracing::warn!("failed to list oauth connections for {user_id}: {e}");
+                    return;
+                }
+            };
+            for conn in connections {
+                if let Err(e) = refresh_oauth_connection(&state, user_id, &conn.provider).await {
+                    tracing::warn!(
+                        "lazy oauth refresh failed for {user_id}/{}: {e}",
+                        conn.provider
+                    );
+                }
+            }
+        });
+    }
+
     // Create token
-    let token = create_token(user.id, &user.email, user.total_xp as u32)?;
-    
-    Ok(Json(AuthResponse {
-        token,
-        user: UserResponse {
-            id: user.id,
-            email: user.email,
-            username: user.username,
-            email_verified: user.email_verified,
-            total_xp: user.total_xp as u32,
-            created_at: user.created_at,
-        },
-    }))
+    let (token, refresh_token) = start_session(&state, &user, &headers).await?;
+    let jar = CookieJar::new().add(access_token_cookie(&token));
+
+    Ok((
+        jar,
+        Json(AuthResponse {
+            token,
+            refresh_token,
+            user: UserResponse {
+                id: user.id,
+                email: user.email,
+                username: user.username,
+                email_verified: user.email_verified,
+                total_xp: user.total_xp as u32,
+                created_at: user.created_at,
+                role: user.role,
+            },
+        }),
+    ))
+}
+
+/// POST /api/auth/refresh
+///
+/// Exchanges a refresh token for a new (access token, refresh token) pair,
+/// rotating the presented refresh token in the process - it's revoked here
+/// and replaced by a freshly random one, same as the access token itself
+/// rotates its `jti` every time this runs. Presenting a refresh token that's
+/// already revoked is treated as a theft signal (someone else may have
+/// gotten a copy of it) and revokes every refresh token the user holds.
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "Rotated token pair", body = AuthResponse),
+        (status = 401, description = "Refresh token is invalid, expired, or already revoked"),
+        (status = 403, description = "Account is suspended or blacklisted"),
+    ),
+    tag = "auth",
+)]
+pub async fn refresh(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Json(payload): Json<RefreshRequest>,
+) -> Result<(CookieJar, Json<AuthResponse>), AuthError> {
+    let token_hash = hash_token(&payload.refresh_token);
+
+    let stored = operations::get_refresh_token_by_hash(&state.db, &token_hash)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .ok_or(AuthError::InvalidToken)?;
+
+    if stored.revoked_at.is_some() {
+        operations::revoke_all_for_user(&state.db, stored.user_id)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+        return Err(AuthError::InvalidToken);
+    }
+
+    if stored.expires_at < Utc::now() {
+        return Err(AuthError::InvalidToken);
+    }
+
+    operations::revoke_refresh_token(&state.db, stored.id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    let user = operations::get_user_by_id(&state.db, stored.user_id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .ok_or(AuthError::UserNotFound)?;
+
+    ensure_not_banned(&state.db, user.id).await?;
+
+    let (token, refresh_token) = start_session(&state, &user, &headers).await?;
+    let jar = CookieJar::new().add(access_token_cookie(&token));
+
+    Ok((
+        jar,
+        Json(AuthResponse {
+            token,
+            refresh_token,
+            user: UserResponse {
+                id: user.id,
+                email: user.email,
+                username: user.username,
+                email_verified: user.email_verified,
+                total_xp: user.total_xp as u32,
+                created_at: user.created_at,
+                role: user.role,
+            },
+        }),
+    ))
 }
 
 /// POST /api/auth/logout
-pub async fn logout() -> Json<serde_json::Value> {
-    // JWT tokens are stateless - client should discard the token
-    // For enhanced security, we could maintain a token blocklist in Redis
-    Json(json!({ "success": true, "message": "Logged out successfully" }))
+///
+/// Revokes the session the caller's own token belongs to, by its jti - other
+/// sessions (and their tokens) are untouched. Use /api/auth/logout-all to
+/// revoke every session at once.
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    responses(
+        (status = 200, description = "Session revoked"),
+        (status = 401, description = "Missing or invalid token"),
+    ),
+    tag = "auth",
+)]
+pub async fn logout(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+    operations::revoke_auth_session_by_jti(&state.db, claims.jti)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    Ok(Json(json!({ "success": true, "message": "Logged out successfully" })))
+}
+
+/// POST /api/auth/logout-all
+///
+/// Rotates the caller's security stamp and revokes every one of their
+/// sessions and refresh tokens, which invalidates every JWT issued before
+/// this call (and stops any of them being renewed via /api/auth/refresh) -
+/// the only real revocation available to a stateless JWT scheme, short of a
+/// blocklist.
+pub async fn logout_all(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+    rotate_security_stamp(&state, claims.sub).await?;
+    operations::revoke_all_auth_sessions(&state.db, claims.sub)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+    operations::revoke_all_for_user(&state.db, claims.sub)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    Ok(Json(
+        json!({ "success": true, "message": "Logged out of all sessions" }),
+    ))
+}
+
+/// GET /api/auth/sessions
+///
+/// Lists the caller's active (non-revoked) sessions/devices, most recently
+/// seen first, so they can spot an unfamiliar login before deciding whether
+/// to revoke it.
+pub async fn list_sessions(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<AuthSessionResponse>>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+    let sessions = operations::list_active_auth_sessions(&state.db, claims.sub)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    Ok(Json(
+        sessions
+            .into_iter()
+            .map(|s| AuthSessionResponse {
+                current: s.jti == claims.jti,
+                id: s.id,
+                user_agent: s.user_agent,
+                ip_address: s.ip_address,
+                created_at: s.created_at,
+                last_seen_at: s.last_seen_at,
+            })
+            .collect(),
+    ))
+}
+
+/// DELETE /api/auth/sessions/{id}
+///
+/// Revokes one of the caller's own sessions (e.g. "sign out" a lost device),
+/// without affecting any of their other sessions.
+pub async fn revoke_session(
+    State(state): State<Arc<AuthState>>,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+    let revoked = operations::revoke_auth_session(&state.db, claims.sub, session_id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    if !revoked {
+        return Err(AuthError::SessionNotFound);
+    }
+
+    Ok(Json(json!({ "success": true })))
 }
 
 /// GET /api/auth/me
+#[utoipa::path(
+    get,
+    path = "/api/auth/me",
+    responses(
+        (status = 200, description = "Current user", body = UserResponse),
+        (status = 401, description = "Missing or invalid token"),
+        (status = 404, description = "User no longer exists"),
+    ),
+    tag = "auth",
+)]
 pub async fn me(
     State(state): State<Arc<AuthState>>,
     headers: HeaderMap,
 ) -> Result<Json<UserResponse>, AuthError> {
-    let claims = extract_and_verify_token(&headers)?;
-    
+    let claims = extract_and_verify_token(&state, &headers).await?;
+
     // Get user using db::operations
     let user = operations::get_user_by_id(&state.db, claims.sub)
         .await
@@ -209,10 +564,21 @@ pub async fn me(
         email_verified: user.email_verified,
         total_xp: user.total_xp as u32,
         created_at: user.created_at,
+        role: user.role,
     }))
 }
 
 /// POST /api/auth/verify-email
+#[utoipa::path(
+    post,
+    path = "/api/auth/verify-email",
+    request_body = VerifyEmailRequest,
+    responses(
+        (status = 200, description = "Email verified"),
+        (status = 401, description = "Token is invalid or does not match its claimed user"),
+    ),
+    tag = "auth",
+)]
 pub async fn verify_email(
     State(state): State<Arc<AuthState>>,
     Json(payload): Json<VerifyEmailRequest>,
@@ -246,6 +612,15 @@ pub async fn verify_email(
 }
 
 /// POST /api/auth/resend-verify
+#[utoipa::path(
+    post,
+    path = "/api/auth/resend-verify",
+    request_body = ResendVerifyRequest,
+    responses(
+        (status = 200, description = "Verification email sent if the account exists and is unverified"),
+    ),
+    tag = "auth",
+)]
 pub async fn resend_verify(
     State(state): State<Arc<AuthState>>,
     Json(payload): Json<ResendVerifyRequest>,
@@ -267,7 +642,7 @@ pub async fn resend_verify(
     }
     
     // Create new verification token (db::operations::create_email_token deletes old ones)
-    let verify_token_str = create_short_token(user.id, &user.email, 24)?;
+    let verify_token_str = create_short_token(user.id, &user.email, &user.security_stamp, &user.role, 24)?;
     let token_hash = hash_token(&verify_token_str);
     
     let new_token = NewEmailToken {
@@ -294,6 +669,15 @@ pub async fn resend_verify(
 }
 
 /// POST /api/auth/request-reset
+#[utoipa::path(
+    post,
+    path = "/api/auth/request-reset",
+    request_body = RequestResetRequest,
+    responses(
+        (status = 200, description = "Reset email sent if the account exists"),
+    ),
+    tag = "auth",
+)]
 pub async fn request_reset(
     State(state): State<Arc<AuthState>>,
     Json(payload): Json<RequestResetRequest>,
@@ -311,7 +695,7 @@ pub async fn request_reset(
     };
     
     // Create reset token (db::operations::create_email_token deletes old ones)
-    let reset_token = create_short_token(user.id, &user.email, 1)?;
+    let reset_token = create_short_token(user.id, &user.email, &user.security_stamp, &user.role, 1)?;
     let token_hash = hash_token(&reset_token);
     
     let new_token = NewEmailToken {
@@ -338,6 +722,17 @@ pub async fn request_reset(
 }
 
 /// POST /api/auth/reset-password
+#[utoipa::path(
+    post,
+    path = "/api/auth/reset-password",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "Password reset"),
+        (status = 400, description = "New password too weak"),
+        (status = 401, description = "Token is invalid or does not match its claimed user"),
+    ),
+    tag = "auth",
+)]
 pub async fn reset_password(
     State(state): State<Arc<AuthState>>,
     Json(payload): Json<ResetPasswordRequest>,
@@ -359,168 +754,962 @@ pub async fn reset_password(
     
     // Hash new password (also validates strength)
     let password_hash = hash_password(&payload.new_password)?;
-    
-    // Update password using db::operations
-    operations::update_user_password(&state.db, claims.sub, &password_hash)
+
+    // Update the password and rotate the security stamp together, so a
+    // reset link can't land in the gap between an outstanding session
+    // reading the old password and the stamp rotation that should revoke it.
+    let new_stamp = generate_security_stamp();
+    operations::update_password_and_rotate_stamp(&state.db, claims.sub, &password_hash, &new_stamp)
         .await
         .map_err(|e| AuthError::Database(e.to_string()))?;
-    
+    state.security_stamps.insert(claims.sub, new_stamp);
+
     // Mark token as used using db::operations
     operations::mark_email_token_used(&state.db, token_record.id)
         .await
         .map_err(|e| AuthError::Database(e.to_string()))?;
     
-    // Delete all reset tokens for this user (invalidate any other reset links)
-    // Note: This is done by creating a new token which deletes old ones, but we already
-    // marked the current one as used. We could add a cleanup function, but for now
-    // expired tokens will be cleaned up by the periodic cleanup task.
-    
+    // `create_email_token` already deletes any other outstanding "reset" token
+    // for this user when one is issued, so there's nothing else to invalidate
+    // here; `spawn_email_token_cleanup` sweeps this one away once it's used.
+
     Ok(Json(json!({ "success": true, "message": "Password reset successfully" })))
 }
 
-fn is_valid_email(email: &str) -> bool {
-    let parts: Vec<&str> = email.split('@').collect();
-    if parts.len() != 2 {
-        return false;
-    }
-    let local = parts[0];
-    let domain = parts[1];
-    
-    !local.is_empty() 
-        && !domain.is_empty() 
-        && domain.contains('.') 
-        && !domain.starts_with('.') 
-        && !domain.ends_with('.')
-        && email.len() <= 254
-}
+/// POST /api/auth/delete/request
+///
+/// First step of account deletion: for password accounts, re-checks the
+/// current password before issuing a short-lived `"delete"` email token and
+/// mailing the confirmation link - nothing is deleted until that link is
+/// followed and posted to `delete/confirm`.
+pub async fn delete_request(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Json(payload): Json<RequestDeleteRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+    let user = operations::get_user_by_id(&state.db, claims.sub)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .ok_or(AuthError::UserNotFound)?;
 
-fn extract_and_verify_token(headers: &HeaderMap) -> Result<super::jwt::JwtClaims, AuthError> {
-    let auth_header = headers
-        .get(header::AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .ok_or(AuthError::InvalidToken)?;
-    
-    let token = extract_bearer_token(auth_header).ok_or(AuthError::InvalidToken)?;
-    
-    verify_token(token)
-}
+    if let Some(ref password_hash) = user.password_hash {
+        let password = payload.password.as_deref().ok_or(AuthError::InvalidCredentials)?;
+        if !verify_password(password, password_hash)? {
+            return Err(AuthError::InvalidCredentials);
+        }
+    }
 
-// OAuth
+    let delete_token = create_short_token(user.id, &user.email, &user.security_stamp, &user.role, 1)?;
+    let token_hash = hash_token(&delete_token);
 
-#[derive(Debug, Deserialize)]
-pub struct OAuthCallbackQuery {
-    pub code: String,
-    pub state: String,
-}
+    let new_token = NewEmailToken {
+        user_id: user.id,
+        token_type: "delete".to_string(),
+        token_hash,
+        expires_at: Utc::now() + Duration::hours(1),
+    };
 
-/// GET /api/auth/oauth/google/start
-pub async fn google_oauth_start(
-    State(state): State<Arc<AuthState>>,
-) -> Result<Redirect, AuthError> {
-    let google = state
-        .google_oauth
-        .as_ref()
-        .ok_or_else(|| AuthError::Internal("Google OAuth not configured".to_string()))?;
+    operations::create_email_token(&state.db, &new_token)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
 
-    let oauth_state = OAuthState::new(Some("/".to_string()));
-    let auth_url = google.get_authorization_url(&oauth_state);
+    if let Err(e) = state
+        .email
+        .send_delete_confirmation(&user.email, user.username.clone(), &delete_token, &state.frontend_url, 1)
+        .await
+    {
+        tracing::warn!("Failed to send delete-confirmation email to {}: {}", user.email, e);
+    }
 
-    Ok(Redirect::temporary(&auth_url))
+    Ok(Json(
+        json!({ "success": true, "message": "Check your email to confirm account deletion" }),
+    ))
 }
 
-/// GET /api/auth/oauth/google/callback
-pub async fn google_oauth_callback(
+/// POST /api/auth/delete/confirm
+///
+/// Second step of account deletion: verifies the token from `delete/request`
+/// exactly like `reset_password` does, then permanently removes the account
+/// and its dependent rows.
+pub async fn delete_confirm(
     State(state): State<Arc<AuthState>>,
-    Query(query): Query<OAuthCallbackQuery>,
-) -> Result<Redirect, AuthError> {
-    let google = state
-        .google_oauth
-        .as_ref()
-        .ok_or_else(|| AuthError::Internal("Google OAuth not configured".to_string()))?;
-
-    // Validate state parameter (CSRF protection)
-    let oauth_state = OAuthState::decode(&query.state)
-        .ok_or_else(|| AuthError::Internal("Invalid OAuth state".to_string()))?;
+    Json(payload): Json<ConfirmDeleteRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = verify_token(&payload.token)?;
+    let token_hash = hash_token(&payload.token);
 
-    // Exchange code for user info
-    let user_info = google
-        .authenticate(&query.code)
+    let token_record = operations::get_email_token_by_hash(&state.db, &token_hash, "delete")
         .await
-        .map_err(|e| AuthError::Internal(format!("Google auth failed: {}", e)))?;
-
-    // Find or create user and generate token
-    let (user, token) = find_or_create_oauth_user(&state, "google", &user_info).await?;
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .ok_or(AuthError::InvalidToken)?;
 
-    // Redirect to frontend with token
-    let redirect_to = oauth_state.redirect_to.unwrap_or_else(|| "/".to_string());
-    let redirect_url = format!(
-        "{}{}?token={}&user_id={}",
-        state.frontend_url,
-        redirect_to,
-        urlencoding::encode(&token),
-        user.id
-    );
+    if token_record.user_id != claims.sub {
+        return Err(AuthError::InvalidToken);
+    }
 
-    Ok(Redirect::temporary(&redirect_url))
-}
+    operations::mark_email_token_used(&state.db, token_record.id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
 
-/// GET /api/auth/oauth/github/start
-pub async fn github_oauth_start(
-    State(state): State<Arc<AuthState>>,
-) -> Result<Redirect, AuthError> {
-    let github = state
-        .github_oauth
-        .as_ref()
-        .ok_or_else(|| AuthError::Internal("GitHub OAuth not configured".to_string()))?;
+    operations::delete_user(&state.db, claims.sub)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
 
-    let oauth_state = OAuthState::new(Some("/".to_string()));
-    let auth_url = github.get_authorization_url(&oauth_state);
+    state.security_stamps.remove(&claims.sub);
 
-    Ok(Redirect::temporary(&auth_url))
+    Ok(Json(json!({ "success": true, "message": "Account deleted" })))
 }
 
-/// GET /api/auth/oauth/github/callback
-pub async fn github_oauth_callback(
+/// POST /api/auth/change-email
+///
+/// First step of the email-change flow, mirroring vaultwarden's
+/// `post_email_token`: checks the current password, validates and
+/// uniqueness-checks `new_email`, then mails a confirmation link to it. The
+/// token's own `email` claim carries the pending new address, so
+/// `change_email_confirm` doesn't need anywhere else to read it from.
+pub async fn change_email(
     State(state): State<Arc<AuthState>>,
-    Query(query): Query<OAuthCallbackQuery>,
-) -> Result<Redirect, AuthError> {
-    let github = state
-        .github_oauth
-        .as_ref()
-        .ok_or_else(|| AuthError::Internal("GitHub OAuth not configured".to_string()))?;
+    headers: HeaderMap,
+    Json(payload): Json<ChangeEmailRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+    let user = operations::get_user_by_id(&state.db, claims.sub)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .ok_or(AuthError::UserNotFound)?;
 
-    // Validate state parameter (CSRF protection)
-    let oauth_state = OAuthState::decode(&query.state)
-        .ok_or_else(|| AuthError::Internal("Invalid OAuth state".to_string()))?;
+    let password_hash = user.password_hash.as_ref().ok_or(AuthError::InvalidCredentials)?;
+    if !verify_password(&payload.password, password_hash)? {
+        return Err(AuthError::InvalidCredentials);
+    }
+
+    if !is_valid_email(&payload.new_email) {
+        return Err(AuthError::InvalidEmail);
+    }
+    let new_email = payload.new_email.to_lowercase();
 
-    // Exchange code for user info
-    let user_info = github
-        .authenticate(&query.code)
+    if operations::get_user_by_email(&state.db, &new_email)
         .await
-        .map_err(|e| AuthError::Internal(format!("GitHub auth failed: {}", e)))?;
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .is_some()
+    {
+        return Err(AuthError::EmailExists);
+    }
 
-    // Find or create user and generate token
-    let (user, token) = find_or_create_oauth_user(&state, "github", &user_info).await?;
+    let change_token = create_short_token(user.id, &new_email, &user.security_stamp, &user.role, 1)?;
+    let token_hash = hash_token(&change_token);
 
-    // Redirect to frontend with token
-    let redirect_to = oauth_state.redirect_to.unwrap_or_else(|| "/".to_string());
-    let redirect_url = format!(
-        "{}{}?token={}&user_id={}",
-        state.frontend_url,
+    let new_token = NewEmailToken {
+        user_id: user.id,
+        token_type: "email_change".to_string(),
+        token_hash,
+        expires_at: Utc::now() + Duration::hours(1),
+    };
+
+    operations::create_email_token(&state.db, &new_token)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    if let Err(e) = state
+        .email
+        .send_confirm_email_change(&new_email, user.username.clone(), &change_token, &state.frontend_url)
+        .await
+    {
+        tracing::warn!("Failed to send email-change confirmation to {}: {}", new_email, e);
+    }
+
+    if let Err(e) = state
+        .email
+        .send_email_change_notice(&user.email, user.username.clone(), &new_email)
+        .await
+    {
+        tracing::warn!("Failed to send email-change notice to {}: {}", user.email, e);
+    }
+
+    Ok(Json(
+        json!({ "success": true, "message": "Check your new email address to confirm the change" }),
+    ))
+}
+
+/// POST /api/auth/change-email/confirm
+///
+/// Second step: verifies the token exactly like `verify_email` does, then
+/// atomically moves the account to the new address (embedded in the token's
+/// own `email` claim) and re-marks it verified.
+pub async fn change_email_confirm(
+    State(state): State<Arc<AuthState>>,
+    Json(payload): Json<ConfirmChangeEmailRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = verify_token(&payload.token)?;
+    let token_hash = hash_token(&payload.token);
+
+    let token_record = operations::get_email_token_by_hash(&state.db, &token_hash, "email_change")
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .ok_or(AuthError::InvalidToken)?;
+
+    if token_record.user_id != claims.sub {
+        return Err(AuthError::InvalidToken);
+    }
+
+    operations::update_user_email(&state.db, claims.sub, &claims.email)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    operations::mark_email_token_used(&state.db, token_record.id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    Ok(Json(json!({ "success": true, "message": "Email address updated" })))
+}
+
+/// POST /api/auth/invite
+///
+/// Admin-only (see `require_role`). Creates a single-use invite for `email`
+/// and emails it, for deployments running with `require_invite` on.
+pub async fn create_invite(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateInviteRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    require_role(&state, &headers, "admin").await?;
+
+    if !is_valid_email(&payload.email) {
+        return Err(AuthError::InvalidEmail);
+    }
+    let email = payload.email.to_lowercase();
+
+    let invite_token = generate_secure_token();
+    let token_hash = hash_token(&invite_token);
+
+    let new_invite = NewInvite {
+        email: email.clone(),
+        token_hash,
+        invited_by: None,
+        expires_at: Utc::now() + Duration::hours(72),
+    };
+
+    operations::create_invite(&state.db, &new_invite)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    if let Err(e) = state
+        .email
+        .send_invite(&email, &invite_token, &state.frontend_url)
+        .await
+    {
+        tracing::warn!("Failed to send invite email to {}: {}", email, e);
+    }
+
+    Ok(Json(json!({ "success": true, "message": "Invite sent" })))
+}
+
+/// POST /api/auth/invite-codes
+///
+/// Admin-only. Creates a multi-use beta invite code (see `auth::invite`),
+/// optionally bound to one email and/or an expiry, returning the code
+/// itself so the admin can hand it out (there's no emailed-link flow for
+/// these, unlike `create_invite`).
+pub async fn create_invite_code(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateInviteCodeRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let admin = require_role(&state, &headers, "admin").await?;
+
+    if let Some(ref email) = payload.email {
+        if !is_valid_email(email) {
+            return Err(AuthError::InvalidEmail);
+        }
+    }
+
+    let expires_at = payload
+        .expires_in_hours
+        .map(|hours| Utc::now() + Duration::hours(hours));
+
+    let invite = invite::create_invite_code(
+        &state.db,
+        admin.sub,
+        payload.email.map(|e| e.to_lowercase()),
+        payload.max_uses,
+        expires_at,
+    )
+    .await?;
+
+    Ok(Json(json!({ "success": true, "code": invite.code })))
+}
+
+/// Validate a registration's invite token against `email`: present, hashes to
+/// an unused/unexpired `invites` row, and was issued for this exact email.
+/// Returns the invite record so the caller can mark it used once the account
+/// it gated has actually been created.
+async fn validate_invite(
+    state: &AuthState,
+    email: &str,
+    invite_token: Option<&str>,
+) -> Result<crate::db::models::Invite, AuthError> {
+    let invite_token = invite_token.ok_or(AuthError::InviteRequired)?;
+    let token_hash = hash_token(invite_token);
+
+    let invite = operations::get_invite_by_token_hash(&state.db, &token_hash)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .ok_or(AuthError::InvalidInvite)?;
+
+    if invite.email != email {
+        return Err(AuthError::InvalidInvite);
+    }
+
+    Ok(invite)
+}
+
+/// Like `extract_and_verify_token`, but also rejects the request unless the
+/// caller's role claim is `required_role` or `"admin"` (which can do anything
+/// a lower role can). Lets admin-only handlers like `create_invite` gate
+/// access declaratively instead of rolling their own check.
+async fn require_role(
+    state: &AuthState,
+    headers: &HeaderMap,
+    required_role: &str,
+) -> Result<super::jwt::JwtClaims, AuthError> {
+    let claims = extract_and_verify_token(state, headers).await?;
+    if claims.role != required_role && claims.role != "admin" {
+        return Err(AuthError::Forbidden);
+    }
+    Ok(claims)
+}
+
+fn is_valid_email(email: &str) -> bool {
+    let parts: Vec<&str> = email.split('@').collect();
+    if parts.len() != 2 {
+        return false;
+    }
+    let local = parts[0];
+    let domain = parts[1];
+
+    !local.is_empty()
+        && !domain.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && email.len() <= 254
+}
+
+// Admin moderation
+
+#[derive(Debug, Deserialize)]
+pub struct UsersOverviewQuery {
+    #[serde(default = "default_users_overview_limit")]
+    limit: i64,
+    #[serde(default)]
+    offset: i64,
+}
+
+fn default_users_overview_limit() -> i64 {
+    50
+}
+
+/// GET /api/auth/admin/users
+///
+/// Admin-only. Paginated list of every user with their current ban state
+/// (if any) joined in, newest signups first - the overview page of a
+/// self-hosted admin panel.
+pub async fn users_overview(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Query(query): Query<UsersOverviewQuery>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    require_role(&state, &headers, "admin").await?;
+
+    let users = operations::list_users_overview(&state.db, query.limit, query.offset)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    Ok(Json(json!({ "users": users })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuspendUserRequest {
+    pub duration_seconds: i64,
+    pub reason: String,
+}
+
+/// POST /api/auth/admin/users/{user_id}/suspend
+///
+/// Admin-only. Issues a timed ban expiring `duration_seconds` from now (see
+/// [`crate::db::models::Ban`]) and immediately deauthenticates the target,
+/// same as [`deauth_user`], so the suspension takes effect on their very
+/// next request rather than once their current token happens to expire.
+pub async fn suspend_user(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<SuspendUserRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let admin = require_role(&state, &headers, "admin").await?;
+
+    let new_ban = crate::db::models::NewBan {
+        user_id,
+        issued_by: Some(admin.sub),
+        reason: payload.reason,
+        expires_at: Some(Utc::now() + Duration::seconds(payload.duration_seconds)),
+    };
+    operations::create_ban(&state.db, &new_ban)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+    deauthenticate(&state, user_id).await?;
+
+    tracing::warn!(
+        "admin {} suspended user {} for {}s: {}",
+        admin.sub,
+        user_id,
+        payload.duration_seconds,
+        new_ban.reason
+    );
+
+    Ok(Json(json!({ "success": true, "message": "User suspended" })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlacklistUserRequest {
+    pub reason: String,
+}
+
+/// POST /api/auth/admin/users/{user_id}/blacklist
+///
+/// Admin-only. Issues a permanent ban (`expires_at: None`) and
+/// deauthenticates the target - see [`suspend_user`] for the timed
+/// equivalent.
+pub async fn blacklist_user(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<BlacklistUserRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let admin = require_role(&state, &headers, "admin").await?;
+
+    let new_ban = crate::db::models::NewBan {
+        user_id,
+        issued_by: Some(admin.sub),
+        reason: payload.reason,
+        expires_at: None,
+    };
+    operations::create_ban(&state.db, &new_ban)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+    deauthenticate(&state, user_id).await?;
+
+    tracing::warn!(
+        "admin {} blacklisted user {}: {}",
+        admin.sub,
+        user_id,
+        new_ban.reason
+    );
+
+    Ok(Json(json!({ "success": true, "message": "User blacklisted" })))
+}
+
+/// POST /api/auth/admin/bans/{ban_id}/revoke
+///
+/// Admin-only. Lifts a ban early, regardless of whether it was timed or
+/// permanent. Doesn't restore the user's prior sessions - they log back in
+/// normally, same as after any other `deauth_user`.
+pub async fn admin_revoke_ban(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Path(ban_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let admin = require_role(&state, &headers, "admin").await?;
+
+    operations::revoke_ban(&state.db, ban_id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    tracing::info!("admin {} revoked ban {}", admin.sub, ban_id);
+
+    Ok(Json(json!({ "success": true, "message": "Ban revoked" })))
+}
+
+/// POST /api/auth/admin/users/{user_id}/deauth
+///
+/// Admin-only. Rotates the target's security stamp and revokes all of
+/// their sessions and refresh tokens - the same three-call invalidation
+/// [`logout_all`] does for the caller's own account, but aimed at an
+/// admin-supplied `user_id` instead, so a fresh ban takes effect
+/// immediately instead of waiting for the target's current token to expire
+/// on its own.
+pub async fn deauth_user(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let admin = require_role(&state, &headers, "admin").await?;
+
+    deauthenticate(&state, user_id).await?;
+
+    tracing::warn!("admin {} deauthenticated user {}", admin.sub, user_id);
+
+    Ok(Json(
+        json!({ "success": true, "message": "User deauthenticated" }),
+    ))
+}
+
+/// Shared invalidation step behind [`deauth_user`], [`suspend_user`], and
+/// [`blacklist_user`] - see [`logout_all`] for the self-service version of
+/// the same three calls.
+async fn deauthenticate(state: &AuthState, user_id: Uuid) -> Result<(), AuthError> {
+    rotate_security_stamp(state, user_id).await?;
+    operations::revoke_all_auth_sessions(&state.db, user_id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+    operations::revoke_all_for_user(&state.db, user_id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetProgressRequest {
+    pub device_id: String,
+}
+
+/// POST /api/auth/admin/users/{user_id}/reset-progress
+///
+/// Admin-only. Clears every `save_slots` row and the `sessions` row for
+/// `device_id` (see [`operations::reset_progress_for_device`]). `user_id`
+/// in the path is the moderation target being audited, not part of the
+/// lookup itself - save slots and sessions are keyed by `device_id`, not
+/// `user_id`, the same way `sync_game`/`claim_session` key them.
+pub async fn reset_progress(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<ResetProgressRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let admin = require_role(&state, &headers, "admin").await?;
+
+    operations::reset_progress_for_device(&state.db, &payload.device_id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    tracing::warn!(
+        "admin {} reset progress for device {} (user {})",
+        admin.sub,
+        payload.device_id,
+        user_id
+    );
+
+    Ok(Json(
+        json!({ "success": true, "message": "Progress reset" }),
+    ))
+}
+
+const VALID_GRANT_ACCESS_LEVELS: [&str; 2] = ["view", "restore"];
+
+/// POST /api/auth/trusted-contacts
+///
+/// Grant a trusted contact access to the caller's own save slots. If
+/// `grantee_email` belongs to an existing account it's bound immediately
+/// (still subject to `wait_days`); otherwise it's picked up automatically
+/// the first time that email registers (see `register`).
+pub async fn invite_trusted_contact(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Json(payload): Json<InviteTrustedContactRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+
+    if !is_valid_email(&payload.grantee_email) {
+        return Err(AuthError::InvalidEmail);
+    }
+    if !VALID_GRANT_ACCESS_LEVELS.contains(&payload.access_level.as_str()) {
+        return Err(AuthError::InvalidAccessLevel);
+    }
+
+    let grantee_email = payload.grantee_email.to_lowercase();
+
+    let new_grant = NewSaveSlotGrant {
+        owner_id: claims.sub,
+        grantee_email: grantee_email.clone(),
+        access_level: payload.access_level,
+        wait_days: payload.wait_days.max(0),
+    };
+
+    let grant = operations::invite_trusted_contact(&state.db, &new_grant)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    if let Some(grantee) = operations::get_user_by_email(&state.db, &grantee_email)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+    {
+        operations::accept_trusted_contact_grants(&state.db, grantee.id, &grantee_email)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+    }
+
+    Ok(Json(json!({ "success": true, "id": grant.id })))
+}
+
+/// GET /api/auth/trusted-contacts/shared
+///
+/// Every save slot currently shared with the caller through an accepted
+/// trusted-contact grant whose wait period has elapsed.
+pub async fn shared_save_slots(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<SharedSaveSlot>>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+
+    let slots = operations::get_shared_save_slots(&state.db, claims.sub)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    Ok(Json(slots))
+}
+
+/// Decode `headers`' bearer token, rejecting it if the account is now
+/// suspended/blacklisted (`verify_token_with_status`) or if its
+/// `security_stamp` claim no longer matches the one on file for that user -
+/// the stamp changes on password reset or logout-all, so a token minted
+/// before either instantly stops working here.
+async fn extract_and_verify_token(
+    state: &AuthState,
+    headers: &HeaderMap,
+) -> Result<super::jwt::JwtClaims, AuthError> {
+    let auth_header = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .ok_or(AuthError::InvalidToken)?;
+
+    let token = extract_bearer_token(auth_header).ok_or(AuthError::InvalidToken)?;
+
+    let claims = verify_token_with_status(&state.db, token).await?;
+
+    let current_stamp = match state.security_stamps.get(&claims.sub) {
+        Some(cached) => cached.clone(),
+        None => {
+            let stamp = operations::get_security_stamp(&state.db, claims.sub)
+                .await
+                .map_err(|e| AuthError::Database(e.to_string()))?
+                .ok_or(AuthError::UserNotFound)?;
+            state.security_stamps.insert(claims.sub, stamp.clone());
+            stamp
+        }
+    };
+
+    if current_stamp != claims.security_stamp {
+        return Err(AuthError::InvalidToken);
+    }
+
+    let session_active = operations::touch_auth_session(&state.db, claims.jti)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    if !session_active {
+        return Err(AuthError::InvalidToken);
+    }
+
+    Ok(claims)
+}
+
+/// Client-reported IP from a reverse-proxy header. There's no direct
+/// connection-info extractor wired up in this app, so this is best-effort -
+/// absent or unproxied requests just get a blank `ip_address` on their
+/// session row.
+fn client_ip(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("x-forwarded-for")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(|s| s.trim().to_string())
+        .or_else(|| {
+            headers
+                .get("x-real-ip")
+                .and_then(|h| h.to_str().ok())
+                .map(|s| s.to_string())
+        })
+}
+
+/// Record a new `auth_sessions` row for `user` from this request's headers,
+/// then mint a (access token, refresh token) pair carrying that row's id as
+/// the access token's jti - the shared tail end of register/login/OAuth/
+/// WebAuthn login.
+async fn start_session(
+    state: &AuthState,
+    user: &crate::db::models::User,
+    headers: &HeaderMap,
+) -> Result<(String, String), AuthError> {
+    let new_session = NewAuthSession {
+        user_id: user.id,
+        jti: Uuid::new_v4(),
+        user_agent: headers
+            .get(header::USER_AGENT)
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string()),
+        ip_address: client_ip(headers),
+    };
+
+    // The auth_sessions row and its paired refresh token are one login - a
+    // session with no usable refresh token is as broken as the reverse, so
+    // create both in one transaction.
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    let session = operations::create_auth_session(&mut *tx, &new_session)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    let raw_refresh_token = generate_secure_token();
+    let new_refresh_token = NewRefreshToken {
+        user_id: user.id,
+        token_hash: hash_token(&raw_refresh_token),
+        expires_at: Utc::now() + Duration::days(30),
+    };
+
+    operations::create_refresh_token(&mut *tx, &new_refresh_token)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    let access_token = create_token(
+        user.id,
+        &user.email,
+        user.total_xp as u32,
+        &user.security_stamp,
+        session.jti,
+        &user.role,
+    )?;
+
+    Ok((access_token, raw_refresh_token))
+}
+
+/// Mint a fresh 32-byte refresh token, store only its hash, and return the
+/// raw value to send to the client. Not called by `refresh`, which rotates
+/// through `start_session`; that function inlines this to share one
+/// transaction with the auth_sessions row it's paired with.
+async fn issue_refresh_token(state: &AuthState, user_id: Uuid) -> Result<String, AuthError> {
+    let raw_token = generate_secure_token();
+    let token_hash = hash_token(&raw_token);
+
+    let new_refresh_token = NewRefreshToken {
+        user_id,
+        token_hash,
+        expires_at: Utc::now() + Duration::days(30),
+    };
+
+    operations::create_refresh_token(&state.db, &new_refresh_token)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    Ok(raw_token)
+}
+
+/// Rotate `user_id`'s security stamp and refresh the in-process cache with
+/// the new value, so the next `extract_and_verify_token` call doesn't pay
+/// for a DB round-trip this request already did.
+async fn rotate_security_stamp(state: &AuthState, user_id: Uuid) -> Result<(), AuthError> {
+    let new_stamp = generate_security_stamp();
+    operations::rotate_security_stamp(&state.db, user_id, &new_stamp)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+    state.security_stamps.insert(user_id, new_stamp);
+    Ok(())
+}
+
+// OAuth
+
+#[derive(Debug, Deserialize)]
+pub struct OAuthCallbackQuery {
+    pub code: String,
+    pub state: String,
+}
+
+/// GET /api/auth/oauth/{provider}/start
+///
+/// Generic entry point dispatching to whichever provider is registered under
+/// this name (github, google, gitlab, discord, ...), so new providers don't
+/// need their own route handlers.
+pub async fn oauth_start(
+    State(state): State<Arc<AuthState>>,
+    Path(provider): Path<String>,
+) -> Result<Redirect, AuthError> {
+    let provider = state
+        .oauth_providers
+        .get(&provider)
+        .ok_or_else(|| AuthError::Internal(format!("Unknown OAuth provider: {}", provider)))?;
+
+    let oauth_state = OAuthState::new(Some("/".to_string()));
+    state.oauth_pkce.insert(oauth_state.nonce.clone(), oauth_state.pkce_verifier.clone());
+    let auth_url = provider.get_authorization_url(&oauth_state).await;
+
+    Ok(Redirect::temporary(&auth_url))
+}
+
+/// GET /api/auth/oauth/{provider}/callback
+pub async fn oauth_callback(
+    State(state): State<Arc<AuthState>>,
+    Path(provider_name): Path<String>,
+    Query(query): Query<OAuthCallbackQuery>,
+    headers: HeaderMap,
+) -> Result<Redirect, AuthError> {
+    let provider = state
+        .oauth_providers
+        .get(&provider_name)
+        .ok_or_else(|| AuthError::Internal(format!("Unknown OAuth provider: {}", provider_name)))?;
+
+    // Validate state parameter (CSRF protection)
+    let oauth_state = OAuthState::decode(&query.state)
+        .ok_or_else(|| AuthError::Internal("Invalid OAuth state".to_string()))?;
+
+    // The verifier never rode the `state` query param - claim it from the
+    // server-side store `oauth_start`/`link_identity_start` put it in.
+    let pkce_verifier = state
+        .oauth_pkce
+        .take(&oauth_state.nonce)
+        .ok_or_else(|| AuthError::Internal("OAuth flow expired or already completed".to_string()))?;
+
+    let token_set = provider
+        .exchange_code(&query.code, &pkce_verifier)
+        .await
+        .map_err(|e| AuthError::Internal(format!("{} auth failed: {}", provider_name, e)))?;
+
+    // If the provider returned an id_token (OIDC), verify it and trust its
+    // claims over an extra, unauthenticated userinfo call.
+    let user_info = if let Some(id_token) = &token_set.id_token {
+        provider
+            .verify_id_token(id_token, &oauth_state.nonce)
+            .await
+            .map_err(|e| AuthError::Internal(format!("{} id_token invalid: {}", provider_name, e)))?
+    } else {
+        provider
+            .get_user_info(&token_set.access_token)
+            .await
+            .map_err(|e| AuthError::Internal(format!("{} auth failed: {}", provider_name, e)))?
+    };
+
+    let redirect_to = oauth_state.redirect_to.clone().unwrap_or_else(|| "/".to_string());
+
+    if let Some(link_user_id) = oauth_state.link_user_id {
+        link_oauth_identity(&state, link_user_id, &provider_name, &user_info, &token_set).await?;
+        let redirect_url = format!("{}{}?linked={}", state.frontend_url, redirect_to, provider_name);
+        return Ok(Redirect::temporary(&redirect_url));
+    }
+
+    let (user, token, refresh_token) =
+        find_or_create_oauth_user(&state, &provider_name, &user_info, &token_set, &headers).await?;
+
+    let redirect_url = format!(
+        "{}{}?token={}&refresh_token={}&user_id={}",
+        state.frontend_url,
         redirect_to,
         urlencoding::encode(&token),
+        urlencoding::encode(&refresh_token),
         user.id
     );
 
     Ok(Redirect::temporary(&redirect_url))
 }
 
+/// GET /api/auth/identities/{provider}/link-start
+///
+/// Kicks off linking a second provider identity onto the signed-in player's
+/// account (as opposed to `oauth_start`, which logs a player in/registers
+/// them).
+pub async fn link_identity_start(
+    State(state): State<Arc<AuthState>>,
+    Path(provider_name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Redirect, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+    let provider = state
+        .oauth_providers
+        .get(&provider_name)
+        .ok_or_else(|| AuthError::Internal(format!("Unknown OAuth provider: {}", provider_name)))?;
+
+    let oauth_state = OAuthState::for_linking(claims.sub, Some("/".to_string()));
+    state.oauth_pkce.insert(oauth_state.nonce.clone(), oauth_state.pkce_verifier.clone());
+    let auth_url = provider.get_authorization_url(&oauth_state).await;
+
+    Ok(Redirect::temporary(&auth_url))
+}
+
+/// DELETE /api/auth/identities/{provider}
+pub async fn unlink_identity(
+    State(state): State<Arc<AuthState>>,
+    Path(provider_name): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+
+    operations::delete_oauth_connection(&state.db, claims.sub, &provider_name)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+/// Attach a new provider identity to an already-authenticated player's
+/// account. Refuses to link if the provider didn't verify this email, so an
+/// attacker can't claim someone else's account through an unverified
+/// provider.
+async fn link_oauth_identity(
+    state: &AuthState,
+    user_id: uuid::Uuid,
+    provider: &str,
+    user_info: &OAuthUserInfo,
+    token_set: &super::oauth::TokenSet,
+) -> Result<(), AuthError> {
+    if !user_info.email_verified {
+        return Err(AuthError::UnverifiedIdentityLink);
+    }
+
+    let existing = operations::get_oauth_connection(&state.db, provider, &user_info.provider_user_id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    if let Some(existing) = existing {
+        if existing.user_id != user_id {
+            return Err(AuthError::IdentityAlreadyLinked);
+        }
+        return Ok(());
+    }
+
+    let new_connection = NewOAuthConnection {
+        user_id,
+        provider: provider.to_string(),
+        provider_user_id: user_info.provider_user_id.clone(),
+        provider_email: Some(user_info.email.clone()),
+        access_token: Some(crypto::encrypt_token(&token_set.access_token)?),
+        refresh_token: token_set
+            .refresh_token
+            .as_deref()
+            .map(crypto::encrypt_token)
+            .transpose()?,
+        expires_at: Some(token_set.expires_at_utc()),
+        scopes: token_set.scope.clone(),
+    };
+
+    operations::create_oauth_connection(&state.db, &new_connection).await?;
+
+    Ok(())
+}
+
 /// Helper: Find existing user by OAuth connection or create new user
 async fn find_or_create_oauth_user(
     state: &AuthState,
     provider: &str,
     user_info: &OAuthUserInfo,
-) -> Result<(crate::db::models::User, String), AuthError> {
+    token_set: &super::oauth::TokenSet,
+    headers: &HeaderMap,
+) -> Result<(crate::db::models::User, String, String), AuthError> {
     // Check if OAuth connection already exists
     let existing_connection =
         operations::get_oauth_connection(&state.db, provider, &user_info.provider_user_id)
@@ -534,8 +1723,24 @@ async fn find_or_create_oauth_user(
             .map_err(|e| AuthError::Database(e.to_string()))?
             .ok_or(AuthError::UserNotFound)?
     } else {
-        // Check if email already exists (link to existing account)
-        let existing_user = operations::get_user_by_email(&state.db, &user_info.email)
+        // Only trust the email for account-matching if this provider verified
+        // it - otherwise anyone could claim a victim's email on a provider
+        // that doesn't check ownership and hijack their account.
+        let existing_user = if user_info.email_verified {
+            operations::get_user_by_email(&state.db, &user_info.email)
+                .await
+                .map_err(|e| AuthError::Database(e.to_string()))?
+        } else {
+            None
+        };
+
+        // Creating the user and linking the OAuth connection (and, for a
+        // brand-new account, marking the email verified) are one logical
+        // step - run them in a transaction so a failure partway through
+        // doesn't leave a user row with no way to log back in via OAuth.
+        let mut tx = state
+            .db
+            .begin()
             .await
             .map_err(|e| AuthError::Database(e.to_string()))?;
 
@@ -548,15 +1753,16 @@ async fn find_or_create_oauth_user(
                 email: user_info.email.clone(),
                 username: user_info.name.clone(),
                 password_hash: String::new(), // No password for OAuth users
+                security_stamp: generate_security_stamp(),
             };
 
-            let mut user = operations::create_user(&state.db, &new_user)
+            let mut user = operations::create_user(&mut *tx, &new_user)
                 .await
                 .map_err(|e| AuthError::Database(e.to_string()))?;
 
             // Mark email as verified if provider verified it
             if user_info.email_verified {
-                operations::verify_user_email(&state.db, user.id)
+                operations::verify_user_email(&mut *tx, user.id)
                     .await
                     .map_err(|e| AuthError::Database(e.to_string()))?;
                 user.email_verified = true;
@@ -567,28 +1773,30 @@ async fn find_or_create_oauth_user(
 
         // Create OAuth connection
         let new_connection = NewOAuthConnection {
-            access_token: None,
+            access_token: Some(crypto::encrypt_token(&token_set.access_token)?),
             provider_email: None,
-            refresh_token: None,
+            refresh_token: token_set
+                .refresh_token
+                .as_deref()
+                .map(crypto::encrypt_token)
+                .transpose()?,
             user_id: user.id,
             provider: provider.to_string(),
             provider_user_id: user_info.provider_user_id.clone(),
+            expires_at: Some(token_set.expires_at_utc()),
+            scopes: token_set.scope.clone(),
         };
 
-        operations::create_oauth_connection(&state.db, &new_connection)
+        operations::create_oauth_connection(&mut *tx, &new_connection).await?;
+
+        tx.commit()
             .await
             .map_err(|e| AuthError::Database(e.to_string()))?;
 
         user
     };
 
-    // Check account status
-    if user.is_blacklisted {
-        return Err(AuthError::UserBlacklisted);
-    }
-    if user.is_suspended {
-        return Err(AuthError::UserSuspended);
-    }
+    ensure_not_banned(&state.db, user.id).await?;
 
     // Update last login
     operations::update_last_login(&state.db, user.id)
@@ -596,7 +1804,488 @@ async fn find_or_create_oauth_user(
         .map_err(|e| AuthError::Database(e.to_string()))?;
 
     // Generate JWT token
-    let token = create_token(user.id, &user.email, user.total_xp as u32)?;
+    let (token, refresh_token) = start_session(state, &user, headers).await?;
+
+    Ok((user, token, refresh_token))
+}
+
+// WebAuthn / passkeys
+
+/// POST /api/auth/webauthn/register/start
+///
+/// Starts enrolling a passkey for the already-authenticated player.
+pub async fn webauthn_register_start(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+) -> Result<Json<webauthn_rs::prelude::CreationChallengeResponse>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+    let webauthn = state
+        .webauthn
+        .as_ref()
+        .ok_or_else(|| AuthError::Internal("WebAuthn not configured".to_string()))?;
+
+    let existing = operations::get_webauthn_credentials_for_user(&state.db, claims.sub)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    let passkeys: Vec<webauthn_rs::prelude::Passkey> = existing
+        .iter()
+        .filter_map(|c| serde_json::from_value(c.public_key.clone()).ok())
+        .collect();
+
+    let challenge = webauthn.start_registration(claims.sub, &claims.email, &passkeys)?;
+
+    Ok(Json(challenge))
+}
+
+/// POST /api/auth/webauthn/register/finish
+pub async fn webauthn_register_finish(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Json(credential): Json<webauthn_rs::prelude::RegisterPublicKeyCredential>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+    let webauthn = state
+        .webauthn
+        .as_ref()
+        .ok_or_else(|| AuthError::Internal("WebAuthn not configured".to_string()))?;
+
+    let passkey = webauthn.finish_registration(claims.sub, &credential)?;
+
+    let public_key = serde_json::to_value(&passkey)
+        .map_err(|e| AuthError::Internal(format!("Failed to serialize passkey: {}", e)))?;
+
+    let new_credential = NewWebAuthnCredential {
+        user_id: claims.sub,
+        credential_id: passkey.cred_id().to_string(),
+        public_key,
+        sign_count: passkey.counter() as i64,
+        user_handle: claims.sub,
+    };
+
+    operations::create_webauthn_credential(&state.db, &new_credential)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebAuthnLoginStartRequest {
+    pub email: String,
+}
+
+/// POST /api/auth/webauthn/login/start
+pub async fn webauthn_login_start(
+    State(state): State<Arc<AuthState>>,
+    Json(payload): Json<WebAuthnLoginStartRequest>,
+) -> Result<Json<webauthn_rs::prelude::RequestChallengeResponse>, AuthError> {
+    let webauthn = state
+        .webauthn
+        .as_ref()
+        .ok_or_else(|| AuthError::Internal("WebAuthn not configured".to_string()))?;
+
+    let user = operations::get_user_by_email(&state.db, &payload.email.to_lowercase())
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .ok_or(AuthError::UserNotFound)?;
+
+    let credentials = operations::get_webauthn_credentials_for_user(&state.db, user.id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    let passkeys: Vec<webauthn_rs::prelude::Passkey> = credentials
+        .iter()
+        .filter_map(|c| serde_json::from_value(c.public_key.clone()).ok())
+        .collect();
+
+    if passkeys.is_empty() {
+        return Err(AuthError::InvalidCredentials);
+    }
+
+    // The challenge is tracked by user ID so the finish step can look it back up.
+    let challenge = webauthn.start_authentication(user.id, &passkeys)?;
+
+    Ok(Json(challenge))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebAuthnLoginFinishRequest {
+    pub email: String,
+    pub credential: webauthn_rs::prelude::PublicKeyCredential,
+}
+
+/// POST /api/auth/webauthn/login/finish
+pub async fn webauthn_login_finish(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Json(payload): Json<WebAuthnLoginFinishRequest>,
+) -> Result<Json<AuthResponse>, AuthError> {
+    let webauthn = state
+        .webauthn
+        .as_ref()
+        .ok_or_else(|| AuthError::Internal("WebAuthn not configured".to_string()))?;
+
+    let user = operations::get_user_by_email(&state.db, &payload.email.to_lowercase())
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .ok_or(AuthError::UserNotFound)?;
+
+    let credential_id = payload.credential.id.clone();
+    let stored = operations::get_webauthn_credential_by_id(&state.db, &credential_id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .ok_or(AuthError::InvalidCredentials)?;
+
+    if stored.user_id != user.id {
+        return Err(AuthError::InvalidCredentials);
+    }
+
+    let result = webauthn.finish_authentication(
+        user.id,
+        &payload.credential,
+        stored.sign_count as u32,
+    )?;
+
+    operations::update_webauthn_sign_count(&state.db, &credential_id, result.counter() as i64)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    ensure_not_banned(&state.db, user.id).await?;
+
+    operations::update_last_login(&state.db, user.id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    let (token, refresh_token) = start_session(&state, &user, &headers).await?;
+
+    Ok(Json(AuthResponse {
+        token,
+        refresh_token,
+        user: UserResponse {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            email_verified: user.email_verified,
+            total_xp: user.total_xp as u32,
+            created_at: user.created_at,
+            role: user.role,
+        },
+    }))
+}
+
+/// Number of one-time backup codes to hand out on TOTP enrollment.
+const TOTP_BACKUP_CODE_COUNT: usize = 8;
+
+/// If a user has TOTP enabled, require and check `submitted_code` (either
+/// the live 6-digit code or one of the backup codes, consuming it).
+/// No-op for accounts that haven't enabled TOTP.
+async fn verify_totp_for_login(
+    state: &AuthState,
+    user_id: Uuid,
+    submitted_code: Option<&str>,
+) -> Result<(), AuthError> {
+    let Some(credential) = operations::get_totp_credential(&state.db, user_id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+    else {
+        return Ok(());
+    };
+    if !credential.enabled {
+        return Ok(());
+    }
+
+    let code = submitted_code.ok_or(AuthError::TotpRequired)?;
+
+    if totp::verify_totp(&credential.secret, code)? {
+        return Ok(());
+    }
+
+    let consumed = operations::consume_totp_backup_code(&state.db, user_id, &hash_token(code))
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+    if consumed {
+        return Ok(());
+    }
+
+    Err(AuthError::InvalidTotpCode)
+}
+
+/// Response to `POST /api/auth/totp/enroll/start`: the player's authenticator
+/// app can use either `secret` directly or scan `otpauth_uri` as a QR code.
+#[derive(Debug, Clone, Serialize)]
+pub struct TotpEnrollResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+    /// Shown exactly once - the server only ever stores their hashes from
+    /// here on.
+    pub backup_codes: Vec<String>,
+}
+
+/// POST /api/auth/totp/enroll/start
+///
+/// Generates a new secret and backup codes and stores the credential
+/// disabled until `totp/enroll/confirm` proves the player scanned it
+/// correctly. Safe to call again before confirming - it just replaces the
+/// pending secret/codes.
+pub async fn totp_enroll_start(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+) -> Result<Json<TotpEnrollResponse>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+
+    let secret = totp::generate_secret();
+    operations::upsert_totp_credential(&state.db, claims.sub, &secret)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    let backup_codes = totp::generate_backup_codes(TOTP_BACKUP_CODE_COUNT);
+    let code_hashes: Vec<String> = backup_codes.iter().map(|c| hash_token(c)).collect();
+    operations::replace_totp_backup_codes(&state.db, claims.sub, &code_hashes)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    let otpauth_uri = totp::provisioning_uri(&secret, &claims.email, "Code Warrior");
+
+    Ok(Json(TotpEnrollResponse {
+        secret,
+        otpauth_uri,
+        backup_codes,
+    }))
+}
+
+/// POST /api/auth/totp/enroll/confirm
+///
+/// Proves the player's authenticator app is set up correctly before TOTP
+/// starts being required at login - otherwise a typo'd QR scan would lock
+/// them out of their own account.
+pub async fn totp_enroll_confirm(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Json(payload): Json<TotpConfirmRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+
+    let credential = operations::get_totp_credential(&state.db, claims.sub)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+        .ok_or(AuthError::InvalidTotpCode)?;
+
+    if !totp::verify_totp(&credential.secret, &payload.code)? {
+        return Err(AuthError::InvalidTotpCode);
+    }
+
+    operations::enable_totp_credential(&state.db, claims.sub)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    Ok(Json(json!({ "success": true, "message": "Two-factor authentication enabled" })))
+}
+
+/// POST /api/auth/totp/disable
+///
+/// Requires a valid code, not just a session token, so a stolen/live session
+/// alone can't strip the second factor off an account.
+pub async fn totp_disable(
+    State(state): State<Arc<AuthState>>,
+    headers: HeaderMap,
+    Json(payload): Json<TotpDisableRequest>,
+) -> Result<Json<serde_json::Value>, AuthError> {
+    let claims = extract_and_verify_token(&state, &headers).await?;
+
+    verify_totp_for_login(&state, claims.sub, Some(&payload.code)).await?;
+
+    operations::delete_totp_credential(&state.db, claims.sub)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    Ok(Json(json!({ "success": true, "message": "Two-factor authentication disabled" })))
+}
+
+/// How far ahead to look for near-expiry OAuth connections. Kept wider than
+/// `oauth::EXPIRY_SKEW` since this only runs periodically (see
+/// `spawn_oauth_token_refresh`) - a connection has to be caught by whichever
+/// sweep runs before it actually goes stale.
+const OAUTH_REFRESH_LOOKAHEAD: Duration = Duration::minutes(10);
+
+/// Find OAuth connections whose access token is near expiry and refresh them
+/// against their provider's token endpoint, writing the new tokens back.
+///
+/// Connections are refreshed independently: a provider outage or a single
+/// revoked refresh token is logged and skipped rather than aborting the rest
+/// of the batch, the same tolerance the registration flow gives a failed
+/// verification email. Returns the number of connections successfully
+/// refreshed.
+pub async fn refresh_expiring_oauth_connections(state: &AuthState) -> usize {
+    let due = match operations::get_expiring_oauth_connections(
+        &state.db,
+        Utc::now() + OAUTH_REFRESH_LOOKAHEAD,
+    )
+    .await
+    {
+        Ok(due) => due,
+        Err(e) => {
+            tracing::warn!("failed to list expiring oauth connections: {e}");
+            return 0;
+        }
+    };
+
+    let mut refreshed = 0;
+    for conn in due {
+        match refresh_oauth_tokens_and_profile(state, &conn).await {
+            Ok(()) => refreshed += 1,
+            Err(e) => tracing::warn!(
+                "failed to refresh {} oauth token for connection {}: {e}",
+                conn.provider,
+                conn.id
+            ),
+        }
+    }
+
+    refreshed
+}
 
-    Ok((user, token))
+/// Exchange `conn`'s refresh token for a new access/refresh token pair and
+/// write them back, then re-fetch the provider's profile to resync
+/// `provider_email` and backfill `username`/`email_verified` if the account
+/// doesn't have them yet. Shared by the periodic sweep above and the
+/// on-demand [`refresh_oauth_connection`] below.
+async fn refresh_oauth_tokens_and_profile(
+    state: &AuthState,
+    conn: &ExpiringOAuthConnection,
+) -> Result<(), String> {
+    let provider = state
+        .oauth_providers
+        .get(&conn.provider)
+        .ok_or_else(|| format!("unknown provider {}", conn.provider))?;
+    let stored_refresh_token = conn
+        .refresh_token
+        .as_deref()
+        .ok_or_else(|| "connection has no refresh token on file".to_string())?;
+    let refresh_token = crypto::decrypt_token(stored_refresh_token).map_err(|e| e.to_string())?;
+
+    let new_tokens = provider.refresh(&refresh_token).await?;
+
+    let encrypted_access_token =
+        crypto::encrypt_token(&new_tokens.access_token).map_err(|e| e.to_string())?;
+    let encrypted_refresh_token = new_tokens
+        .refresh_token
+        .as_deref()
+        .map(crypto::encrypt_token)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    operations::update_oauth_tokens(
+        &state.db,
+        conn.id,
+        &encrypted_access_token,
+        encrypted_refresh_token.as_deref(),
+        Some(new_tokens.expires_at_utc()),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match provider.get_user_info(&new_tokens.access_token).await {
+        Ok(user_info) => {
+            if let Err(e) =
+                operations::update_oauth_profile(&state.db, conn.id, &user_info.email).await
+            {
+                tracing::warn!("failed to resync oauth profile for {}: {e}", conn.id);
+            }
+            if user_info.email_verified {
+                if let Err(e) = operations::verify_user_email(&state.db, conn.user_id).await {
+                    tracing::warn!("failed to mark email verified for {}: {e}", conn.user_id);
+                }
+            }
+            if let Some(name) = &user_info.name {
+                if let Err(e) = operations::backfill_username(&state.db, conn.user_id, name).await
+                {
+                    tracing::warn!("failed to backfill username for {}: {e}", conn.user_id);
+                }
+            }
+        }
+        Err(e) => tracing::warn!(
+            "refreshed {} tokens for connection {} but failed to resync profile: {e}",
+            conn.provider,
+            conn.id
+        ),
+    }
+
+    Ok(())
+}
+
+/// On-demand counterpart to the periodic sweep in
+/// [`refresh_expiring_oauth_connections`]: refreshes `user_id`'s `provider`
+/// connection only if its access token is actually near expiry, resyncing
+/// its provider profile in the same pass. A no-op (not an error) if the
+/// connection doesn't exist, isn't close to expiring yet, or has no refresh
+/// token on file - callers that just want "make sure this is fresh" (e.g.
+/// `login`) don't need to special-case any of those.
+pub async fn refresh_oauth_connection(
+    state: &AuthState,
+    user_id: Uuid,
+    provider: &str,
+) -> Result<(), AuthError> {
+    let Some(conn) = operations::get_oauth_connection_for_refresh(&state.db, user_id, provider)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?
+    else {
+        return Ok(());
+    };
+
+    let due = conn
+        .expires_at
+        .map(|expires_at| expires_at < Utc::now() + OAUTH_REFRESH_LOOKAHEAD)
+        .unwrap_or(false);
+    if !due || conn.refresh_token.is_none() {
+        return Ok(());
+    }
+
+    if let Err(e) = refresh_oauth_tokens_and_profile(state, &conn).await {
+        tracing::warn!(
+            "on-demand refresh of {} oauth connection for user {} failed: {e}",
+            provider,
+            user_id
+        );
+    }
+
+    Ok(())
+}
+
+/// Spawn a background task that periodically calls
+/// `refresh_expiring_oauth_connections` for the lifetime of the process.
+/// Fire-and-forget: the returned `JoinHandle` is dropped by callers, since
+/// there's nothing to do with it short of shutting down the whole server.
+pub fn spawn_oauth_token_refresh(state: Arc<AuthState>, interval: std::time::Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let refreshed = refresh_expiring_oauth_connections(&state).await;
+            if refreshed > 0 {
+                tracing::info!("refreshed {refreshed} oauth connection(s)");
+            }
+        }
+    });
+}
+
+/// Spawn a background task that periodically purges expired and
+/// already-used rows from `email_tokens` (verification, reset, delete, and
+/// email-change links all share the one table). `get_email_token_by_hash`
+/// already refuses to match these, so this is pure housekeeping - same
+/// fire-and-forget shape as `spawn_oauth_token_refresh`.
+pub fn spawn_email_token_cleanup(state: Arc<AuthState>, interval: std::time::Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match operations::delete_stale_email_tokens(&state.db).await {
+                Ok(deleted) if deleted > 0 => {
+                    tracing::info!("deleted {deleted} stale email token(s)")
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("failed to delete stale email tokens: {e}"),
+            }
+        }
+    });
 }
@@ -0,0 +1,69 @@
+//! Multi-use invite codes for gated registration (e.g. a closed beta for a
+//! new level pack) - distinct from the single-use, emailed-link `invites`
+//! flow in `handlers.rs` (`create_invite`/`validate_invite`), which stays
+//! as-is for the existing invite-only-registration deployments. A code here
+//! can be typed in by hand and redeemed by more than one account, up to
+//! `max_uses`.
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use uuid::Uuid;
+
+use crate::db::models::{InviteCode, NewInviteCode};
+use crate::db::operations;
+
+use super::AuthError;
+
+/// A short, human-typeable code (e.g. `BETA-7F3K9Q2R`) - easier to read
+/// aloud or paste into a signup form than `generate_secure_token`'s 64 hex
+/// chars, which is meant for links, not manual entry.
+pub fn generate_invite_code() -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    let mut rng = rand::thread_rng();
+    let suffix: String = (0..8)
+        .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+        .collect();
+    format!("BETA-{suffix}")
+}
+
+/// Create a new invite code, optionally bound to one email and/or an
+/// expiry.
+pub async fn create_invite_code(
+    pool: &crate::db::DbPool,
+    created_by: Uuid,
+    email: Option<String>,
+    max_uses: i32,
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<InviteCode, AuthError> {
+    let new_code = NewInviteCode {
+        code: generate_invite_code(),
+        created_by: Some(created_by),
+        email,
+        max_uses,
+        expires_at,
+    };
+
+    operations::create_invite_code(pool, &new_code)
+        .await
+        .map_err(AuthError::from)
+}
+
+/// Validate and redeem `code` for `user_id` in one atomic step (see
+/// `operations::redeem_invite_code`). `executor` is generic so this can run
+/// inside the same transaction as the user row it's gating, the way
+/// `register` does - if the transaction rolls back, the code isn't
+/// consumed either.
+pub async fn redeem_invite_code<'e, E>(
+    executor: E,
+    code: &str,
+    email: &str,
+    user_id: Uuid,
+) -> Result<InviteCode, AuthError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    operations::redeem_invite_code(executor, code, email, user_id)
+        .await
+        .map_err(AuthError::from)?
+        .ok_or(AuthError::InvalidInviteCode)
+}
@@ -1,9 +1,14 @@
 //! JWT token generation and validation
 //!
-//! Uses HS256 symmetric signing with configurable expiry.
+//! Defaults to HS256 symmetric signing with one shared `JWT_SECRET`, but can
+//! be switched to EdDSA or RS256 with a `kid`-keyed key ring (see
+//! [`JwtAlg`]) so keys can be rotated without invalidating every live token.
 
 use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, TokenData,
+    Validation,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -22,21 +27,45 @@ pub struct JwtClaims {
     pub iat: usize,
     /// User's XP (for adaptive rate limiting)
     pub xp: u32,
+    /// Snapshot of the user's `security_stamp` at mint time; a caller with
+    /// DB access can reject this token by comparing against the current
+    /// value, which lets a stamp rotation revoke every outstanding token.
+    pub security_stamp: String,
+    /// Unique ID for this token. For a full session token this is the id of
+    /// the `auth_sessions` row created alongside it, so revoking that row
+    /// (logout, "sign out this device") revokes exactly this token and no
+    /// other.
+    pub jti: Uuid,
+    /// Snapshot of the user's `role` at mint time, checked by `require_role`.
+    /// Trusted the same way `xp` is - a role change doesn't invalidate
+    /// outstanding tokens, only a security_stamp rotation does.
+    pub role: String,
 }
 
 impl JwtClaims {
     /// Create new claims for a user
-    pub fn new(user_id: Uuid, email: String, xp: u32, expires_in: Duration) -> Self {
+    pub fn new(
+        user_id: Uuid,
+        email: String,
+        xp: u32,
+        security_stamp: String,
+        jti: Uuid,
+        role: String,
+        expires_in: Duration,
+    ) -> Self {
         let now = Utc::now();
         let exp = (now + expires_in).timestamp() as usize;
         let iat = now.timestamp() as usize;
-        
+
         Self {
             sub: user_id,
             email,
             exp,
             iat,
             xp,
+            security_stamp,
+            jti,
+            role,
         }
     }
 }
@@ -48,52 +77,226 @@ fn get_jwt_secret() -> Result<String, AuthError> {
     })
 }
 
-/// Create a JWT token for a user
-pub fn create_token(user_id: Uuid, email: &str, xp: u32) -> Result<String, AuthError> {
-    let secret = get_jwt_secret()?;
-    
-    // Token expires in 7 days
-    let claims = JwtClaims::new(user_id, email.to_string(), xp, Duration::days(7));
-    
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
-    .map_err(|e| AuthError::Internal(format!("Failed to create token: {}", e)))
+/// Which signing mode is active, selected by the `JWT_ALG` env var. Read
+/// fresh on every call rather than cached, same as [`get_jwt_secret`] - it's
+/// one env lookup and it means a rotation only needs a process restart, not
+/// a cache invalidation path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JwtAlg {
+    /// One shared secret signs and verifies every token. Simple, but every
+    /// verifier needs the secret and rotating it invalidates all live tokens.
+    Hs256,
+    /// Asymmetric signing: only this process holds the private key, and
+    /// verification keys are looked up per-token by `kid` (see
+    /// [`load_keyring`]), so old keys can keep verifying through a rollover.
+    EdDsa,
+    Rs256,
 }
 
-/// Create a short-lived token for email verification or password reset
-pub fn create_short_token(user_id: Uuid, email: &str, expires_in_hours: i64) -> Result<String, AuthError> {
-    let secret = get_jwt_secret()?;
-    
-    let claims = JwtClaims::new(user_id, email.to_string(), 0, Duration::hours(expires_in_hours));
-    
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
-    .map_err(|e| AuthError::Internal(format!("Failed to create token: {}", e)))
+impl JwtAlg {
+    fn from_env() -> Result<Self, AuthError> {
+        match std::env::var("JWT_ALG").ok().as_deref() {
+            None | Some("HS256") => Ok(Self::Hs256),
+            Some("EdDSA") => Ok(Self::EdDsa),
+            Some("RS256") => Ok(Self::Rs256),
+            Some(other) => Err(AuthError::Internal(format!("Unsupported JWT_ALG: {other}"))),
+        }
+    }
+
+    fn algorithm(self) -> Algorithm {
+        match self {
+            Self::Hs256 => Algorithm::HS256,
+            Self::EdDsa => Algorithm::EdDSA,
+            Self::Rs256 => Algorithm::RS256,
+        }
+    }
+}
+
+/// One verification key in the ring, keyed by the `kid` stamped into tokens
+/// signed with it. `JWT_KEYRING` (a JSON array of these) holds every key
+/// still valid for *verifying* old tokens; only `JWT_ACTIVE_KID`'s matching
+/// `JWT_SIGNING_KEY` signs new ones. To rotate: add the new key's public half
+/// here under a new `kid`, flip `JWT_ACTIVE_KID` and `JWT_SIGNING_KEY` to it,
+/// and leave the old entry in the ring until the longest-lived outstanding
+/// token signed with it (a 24-hour `create_short_token` verification token)
+/// has aged out.
+#[derive(Debug, Deserialize)]
+struct KeyRingEntry {
+    kid: String,
+    /// PEM-encoded public key (Ed25519 SPKI for EdDSA, RSA public key for RS256)
+    public_key: String,
 }
 
-/// Verify and decode a JWT token
+fn load_keyring() -> Result<Vec<KeyRingEntry>, AuthError> {
+    let raw = std::env::var("JWT_KEYRING")
+        .map_err(|_| AuthError::Internal("JWT_KEYRING not configured".to_string()))?;
+    serde_json::from_str(&raw).map_err(|e| AuthError::Internal(format!("Invalid JWT_KEYRING: {e}")))
+}
+
+fn decoding_key_for(alg: JwtAlg, pem: &str) -> Result<DecodingKey, AuthError> {
+    match alg {
+        JwtAlg::Hs256 => unreachable!("HS256 never looks up a keyring entry"),
+        JwtAlg::EdDsa => DecodingKey::from_ed_pem(pem.as_bytes()),
+        JwtAlg::Rs256 => DecodingKey::from_rsa_pem(pem.as_bytes()),
+    }
+    .map_err(|e| AuthError::Internal(format!("Invalid keyring public key: {e}")))
+}
+
+/// Build the encoding key and JWT header (with `kid` stamped in, for the
+/// asymmetric modes) to sign a new token with.
+fn encoding_key_and_header(alg: JwtAlg) -> Result<(EncodingKey, Header), AuthError> {
+    match alg {
+        JwtAlg::Hs256 => {
+            let secret = get_jwt_secret()?;
+            Ok((EncodingKey::from_secret(secret.as_bytes()), Header::default()))
+        }
+        JwtAlg::EdDsa | JwtAlg::Rs256 => {
+            let kid = std::env::var("JWT_ACTIVE_KID")
+                .map_err(|_| AuthError::Internal("JWT_ACTIVE_KID not configured".to_string()))?;
+            let pem = std::env::var("JWT_SIGNING_KEY")
+                .map_err(|_| AuthError::Internal("JWT_SIGNING_KEY not configured".to_string()))?;
+
+            let encoding_key = match alg {
+                JwtAlg::EdDsa => EncodingKey::from_ed_pem(pem.as_bytes()),
+                JwtAlg::Rs256 => EncodingKey::from_rsa_pem(pem.as_bytes()),
+                JwtAlg::Hs256 => unreachable!(),
+            }
+            .map_err(|e| AuthError::Internal(format!("Invalid JWT_SIGNING_KEY: {e}")))?;
+
+            let mut header = Header::new(alg.algorithm());
+            header.kid = Some(kid);
+            Ok((encoding_key, header))
+        }
+    }
+}
+
+/// Create a JWT token for a user. `jti` should be the id of the
+/// `auth_sessions` row recording this login, so it can be revoked later.
+pub fn create_token(
+    user_id: Uuid,
+    email: &str,
+    xp: u32,
+    security_stamp: &str,
+    jti: Uuid,
+    role: &str,
+) -> Result<String, AuthError> {
+    // Short-lived: a client is expected to hold onto the refresh token
+    // issued alongside this and call /api/auth/refresh well before it expires.
+    let claims = JwtClaims::new(
+        user_id,
+        email.to_string(),
+        xp,
+        security_stamp.to_string(),
+        jti,
+        role.to_string(),
+        Duration::minutes(15),
+    );
+
+    let (encoding_key, header) = encoding_key_and_header(JwtAlg::from_env()?)?;
+    encode(&header, &claims, &encoding_key)
+        .map_err(|e| AuthError::Internal(format!("Failed to create token: {}", e)))
+}
+
+/// Create a short-lived token for email verification or password reset.
+/// These aren't tracked in `auth_sessions` (they're single-use and already
+/// revocable via the `email_tokens` table), so `jti` is just a fresh random
+/// id rather than a session reference.
+pub fn create_short_token(
+    user_id: Uuid,
+    email: &str,
+    security_stamp: &str,
+    role: &str,
+    expires_in_hours: i64,
+) -> Result<String, AuthError> {
+    let claims = JwtClaims::new(
+        user_id,
+        email.to_string(),
+        0,
+        security_stamp.to_string(),
+        Uuid::new_v4(),
+        role.to_string(),
+        Duration::hours(expires_in_hours),
+    );
+
+    let (encoding_key, header) = encoding_key_and_header(JwtAlg::from_env()?)?;
+    encode(&header, &claims, &encoding_key)
+        .map_err(|e| AuthError::Internal(format!("Failed to create token: {}", e)))
+}
+
+/// Verify and decode a JWT token. In asymmetric mode the decoding key is
+/// selected by the token's `kid` header so tokens signed under a previous
+/// key keep validating until they naturally expire (see [`KeyRingEntry`]).
 pub fn verify_token(token: &str) -> Result<JwtClaims, AuthError> {
-    let secret = get_jwt_secret()?;
-    
-    let token_data: TokenData<JwtClaims> = decode(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map_err(|e| {
-        tracing::debug!("Token verification failed: {}", e);
-        AuthError::InvalidToken
-    })?;
-    
+    let alg = JwtAlg::from_env()?;
+
+    let (decoding_key, validation) = match alg {
+        JwtAlg::Hs256 => {
+            let secret = get_jwt_secret()?;
+            (
+                DecodingKey::from_secret(secret.as_bytes()),
+                Validation::default(),
+            )
+        }
+        JwtAlg::EdDsa | JwtAlg::Rs256 => {
+            let header = decode_header(token).map_err(|e| {
+                tracing::debug!("Token header decode failed: {}", e);
+                AuthError::InvalidToken
+            })?;
+            let kid = header.kid.ok_or(AuthError::InvalidToken)?;
+            let keyring = load_keyring()?;
+            let entry = keyring
+                .iter()
+                .find(|entry| entry.kid == kid)
+                .ok_or(AuthError::InvalidToken)?;
+
+            (
+                decoding_key_for(alg, &entry.public_key)?,
+                Validation::new(alg.algorithm()),
+            )
+        }
+    };
+
+    let token_data: TokenData<JwtClaims> = decode(token, &decoding_key, &validation)
+        .map_err(|e| {
+            tracing::debug!("Token verification failed: {}", e);
+            AuthError::InvalidToken
+        })?;
+
     Ok(token_data.claims)
 }
 
+/// Reject `user_id` if it has an active row in the `bans` table - a
+/// permanent ban (`expires_at` is `None`) maps to [`AuthError::UserBlacklisted`],
+/// a timed one to [`AuthError::UserSuspended`]. Shared by every place an
+/// account's standing has to be re-checked outside `ban_check_middleware`
+/// (which only covers `protected_routes`): token verification here, and the
+/// password/OAuth/WebAuthn login-finish handlers, which mint a fresh token
+/// before that middleware ever runs.
+pub async fn ensure_not_banned(pool: &crate::db::DbPool, user_id: Uuid) -> Result<(), AuthError> {
+    let ban = crate::db::get_active_ban(pool, user_id)
+        .await
+        .map_err(|e| AuthError::Database(e.to_string()))?;
+
+    match ban {
+        Some(ban) if ban.expires_at.is_none() => Err(AuthError::UserBlacklisted),
+        Some(_) => Err(AuthError::UserSuspended),
+        None => Ok(()),
+    }
+}
+
+/// Verify a token like [`verify_token`], then additionally reject it if the
+/// account it belongs to now has an active ban - a ban takes effect on the
+/// account's very next request instead of waiting for every outstanding
+/// token to expire on its own.
+pub async fn verify_token_with_status(
+    pool: &crate::db::DbPool,
+    token: &str,
+) -> Result<JwtClaims, AuthError> {
+    let claims = verify_token(token)?;
+    ensure_not_banned(pool, claims.sub).await?;
+    Ok(claims)
+}
+
 /// Extract bearer token from Authorization header
 pub fn extract_bearer_token(auth_header: &str) -> Option<&str> {
     auth_header.strip_prefix("Bearer ").or_else(|| auth_header.strip_prefix("bearer "))
@@ -111,13 +314,19 @@ mod tests {
         let user_id = Uuid::new_v4();
         let email = "test@example.com";
         let xp = 100;
-        
-        let token = create_token(user_id, email, xp).expect("should create token");
+        let security_stamp = "test-stamp";
+        let jti = Uuid::new_v4();
+
+        let token = create_token(user_id, email, xp, security_stamp, jti, "user")
+            .expect("should create token");
         let claims = verify_token(&token).expect("should verify token");
-        
+
         assert_eq!(claims.sub, user_id);
         assert_eq!(claims.email, email);
         assert_eq!(claims.xp, xp);
+        assert_eq!(claims.security_stamp, security_stamp);
+        assert_eq!(claims.jti, jti);
+        assert_eq!(claims.role, "user");
     }
 
     #[test]
@@ -133,8 +342,9 @@ mod tests {
         
         let user_id = Uuid::new_v4();
         let email = "test@example.com";
-        
-        let token = create_short_token(user_id, email, 1).expect("should create short token");
+
+        let token = create_short_token(user_id, email, "test-stamp", "user", 1)
+            .expect("should create short token");
         let claims = verify_token(&token).expect("should verify token");
         
         assert_eq!(claims.sub, user_id);
@@ -3,24 +3,32 @@
 //! Provides email/password authentication with JWT tokens.
 //! OAuth (Google, GitHub) and email services.
 
+pub mod crypto;
 pub mod handlers;
+pub mod invite;
 pub mod jwt;
 pub mod oauth;
 pub mod password;
+pub mod totp;
+pub mod webauthn;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 /// Standard auth response with token and user info
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct AuthResponse {
     pub token: String,
+    /// Long-lived, single-use credential for `POST /api/auth/refresh`;
+    /// `token` itself now only lasts 15 minutes.
+    pub refresh_token: String,
     pub user: UserResponse,
 }
 
 /// User info returned in auth responses
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct UserResponse {
     pub id: Uuid,
     pub email: String,
@@ -28,48 +36,136 @@ pub struct UserResponse {
     pub email_verified: bool,
     pub total_xp: u32,
     pub created_at: DateTime<Utc>,
+    pub role: String,
 }
 
 /// Register request payload
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct RegisterRequest {
     pub email: String,
     pub password: String,
     pub username: Option<String>,
+    /// Required when `AuthState::require_invite` is on
+    pub invite_token: Option<String>,
+    /// A multi-use beta invite code (see `auth::invite`), independent of
+    /// `require_invite` - redeemed atomically with account creation if
+    /// present.
+    pub invite_code: Option<String>,
+}
+
+/// Create an invite request (admin-authenticated)
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateInviteRequest {
+    pub email: String,
+}
+
+/// Create a multi-use beta invite code (admin-authenticated). See
+/// `auth::invite`.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateInviteCodeRequest {
+    pub email: Option<String>,
+    #[serde(default = "default_invite_code_max_uses")]
+    pub max_uses: i32,
+    pub expires_in_hours: Option<i64>,
+}
+
+fn default_invite_code_max_uses() -> i32 {
+    1
+}
+
+/// Grant a trusted contact access to the caller's own save slots. Access
+/// doesn't take effect until `wait_days` has elapsed past acceptance, giving
+/// the owner a window to revoke a grant issued in error or under duress.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct InviteTrustedContactRequest {
+    pub grantee_email: String,
+    pub access_level: String,
+    #[serde(default)]
+    pub wait_days: i32,
 }
 
 /// Login request payload
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
+    /// Required (and checked) only if the account has TOTP enabled - either
+    /// a 6-digit authenticator code or one of its backup codes.
+    pub totp_code: Option<String>,
+}
+
+/// Exchange a refresh token for a new access token (and a rotated refresh token)
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
 }
 
 /// Email verification request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct VerifyEmailRequest {
     pub token: String,
 }
 
 /// Resend verification email request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ResendVerifyRequest {
     pub email: String,
 }
 
 /// Request password reset
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct RequestResetRequest {
     pub email: String,
 }
 
 /// Reset password with token
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ResetPasswordRequest {
     pub token: String,
     pub new_password: String,
 }
 
+/// Start the two-step account deletion flow
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RequestDeleteRequest {
+    /// Required for password accounts; ignored for OAuth-only accounts
+    pub password: Option<String>,
+}
+
+/// Confirm account deletion with the token emailed by `delete/request`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConfirmDeleteRequest {
+    pub token: String,
+}
+
+/// Request to change the caller's email address
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChangeEmailRequest {
+    pub new_email: String,
+    pub password: String,
+}
+
+/// Confirm a pending email change with the token emailed to the new address
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConfirmChangeEmailRequest {
+    pub token: String,
+}
+
+/// Confirm a TOTP enrollment with a code generated from the secret returned
+/// by `totp/enroll/start`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TotpConfirmRequest {
+    pub code: String,
+}
+
+/// Disable TOTP for the caller's account - requires re-proving possession
+/// of the second factor, not just the session token, so a stolen session
+/// alone can't be used to remove it
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TotpDisableRequest {
+    pub code: String,
+}
+
 /// Auth error types
 #[derive(Debug, thiserror::Error)]
 pub enum AuthError {
@@ -100,11 +196,68 @@ pub enum AuthError {
     #[error("User not found")]
     UserNotFound,
 
+    #[error("Session not found")]
+    SessionNotFound,
+
+    #[error("An invite is required to register")]
+    InviteRequired,
+
+    #[error("Invalid, expired, or already-used invite")]
+    InvalidInvite,
+
+    #[error("You don't have permission to do that")]
+    Forbidden,
+
     #[error("Database error: {0}")]
     Database(String),
 
     #[error("Internal error: {0}")]
     Internal(String),
+
+    #[error("Two-factor authentication code required")]
+    TotpRequired,
+
+    #[error("Invalid two-factor authentication or backup code")]
+    InvalidTotpCode,
+
+    #[error("Cannot link an identity with an unverified email")]
+    UnverifiedIdentityLink,
+
+    #[error("This identity is already linked to a different account")]
+    IdentityAlreadyLinked,
+
+    #[error("Invalid access level")]
+    InvalidAccessLevel,
+
+    #[error("This OAuth account is already linked to a user")]
+    OAuthAlreadyLinked,
+
+    #[error("Invalid, expired, or exhausted invite code")]
+    InvalidInviteCode,
+}
+
+/// Maps a unique-constraint violation to the typed variant it actually
+/// means (`EmailExists`, `UsernameExists`, `OAuthAlreadyLinked`) by
+/// constraint name, so handlers can `?` straight through an insert instead
+/// of querying for existence first and racing whoever else might insert in
+/// between. Anything else - including a unique violation on a constraint
+/// this doesn't recognize - falls through to `Database`.
+impl From<sqlx::Error> for AuthError {
+    fn from(err: sqlx::Error) -> Self {
+        if let sqlx::Error::Database(ref db_err) = err {
+            if db_err.is_unique_violation() {
+                match db_err.constraint() {
+                    Some("users_email_key") => return AuthError::EmailExists,
+                    Some("users_username_key") => return AuthError::UsernameExists,
+                    Some("oauth_connections_provider_provider_user_id_key") => {
+                        return AuthError::OAuthAlreadyLinked
+                    }
+                    _ => {}
+                }
+            }
+        }
+        AuthError::Database(err.to_string())
+    }
 }
 
 impl AuthError {
@@ -113,10 +266,23 @@ impl AuthError {
         match self {
             AuthError::InvalidCredentials | AuthError::InvalidToken => StatusCode::UNAUTHORIZED,
             AuthError::EmailExists | AuthError::UsernameExists => StatusCode::CONFLICT,
-            AuthError::UserSuspended | AuthError::UserBlacklisted => StatusCode::FORBIDDEN,
-            AuthError::WeakPassword(_) | AuthError::InvalidEmail => StatusCode::BAD_REQUEST,
-            AuthError::UserNotFound => StatusCode::NOT_FOUND,
+            AuthError::UserSuspended | AuthError::UserBlacklisted | AuthError::Forbidden => {
+                StatusCode::FORBIDDEN
+            }
+            AuthError::WeakPassword(_)
+            | AuthError::InvalidEmail
+            | AuthError::InviteRequired
+            | AuthError::InvalidInvite
+            | AuthError::InvalidAccessLevel => StatusCode::BAD_REQUEST,
+            AuthError::UserNotFound | AuthError::SessionNotFound => StatusCode::NOT_FOUND,
             AuthError::Database(_) | AuthError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AuthError::TotpRequired | AuthError::InvalidTotpCode => StatusCode::UNAUTHORIZED,
+            AuthError::UnverifiedIdentityLink | AuthError::InvalidInviteCode => {
+                StatusCode::FORBIDDEN
+            }
+            AuthError::IdentityAlreadyLinked | AuthError::OAuthAlreadyLinked => {
+                StatusCode::CONFLICT
+            }
         }
     }
 }
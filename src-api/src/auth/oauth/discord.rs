@@ -0,0 +1,184 @@
+//! Discord OAuth 2.0 implementation
+
+use async_trait::async_trait;
+use oauth2::basic::BasicClient;
+use oauth2::{AuthorizationCode, PkceCodeVerifier, RefreshToken, Scope, TokenResponse};
+use serde::Deserialize;
+
+use super::{
+    build_oauth2_client, OAuthConfig, OAuthProvider, OAuthState, OAuthTransportConfig,
+    OAuthUserInfo, TokenSet,
+};
+
+const AUTH_URL: &str = "https://discord.com/api/oauth2/authorize";
+const TOKEN_URL: &str = "https://discord.com/api/oauth2/token";
+
+/// Discord OAuth client
+#[derive(Clone)]
+pub struct DiscordOAuth {
+    config: OAuthConfig,
+    client: reqwest::Client,
+    oauth2_client: BasicClient,
+}
+
+/// Discord user info response
+#[derive(Deserialize)]
+struct DiscordUserInfo {
+    id: String,
+    username: String,
+    email: Option<String>,
+    verified: Option<bool>,
+    avatar: Option<String>,
+}
+
+impl DiscordOAuth {
+    /// Create a new Discord OAuth client
+    pub fn new(config: OAuthConfig, transport: &OAuthTransportConfig) -> Self {
+        Self {
+            oauth2_client: build_oauth2_client(&config, AUTH_URL, TOKEN_URL),
+            client: transport
+                .apply(reqwest::Client::builder())
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new()),
+            config,
+        }
+    }
+
+    /// Create from environment variables
+    pub fn from_env(api_base_url: &str, transport: &OAuthTransportConfig) -> Option<Self> {
+        let config = OAuthConfig {
+            client_id: std::env::var("DISCORD_CLIENT_ID").ok()?,
+            client_secret: std::env::var("DISCORD_CLIENT_SECRET").ok()?,
+            redirect_uri: format!("{}/api/auth/oauth/discord/callback", api_base_url),
+        };
+        Some(Self::new(config, transport))
+    }
+
+    /// Generate the authorization URL for Discord OAuth
+    pub fn get_authorization_url(&self, state: &OAuthState) -> String {
+        let (auth_url, _csrf_token) = self
+            .oauth2_client
+            .authorize_url(|| oauth2::CsrfToken::new(state.encode()))
+            .add_scope(Scope::new("identify".to_string()))
+            .add_scope(Scope::new("email".to_string()))
+            .set_pkce_challenge(state.pkce_challenge())
+            .url();
+
+        auth_url.to_string()
+    }
+
+    /// Exchange authorization code for a token set
+    pub async fn exchange_code(&self, code: &str, pkce_verifier: &str) -> Result<TokenSet, String> {
+        let token = self
+            .oauth2_client
+            .exchange_code(AuthorizationCode::new(code.to_string()))
+            .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier.to_string()))
+            .request_async(oauth2::reqwest::async_http_client)
+            .await
+            .map_err(|e| format!("Token exchange failed: {}", e))?;
+
+        Ok(TokenSet::new(
+            token.access_token().secret().clone(),
+            token.refresh_token().map(|t| t.secret().clone()),
+            Some("Bearer".to_string()),
+            token.expires_in().map(|d| d.as_secs()),
+            token
+                .scopes()
+                .map(|s| s.iter().map(|sc| sc.to_string()).collect::<Vec<_>>().join(" ")),
+        ))
+    }
+
+    /// Exchange a refresh token for a new token set. Discord always rotates
+    /// the refresh token, so the caller must persist the new one.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<TokenSet, String> {
+        let token = self
+            .oauth2_client
+            .exchange_refresh_token(&RefreshToken::new(refresh_token.to_string()))
+            .request_async(oauth2::reqwest::async_http_client)
+            .await
+            .map_err(|e| format!("Token refresh failed: {}", e))?;
+
+        Ok(TokenSet::new(
+            token.access_token().secret().clone(),
+            token.refresh_token().map(|t| t.secret().clone()),
+            Some("Bearer".to_string()),
+            token.expires_in().map(|d| d.as_secs()),
+            token
+                .scopes()
+                .map(|s| s.iter().map(|sc| sc.to_string()).collect::<Vec<_>>().join(" ")),
+        ))
+    }
+
+    /// Get user info using access token
+    pub async fn get_user_info(&self, access_token: &str) -> Result<OAuthUserInfo, String> {
+        let response = self
+            .client
+            .get("https://discord.com/api/users/@me")
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to get user info: {}", e))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            tracing::error!("Discord user info failed: {}", error_text);
+            return Err(format!("Failed to get user info: {}", error_text));
+        }
+
+        let user_info: DiscordUserInfo = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse user info: {}", e))?;
+
+        let avatar_url = user_info.avatar.map(|hash| {
+            format!("https://cdn.discordapp.com/avatars/{}/{}.png", user_info.id, hash)
+        });
+
+        Ok(OAuthUserInfo {
+            provider_user_id: user_info.id,
+            email: user_info
+                .email
+                .ok_or_else(|| "Discord account has no email".to_string())?,
+            name: Some(user_info.username),
+            avatar_url,
+            email_verified: user_info.verified.unwrap_or(false),
+        })
+    }
+
+    /// Complete OAuth flow: exchange code and get user info
+    pub async fn authenticate(
+        &self,
+        code: &str,
+        pkce_verifier: &str,
+    ) -> Result<OAuthUserInfo, String> {
+        let token_set = self.exchange_code(code, pkce_verifier).await?;
+        self.get_user_info(&token_set.access_token).await
+    }
+}
+
+#[async_trait]
+impl OAuthProvider for DiscordOAuth {
+    fn provider_name(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn get_authorization_url(&self, state: &OAuthState) -> String {
+        self.get_authorization_url(state)
+    }
+
+    async fn exchange_code(&self, code: &str, pkce_verifier: &str) -> Result<TokenSet, String> {
+        DiscordOAuth::exchange_code(self, code, pkce_verifier).await
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<TokenSet, String> {
+        DiscordOAuth::refresh(self, refresh_token).await
+    }
+
+    async fn get_user_info(&self, access_token: &str) -> Result<OAuthUserInfo, String> {
+        DiscordOAuth::get_user_info(self, access_token).await
+    }
+
+    async fn authenticate(&self, code: &str, pkce_verifier: &str) -> Result<OAuthUserInfo, String> {
+        DiscordOAuth::authenticate(self, code, pkce_verifier).await
+    }
+}
@@ -1,23 +1,69 @@
 //! GitHub OAuth 2.0 implementation
 
-use super::{OAuthConfig, OAuthState, OAuthUserInfo};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use oauth2::basic::BasicClient;
+use oauth2::{AuthorizationCode, PkceCodeVerifier, RefreshToken, Scope, TokenResponse};
+use reqwest::StatusCode;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::{
+    build_oauth2_client, OAuthConfig, OAuthProvider, OAuthState, OAuthTransportConfig,
+    OAuthUserInfo, TokenSet,
+};
+
+const AUTH_URL: &str = "https://github.com/login/oauth/authorize";
+const TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+
+/// How long a cached `/user` response is trusted before we revalidate with
+/// GitHub even when we have an ETag (bounds staleness if a player's profile
+/// changes but GitHub's ETag logic somehow misses it).
+const USER_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Cap on how long we'll sleep for a rate-limit reset before giving up and
+/// surfacing an error instead (GitHub's primary limit window is an hour).
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Cached user info plus the ETag GitHub returned for it, so the next
+/// request can send `If-None-Match` and get a `304` instead of a full body.
+#[derive(Clone)]
+struct CachedUserInfo {
+    info: OAuthUserInfo,
+    etag: Option<String>,
+    cached_at: Instant,
+}
+
+/// Typed errors for the resilience layer around GitHub's REST API (rate
+/// limiting, retries). Public callers still see a flattened `String` through
+/// [`OAuthProvider`], but internal retry logic matches on the variant.
+#[derive(Debug)]
+enum GitHubApiError {
+    RateLimited { retry_after: Duration },
+    Http(String),
+}
+
+impl std::fmt::Display for GitHubApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitHubApiError::RateLimited { retry_after } => {
+                write!(f, "GitHub rate limit exceeded, retry after {:?}", retry_after)
+            }
+            GitHubApiError::Http(msg) => write!(f, "{}", msg),
+        }
+    }
+}
 
 /// GitHub OAuth client
 #[derive(Clone)]
 pub struct GitHubOAuth {
     config: OAuthConfig,
     client: reqwest::Client,
-}
-
-/// GitHub OAuth token response
-#[derive(Deserialize)]
-struct GitHubTokenResponse {
-    access_token: String,
-    #[allow(dead_code)]
-    token_type: String,
-    #[allow(dead_code)]
-    scope: String,
+    oauth2_client: BasicClient,
+    user_cache: Arc<DashMap<String, CachedUserInfo>>,
 }
 
 /// GitHub user info response
@@ -40,86 +86,106 @@ struct GitHubEmail {
 
 impl GitHubOAuth {
     /// Create a new GitHub OAuth client
-    pub fn new(config: OAuthConfig) -> Self {
+    pub fn new(config: OAuthConfig, transport: &OAuthTransportConfig) -> Self {
         Self {
-            config,
-            client: reqwest::Client::builder()
-                .user_agent("Code-Warrior-App")
+            oauth2_client: build_oauth2_client(&config, AUTH_URL, TOKEN_URL),
+            client: transport
+                .apply(reqwest::Client::builder().user_agent("Code-Warrior-App"))
                 .build()
                 .unwrap_or_else(|_| reqwest::Client::new()),
+            config,
+            user_cache: Arc::new(DashMap::new()),
         }
     }
 
     /// Create from environment variables
-    pub fn from_env(api_base_url: &str) -> Option<Self> {
+    pub fn from_env(api_base_url: &str, transport: &OAuthTransportConfig) -> Option<Self> {
         let config = OAuthConfig {
             client_id: std::env::var("GITHUB_CLIENT_ID").ok()?,
             client_secret: std::env::var("GITHUB_CLIENT_SECRET").ok()?,
             redirect_uri: format!("{}/api/auth/oauth/github/callback", api_base_url),
         };
-        Some(Self::new(config))
+        Some(Self::new(config, transport))
     }
 
     /// Generate the authorization URL for GitHub OAuth
     pub fn get_authorization_url(&self, state: &OAuthState) -> String {
-        let scopes = "user:email read:user";
-        let encoded_state = state.encode();
-
-        format!(
-            "https://github.com/login/oauth/authorize?\
-            client_id={}&\
-            redirect_uri={}&\
-            scope={}&\
-            state={}",
-            urlencoding::encode(&self.config.client_id),
-            urlencoding::encode(&self.config.redirect_uri),
-            urlencoding::encode(scopes),
-            urlencoding::encode(&encoded_state)
-        )
-    }
-
-    /// Exchange authorization code for access token
-    pub async fn exchange_code(&self, code: &str) -> Result<String, String> {
-        let params = [
-            ("client_id", self.config.client_id.as_str()),
-            ("client_secret", self.config.client_secret.as_str()),
-            ("code", code),
-            ("redirect_uri", self.config.redirect_uri.as_str()),
-        ];
+        let (auth_url, _csrf_token) = self
+            .oauth2_client
+            .authorize_url(|| oauth2::CsrfToken::new(state.encode()))
+            .add_scope(Scope::new("user:email".to_string()))
+            .add_scope(Scope::new("read:user".to_string()))
+            .set_pkce_challenge(state.pkce_challenge())
+            .url();
 
-        let response = self
-            .client
-            .post("https://github.com/login/oauth/access_token")
-            .header("Accept", "application/json")
-            .form(&params)
-            .send()
+        auth_url.to_string()
+    }
+
+    /// Exchange authorization code for a token set
+    pub async fn exchange_code(&self, code: &str, pkce_verifier: &str) -> Result<TokenSet, String> {
+        let token = self
+            .oauth2_client
+            .exchange_code(AuthorizationCode::new(code.to_string()))
+            .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier.to_string()))
+            .request_async(oauth2::reqwest::async_http_client)
             .await
-            .map_err(|e| format!("Failed to exchange code: {}", e))?;
+            .map_err(|e| format!("Token exchange failed: {}", e))?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            tracing::error!("GitHub token exchange failed: {}", error_text);
-            return Err(format!("Token exchange failed: {}", error_text));
-        }
+        Ok(TokenSet::new(
+            token.access_token().secret().clone(),
+            token.refresh_token().map(|t| t.secret().clone()),
+            Some("Bearer".to_string()),
+            token.expires_in().map(|d| d.as_secs()),
+            token
+                .scopes()
+                .map(|s| s.iter().map(|sc| sc.to_string()).collect::<Vec<_>>().join(" ")),
+        ))
+    }
 
-        let token_response: GitHubTokenResponse = response
-            .json()
+    /// Exchange a refresh token for a new token set (GitHub App user tokens only)
+    pub async fn refresh(&self, refresh_token: &str) -> Result<TokenSet, String> {
+        let token = self
+            .oauth2_client
+            .exchange_refresh_token(&RefreshToken::new(refresh_token.to_string()))
+            .request_async(oauth2::reqwest::async_http_client)
             .await
-            .map_err(|e| format!("Failed to parse token response: {}", e))?;
+            .map_err(|e| format!("Token refresh failed: {}", e))?;
 
-        Ok(token_response.access_token)
+        Ok(TokenSet::new(
+            token.access_token().secret().clone(),
+            token.refresh_token().map(|t| t.secret().clone()),
+            Some("Bearer".to_string()),
+            token.expires_in().map(|d| d.as_secs()),
+            token
+                .scopes()
+                .map(|s| s.iter().map(|sc| sc.to_string()).collect::<Vec<_>>().join(" ")),
+        ))
     }
 
-    /// Get user info using access token
+    /// Get user info using access token. Revalidates against a cached copy
+    /// with `If-None-Match` so a player re-authenticating within
+    /// [`USER_CACHE_TTL`] doesn't consume GitHub's API rate limit.
     pub async fn get_user_info(&self, access_token: &str) -> Result<OAuthUserInfo, String> {
-        // Get basic user info
+        let cache_key = Self::cache_key(access_token);
+        let cached = self.user_cache.get(&cache_key).map(|entry| entry.clone());
+        let fresh_cached = cached
+            .as_ref()
+            .filter(|c| c.cached_at.elapsed() < USER_CACHE_TTL);
+        let etag = fresh_cached.as_ref().and_then(|c| c.etag.clone());
+
         let user_response = self
-            .client
-            .get("https://api.github.com/user")
-            .bearer_auth(access_token)
-            .send()
+            .get_with_retry("https://api.github.com/user", access_token, etag.as_deref())
             .await
-            .map_err(|e| format!("Failed to get user info: {}", e))?;
+            .map_err(|e| e.to_string())?;
+
+        if user_response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(cached) = fresh_cached {
+                return Ok(cached.info);
+            }
+            // We had no usable cache entry to revalidate against; fall through
+            // and treat this as an error rather than fabricating a response.
+            return Err("GitHub returned 304 Not Modified with no cached entry".to_string());
+        }
 
         if !user_response.status().is_success() {
             let error_text = user_response.text().await.unwrap_or_default();
@@ -127,6 +193,12 @@ impl GitHubOAuth {
             return Err(format!("Failed to get user info: {}", error_text));
         }
 
+        let new_etag = user_response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+
         let user_info: GitHubUserInfo = user_response
             .json()
             .await
@@ -139,24 +211,32 @@ impl GitHubOAuth {
             self.get_primary_email(access_token).await?
         };
 
-        Ok(OAuthUserInfo {
+        let info = OAuthUserInfo {
             provider_user_id: user_info.id.to_string(),
             email,
             name: user_info.name.or(Some(user_info.login)),
             avatar_url: user_info.avatar_url,
             email_verified,
-        })
+        };
+
+        self.user_cache.insert(
+            cache_key,
+            CachedUserInfo {
+                info: info.clone(),
+                etag: new_etag,
+                cached_at: Instant::now(),
+            },
+        );
+
+        Ok(info)
     }
 
     /// Get primary verified email from GitHub
     async fn get_primary_email(&self, access_token: &str) -> Result<(String, bool), String> {
         let response = self
-            .client
-            .get("https://api.github.com/user/emails")
-            .bearer_auth(access_token)
-            .send()
+            .get_with_retry("https://api.github.com/user/emails", access_token, None)
             .await
-            .map_err(|e| format!("Failed to get emails: {}", e))?;
+            .map_err(|e| e.to_string())?;
 
         if !response.status().is_success() {
             return Err("Failed to get user emails".to_string());
@@ -178,10 +258,125 @@ impl GitHubOAuth {
         Ok((primary_email.email.clone(), primary_email.verified))
     }
 
+    /// `SHA-256(access_token)` as the cache key, so the cache never holds raw
+    /// tokens at rest.
+    fn cache_key(access_token: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(access_token.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    /// GET `url` with the access token and an optional `If-None-Match`,
+    /// transparently retrying once if GitHub reports the rate limit is
+    /// exhausted.
+    async fn get_with_retry(
+        &self,
+        url: &str,
+        access_token: &str,
+        etag: Option<&str>,
+    ) -> Result<reqwest::Response, GitHubApiError> {
+        let send = || {
+            let mut req = self.client.get(url).bearer_auth(access_token);
+            if let Some(etag) = etag {
+                req = req.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            req.send()
+        };
+
+        let response = send()
+            .await
+            .map_err(|e| GitHubApiError::Http(format!("Request to {} failed: {}", url, e)))?;
+
+        let Some(retry_after) = rate_limit_wait(&response) else {
+            return Ok(response);
+        };
+
+        let wait = retry_after.min(MAX_RATE_LIMIT_WAIT);
+        tracing::warn!("GitHub rate limited on {}, retrying in {:?}", url, wait);
+        tokio::time::sleep(wait).await;
+
+        let retried = send()
+            .await
+            .map_err(|e| GitHubApiError::Http(format!("Request to {} failed: {}", url, e)))?;
+
+        if rate_limit_wait(&retried).is_some() {
+            return Err(GitHubApiError::RateLimited { retry_after: wait });
+        }
+
+        Ok(retried)
+    }
+
     /// Complete OAuth flow: exchange code and get user info
-    pub async fn authenticate(&self, code: &str) -> Result<OAuthUserInfo, String> {
-        let access_token = self.exchange_code(code).await?;
-        self.get_user_info(&access_token).await
+    pub async fn authenticate(
+        &self,
+        code: &str,
+        pkce_verifier: &str,
+    ) -> Result<OAuthUserInfo, String> {
+        let token_set = self.exchange_code(code, pkce_verifier).await?;
+        self.get_user_info(&token_set.access_token).await
+    }
+}
+
+/// If `response` indicates GitHub's rate limit is exhausted (a `403`/`429`
+/// with `X-RateLimit-Remaining: 0`, or an explicit `Retry-After`), return how
+/// long to wait before retrying.
+fn rate_limit_wait(response: &reqwest::Response) -> Option<Duration> {
+    let status = response.status();
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+
+    let headers = response.headers();
+
+    if let Some(retry_after) = headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+    {
+        return Some(Duration::from_secs(retry_after));
+    }
+
+    let remaining = headers
+        .get("x-ratelimit-remaining")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok());
+    if remaining != Some(0) {
+        return None;
+    }
+
+    let reset_at = headers
+        .get("x-ratelimit-reset")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok())?;
+    let now = chrono::Utc::now().timestamp();
+    let wait_secs = (reset_at - now).max(0) as u64;
+    Some(Duration::from_secs(wait_secs))
+}
+
+#[async_trait]
+impl OAuthProvider for GitHubOAuth {
+    fn provider_name(&self) -> &'static str {
+        "github"
+    }
+
+    async fn get_authorization_url(&self, state: &OAuthState) -> String {
+        self.get_authorization_url(state)
+    }
+
+    async fn exchange_code(&self, code: &str, pkce_verifier: &str) -> Result<TokenSet, String> {
+        GitHubOAuth::exchange_code(self, code, pkce_verifier).await
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<TokenSet, String> {
+        GitHubOAuth::refresh(self, refresh_token).await
+    }
+
+    async fn get_user_info(&self, access_token: &str) -> Result<OAuthUserInfo, String> {
+        GitHubOAuth::get_user_info(self, access_token).await
+    }
+
+    async fn authenticate(&self, code: &str, pkce_verifier: &str) -> Result<OAuthUserInfo, String> {
+        GitHubOAuth::authenticate(self, code, pkce_verifier).await
     }
 }
 
@@ -0,0 +1,177 @@
+//! GitLab OAuth 2.0 implementation
+
+use async_trait::async_trait;
+use oauth2::basic::BasicClient;
+use oauth2::{AuthorizationCode, PkceCodeVerifier, RefreshToken, Scope, TokenResponse};
+use serde::Deserialize;
+
+use super::{
+    build_oauth2_client, OAuthConfig, OAuthProvider, OAuthState, OAuthTransportConfig,
+    OAuthUserInfo, TokenSet,
+};
+
+const AUTH_URL: &str = "https://gitlab.com/oauth/authorize";
+const TOKEN_URL: &str = "https://gitlab.com/oauth/token";
+
+/// GitLab OAuth client
+#[derive(Clone)]
+pub struct GitLabOAuth {
+    config: OAuthConfig,
+    client: reqwest::Client,
+    oauth2_client: BasicClient,
+}
+
+/// GitLab user info response
+#[derive(Deserialize)]
+struct GitLabUserInfo {
+    id: i64,
+    username: String,
+    email: Option<String>,
+    name: Option<String>,
+    avatar_url: Option<String>,
+}
+
+impl GitLabOAuth {
+    /// Create a new GitLab OAuth client
+    pub fn new(config: OAuthConfig, transport: &OAuthTransportConfig) -> Self {
+        Self {
+            oauth2_client: build_oauth2_client(&config, AUTH_URL, TOKEN_URL),
+            client: transport
+                .apply(reqwest::Client::builder())
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new()),
+            config,
+        }
+    }
+
+    /// Create from environment variables
+    pub fn from_env(api_base_url: &str, transport: &OAuthTransportConfig) -> Option<Self> {
+        let config = OAuthConfig {
+            client_id: std::env::var("GITLAB_CLIENT_ID").ok()?,
+            client_secret: std::env::var("GITLAB_CLIENT_SECRET").ok()?,
+            redirect_uri: format!("{}/api/auth/oauth/gitlab/callback", api_base_url),
+        };
+        Some(Self::new(config, transport))
+    }
+
+    /// Generate the authorization URL for GitLab OAuth
+    pub fn get_authorization_url(&self, state: &OAuthState) -> String {
+        let (auth_url, _csrf_token) = self
+            .oauth2_client
+            .authorize_url(|| oauth2::CsrfToken::new(state.encode()))
+            .add_scope(Scope::new("read_user".to_string()))
+            .set_pkce_challenge(state.pkce_challenge())
+            .url();
+
+        auth_url.to_string()
+    }
+
+    /// Exchange authorization code for a token set
+    pub async fn exchange_code(&self, code: &str, pkce_verifier: &str) -> Result<TokenSet, String> {
+        let token = self
+            .oauth2_client
+            .exchange_code(AuthorizationCode::new(code.to_string()))
+            .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier.to_string()))
+            .request_async(oauth2::reqwest::async_http_client)
+            .await
+            .map_err(|e| format!("Token exchange failed: {}", e))?;
+
+        Ok(TokenSet::new(
+            token.access_token().secret().clone(),
+            token.refresh_token().map(|t| t.secret().clone()),
+            Some("Bearer".to_string()),
+            token.expires_in().map(|d| d.as_secs()),
+            token
+                .scopes()
+                .map(|s| s.iter().map(|sc| sc.to_string()).collect::<Vec<_>>().join(" ")),
+        ))
+    }
+
+    /// Exchange a refresh token for a new token set
+    pub async fn refresh(&self, refresh_token: &str) -> Result<TokenSet, String> {
+        let token = self
+            .oauth2_client
+            .exchange_refresh_token(&RefreshToken::new(refresh_token.to_string()))
+            .request_async(oauth2::reqwest::async_http_client)
+            .await
+            .map_err(|e| format!("Token refresh failed: {}", e))?;
+
+        Ok(TokenSet::new(
+            token.access_token().secret().clone(),
+            token.refresh_token().map(|t| t.secret().clone()),
+            Some("Bearer".to_string()),
+            token.expires_in().map(|d| d.as_secs()),
+            token
+                .scopes()
+                .map(|s| s.iter().map(|sc| sc.to_string()).collect::<Vec<_>>().join(" ")),
+        ))
+    }
+
+    /// Get user info using access token
+    pub async fn get_user_info(&self, access_token: &str) -> Result<OAuthUserInfo, String> {
+        let response = self
+            .client
+            .get("https://gitlab.com/api/v4/user")
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to get user info: {}", e))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            tracing::error!("GitLab user info failed: {}", error_text);
+            return Err(format!("Failed to get user info: {}", error_text));
+        }
+
+        let user_info: GitLabUserInfo = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse user info: {}", e))?;
+
+        Ok(OAuthUserInfo {
+            provider_user_id: user_info.id.to_string(),
+            email: user_info.email.ok_or_else(|| "GitLab account has no email".to_string())?,
+            name: user_info.name.or(Some(user_info.username)),
+            avatar_url: user_info.avatar_url,
+            // GitLab only returns the user's primary email once it's already confirmed.
+            email_verified: true,
+        })
+    }
+
+    /// Complete OAuth flow: exchange code and get user info
+    pub async fn authenticate(
+        &self,
+        code: &str,
+        pkce_verifier: &str,
+    ) -> Result<OAuthUserInfo, String> {
+        let token_set = self.exchange_code(code, pkce_verifier).await?;
+        self.get_user_info(&token_set.access_token).await
+    }
+}
+
+#[async_trait]
+impl OAuthProvider for GitLabOAuth {
+    fn provider_name(&self) -> &'static str {
+        "gitlab"
+    }
+
+    async fn get_authorization_url(&self, state: &OAuthState) -> String {
+        self.get_authorization_url(state)
+    }
+
+    async fn exchange_code(&self, code: &str, pkce_verifier: &str) -> Result<TokenSet, String> {
+        GitLabOAuth::exchange_code(self, code, pkce_verifier).await
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<TokenSet, String> {
+        GitLabOAuth::refresh(self, refresh_token).await
+    }
+
+    async fn get_user_info(&self, access_token: &str) -> Result<OAuthUserInfo, String> {
+        GitLabOAuth::get_user_info(self, access_token).await
+    }
+
+    async fn authenticate(&self, code: &str, pkce_verifier: &str) -> Result<OAuthUserInfo, String> {
+        GitLabOAuth::authenticate(self, code, pkce_verifier).await
+    }
+}
@@ -1,19 +1,57 @@
-//! Google OAuth 2.0 implementation
+//! Google OAuth 2.0 / OpenID Connect implementation
 
-use super::{OAuthConfig, OAuthState, OAuthUserInfo};
-use serde::Deserialize;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use oauth2::basic::{
+    BasicErrorResponse, BasicRevocationErrorResponse, BasicTokenIntrospectionResponse,
+    BasicTokenType,
+};
+use oauth2::{
+    AuthorizationCode, Client, ExtraTokenFields, PkceCodeVerifier, RefreshToken, Scope,
+    StandardRevocableToken, StandardTokenResponse, TokenResponse,
+};
+use serde::{Deserialize, Serialize};
+
+use super::oidc::JwksCache;
+use super::{OAuthConfig, OAuthProvider, OAuthState, OAuthTransportConfig, OAuthUserInfo, TokenSet};
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+
+/// Google's OIDC issuer and JWKS endpoint, from its discovery document
+/// (`https://accounts.google.com/.well-known/openid-configuration`), inlined
+/// here since they're static.
+const ISSUER: &str = "https://accounts.google.com";
+const JWKS_URI: &str = "https://www.googleapis.com/oauth2/v3/certs";
+
+/// `id_token` is the one extra field Google's token endpoint returns beyond
+/// the standard OAuth2 token response; `oauth2`'s `BasicClient` doesn't carry
+/// it, so Google gets its own client type with this as its extra fields.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct GoogleExtraFields {
+    id_token: Option<String>,
+}
+
+impl ExtraTokenFields for GoogleExtraFields {}
+
+type GoogleTokenResponse = StandardTokenResponse<GoogleExtraFields, BasicTokenType>;
+type GoogleOAuth2Client = Client<
+    BasicErrorResponse,
+    GoogleTokenResponse,
+    BasicTokenType,
+    BasicTokenIntrospectionResponse,
+    StandardRevocableToken,
+    BasicRevocationErrorResponse,
+>;
 
 /// Google OAuth client
 #[derive(Clone)]
 pub struct GoogleOAuth {
     config: OAuthConfig,
     client: reqwest::Client,
-}
-
-/// Google OAuth token response (only access_token is used, serde ignores other fields)
-#[derive(Deserialize)]
-struct GoogleTokenResponse {
-    access_token: String,
+    oauth2_client: GoogleOAuth2Client,
+    jwks_cache: Arc<JwksCache>,
 }
 
 /// Google user info response
@@ -28,74 +66,127 @@ struct GoogleUserInfo {
 
 impl GoogleOAuth {
     /// Create a new Google OAuth client
-    pub fn new(config: OAuthConfig) -> Self {
+    pub fn new(config: OAuthConfig, transport: &OAuthTransportConfig) -> Self {
+        // Google's id_token doesn't fit `oauth2::basic::BasicClient`'s
+        // `EmptyExtraTokenFields`, so build the client by hand instead of
+        // going through the shared `build_oauth2_client` helper.
+        let oauth2_client = GoogleOAuth2Client::new(
+            oauth2::ClientId::new(config.client_id.clone()),
+            Some(oauth2::ClientSecret::new(config.client_secret.clone())),
+            oauth2::AuthUrl::new(AUTH_URL.to_string()).expect("AUTH_URL is a valid constant URL"),
+            Some(
+                oauth2::TokenUrl::new(TOKEN_URL.to_string())
+                    .expect("TOKEN_URL is a valid constant URL"),
+            ),
+        )
+        .set_redirect_uri(
+            oauth2::RedirectUrl::new(config.redirect_uri.clone())
+                .expect("redirect_uri is a valid URL"),
+        );
+
         Self {
+            oauth2_client,
+            client: transport
+                .apply(reqwest::Client::builder())
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new()),
             config,
-            client: reqwest::Client::new(),
+            jwks_cache: Arc::new(JwksCache::new()),
         }
     }
 
     /// Create from environment variables
-    pub fn from_env(api_base_url: &str) -> Option<Self> {
+    pub fn from_env(api_base_url: &str, transport: &OAuthTransportConfig) -> Option<Self> {
         let config = OAuthConfig {
             client_id: std::env::var("GOOGLE_CLIENT_ID").ok()?,
             client_secret: std::env::var("GOOGLE_CLIENT_SECRET").ok()?,
             redirect_uri: format!("{}/api/auth/oauth/google/callback", api_base_url),
         };
-        Some(Self::new(config))
+        Some(Self::new(config, transport))
     }
 
     /// Generate the authorization URL for Google OAuth
     pub fn get_authorization_url(&self, state: &OAuthState) -> String {
-        let scopes = "openid email profile";
-        let encoded_state = state.encode();
-
-        format!(
-            "https://accounts.google.com/o/oauth2/v2/auth?\
-            client_id={}&\
-            redirect_uri={}&\
-            response_type=code&\
-            scope={}&\
-            state={}&\
-            access_type=offline&\
-            prompt=consent",
-            urlencoding::encode(&self.config.client_id),
-            urlencoding::encode(&self.config.redirect_uri),
-            urlencoding::encode(scopes),
-            urlencoding::encode(&encoded_state)
-        )
-    }
+        let (auth_url, _csrf_token) = self
+            .oauth2_client
+            .authorize_url(|| oauth2::CsrfToken::new(state.encode()))
+            .add_scope(Scope::new("openid".to_string()))
+            .add_scope(Scope::new("email".to_string()))
+            .add_scope(Scope::new("profile".to_string()))
+            .add_extra_param("access_type", "offline")
+            .add_extra_param("prompt", "consent")
+            .add_extra_param("nonce", &state.nonce)
+            .set_pkce_challenge(state.pkce_challenge())
+            .url();
 
-    /// Exchange authorization code for access token
-    pub async fn exchange_code(&self, code: &str) -> Result<String, String> {
-        let params = [
-            ("client_id", self.config.client_id.as_str()),
-            ("client_secret", self.config.client_secret.as_str()),
-            ("code", code),
-            ("grant_type", "authorization_code"),
-            ("redirect_uri", self.config.redirect_uri.as_str()),
-        ];
+        auth_url.to_string()
+    }
 
-        let response = self
-            .client
-            .post("https://oauth2.googleapis.com/token")
-            .form(&params)
-            .send()
+    /// Exchange authorization code for a token set
+    pub async fn exchange_code(&self, code: &str, pkce_verifier: &str) -> Result<TokenSet, String> {
+        let token = self
+            .oauth2_client
+            .exchange_code(AuthorizationCode::new(code.to_string()))
+            .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier.to_string()))
+            .request_async(oauth2::reqwest::async_http_client)
             .await
-            .map_err(|e| format!("Failed to exchange code: {}", e))?;
+            .map_err(|e| format!("Token exchange failed: {}", e))?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            tracing::error!("Google token exchange failed: {}", error_text);
-            return Err(format!("Token exchange failed: {}", error_text));
-        }
+        Ok(TokenSet::with_id_token(
+            token.access_token().secret().clone(),
+            token.refresh_token().map(|t| t.secret().clone()),
+            Some("Bearer".to_string()),
+            token.expires_in().map(|d| d.as_secs()),
+            token
+                .scopes()
+                .map(|s| s.iter().map(|sc| sc.to_string()).collect::<Vec<_>>().join(" ")),
+            token.extra_fields().id_token.clone(),
+        ))
+    }
 
-        let token_response: GoogleTokenResponse = response
-            .json()
+    /// Exchange a refresh token for a new token set
+    pub async fn refresh(&self, refresh_token: &str) -> Result<TokenSet, String> {
+        let token = self
+            .oauth2_client
+            .exchange_refresh_token(&RefreshToken::new(refresh_token.to_string()))
+            .request_async(oauth2::reqwest::async_http_client)
             .await
-            .map_err(|e| format!("Failed to parse token response: {}", e))?;
+            .map_err(|e| format!("Token refresh failed: {}", e))?;
 
-        Ok(token_response.access_token)
+        // Google omits refresh_token on refresh responses; keep the one we
+        // already have since it doesn't rotate.
+        Ok(TokenSet::with_id_token(
+            token.access_token().secret().clone(),
+            token
+                .refresh_token()
+                .map(|t| t.secret().clone())
+                .or_else(|| Some(refresh_token.to_string())),
+            Some("Bearer".to_string()),
+            token.expires_in().map(|d| d.as_secs()),
+            token
+                .scopes()
+                .map(|s| s.iter().map(|sc| sc.to_string()).collect::<Vec<_>>().join(" ")),
+            token.extra_fields().id_token.clone(),
+        ))
+    }
+
+    /// Verify an id_token returned alongside an access token and derive
+    /// identity claims straight from it, per OpenID Connect.
+    pub async fn verify_id_token(
+        &self,
+        id_token: &str,
+        expected_nonce: &str,
+    ) -> Result<OAuthUserInfo, String> {
+        self.jwks_cache
+            .verify_id_token(
+                &self.client,
+                JWKS_URI,
+                ISSUER,
+                &self.config.client_id,
+                expected_nonce,
+                id_token,
+            )
+            .await
     }
 
     /// Get user info using access token
@@ -129,8 +220,47 @@ impl GoogleOAuth {
     }
 
     /// Complete OAuth flow: exchange code and get user info
-    pub async fn authenticate(&self, code: &str) -> Result<OAuthUserInfo, String> {
-        let access_token = self.exchange_code(code).await?;
-        self.get_user_info(&access_token).await
+    pub async fn authenticate(
+        &self,
+        code: &str,
+        pkce_verifier: &str,
+    ) -> Result<OAuthUserInfo, String> {
+        let token_set = self.exchange_code(code, pkce_verifier).await?;
+        self.get_user_info(&token_set.access_token).await
+    }
+}
+
+#[async_trait]
+impl OAuthProvider for GoogleOAuth {
+    fn provider_name(&self) -> &'static str {
+        "google"
+    }
+
+    async fn get_authorization_url(&self, state: &OAuthState) -> String {
+        self.get_authorization_url(state)
+    }
+
+    async fn exchange_code(&self, code: &str, pkce_verifier: &str) -> Result<TokenSet, String> {
+        GoogleOAuth::exchange_code(self, code, pkce_verifier).await
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<TokenSet, String> {
+        GoogleOAuth::refresh(self, refresh_token).await
+    }
+
+    async fn get_user_info(&self, access_token: &str) -> Result<OAuthUserInfo, String> {
+        GoogleOAuth::get_user_info(self, access_token).await
+    }
+
+    async fn authenticate(&self, code: &str, pkce_verifier: &str) -> Result<OAuthUserInfo, String> {
+        GoogleOAuth::authenticate(self, code, pkce_verifier).await
+    }
+
+    async fn verify_id_token(
+        &self,
+        id_token: &str,
+        expected_nonce: &str,
+    ) -> Result<OAuthUserInfo, String> {
+        GoogleOAuth::verify_id_token(self, id_token, expected_nonce).await
     }
 }
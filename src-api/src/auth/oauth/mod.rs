@@ -1,13 +1,33 @@
-//! OAuth authentication providers (Google, GitHub)
+//! OAuth authentication providers (Google, GitHub, GitLab, Discord)
 
+pub mod discord;
 pub mod github;
+pub mod gitlab;
 pub mod google;
+pub mod oidc;
 
+pub use discord::DiscordOAuth;
 pub use github::GitHubOAuth;
+pub use gitlab::GitLabOAuth;
 pub use google::GoogleOAuth;
 
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use oauth2::basic::BasicClient;
+use oauth2::{AuthUrl, ClientId, ClientSecret, PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, TokenUrl};
 use serde::{Deserialize, Serialize};
 
+/// Default request timeout applied to every OAuth provider client unless
+/// overridden by [`OAuthTransportConfig`].
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Skew window applied when deciding whether a token needs refreshing, so we
+/// don't start an API call with a token that expires mid-flight.
+const EXPIRY_SKEW: Duration = Duration::from_secs(60);
+
 /// OAuth user info returned by providers
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OAuthUserInfo {
@@ -23,6 +43,296 @@ pub struct OAuthUserInfo {
     pub email_verified: bool,
 }
 
+/// Token set returned by an OAuth provider after exchanging an authorization
+/// code (or refreshing a previous token set).
+///
+/// `expires_at` is a monotonic [`Instant`] rather than a wall-clock time since
+/// it's only ever compared within the same process that computed it from
+/// `expires_in`.
+#[derive(Debug, Clone)]
+pub struct TokenSet {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub token_type: String,
+    pub expires_at: Instant,
+    pub scope: Option<String>,
+    /// The OIDC id_token, if the provider's token endpoint returned one.
+    /// Only present for OIDC-capable providers (currently Google); other
+    /// providers leave this `None` and are verified via `get_user_info`
+    /// instead.
+    pub id_token: Option<String>,
+}
+
+impl TokenSet {
+    /// Build a token set from the fields a provider's token endpoint returns.
+    /// `expires_in` is the provider's "seconds from now" lifetime; providers
+    /// that don't return one (e.g. GitHub's legacy OAuth app tokens) are
+    /// treated as effectively non-expiring.
+    pub fn new(
+        access_token: String,
+        refresh_token: Option<String>,
+        token_type: Option<String>,
+        expires_in: Option<u64>,
+        scope: Option<String>,
+    ) -> Self {
+        Self::with_id_token(access_token, refresh_token, token_type, expires_in, scope, None)
+    }
+
+    /// Same as [`TokenSet::new`] but also carries the OIDC id_token returned
+    /// alongside the access token.
+    pub fn with_id_token(
+        access_token: String,
+        refresh_token: Option<String>,
+        token_type: Option<String>,
+        expires_in: Option<u64>,
+        scope: Option<String>,
+        id_token: Option<String>,
+    ) -> Self {
+        let ttl = expires_in
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(60 * 60 * 24 * 365));
+        Self {
+            access_token,
+            refresh_token,
+            token_type: token_type.unwrap_or_else(|| "bearer".to_string()),
+            expires_at: Instant::now() + ttl,
+            scope,
+            id_token,
+        }
+    }
+
+    /// A bare token set with no refresh token and a long assumed lifetime,
+    /// for providers/flows that don't track expiry.
+    pub fn opaque(access_token: String) -> Self {
+        Self::new(access_token, None, None, None, None)
+    }
+
+    /// Whether this token is expired, or close enough to expiring (within
+    /// [`EXPIRY_SKEW`]) that it should be refreshed before use.
+    pub fn is_expired(&self) -> bool {
+        Instant::now() + EXPIRY_SKEW >= self.expires_at
+    }
+
+    /// Convert the monotonic `expires_at` to a wall-clock timestamp, for
+    /// persisting to `oauth_connections.expires_at`. Only meaningful
+    /// relative to when this is called - re-derive it fresh rather than
+    /// caching it.
+    pub fn expires_at_utc(&self) -> chrono::DateTime<chrono::Utc> {
+        let remaining = self.expires_at.saturating_duration_since(Instant::now());
+        chrono::Utc::now()
+            + chrono::Duration::from_std(remaining).unwrap_or_else(|_| chrono::Duration::zero())
+    }
+}
+
+/// A parsed set of OAuth scopes, as stored space-delimited in
+/// `oauth_connections.scopes`. Lets a handler ask "is this connection
+/// authorized to do X" without re-parsing the raw string or re-prompting the
+/// user for consent it already has.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScopeSet(std::collections::HashSet<String>);
+
+impl ScopeSet {
+    /// Parse a space-delimited scope string, e.g. `"repo read:user"`.
+    pub fn parse(raw: &str) -> Self {
+        Self(raw.split_whitespace().map(|s| s.to_string()).collect())
+    }
+
+    pub fn contains(&self, scope: &str) -> bool {
+        self.0.contains(scope)
+    }
+
+    /// Whether every scope in `required` is present in this set
+    pub fn is_superset_of(&self, required: &ScopeSet) -> bool {
+        required.0.is_subset(&self.0)
+    }
+}
+
+impl std::fmt::Display for ScopeSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut scopes: Vec<&str> = self.0.iter().map(String::as_str).collect();
+        scopes.sort_unstable();
+        write!(f, "{}", scopes.join(" "))
+    }
+}
+
+/// Common behavior every OAuth provider backend implements.
+///
+/// Each provider normalizes its own token/user responses into [`TokenSet`] and
+/// [`OAuthUserInfo`] so the callback route can dispatch generically through a
+/// [`ProviderRegistry`] instead of hardcoding one provider's flow.
+#[async_trait]
+pub trait OAuthProvider: Send + Sync {
+    /// Name used to key this provider in the registry and in callback routes
+    /// (e.g. `"github"`, `"google"`).
+    fn provider_name(&self) -> &'static str;
+
+    /// Generate the authorization URL the player is redirected to.
+    async fn get_authorization_url(&self, state: &OAuthState) -> String;
+
+    /// Exchange an authorization code for a token set. `pkce_verifier` must
+    /// be the verifier from the same `OAuthState` the authorization URL was
+    /// built with.
+    async fn exchange_code(&self, code: &str, pkce_verifier: &str) -> Result<TokenSet, String>;
+
+    /// Exchange a refresh token for a new token set. Providers that don't
+    /// issue refresh tokens should return an error explaining so.
+    async fn refresh(&self, refresh_token: &str) -> Result<TokenSet, String>;
+
+    /// Fetch the player's identity using an access token.
+    async fn get_user_info(&self, access_token: &str) -> Result<OAuthUserInfo, String>;
+
+    /// Complete the OAuth flow: exchange code and fetch user info.
+    async fn authenticate(&self, code: &str, pkce_verifier: &str) -> Result<OAuthUserInfo, String> {
+        let token_set = self.exchange_code(code, pkce_verifier).await?;
+        self.get_user_info(&token_set.access_token).await
+    }
+
+    /// Verify an OIDC id_token (signature, `iss`/`aud`/`exp`, and that
+    /// `nonce` matches the one generated for this flow) and derive
+    /// [`OAuthUserInfo`] straight from its claims. Only providers that
+    /// expose OIDC discovery (currently Google) override this; the default
+    /// rejects the call so the callback route falls back to
+    /// `get_user_info`.
+    async fn verify_id_token(
+        &self,
+        _id_token: &str,
+        _expected_nonce: &str,
+    ) -> Result<OAuthUserInfo, String> {
+        Err("this provider does not support OpenID Connect".to_string())
+    }
+
+    /// Return `tokens` as-is unless it's within its expiry skew window, in
+    /// which case transparently refresh it first.
+    async fn ensure_fresh(&self, tokens: &TokenSet) -> Result<TokenSet, String> {
+        if !tokens.is_expired() {
+            return Ok(tokens.clone());
+        }
+        let refresh_token = tokens
+            .refresh_token
+            .as_deref()
+            .ok_or_else(|| "Token expired and no refresh token is available".to_string())?;
+        self.refresh(refresh_token).await
+    }
+}
+
+/// HTTP transport settings shared by every provider's `reqwest::Client`, so
+/// operators can run behind a corporate proxy, pin a custom CA bundle, or
+/// override DNS for self-hosted provider mirrors (e.g. an on-prem GitLab)
+/// without each provider reinventing client construction.
+#[derive(Clone, Default)]
+pub struct OAuthTransportConfig {
+    pub proxy: Option<reqwest::Url>,
+    pub extra_ca_pem: Option<Vec<u8>>,
+    pub dns_overrides: Vec<(String, SocketAddr)>,
+    pub timeout: Option<Duration>,
+}
+
+impl OAuthTransportConfig {
+    /// Build from `OAUTH_HTTP_PROXY`, `OAUTH_EXTRA_CA_CERT_PATH`,
+    /// `OAUTH_DNS_OVERRIDE` (comma-separated `host=ip:port` pairs), and
+    /// `OAUTH_HTTP_TIMEOUT_SECS`. Every var is optional; a missing or
+    /// unparsable one just falls back to the default for that setting.
+    pub fn from_env() -> Self {
+        let proxy = std::env::var("OAUTH_HTTP_PROXY")
+            .ok()
+            .and_then(|p| reqwest::Url::parse(&p).ok());
+
+        let extra_ca_pem = std::env::var("OAUTH_EXTRA_CA_CERT_PATH")
+            .ok()
+            .and_then(|path| std::fs::read(path).ok());
+
+        let dns_overrides = std::env::var("OAUTH_DNS_OVERRIDE")
+            .ok()
+            .map(|raw| {
+                raw.split(',')
+                    .filter_map(|pair| {
+                        let (host, addr) = pair.split_once('=')?;
+                        Some((host.to_string(), addr.trim().parse::<SocketAddr>().ok()?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let timeout = std::env::var("OAUTH_HTTP_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_secs);
+
+        Self {
+            proxy,
+            extra_ca_pem,
+            dns_overrides,
+            timeout,
+        }
+    }
+
+    /// Apply this transport config on top of a builder that already has its
+    /// provider-specific defaults (e.g. user agent) set.
+    pub fn apply(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if let Some(proxy_url) = self.proxy.clone() {
+            if let Ok(proxy) = reqwest::Proxy::all(proxy_url) {
+                builder = builder.proxy(proxy);
+            }
+        }
+        if let Some(pem) = &self.extra_ca_pem {
+            if let Ok(cert) = reqwest::Certificate::from_pem(pem) {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        for (host, addr) in &self.dns_overrides {
+            builder = builder.resolve(host, *addr);
+        }
+        builder.timeout(self.timeout.unwrap_or(DEFAULT_TIMEOUT))
+    }
+}
+
+/// Registry of configured OAuth providers keyed by provider name, used by the
+/// generic `/api/auth/oauth/{provider}/callback` route to dispatch without
+/// hardcoding each provider.
+#[derive(Default)]
+pub struct ProviderRegistry {
+    providers: HashMap<String, Box<dyn OAuthProvider>>,
+}
+
+impl ProviderRegistry {
+    pub fn new() -> Self {
+        Self {
+            providers: HashMap::new(),
+        }
+    }
+
+    /// Register a provider under its own `provider_name()`.
+    pub fn register(&mut self, provider: Box<dyn OAuthProvider>) {
+        self.providers.insert(provider.provider_name().to_string(), provider);
+    }
+
+    /// Build a registry from whichever providers have their env vars set,
+    /// sharing one [`OAuthTransportConfig`] across all of them.
+    pub fn from_env(api_base_url: &str) -> Self {
+        let mut registry = Self::new();
+        let transport = OAuthTransportConfig::from_env();
+
+        if let Some(github) = GitHubOAuth::from_env(api_base_url, &transport) {
+            registry.register(Box::new(github));
+        }
+        if let Some(google) = GoogleOAuth::from_env(api_base_url, &transport) {
+            registry.register(Box::new(google));
+        }
+        if let Some(gitlab) = GitLabOAuth::from_env(api_base_url, &transport) {
+            registry.register(Box::new(gitlab));
+        }
+        if let Some(discord) = DiscordOAuth::from_env(api_base_url, &transport) {
+            registry.register(Box::new(discord));
+        }
+
+        registry
+    }
+
+    pub fn get(&self, provider: &str) -> Option<&dyn OAuthProvider> {
+        self.providers.get(provider).map(|p| p.as_ref())
+    }
+}
+
 /// OAuth provider configuration
 #[derive(Clone)]
 pub struct OAuthConfig {
@@ -49,6 +359,93 @@ impl OAuthConfig {
             redirect_uri: format!("{}/api/auth/oauth/github/callback", frontend_url),
         })
     }
+
+    /// Load GitLab OAuth config from environment
+    pub fn gitlab_from_env(frontend_url: &str) -> Option<Self> {
+        Some(Self {
+            client_id: std::env::var("GITLAB_CLIENT_ID").ok()?,
+            client_secret: std::env::var("GITLAB_CLIENT_SECRET").ok()?,
+            redirect_uri: format!("{}/api/auth/oauth/gitlab/callback", frontend_url),
+        })
+    }
+
+    /// Load Discord OAuth config from environment
+    pub fn discord_from_env(frontend_url: &str) -> Option<Self> {
+        Some(Self {
+            client_id: std::env::var("DISCORD_CLIENT_ID").ok()?,
+            client_secret: std::env::var("DISCORD_CLIENT_SECRET").ok()?,
+            redirect_uri: format!("{}/api/auth/oauth/discord/callback", frontend_url),
+        })
+    }
+}
+
+/// Build an `oauth2` crate client from a provider's config and endpoints.
+/// Shared by every provider so each one only has to name its own
+/// authorize/token URLs.
+pub fn build_oauth2_client(
+    config: &OAuthConfig,
+    auth_url: &str,
+    token_url: &str,
+) -> BasicClient {
+    BasicClient::new(
+        ClientId::new(config.client_id.clone()),
+        Some(ClientSecret::new(config.client_secret.clone())),
+        AuthUrl::new(auth_url.to_string()).expect("provider auth_url is a valid constant URL"),
+        Some(TokenUrl::new(token_url.to_string()).expect("provider token_url is a valid constant URL")),
+    )
+    .set_redirect_uri(
+        RedirectUrl::new(config.redirect_uri.clone()).expect("redirect_uri is a valid URL"),
+    )
+}
+
+/// Generate a fresh PKCE code verifier (RFC 7636: 43-128 char, URL-safe).
+/// The matching challenge is derived from it deterministically wherever it's
+/// needed. Kept server-side in a [`PkceStore`] rather than embedded in
+/// `OAuthState` - see that type's doc comment for why.
+fn generate_pkce_verifier() -> String {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// How long a started OAuth flow's PKCE verifier stays claimable before the
+/// player must restart it - mirrors `webauthn::CHALLENGE_TTL`.
+const PKCE_VERIFIER_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Server-side holder for PKCE verifiers in flight, keyed by the flow's
+/// [`OAuthState::nonce`]. The verifier never leaves the server: it's looked
+/// up again when the callback comes back rather than round-tripping through
+/// the `state` query parameter, which - like `code` - rides the callback URL
+/// in the clear (referrers, proxy/access logs, browser history). Letting the
+/// verifier travel on that same channel would hand an observer of the
+/// callback both halves of the exact exchange PKCE is meant to bind
+/// together.
+#[derive(Default)]
+pub struct PkceStore {
+    pending: dashmap::DashMap<String, (String, Instant)>,
+}
+
+impl PkceStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stash `verifier` under `nonce` for the flow that's about to redirect
+    /// the player to the provider.
+    pub(crate) fn insert(&self, nonce: String, verifier: String) {
+        self.pending.insert(nonce, (verifier, Instant::now()));
+    }
+
+    /// Claim the verifier stored for `nonce`, if any and still fresh. A
+    /// one-shot read: a second callback for the same `state` (replayed or
+    /// raced) finds nothing left to exchange with.
+    pub(crate) fn take(&self, nonce: &str) -> Option<String> {
+        let (_, (verifier, started_at)) = self.pending.remove(nonce)?;
+        (started_at.elapsed() <= PKCE_VERIFIER_TTL).then_some(verifier)
+    }
 }
 
 /// State parameter for OAuth flow (prevents CSRF)
@@ -58,6 +455,15 @@ pub struct OAuthState {
     pub nonce: String,
     /// Where to redirect after auth
     pub redirect_to: Option<String>,
+    /// If set, this callback is linking an identity to an already
+    /// signed-in player rather than logging in/registering one.
+    pub link_user_id: Option<uuid::Uuid>,
+    /// PKCE code verifier generated for this flow. Never serialized into the
+    /// encoded state (see [`PkceStore`]) - present here only so
+    /// `pkce_challenge` can read it in-process between `OAuthState::new` and
+    /// the verifier being handed to `PkceStore::insert`.
+    #[serde(skip)]
+    pub pkce_verifier: String,
 }
 
 impl OAuthState {
@@ -67,9 +473,28 @@ impl OAuthState {
         Self {
             nonce: hex::encode(nonce),
             redirect_to,
+            link_user_id: None,
+            pkce_verifier: generate_pkce_verifier(),
         }
     }
 
+    /// Build the state used to kick off linking a new provider identity onto
+    /// an already-authenticated player's account.
+    pub fn for_linking(user_id: uuid::Uuid, redirect_to: Option<String>) -> Self {
+        Self {
+            link_user_id: Some(user_id),
+            ..Self::new(redirect_to)
+        }
+    }
+
+    /// The PKCE code challenge matching this state's verifier, to embed in
+    /// the authorization URL.
+    pub fn pkce_challenge(&self) -> PkceCodeChallenge {
+        PkceCodeChallenge::from_code_verifier_sha256(&PkceCodeVerifier::new(
+            self.pkce_verifier.clone(),
+        ))
+    }
+
     pub fn encode(&self) -> String {
         use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
         let json = serde_json::to_string(self).unwrap_or_default();
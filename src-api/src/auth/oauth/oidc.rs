@@ -0,0 +1,140 @@
+//! OpenID Connect id_token verification shared by providers that support it
+//! (Google via its OIDC discovery endpoints).
+//!
+//! Verifying the id_token lets `find_or_create_oauth_user` trust `sub`/`email`
+//! straight from a provider-signed JWT instead of a separate, unauthenticated
+//! userinfo API call.
+
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+
+use super::OAuthUserInfo;
+
+/// How long a fetched JWKS is trusted before being re-fetched. Providers
+/// rotate signing keys infrequently; this just bounds how long a newly
+/// rotated key takes to be picked up.
+const JWKS_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Claims this codebase cares about from a verified id_token. Providers
+/// include more (e.g. `picture`, `locale`); anything not needed to build an
+/// [`OAuthUserInfo`] is left undeserialized.
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    email: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_loose_bool")]
+    email_verified: bool,
+    name: Option<String>,
+    nonce: Option<String>,
+}
+
+/// Providers disagree on whether `email_verified` is a JSON bool or the
+/// string `"true"`/`"false"` (Google's OIDC id_tokens use the latter), so
+/// accept both rather than failing verification over it.
+fn deserialize_loose_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrString {
+        Bool(bool),
+        Str(String),
+    }
+
+    Ok(match Option::<BoolOrString>::deserialize(deserializer)? {
+        Some(BoolOrString::Bool(b)) => b,
+        Some(BoolOrString::Str(s)) => s == "true",
+        None => false,
+    })
+}
+
+/// Caches a provider's JWKS by its `jwks_uri` so id_token verification
+/// doesn't fetch keys on every login.
+#[derive(Default)]
+pub struct JwksCache {
+    entries: DashMap<String, (JwkSet, Instant)>,
+}
+
+impl JwksCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return a cached JWKS for `jwks_uri` if still fresh, otherwise fetch
+    /// and cache a new one.
+    async fn get_or_fetch(
+        &self,
+        client: &reqwest::Client,
+        jwks_uri: &str,
+    ) -> Result<JwkSet, String> {
+        if let Some(entry) = self.entries.get(jwks_uri) {
+            if entry.1.elapsed() < JWKS_CACHE_TTL {
+                return Ok(entry.0.clone());
+            }
+        }
+
+        let jwks: JwkSet = client
+            .get(jwks_uri)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch JWKS: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse JWKS: {}", e))?;
+
+        self.entries
+            .insert(jwks_uri.to_string(), (jwks.clone(), Instant::now()));
+        Ok(jwks)
+    }
+
+    /// Verify `id_token` against the provider's JWKS and return the player's
+    /// identity if `iss`/`aud`/`exp`/`nonce` all check out.
+    pub async fn verify_id_token(
+        &self,
+        client: &reqwest::Client,
+        jwks_uri: &str,
+        issuer: &str,
+        audience: &str,
+        expected_nonce: &str,
+        id_token: &str,
+    ) -> Result<OAuthUserInfo, String> {
+        let jwks = self.get_or_fetch(client, jwks_uri).await?;
+
+        let header = decode_header(id_token).map_err(|e| format!("Invalid id_token header: {}", e))?;
+        let kid = header.kid.ok_or_else(|| "id_token header has no kid".to_string())?;
+        let jwk = jwks
+            .find(&kid)
+            .ok_or_else(|| format!("No matching JWK for kid {}", kid))?;
+        let decoding_key = DecodingKey::from_jwk(jwk)
+            .map_err(|e| format!("Unusable JWK for kid {}: {}", kid, e))?;
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_issuer(&[issuer]);
+        validation.set_audience(&[audience]);
+
+        let claims = decode::<IdTokenClaims>(id_token, &decoding_key, &validation)
+            .map_err(|e| format!("id_token verification failed: {}", e))?
+            .claims;
+
+        if claims.nonce.as_deref() != Some(expected_nonce) {
+            return Err("id_token nonce does not match the nonce from this flow".to_string());
+        }
+
+        let email = claims
+            .email
+            .ok_or_else(|| "id_token has no email claim".to_string())?;
+
+        Ok(OAuthUserInfo {
+            provider_user_id: claims.sub,
+            email,
+            name: claims.name,
+            avatar_url: None,
+            email_verified: claims.email_verified,
+        })
+    }
+}
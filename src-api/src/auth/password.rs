@@ -2,34 +2,110 @@
 
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Algorithm, Argon2, Params, Version,
 };
 use sha2::{Digest, Sha256};
 
 use super::AuthError;
 
-/// Hash a password using Argon2id
+/// Argon2id cost parameters new hashes are created under. Configurable via
+/// `ARGON2_MEMORY_KIB`, `ARGON2_ITERATIONS`, `ARGON2_PARALLELISM` so costs
+/// can be raised over time (as hardware gets faster) without a code change;
+/// an unset or unparsable var falls back to the `argon2` crate's own
+/// recommended default for that parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Policy {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Argon2Policy {
+    pub fn from_env() -> Self {
+        Self {
+            memory_kib: env_u32("ARGON2_MEMORY_KIB").unwrap_or(Params::DEFAULT_M_COST),
+            iterations: env_u32("ARGON2_ITERATIONS").unwrap_or(Params::DEFAULT_T_COST),
+            parallelism: env_u32("ARGON2_PARALLELISM").unwrap_or(Params::DEFAULT_P_COST),
+        }
+    }
+
+    fn params(&self) -> Params {
+        Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .expect("Argon2 policy produces valid parameters")
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params())
+    }
+
+    /// Whether a hash created under `other` falls short of this policy on
+    /// any axis - memory, iterations, or parallelism - and should therefore
+    /// be upgraded the next time it verifies successfully.
+    fn exceeds(&self, other: &Params) -> bool {
+        self.memory_kib > other.m_cost()
+            || self.iterations > other.t_cost()
+            || self.parallelism > other.p_cost()
+    }
+}
+
+fn env_u32(key: &str) -> Option<u32> {
+    std::env::var(key).ok().and_then(|v| v.parse().ok())
+}
+
+/// Hash a password using Argon2id under the current [`Argon2Policy`]
 pub fn hash_password(password: &str) -> Result<String, AuthError> {
     // Validate password strength first
     validate_password_strength(password)?;
-    
+
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
 
-    argon2
+    Argon2Policy::from_env()
+        .argon2()
         .hash_password(password.as_bytes(), &salt)
         .map(|hash| hash.to_string())
         .map_err(|e| AuthError::Internal(format!("Failed to hash password: {}", e)))
 }
 
-/// Verify a password against a hash
-pub fn verify_password(password: &str, hash: &str) -> Result<bool, AuthError> {
+/// Outcome of verifying a password against its stored hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordVerification {
+    pub valid: bool,
+    /// Set only when `valid` is true: the stored hash's embedded cost
+    /// parameters are weaker than the current `Argon2Policy`, so the caller
+    /// should compute a fresh `hash_password` and persist it in place.
+    pub needs_rehash: bool,
+}
+
+/// Verify a password against a hash, also reporting whether the hash should
+/// be transparently upgraded to the current `Argon2Policy`. Verification
+/// itself always uses the algorithm/parameters encoded in `hash` - a
+/// verifier that demanded today's params on an old hash could never
+/// validate it in the first place, which is why this is a signal for the
+/// caller to act on rather than something `verify_password` applies itself.
+pub fn verify_password_checked(password: &str, hash: &str) -> Result<PasswordVerification, AuthError> {
     let parsed_hash = PasswordHash::new(hash)
         .map_err(|e| AuthError::Internal(format!("Invalid password hash format: {}", e)))?;
 
-    Ok(Argon2::default()
+    let valid = Argon2::default()
         .verify_password(password.as_bytes(), &parsed_hash)
-        .is_ok())
+        .is_ok();
+
+    let needs_rehash = valid
+        && Params::try_from(&parsed_hash)
+            .map(|params| Argon2Policy::from_env().exceeds(&params))
+            .unwrap_or(false);
+
+    Ok(PasswordVerification {
+        valid,
+        needs_rehash,
+    })
+}
+
+/// Verify a password against a hash. Equivalent to
+/// `verify_password_checked(..).valid` for callers that don't act on
+/// `needs_rehash` (only `login` currently does).
+pub fn verify_password(password: &str, hash: &str) -> Result<bool, AuthError> {
+    Ok(verify_password_checked(password, hash)?.valid)
 }
 
 /// Validate password strength
@@ -74,6 +150,20 @@ pub fn hash_token(token: &str) -> String {
     hex::encode(hasher.finalize())
 }
 
+/// Generate a new random security stamp for a user.
+///
+/// Embedded as a claim in every JWT minted for that user; rotating the
+/// stamp (password reset, logout-all, account status change) makes every
+/// previously issued token fail validation without needing a blocklist.
+pub fn generate_security_stamp() -> String {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -133,4 +223,14 @@ mod tests {
         let hash3 = hash_token("different_token");
         assert_ne!(hash1, hash3);
     }
+
+    #[test]
+    fn test_security_stamp() {
+        let stamp1 = generate_security_stamp();
+        let stamp2 = generate_security_stamp();
+
+        // 32 bytes base64url-nopad-encoded is 43 characters
+        assert_eq!(stamp1.len(), 43);
+        assert_ne!(stamp1, stamp2);
+    }
 }
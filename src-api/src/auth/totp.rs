@@ -0,0 +1,216 @@
+//! TOTP (RFC 6238) two-factor authentication
+//!
+//! Parallel to [`super::webauthn`]: enrollment happens while the player is
+//! already authenticated once, and from then on `verify_totp` gates a login
+//! the way a passkey ceremony does. Unlike a passkey, the shared secret has
+//! no public half - this server has to read it back to compute the
+//! expected code - so it's stored as-is rather than hashed like
+//! `hash_token`; only the backup codes (see [`generate_backup_codes`]) are
+//! one-way hashed, since those are bearer secrets the server only ever
+//! needs to compare against, never recompute from.
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+use super::AuthError;
+
+/// RFC 6238 recommends a shared secret at least as long as the hash
+/// function's output (20 bytes for SHA-1).
+const SECRET_BYTES: usize = 20;
+/// RFC 6238 step length. Authenticator apps (Google Authenticator, Authy,
+/// ...) hardcode this too, so it isn't configurable.
+const STEP_SECONDS: u64 = 30;
+/// Steps tolerated on either side of the current one, to absorb clock skew
+/// between this server and the player's authenticator app.
+const SKEW_STEPS: i64 = 1;
+const CODE_DIGITS: u32 = 6;
+
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Generate a new random shared secret, base32-encoded for display/QR and
+/// for storage (there's no raw-bytes column - base32 text round-trips
+/// losslessly and is what every authenticator app expects anyway).
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base32_encode(&bytes)
+}
+
+/// Build the `otpauth://` URI an authenticator app scans as a QR code.
+pub fn provisioning_uri(secret_base32: &str, account_email: &str, issuer: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(issuer),
+        percent_encode(account_email),
+        secret_base32,
+        percent_encode(issuer),
+        CODE_DIGITS,
+        STEP_SECONDS,
+    )
+}
+
+/// Verify a 6-digit code against `secret_base32`, accepting the current
+/// time step and [`SKEW_STEPS`] on either side of it.
+pub fn verify_totp(secret_base32: &str, code: &str) -> Result<bool, AuthError> {
+    let secret = base32_decode(secret_base32)
+        .ok_or_else(|| AuthError::Internal("Invalid TOTP secret encoding".to_string()))?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| AuthError::Internal(format!("System clock before epoch: {}", e)))?
+        .as_secs();
+    let current_step = (now / STEP_SECONDS) as i64;
+
+    for offset in -SKEW_STEPS..=SKEW_STEPS {
+        let step = current_step + offset;
+        if step < 0 {
+            continue;
+        }
+        if hotp(&secret, step as u64) == code {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// RFC 4226 HOTP: `HMAC-SHA1(secret, counter)` with dynamic truncation into
+/// a fixed-width decimal code.
+fn hotp(secret: &[u8], counter: u64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    // Dynamic truncation (RFC 4226 section 5.3): the low 4 bits of the last
+    // byte pick a 4-byte window to read, whose top bit is then masked off
+    // to avoid sign ambiguity before reducing mod 10^digits.
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = ((digest[offset] as u32 & 0x7f) << 24)
+        | ((digest[offset + 1] as u32) << 16)
+        | ((digest[offset + 2] as u32) << 8)
+        | (digest[offset + 3] as u32);
+
+    let code = binary % 10u32.pow(CODE_DIGITS);
+    format!("{:0width$}", code, width = CODE_DIGITS as usize)
+}
+
+/// Generate a fresh set of one-time backup codes. Callers must show these
+/// to the player exactly once and persist only [`hash_token`](super::password::hash_token)
+/// of each - same "store the digest, not the secret" shape as password
+/// reset/verification tokens.
+pub fn generate_backup_codes(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|_| {
+            let mut bytes = [0u8; 7];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            // Hyphenated for readability when typed by hand, e.g. "3K7P9-QX2RT".
+            let code = base32_encode(&bytes);
+            format!("{}-{}", &code[..5], &code[5..10])
+        })
+        .collect()
+}
+
+fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() * 8).div_ceil(5));
+    let mut buffer: u32 = 0;
+    let mut bits_in_buffer = 0u32;
+
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits_in_buffer += 8;
+        while bits_in_buffer >= 5 {
+            bits_in_buffer -= 5;
+            let index = (buffer >> bits_in_buffer) & 0x1f;
+            out.push(BASE32_ALPHABET[index as usize] as char);
+        }
+    }
+
+    if bits_in_buffer > 0 {
+        let index = (buffer << (5 - bits_in_buffer)) & 0x1f;
+        out.push(BASE32_ALPHABET[index as usize] as char);
+    }
+
+    out
+}
+
+fn base32_decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut buffer: u32 = 0;
+    let mut bits_in_buffer = 0u32;
+    let mut out = Vec::with_capacity(encoded.len() * 5 / 8);
+
+    for c in encoded.chars().filter(|c| !c.is_whitespace()) {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&b| b as char == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u32;
+        bits_in_buffer += 5;
+        if bits_in_buffer >= 8 {
+            bits_in_buffer -= 8;
+            out.push(((buffer >> bits_in_buffer) & 0xff) as u8);
+        }
+    }
+
+    Some(out)
+}
+
+/// Minimal percent-encoding for the handful of characters likely to show up
+/// in an issuer name or email within an `otpauth://` URI (no query-string
+/// library pulled in just for this one call site).
+fn percent_encode(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for b in raw.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base32_roundtrip() {
+        let data = b"code-warrior-totp-secret!!";
+        let encoded = base32_encode(data);
+        let decoded = base32_decode(&encoded).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_hotp_rfc4226_vectors() {
+        // RFC 4226 Appendix D test vectors for the ASCII secret "12345678901234567890"
+        let secret = b"12345678901234567890";
+        assert_eq!(hotp(secret, 0), "755224");
+        assert_eq!(hotp(secret, 1), "287082");
+        assert_eq!(hotp(secret, 9), "520489");
+    }
+
+    #[test]
+    fn test_verify_totp_current_step() {
+        let secret = generate_secret();
+        let raw = base32_decode(&secret).unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let code = hotp(&raw, now / STEP_SECONDS);
+
+        assert!(verify_totp(&secret, &code).unwrap());
+        assert!(!verify_totp(&secret, "000000").unwrap() || code == "000000");
+    }
+
+    #[test]
+    fn test_backup_codes_are_unique_and_formatted() {
+        let codes = generate_backup_codes(8);
+        assert_eq!(codes.len(), 8);
+        for code in &codes {
+            assert_eq!(code.len(), 11); // 5 + '-' + 5
+        }
+        let unique: std::collections::HashSet<_> = codes.iter().collect();
+        assert_eq!(unique.len(), codes.len());
+    }
+}
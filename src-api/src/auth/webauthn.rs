@@ -0,0 +1,163 @@
+//! WebAuthn / passkey authentication
+//!
+//! Parallel to the OAuth providers in [`super::oauth`]: a player enrolls a
+//! passkey after signing in once via another method, then authenticates
+//! passwordless thereafter. The actual challenge/response cryptography is
+//! handled by `webauthn-rs`; this module matches credentials to player
+//! accounts and enforces the clone-detection invariant on the signature
+//! counter.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use uuid::Uuid;
+use webauthn_rs::prelude::*;
+
+use super::AuthError;
+
+/// How long a registration/authentication challenge stays valid before the
+/// player must restart the ceremony.
+const CHALLENGE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Wraps a `webauthn-rs` instance plus the in-memory state for ceremonies
+/// that haven't completed yet. Pending state is intentionally not persisted:
+/// if the process restarts mid-ceremony the player just restarts it.
+pub struct WebAuthnService {
+    webauthn: Webauthn,
+    pending_registrations: DashMap<Uuid, (PasskeyRegistration, Instant)>,
+    pending_authentications: DashMap<Uuid, (PasskeyAuthentication, Instant)>,
+}
+
+impl WebAuthnService {
+    /// Build the service for a given relying party id/origin, e.g.
+    /// `rp_id = "codewarrior.app"`, `rp_origin = "https://codewarrior.app"`.
+    pub fn new(rp_id: &str, rp_origin: &str) -> Result<Self, AuthError> {
+        let origin = Url::parse(rp_origin)
+            .map_err(|e| AuthError::Internal(format!("Invalid WebAuthn origin: {}", e)))?;
+
+        let webauthn = WebauthnBuilder::new(rp_id, &origin)
+            .map_err(|e| AuthError::Internal(format!("Invalid WebAuthn config: {}", e)))?
+            .rp_name("Code Warrior")
+            .build()
+            .map_err(|e| AuthError::Internal(format!("Failed to build WebAuthn: {}", e)))?;
+
+        Ok(Self {
+            webauthn,
+            pending_registrations: DashMap::new(),
+            pending_authentications: DashMap::new(),
+        })
+    }
+
+    /// Create from environment variables (`WEBAUTHN_RP_ID`, `WEBAUTHN_RP_ORIGIN`)
+    pub fn from_env() -> Option<Self> {
+        let rp_id = std::env::var("WEBAUTHN_RP_ID").ok()?;
+        let rp_origin = std::env::var("WEBAUTHN_RP_ORIGIN").ok()?;
+        Self::new(&rp_id, &rp_origin).ok()
+    }
+
+    /// Start enrolling a new passkey for an already-authenticated player.
+    /// `existing` should be every passkey already on the account so the
+    /// authenticator can refuse to re-register the same device.
+    pub fn start_registration(
+        &self,
+        user_id: Uuid,
+        email: &str,
+        existing: &[Passkey],
+    ) -> Result<CreationChallengeResponse, AuthError> {
+        let exclude_credentials = (!existing.is_empty())
+            .then(|| existing.iter().map(|p| p.cred_id().clone()).collect());
+
+        let (challenge, reg_state) = self
+            .webauthn
+            .start_passkey_registration(user_id, email, email, exclude_credentials)
+            .map_err(|e| AuthError::Internal(format!("Failed to start passkey registration: {}", e)))?;
+
+        self.pending_registrations
+            .insert(user_id, (reg_state, Instant::now()));
+
+        Ok(challenge)
+    }
+
+    /// Verify the authenticator's response and return the credential to
+    /// persist. The caller is responsible for storing it against `user_id`.
+    pub fn finish_registration(
+        &self,
+        user_id: Uuid,
+        credential: &RegisterPublicKeyCredential,
+    ) -> Result<Passkey, AuthError> {
+        let (_, (reg_state, started_at)) = self
+            .pending_registrations
+            .remove(&user_id)
+            .ok_or(AuthError::InvalidToken)?;
+
+        if started_at.elapsed() > CHALLENGE_TTL {
+            return Err(AuthError::InvalidToken);
+        }
+
+        self.webauthn
+            .finish_passkey_registration(credential, &reg_state)
+            .map_err(|e| AuthError::Internal(format!("Passkey registration failed: {}", e)))
+    }
+
+    /// Start a login ceremony against a known set of passkeys (every
+    /// credential on the account the player claims to be, or every
+    /// discoverable credential for usernameless login).
+    pub fn start_authentication(
+        &self,
+        challenge_id: Uuid,
+        credentials: &[Passkey],
+    ) -> Result<RequestChallengeResponse, AuthError> {
+        let (challenge, auth_state) = self
+            .webauthn
+            .start_passkey_authentication(credentials)
+            .map_err(|e| AuthError::Internal(format!("Failed to start passkey authentication: {}", e)))?;
+
+        self.pending_authentications
+            .insert(challenge_id, (auth_state, Instant::now()));
+
+        Ok(challenge)
+    }
+
+    /// Verify the assertion signature. Enforces the clone-detection
+    /// invariant: the authenticator's returned counter must be strictly
+    /// greater than `stored_sign_count`, otherwise this credential may have
+    /// been cloned and the authentication is rejected outright.
+    pub fn finish_authentication(
+        &self,
+        challenge_id: Uuid,
+        credential: &PublicKeyCredential,
+        stored_sign_count: u32,
+    ) -> Result<AuthenticationResult, AuthError> {
+        let (_, (auth_state, started_at)) = self
+            .pending_authentications
+            .remove(&challenge_id)
+            .ok_or(AuthError::InvalidToken)?;
+
+        if started_at.elapsed() > CHALLENGE_TTL {
+            return Err(AuthError::InvalidToken);
+        }
+
+        let result = self
+            .webauthn
+            .finish_passkey_authentication(credential, &auth_state)
+            .map_err(|e| AuthError::Internal(format!("Passkey authentication failed: {}", e)))?;
+
+        // Authenticators that don't implement a counter report 0 forever;
+        // only enforce strict monotonicity once counting has actually begun.
+        if result.counter() != 0 && result.counter() <= stored_sign_count {
+            tracing::error!(
+                "WebAuthn signature counter did not increase ({} <= {}) - possible cloned authenticator",
+                result.counter(),
+                stored_sign_count
+            );
+            return Err(AuthError::InvalidCredentials);
+        }
+
+        Ok(result)
+    }
+}
+
+/// Wraps a [`WebAuthnService`] behind an `Arc` for cheap cloning into axum
+/// handler state, mirroring how OAuth clients are shared.
+pub type SharedWebAuthnService = Arc<WebAuthnService>;
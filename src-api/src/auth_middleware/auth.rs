@@ -8,10 +8,34 @@ use axum::{
     middleware::Next,
     response::Response,
 };
+use axum_extra::extract::cookie::CookieJar;
 use jsonwebtoken::{decode, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Name of the HttpOnly cookie the browser front-end gets the access token
+/// under (see wherever `AuthResponse` is issued) - a Tauri/native client
+/// never sets this and just keeps using the `Authorization` header.
+const ACCESS_TOKEN_COOKIE: &str = "access_token";
+
+/// Pull the JWT out of the request: an `Authorization: Bearer` header first,
+/// falling back to the `access_token` cookie so the web client can
+/// authenticate without ever holding the token in JS-reachable storage.
+fn extract_token(req: &Request<Body>) -> Option<String> {
+    if let Some(token) = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+    {
+        return Some(token.to_string());
+    }
+
+    CookieJar::from_headers(req.headers())
+        .get(ACCESS_TOKEN_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+}
+
 /// JWT claims structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JwtClaims {
@@ -54,20 +78,12 @@ pub async fn jwt_auth_middleware(
     mut req: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    // Extract Authorization header
-    let auth_header = req
-        .headers()
-        .get(AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
-
-    // Extract Bearer token
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+    // Extract the token from the Authorization header, or the access_token
+    // cookie if there isn't one
+    let token = extract_token(&req).ok_or(StatusCode::UNAUTHORIZED)?;
 
     // Validate token and extract claims
-    let claims = validate_token(token).map_err(|e| {
+    let claims = validate_token(&token).map_err(|e| {
         tracing::warn!("JWT validation failed: {}", e);
         StatusCode::UNAUTHORIZED
     })?;
@@ -99,26 +115,19 @@ pub async fn optional_jwt_auth_middleware(
     mut req: Request<Body>,
     next: Next,
 ) -> Response {
-    // Try to extract Authorization header
-    if let Some(auth_header) = req
-        .headers()
-        .get(AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-    {
-        // Try to extract Bearer token
-        if let Some(token) = auth_header.strip_prefix("Bearer ") {
-            // Try to validate token
-            if let Ok(claims) = validate_token(token) {
-                let now = chrono::Utc::now().timestamp() as usize;
-                if claims.exp >= now {
-                    // Valid token - inject AuthUser
-                    let auth_user = AuthUser {
-                        user_id: claims.sub,
-                        email: claims.email,
-                        xp: claims.xp,
-                    };
-                    req.extensions_mut().insert(auth_user);
-                }
+    // Try the Authorization header, then the access_token cookie
+    if let Some(token) = extract_token(&req) {
+        // Try to validate token
+        if let Ok(claims) = validate_token(&token) {
+            let now = chrono::Utc::now().timestamp() as usize;
+            if claims.exp >= now {
+                // Valid token - inject AuthUser
+                let auth_user = AuthUser {
+                    user_id: claims.sub,
+                    email: claims.email,
+                    xp: claims.xp,
+                };
+                req.extensions_mut().insert(auth_user);
             }
         }
     }
@@ -1,7 +1,8 @@
 //! Ban and suspension check middleware
 //!
-//! Checks if a user is banned or suspended before allowing access.
-//! Must run after JWT auth middleware (needs AuthUser in extensions).
+//! Checks if a user has an active entry in the `bans` table before allowing
+//! access. Must run after JWT auth middleware (needs AuthUser in
+//! extensions).
 
 use axum::{
     body::Body,
@@ -11,12 +12,13 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 use sqlx::{Pool, Postgres};
 use std::sync::Arc;
-use uuid::Uuid;
 
 use super::auth::AuthUser;
+use crate::db::get_active_ban;
 
 /// Response for banned/suspended users
 #[derive(Debug, Serialize)]
@@ -24,31 +26,18 @@ pub struct BanResponse {
     pub error: String,
     pub code: String,
     pub message: String,
-}
-
-/// Check user status (suspended/blacklisted) from database
-async fn check_user_status(
-    pool: &Pool<Postgres>,
-    user_id: Uuid,
-) -> Result<(bool, bool), sqlx::Error> {
-    let result: Option<(bool, bool)> = sqlx::query_as(
-        r#"
-        SELECT is_suspended, is_blacklisted
-        FROM users
-        WHERE id = $1
-        "#,
-    )
-    .bind(user_id)
-    .fetch_optional(pool)
-    .await?;
-
-    Ok(result.unwrap_or((false, false)))
+    pub reason: String,
+    /// `None` for a permanent blacklist, `Some(_)` for a timed suspension.
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 /// Ban check middleware
 ///
-/// Checks if the authenticated user is banned or suspended.
-/// Returns 403 Forbidden if user is banned, 423 Locked if suspended.
+/// Checks the `bans` table for an active ban on the authenticated user.
+/// Returns 403 Forbidden for a permanent ban (`expires_at` is `None`), 423
+/// Locked for a timed suspension (`expires_at` is in the future). An expired
+/// suspension simply isn't "active" per `get_active_ban`'s query, so it lets
+/// the request through with no admin action needed to lift it.
 ///
 /// Must be used after jwt_auth_middleware.
 pub async fn ban_check_middleware(
@@ -66,46 +55,49 @@ pub async fn ban_check_middleware(
         }
     };
 
-    // Check user status in database
-    let (is_suspended, is_blacklisted) = match check_user_status(&pool, auth_user.user_id).await {
-        Ok(status) => status,
+    let active_ban = match get_active_ban(&pool, auth_user.user_id).await {
+        Ok(ban) => ban,
         Err(e) => {
-            tracing::error!("Failed to check user status: {}", e);
+            tracing::error!("Failed to check ban status: {}", e);
             // On database error, let request through rather than blocking
             return Ok(next.run(req).await);
         }
     };
 
-    // Check if blacklisted (permanent ban)
-    if is_blacklisted {
-        tracing::warn!("Blacklisted user {} attempted access", auth_user.user_id);
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(BanResponse {
-                error: "forbidden".to_string(),
-                code: "USER_BLACKLISTED".to_string(),
-                message: "Your account has been permanently banned.".to_string(),
-            }),
-        )
-            .into_response());
-    }
+    let Some(ban) = active_ban else {
+        return Ok(next.run(req).await);
+    };
 
-    // Check if suspended (temporary)
-    if is_suspended {
-        tracing::warn!("Suspended user {} attempted access", auth_user.user_id);
-        return Err((
-            StatusCode::LOCKED,
-            Json(BanResponse {
-                error: "locked".to_string(),
-                code: "USER_SUSPENDED".to_string(),
-                message: "Your account has been temporarily suspended.".to_string(),
-            }),
-        )
-            .into_response());
+    match ban.expires_at {
+        None => {
+            tracing::warn!("Blacklisted user {} attempted access", auth_user.user_id);
+            Err((
+                StatusCode::FORBIDDEN,
+                Json(BanResponse {
+                    error: "forbidden".to_string(),
+                    code: "USER_BLACKLISTED".to_string(),
+                    message: "Your account has been permanently banned.".to_string(),
+                    reason: ban.reason,
+                    expires_at: None,
+                }),
+            )
+                .into_response())
+        }
+        Some(expires_at) => {
+            tracing::warn!("Suspended user {} attempted access", auth_user.user_id);
+            Err((
+                StatusCode::LOCKED,
+                Json(BanResponse {
+                    error: "locked".to_string(),
+                    code: "USER_SUSPENDED".to_string(),
+                    message: "Your account has been temporarily suspended.".to_string(),
+                    reason: ban.reason,
+                    expires_at: Some(expires_at),
+                }),
+            )
+                .into_response())
+        }
     }
-
-    // User is not banned - continue
-    Ok(next.run(req).await)
 }
 
 #[cfg(test)]
@@ -118,10 +110,12 @@ mod tests {
             error: "forbidden".to_string(),
             code: "USER_BLACKLISTED".to_string(),
             message: "Your account has been permanently banned.".to_string(),
+            reason: "Cheating".to_string(),
+            expires_at: None,
         };
-        
+
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains("USER_BLACKLISTED"));
+        assert!(json.contains("Cheating"));
     }
 }
-
@@ -9,8 +9,11 @@ pub mod rate_limit;
 pub mod verification;
 
 // Re-export commonly used types
-pub use auth::{AuthUser, jwt_auth_middleware};
+pub use auth::{optional_jwt_auth_middleware, AuthUser, jwt_auth_middleware};
 pub use ban::ban_check_middleware;
-pub use rate_limit::{create_rate_limiter, SharedRateLimiter, rate_limit_middleware, auth_rate_limit_middleware};
+pub use rate_limit::{
+    auth_rate_limit_middleware, create_rate_limiter, create_rate_limiter_with_config,
+    rate_limit_middleware, RateLimitConfig, SharedRateLimiter,
+};
 pub use verification::verification_check_middleware;
 
@@ -1,30 +1,165 @@
 //! Adaptive rate limiting middleware
 //!
-//! Rate limits are based on user XP level:
+//! Each identifier (authenticated user, or IP for anonymous callers) gets its
+//! own sliding-window counter. The *limit* scales with the player's XP, so
+//! higher-progression players get a proportionally larger request budget,
+//! in line with the documented tiers:
 //! - XP 0-99:     30 requests/min
 //! - XP 100-499:  60 requests/min
 //! - XP 500-1999: 120 requests/min
 //! - XP 2000+:    240 requests/min
 //!
 //! For unauthenticated requests (auth endpoints), uses IP-based limiting.
+//!
+//! The counter tracks two adjacent fixed windows (`prev_count`/`curr_count`)
+//! instead of just one, so a caller can't double their effective rate by
+//! bursting at the tail of one window and the head of the next - a plain
+//! fixed-window counter would let up to `2 * limit` requests through across
+//! that boundary.
 
 use axum::{
     body::Body,
+    extract::ConnectInfo,
     http::{Request, StatusCode},
     middleware::Next,
     response::Response,
 };
 use dashmap::DashMap;
-use std::net::IpAddr;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use super::auth::AuthUser;
 
-/// Rate limit configuration
-#[derive(Debug, Clone)]
+/// A parsed `address/prefix_len` CIDR block, in the spirit of the
+/// `ip_network` crate but scoped to exactly what this module needs:
+/// membership testing for IPv4 and IPv6 ranges loaded from `config.toml`.
+#[derive(Debug, Clone, Copy)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Parses `"10.0.0.0/8"` or `"::1/128"`. Returns `None` on malformed
+    /// input (bad address, missing `/`, or a prefix length that doesn't fit
+    /// the address family) rather than erroring, so a typo in one entry of
+    /// a CIDR list doesn't take down the whole config.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (addr_str, prefix_str) = s.split_once('/')?;
+        let network: IpAddr = addr_str.trim().parse().ok()?;
+        let prefix_len: u8 = prefix_str.trim().parse().ok()?;
+        let max_prefix = match network {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if prefix_len > max_prefix {
+            return None;
+        }
+        Some(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    /// Whether `ip` falls inside this block. Addresses from a different
+    /// family than the block never match (no implicit v4/v6 mapping).
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(net), IpAddr::V4(candidate)) => {
+                let mask = Self::v4_mask(self.prefix_len);
+                u32::from(net) & mask == u32::from(candidate) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(candidate)) => {
+                let mask = Self::v6_mask(self.prefix_len);
+                u128::from(net) & mask == u128::from(candidate) & mask
+            }
+            _ => false,
+        }
+    }
+
+    fn v4_mask(prefix_len: u8) -> u32 {
+        if prefix_len == 0 {
+            0
+        } else {
+            u32::MAX << (32 - prefix_len)
+        }
+    }
+
+    fn v6_mask(prefix_len: u8) -> u128 {
+        if prefix_len == 0 {
+            0
+        } else {
+            u128::MAX << (128 - prefix_len)
+        }
+    }
+}
+
+/// Parses every entry in `cidrs`, silently dropping (with a warning) any
+/// that fail to parse, so a bad config entry degrades gracefully instead of
+/// taking down startup.
+fn parse_cidr_list(cidrs: &[String]) -> Vec<IpCidr> {
+    cidrs
+        .iter()
+        .filter_map(|s| {
+            let parsed = IpCidr::parse(s);
+            if parsed.is_none() {
+                tracing::warn!("Ignoring invalid CIDR in rate-limit config: {}", s);
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// The dimension a request is rate limited along. Every request is checked
+/// against its own per-user/IP bucket for its class *and* the shared
+/// [`LimitClass::Global`] bucket, so one expensive class of traffic can't
+/// starve the others even if each caller is individually within their
+/// per-class limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitClass {
+    /// Ordinary reads - game state, levels, leaderboards, etc.
+    Read,
+    /// Code compilation/submission endpoints, far more expensive than reads.
+    CodeExecution,
+    /// Login/register/etc - limited separately to blunt brute force attempts.
+    Auth,
+    /// The single instance-wide ceiling shared by every caller and class.
+    Global,
+}
+
+impl fmt::Display for LimitClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LimitClass::Read => "read",
+            LimitClass::CodeExecution => "code_execution",
+            LimitClass::Auth => "auth",
+            LimitClass::Global => "global",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Classifies a matched route path into the [`LimitClass`] it should be
+/// billed against. Defaults to [`LimitClass::Read`] for anything that isn't
+/// specifically called out as more expensive.
+fn classify_route(path: &str) -> LimitClass {
+    if path.contains("/code/submit") {
+        LimitClass::CodeExecution
+    } else {
+        LimitClass::Read
+    }
+}
+
+/// Rate limit configuration. Deserializable so [`crate::config::AppConfig`]
+/// can load it straight from `config.toml`'s `[rate_limit]` table; any
+/// fields the table omits keep their [`Default`] value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RateLimitConfig {
-    /// Requests per minute for each XP tier
+    /// Requests per minute for each XP tier (read-class, for authenticated users)
     pub xp_0_99: u32,
     pub xp_100_499: u32,
     pub xp_500_1999: u32,
@@ -33,6 +168,24 @@ pub struct RateLimitConfig {
     pub unauthenticated: u32,
     /// Rate for auth endpoints (stricter)
     pub auth_endpoints: u32,
+    /// Flat per-user/IP rate for code-compilation/submission endpoints,
+    /// applied regardless of XP tier since these cost far more than reads.
+    pub code_execution: u32,
+    /// Instance-wide ceiling shared by every caller and every class.
+    pub global: u32,
+    /// CIDR blocks of proxies allowed to set `X-Forwarded-For`/`X-Real-IP`.
+    /// Requests from any other peer have those headers ignored entirely, so
+    /// a direct client can't spoof its way around IP-based limiting.
+    pub trusted_proxies: Vec<String>,
+    /// CIDR blocks exempted from rate limiting entirely (e.g. internal
+    /// health checks or office ranges).
+    pub allow_list: Vec<String>,
+    /// CIDR blocks rejected with `403` before a bucket is even consulted.
+    pub deny_list: Vec<String>,
+    /// Hard cap on tracked identifiers. Once exceeded, the oldest windows
+    /// are evicted immediately so a flood of distinct (e.g. spoofed)
+    /// identifiers can't grow the map without bound between reaper runs.
+    pub max_entries: usize,
 }
 
 impl Default for RateLimitConfig {
@@ -44,98 +197,283 @@ impl Default for RateLimitConfig {
             xp_2000_plus: 240,
             unauthenticated: 30,
             auth_endpoints: 10, // Stricter for login/register to prevent brute force
+            code_execution: 10, // Compilation is expensive; keep it tight regardless of XP
+            global: 2000,
+            trusted_proxies: Vec::new(),
+            allow_list: Vec::new(),
+            deny_list: Vec::new(),
+            max_entries: 100_000,
         }
     }
 }
 
-/// Rate limit entry for a single identifier
+/// Sliding-window request counter for a single identifier. `curr_window_start`
+/// marks the start of the window `curr_count` is accumulating into;
+/// `prev_count` holds the previous window's final count, whose contribution
+/// decays linearly as the current window progresses (see
+/// [`RateLimiter::check_rate_limit`]).
 #[derive(Debug, Clone)]
 struct RateLimitEntry {
-    count: u32,
-    window_start: Instant,
+    prev_count: u32,
+    curr_count: u32,
+    curr_window_start: Instant,
 }
 
 /// In-memory rate limiter state
 #[derive(Debug)]
 pub struct RateLimiter {
-    /// Map of identifier -> rate limit entry
+    /// Map of identifier -> sliding-window entry
     entries: DashMap<String, RateLimitEntry>,
     config: RateLimitConfig,
     window_duration: Duration,
+    trusted_proxies: Vec<IpCidr>,
+    allow_list: Vec<IpCidr>,
+    deny_list: Vec<IpCidr>,
 }
 
 impl RateLimiter {
     pub fn new(config: RateLimitConfig) -> Self {
+        let trusted_proxies = parse_cidr_list(&config.trusted_proxies);
+        let allow_list = parse_cidr_list(&config.allow_list);
+        let deny_list = parse_cidr_list(&config.deny_list);
         Self {
             entries: DashMap::new(),
             config,
             window_duration: Duration::from_secs(60), // 1 minute window
+            trusted_proxies,
+            allow_list,
+            deny_list,
         }
     }
 
-    /// Check if request should be rate limited
-    /// Returns Ok(remaining) if allowed, Err(retry_after_secs) if limited
+    /// Whether `peer` is a trusted proxy allowed to set forwarding headers.
+    fn is_trusted_proxy(&self, peer: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|cidr| cidr.contains(peer))
+    }
+
+    /// Whether `ip` is exempt from rate limiting via the allow list.
+    pub fn is_allow_listed(&self, ip: IpAddr) -> bool {
+        self.allow_list.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    /// Whether `ip` is hard-blocked via the deny list.
+    pub fn is_deny_listed(&self, ip: IpAddr) -> bool {
+        self.deny_list.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    /// Check if a request against the given `limit` should be rate limited,
+    /// using a sliding window estimate rather than a single fixed window, so
+    /// bursting across a window boundary can't double the effective rate.
+    ///
+    /// Rolls `curr_window_start` forward whenever it's more than a window
+    /// old (shifting `curr_count` into `prev_count`, or zeroing both if more
+    /// than two windows have elapsed since the last request), then estimates
+    /// the request rate as `weighted = prev_count * (1 - elapsed_fraction) +
+    /// curr_count`, where `elapsed_fraction` is how far into the current
+    /// window `now` falls. Returns Ok(remaining) if allowed, Err(retry_after_secs)
+    /// if limited.
     pub fn check_rate_limit(&self, identifier: &str, limit: u32) -> Result<u32, u64> {
         let now = Instant::now();
+        let window_secs = self.window_duration.as_secs_f64();
+        let limit_f = limit as f64;
 
+        let is_new_identifier = !self.entries.contains_key(identifier);
         let mut entry = self.entries.entry(identifier.to_string()).or_insert(RateLimitEntry {
-            count: 0,
-            window_start: now,
+            prev_count: 0,
+            curr_count: 0,
+            curr_window_start: now,
         });
 
-        // Check if window has expired
-        if now.duration_since(entry.window_start) >= self.window_duration {
-            // Reset window
-            entry.count = 1;
-            entry.window_start = now;
-            return Ok(limit - 1);
+        let elapsed = now.saturating_duration_since(entry.curr_window_start);
+        if elapsed >= self.window_duration * 2 {
+            entry.prev_count = 0;
+            entry.curr_count = 0;
+            entry.curr_window_start = now;
+        } else if elapsed >= self.window_duration {
+            entry.prev_count = entry.curr_count;
+            entry.curr_count = 0;
+            entry.curr_window_start += self.window_duration;
+        }
+
+        let elapsed_fraction = (now.saturating_duration_since(entry.curr_window_start).as_secs_f64()
+            / window_secs)
+            .min(1.0);
+        let weighted = entry.prev_count as f64 * (1.0 - elapsed_fraction) + entry.curr_count as f64;
+
+        let result = if weighted + 1.0 > limit_f {
+            let retry_after = Self::retry_after_secs(
+                entry.prev_count,
+                entry.curr_count,
+                elapsed_fraction,
+                limit_f,
+                window_secs,
+            );
+            Err(retry_after)
+        } else {
+            entry.curr_count += 1;
+            let weighted_after =
+                entry.prev_count as f64 * (1.0 - elapsed_fraction) + entry.curr_count as f64;
+            Ok((limit_f - weighted_after.ceil()).max(0.0) as u32)
+        };
+
+        // Drop the shard guard before possibly scanning the whole map below -
+        // `evict_oldest` locks every shard in turn, which would deadlock if
+        // this identifier's shard were still held.
+        drop(entry);
+
+        if is_new_identifier && self.entries.len() > self.config.max_entries {
+            self.evict_oldest(self.entries.len() - self.config.max_entries);
         }
 
-        // Check if limit exceeded
-        if entry.count >= limit {
-            let retry_after = self.window_duration.as_secs()
-                - now.duration_since(entry.window_start).as_secs();
-            return Err(retry_after);
+        result
+    }
+
+    /// Removes entries whose window hasn't seen a request in over two full
+    /// windows - i.e. ones [`check_rate_limit`] would reset from scratch
+    /// anyway - so the identifier map doesn't grow unbounded from a stream
+    /// of one-off callers. Safe to call concurrently with normal traffic.
+    pub fn evict_expired(&self) {
+        let now = Instant::now();
+        let window_duration = self.window_duration;
+        self.entries
+            .retain(|_, entry| now.saturating_duration_since(entry.curr_window_start) < window_duration * 2);
+    }
+
+    /// Eagerly removes the `count` identifiers with the oldest
+    /// `curr_window_start`, used to enforce [`RateLimitConfig::max_entries`]
+    /// between reaper runs.
+    fn evict_oldest(&self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let mut by_age: Vec<(String, Instant)> = self
+            .entries
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.curr_window_start))
+            .collect();
+        by_age.sort_by_key(|(_, started)| *started);
+        for (key, _) in by_age.into_iter().take(count) {
+            self.entries.remove(&key);
         }
+    }
 
-        // Increment count
-        entry.count += 1;
-        Ok(limit - entry.count)
+    /// Number of identifiers currently tracked.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no identifiers are currently tracked.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Spawns a background task that calls [`Self::evict_expired`] on
+    /// `interval`. The task holds its own `Arc` to the limiter and loops
+    /// until it is aborted through the returned handle or the runtime shuts
+    /// down; dropping the handle does not stop it.
+    /// Must be called from within a Tokio runtime.
+    pub fn spawn_reaper(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let limiter = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                limiter.evict_expired();
+            }
+        })
+    }
+
+    /// Seconds until the weighted estimate would fall back to `limit`,
+    /// assuming no further requests arrive - i.e. how long `prev_count`'s
+    /// decaying contribution takes to bring the total back under the limit,
+    /// or until the window rolls over if `prev_count` is already zero.
+    fn retry_after_secs(
+        prev_count: u32,
+        curr_count: u32,
+        elapsed_fraction: f64,
+        limit: f64,
+        window_secs: f64,
+    ) -> u64 {
+        if prev_count == 0 {
+            return ((1.0 - elapsed_fraction) * window_secs).ceil().max(1.0) as u64;
+        }
+
+        let target_fraction =
+            (1.0 - (limit - curr_count as f64) / prev_count as f64).clamp(elapsed_fraction, 1.0);
+        ((target_fraction - elapsed_fraction) * window_secs).ceil().max(1.0) as u64
     }
 }
 
 /// Shared rate limiter state
 pub type SharedRateLimiter = Arc<RateLimiter>;
 
-/// Create a new shared rate limiter
+/// How often the background reaper sweeps expired identifiers - twice the
+/// window duration, since that's the point [`RateLimiter::check_rate_limit`]
+/// would reset an idle entry from scratch anyway.
+const REAP_INTERVAL: Duration = Duration::from_secs(120);
+
+/// Create a new shared rate limiter with hard-coded defaults. Used as the
+/// fallback when a request is somehow missing the `SharedRateLimiter`
+/// extension (app setup bug) - the real limiter installed at startup comes
+/// from [`create_rate_limiter_with_config`] instead. Deliberately doesn't
+/// spawn a reaper task, since this fallback runs inline per-request and
+/// should never fire in a correctly wired app.
 pub fn create_rate_limiter() -> SharedRateLimiter {
     Arc::new(RateLimiter::new(RateLimitConfig::default()))
 }
 
-/// Extract client IP from request
-fn get_client_ip(req: &Request<Body>) -> Option<IpAddr> {
-    // Try X-Forwarded-For header first (for proxied requests)
-    if let Some(forwarded) = req.headers().get("x-forwarded-for") {
-        if let Ok(forwarded_str) = forwarded.to_str() {
-            if let Some(first_ip) = forwarded_str.split(',').next() {
-                if let Ok(ip) = first_ip.trim().parse() {
-                    return Some(ip);
+/// Create a new shared rate limiter from a loaded [`RateLimitConfig`] (see
+/// `crate::config::AppConfig::load`), so operators can tune tiers via
+/// `config.toml`/env vars without recompiling. Also spawns a background
+/// reaper (see [`RateLimiter::spawn_reaper`]) that evicts expired
+/// identifiers on [`REAP_INTERVAL`], so the map can't grow unbounded over
+/// the life of the process. Must be called from within a Tokio runtime.
+pub fn create_rate_limiter_with_config(config: RateLimitConfig) -> SharedRateLimiter {
+    let limiter = Arc::new(RateLimiter::new(config));
+    limiter.spawn_reaper(REAP_INTERVAL);
+    limiter
+}
+
+/// Extract the real client IP from `req`, trusting forwarding headers only
+/// when the direct TCP peer is a configured trusted proxy. An untrusted peer
+/// can claim to be anyone via `X-Forwarded-For`, so in that case we use the
+/// peer address itself and ignore the header entirely.
+///
+/// When the peer *is* trusted, `X-Forwarded-For` is walked right-to-left
+/// (closest hop first) skipping further trusted-proxy entries, returning the
+/// first hop that isn't itself a trusted proxy - i.e. the real client, not
+/// an intermediate hop a proxy chain added.
+fn get_client_ip(req: &Request<Body>, limiter: &RateLimiter) -> Option<IpAddr> {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    let peer_is_trusted = matches!(peer, Some(ip) if limiter.is_trusted_proxy(ip));
+
+    if peer_is_trusted {
+        if let Some(forwarded) = req.headers().get("x-forwarded-for") {
+            if let Ok(forwarded_str) = forwarded.to_str() {
+                for hop in forwarded_str.split(',').rev() {
+                    if let Ok(ip) = hop.trim().parse::<IpAddr>() {
+                        if !limiter.is_trusted_proxy(ip) {
+                            return Some(ip);
+                        }
+                    }
                 }
             }
         }
-    }
 
-    // Try X-Real-IP header
-    if let Some(real_ip) = req.headers().get("x-real-ip") {
-        if let Ok(ip_str) = real_ip.to_str() {
-            if let Ok(ip) = ip_str.parse() {
-                return Some(ip);
+        if let Some(real_ip) = req.headers().get("x-real-ip") {
+            if let Ok(ip_str) = real_ip.to_str() {
+                if let Ok(ip) = ip_str.trim().parse() {
+                    return Some(ip);
+                }
             }
         }
     }
 
-    // Fallback: would need connection info which isn't available in middleware
-    None
+    peer
 }
 
 /// Rate limiting middleware for authenticated routes
@@ -152,58 +490,109 @@ pub async fn rate_limit_middleware(
         .cloned()
         .unwrap_or_else(create_rate_limiter);
 
+    let class = req
+        .extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map(|matched| classify_route(matched.as_str()))
+        .unwrap_or(LimitClass::Read);
+
+    let client_ip = get_client_ip(&req, &rate_limiter);
+    if let Some(ip) = client_ip {
+        if rate_limiter.is_deny_listed(ip) {
+            tracing::warn!("Rejecting request from deny-listed IP {}", ip);
+            return Ok(Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::from("Forbidden"))
+                .unwrap());
+        }
+        if rate_limiter.is_allow_listed(ip) {
+            return Ok(next.run(req).await);
+        }
+    }
+
     // Determine identifier and limit
     let (identifier, limit) = if let Some(auth_user) = req.extensions().get::<AuthUser>() {
-        // Authenticated user - use user_id and XP-based limit
+        // Authenticated user - use user_id and XP-based limit, unless the
+        // route's class has its own flat limit that overrides it.
         let xp = auth_user.xp;
-        let actual_limit = match xp {
+        let read_limit = match xp {
             0..=99 => rate_limiter.config.xp_0_99,
             100..=499 => rate_limiter.config.xp_100_499,
             500..=1999 => rate_limiter.config.xp_500_1999,
             _ => rate_limiter.config.xp_2000_plus,
         };
+        let actual_limit = match class {
+            LimitClass::CodeExecution => rate_limiter.config.code_execution,
+            _ => read_limit,
+        };
         (format!("user:{}", auth_user.user_id), actual_limit)
     } else {
         // Unauthenticated - use IP-based limit
-        let ip = get_client_ip(&req)
+        let ip = client_ip
             .map(|ip| ip.to_string())
             .unwrap_or_else(|| "unknown".to_string());
-        (format!("ip:{}", ip), rate_limiter.config.unauthenticated)
+        let actual_limit = match class {
+            LimitClass::CodeExecution => rate_limiter.config.code_execution,
+            _ => rate_limiter.config.unauthenticated,
+        };
+        (format!("ip:{}", ip), actual_limit)
     };
 
-    // Check rate limit
-    match rate_limiter.check_rate_limit(&identifier, limit) {
-        Ok(remaining) => {
-            // Add rate limit headers to response
-            let mut response = next.run(req).await;
-            response.headers_mut().insert(
-                "X-RateLimit-Limit",
-                limit.to_string().parse().unwrap(),
-            );
-            response.headers_mut().insert(
-                "X-RateLimit-Remaining",
-                remaining.to_string().parse().unwrap(),
-            );
-            Ok(response)
-        }
+    // Check the per-user/IP bucket for this class first, then the shared
+    // instance-wide bucket - either one being exhausted rejects the request.
+    let class_key = format!("{class}:{identifier}");
+    let remaining = match rate_limiter.check_rate_limit(&class_key, limit) {
+        Ok(remaining) => remaining,
         Err(retry_after) => {
-            tracing::warn!("Rate limit exceeded for {}", identifier);
-            let mut response = Response::builder()
-                .status(StatusCode::TOO_MANY_REQUESTS)
-                .body(Body::from("Rate limit exceeded"))
-                .unwrap();
-            response.headers_mut().insert(
-                "Retry-After",
-                retry_after.to_string().parse().unwrap(),
-            );
-            Err(StatusCode::TOO_MANY_REQUESTS)
+            tracing::warn!("{} rate limit exceeded for {}", class, identifier);
+            return Ok(rate_limit_exceeded_response(retry_after, class));
         }
+    };
+
+    if let Err(retry_after) =
+        rate_limiter.check_rate_limit("global", rate_limiter.config.global)
+    {
+        tracing::warn!("Global rate limit exceeded (triggered by {})", identifier);
+        return Ok(rate_limit_exceeded_response(retry_after, LimitClass::Global));
     }
+
+    // Add rate limit headers to response
+    let mut response = next.run(req).await;
+    response.headers_mut().insert(
+        "X-RateLimit-Limit",
+        limit.to_string().parse().unwrap(),
+    );
+    response.headers_mut().insert(
+        "X-RateLimit-Remaining",
+        remaining.to_string().parse().unwrap(),
+    );
+    Ok(response)
+}
+
+/// Builds the 429 response for a request rejected by `class`'s bucket,
+/// reporting which limit was hit so the caller can back off the right
+/// dimension instead of blindly retrying.
+fn rate_limit_exceeded_response(retry_after: u64, class: LimitClass) -> Response {
+    let mut response = Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .body(Body::from("Rate limit exceeded"))
+        .unwrap();
+    response.headers_mut().insert(
+        "Retry-After",
+        retry_after.to_string().parse().unwrap(),
+    );
+    response.headers_mut().insert(
+        "X-RateLimit-Limited-By",
+        class.to_string().parse().unwrap(),
+    );
+    response
 }
 
 /// Stricter rate limiting for auth endpoints
 ///
-/// Uses IP-based limiting with lower thresholds to prevent brute force attacks.
+/// Uses IP-based limiting with lower thresholds to prevent brute force
+/// attacks, and also counts against the shared global bucket so an auth
+/// flood can't starve other traffic either.
 pub async fn auth_rate_limit_middleware(
     req: Request<Body>,
     next: Next,
@@ -215,14 +604,33 @@ pub async fn auth_rate_limit_middleware(
         .unwrap_or_else(create_rate_limiter);
 
     // Always use IP for auth endpoints
-    let ip = get_client_ip(&req)
+    let client_ip = get_client_ip(&req, &rate_limiter);
+    if let Some(ip) = client_ip {
+        if rate_limiter.is_deny_listed(ip) {
+            tracing::warn!("Rejecting auth request from deny-listed IP {}", ip);
+            return Err(StatusCode::FORBIDDEN);
+        }
+        if rate_limiter.is_allow_listed(ip) {
+            return Ok(next.run(req).await);
+        }
+    }
+    let ip = client_ip
         .map(|ip| ip.to_string())
         .unwrap_or_else(|| "unknown".to_string());
-    let identifier = format!("auth:{}", ip);
+    let class_key = format!("{}:ip:{}", LimitClass::Auth, ip);
 
     // Use stricter auth endpoint limit
-    match rate_limiter.check_rate_limit(&identifier, rate_limiter.config.auth_endpoints) {
-        Ok(_) => Ok(next.run(req).await),
+    match rate_limiter.check_rate_limit(&class_key, rate_limiter.config.auth_endpoints) {
+        Ok(_) => {
+            if rate_limiter
+                .check_rate_limit("global", rate_limiter.config.global)
+                .is_err()
+            {
+                tracing::warn!("Global rate limit exceeded (triggered by auth IP {})", ip);
+                return Err(StatusCode::TOO_MANY_REQUESTS);
+            }
+            Ok(next.run(req).await)
+        }
         Err(_retry_after) => {
             tracing::warn!("Auth rate limit exceeded for IP {}", ip);
             Err(StatusCode::TOO_MANY_REQUESTS)
@@ -266,5 +674,101 @@ mod tests {
         let result = limiter.check_rate_limit("test", 30);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_rate_limiter_retry_after_is_positive_when_blocked() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+
+        for _ in 0..10 {
+            let _ = limiter.check_rate_limit("test", 10);
+        }
+
+        let retry_after = limiter
+            .check_rate_limit("test", 10)
+            .expect_err("limit should be exhausted");
+        assert!(retry_after > 0);
+    }
+
+    #[test]
+    fn test_rate_limiter_tracks_identifiers_independently() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+
+        for _ in 0..5 {
+            let _ = limiter.check_rate_limit("a", 5);
+        }
+        assert!(limiter.check_rate_limit("a", 5).is_err());
+
+        // A different identifier has its own window and isn't affected.
+        assert!(limiter.check_rate_limit("b", 5).is_ok());
+    }
+
+    #[test]
+    fn test_ip_cidr_matches_within_range() {
+        let cidr = IpCidr::parse("10.0.0.0/8").unwrap();
+        assert!(cidr.contains("10.1.2.3".parse().unwrap()));
+        assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_ip_cidr_rejects_cross_family_and_malformed() {
+        let cidr = IpCidr::parse("10.0.0.0/8").unwrap();
+        assert!(!cidr.contains("::1".parse().unwrap()));
+        assert!(IpCidr::parse("not-an-ip/8").is_none());
+        assert!(IpCidr::parse("10.0.0.0/33").is_none());
+    }
+
+    #[test]
+    fn test_deny_list_blocks_configured_ip() {
+        let mut config = RateLimitConfig::default();
+        config.deny_list = vec!["203.0.113.0/24".to_string()];
+        let limiter = RateLimiter::new(config);
+
+        assert!(limiter.is_deny_listed("203.0.113.5".parse().unwrap()));
+        assert!(!limiter.is_deny_listed("198.51.100.5".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_allow_list_exempts_configured_ip() {
+        let mut config = RateLimitConfig::default();
+        config.allow_list = vec!["192.168.0.0/16".to_string()];
+        let limiter = RateLimiter::new(config);
+
+        assert!(limiter.is_allow_listed("192.168.1.1".parse().unwrap()));
+        assert!(!limiter.is_allow_listed("10.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_evict_expired_keeps_fresh_entries() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        let _ = limiter.check_rate_limit("a", 30);
+        let _ = limiter.check_rate_limit("b", 30);
+
+        limiter.evict_expired();
+
+        assert_eq!(limiter.len(), 2);
+    }
+
+    #[test]
+    fn test_max_entries_caps_tracked_identifiers() {
+        let mut config = RateLimitConfig::default();
+        config.max_entries = 3;
+        let limiter = RateLimiter::new(config);
+
+        for i in 0..10 {
+            let _ = limiter.check_rate_limit(&format!("id-{i}"), 30);
+        }
+
+        assert!(limiter.len() <= 3, "expected eviction to cap at max_entries, got {}", limiter.len());
+    }
+
+    #[test]
+    fn test_len_and_is_empty() {
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        assert!(limiter.is_empty());
+
+        let _ = limiter.check_rate_limit("a", 30);
+        assert_eq!(limiter.len(), 1);
+        assert!(!limiter.is_empty());
+    }
 }
 
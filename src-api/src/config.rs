@@ -0,0 +1,131 @@
+//! Centralized application configuration, loaded once at startup from a
+//! `config.toml` file with individual fields overridable via environment
+//! variables - one typed root instead of the scattered `std::env::var` calls
+//! the rest of this crate otherwise relies on, for the settings operators
+//! most often want to retune without a recompile: rate limit tiers and
+//! transactional email branding.
+
+use serde::{Deserialize, Serialize};
+
+use crate::auth_middleware::RateLimitConfig;
+
+/// Branding and contact details baked into transactional emails, so an
+/// operator running their own deployment can re-skin them. Missing fields in
+/// `config.toml` fall back to the stock Code Warrior branding.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailBranding {
+    pub app_name: String,
+    pub logo_emoji: String,
+    pub tagline: String,
+    pub copyright_year: u32,
+    /// Linked from the welcome email's call-to-action button.
+    pub base_url: String,
+    pub support_email: String,
+    /// Brand accent gradient used on call-to-action buttons across templates.
+    pub primary_color: String,
+    pub primary_color_dark: String,
+}
+
+impl Default for EmailBranding {
+    fn default() -> Self {
+        Self {
+            app_name: "Code Warrior".to_string(),
+            logo_emoji: "⚔️".to_string(),
+            tagline: "Master C programming through adventure.".to_string(),
+            copyright_year: 2024,
+            base_url: "https://code-warrior-seven.vercel.app".to_string(),
+            support_email: "support@codewarrior.dev".to_string(),
+            primary_color: "#f59e0b".to_string(),
+            primary_color_dark: "#d97706".to_string(),
+        }
+    }
+}
+
+/// Root application config: `[rate_limit]` tiers plus `[email]` branding.
+/// Loaded once at startup via [`AppConfig::load`].
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AppConfig {
+    pub rate_limit: RateLimitConfig,
+    pub email: EmailBranding,
+}
+
+impl AppConfig {
+    /// Loads `config.toml` (path overridable via `CONFIG_PATH`), falling
+    /// back to defaults for any table/field the file omits - or entirely if
+    /// the file is missing or fails to parse, since every field here already
+    /// has a sensible built-in default. Environment variable overrides are
+    /// then applied on top, so a deployment can tweak one setting without
+    /// shipping a whole `config.toml`.
+    pub fn load() -> Self {
+        let path = std::env::var("CONFIG_PATH").unwrap_or_else(|_| "config.toml".to_string());
+        let mut config: Self = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        config.apply_env_overrides();
+        config
+    }
+
+    fn apply_env_overrides(&mut self) {
+        override_from_env("RATE_LIMIT_XP_0_99", &mut self.rate_limit.xp_0_99);
+        override_from_env("RATE_LIMIT_XP_100_499", &mut self.rate_limit.xp_100_499);
+        override_from_env("RATE_LIMIT_XP_500_1999", &mut self.rate_limit.xp_500_1999);
+        override_from_env("RATE_LIMIT_XP_2000_PLUS", &mut self.rate_limit.xp_2000_plus);
+        override_from_env(
+            "RATE_LIMIT_UNAUTHENTICATED",
+            &mut self.rate_limit.unauthenticated,
+        );
+        override_from_env(
+            "RATE_LIMIT_AUTH_ENDPOINTS",
+            &mut self.rate_limit.auth_endpoints,
+        );
+        override_from_env(
+            "RATE_LIMIT_CODE_EXECUTION",
+            &mut self.rate_limit.code_execution,
+        );
+        override_from_env("RATE_LIMIT_GLOBAL", &mut self.rate_limit.global);
+        override_list_from_env("RATE_LIMIT_TRUSTED_PROXIES", &mut self.rate_limit.trusted_proxies);
+        override_list_from_env("RATE_LIMIT_ALLOW_LIST", &mut self.rate_limit.allow_list);
+        override_list_from_env("RATE_LIMIT_DENY_LIST", &mut self.rate_limit.deny_list);
+        override_from_env("RATE_LIMIT_MAX_ENTRIES", &mut self.rate_limit.max_entries);
+
+        override_from_env("EMAIL_APP_NAME", &mut self.email.app_name);
+        override_from_env("EMAIL_LOGO_EMOJI", &mut self.email.logo_emoji);
+        override_from_env("EMAIL_TAGLINE", &mut self.email.tagline);
+        override_from_env("EMAIL_COPYRIGHT_YEAR", &mut self.email.copyright_year);
+        override_from_env("EMAIL_BASE_URL", &mut self.email.base_url);
+        override_from_env("EMAIL_SUPPORT_EMAIL", &mut self.email.support_email);
+        override_from_env("EMAIL_PRIMARY_COLOR", &mut self.email.primary_color);
+        override_from_env(
+            "EMAIL_PRIMARY_COLOR_DARK",
+            &mut self.email.primary_color_dark,
+        );
+    }
+}
+
+/// Overwrites `field` with the parsed value of env var `key`, leaving it
+/// untouched if the var is unset or fails to parse as `T`.
+fn override_from_env<T: std::str::FromStr>(key: &str, field: &mut T) {
+    if let Ok(value) = std::env::var(key) {
+        if let Ok(parsed) = value.parse() {
+            *field = parsed;
+        }
+    }
+}
+
+/// Overwrites `field` with the comma-separated entries of env var `key`
+/// (e.g. `"10.0.0.0/8,172.16.0.0/12"`), leaving it untouched if unset.
+/// Individual CIDR syntax isn't validated here - that happens when
+/// [`crate::auth_middleware::RateLimiter`] parses the list.
+fn override_list_from_env(key: &str, field: &mut Vec<String>) {
+    if let Ok(value) = std::env::var(key) {
+        *field = value
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+    }
+}
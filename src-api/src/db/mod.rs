@@ -8,9 +8,20 @@ pub mod schema;
 
 // Re-export only what's used by main.rs (auth handlers use operations directly)
 pub use operations::{
-    delete_save_slot_for_user, get_save_slot_by_user_id, get_session_by_user_id,
-    list_save_slots_by_user_id, update_user_session_state, upsert_save_slot_for_user,
-    upsert_session_by_user_id,
+    claim_session, consume_totp_backup_code, create_ban, create_push_subscription, create_room,
+    delete_push_subscription, delete_save_slot_for_user, delete_stale_email_tokens,
+    delete_totp_credential, enable_totp_credential, enqueue_device_command, get_active_ban,
+    get_device_for_user, get_fastest_completions, get_push_subscriptions, get_room,
+    get_save_slot_by_user_id, get_session_by_device_id, get_session_by_user_id,
+    get_totp_credential, get_user_level_duration_rank, get_user_level_xp_rank, get_user_xp_rank,
+    insert_submission, is_room_participant, join_level, join_room, leave_level, leave_room,
+    level_leaderboard, level_leaderboard_by_duration,
+    list_devices_for_user, list_pending_device_commands, list_room_participant_ids,
+    list_save_slots_by_user_id, list_submissions_by_user_level, mark_device_commands_delivered,
+    record_level_completion, register_device, replace_totp_backup_codes, revoke_ban,
+    top_users_by_xp, touch_device, update_session_state_by_device_id, update_user_session_state,
+    upsert_level_best_xp, upsert_save_slot_for_user, upsert_session_by_device_id,
+    upsert_session_by_user_id, upsert_totp_credential, SessionUpdateError,
 };
 
 use sqlx::{Pool, Postgres};
@@ -18,7 +29,23 @@ use sqlx::{Pool, Postgres};
 /// Type alias for the database connection pool
 pub type DbPool = Pool<Postgres>;
 
+/// A transaction in progress. `operations` functions that are generic over
+/// `impl sqlx::PgExecutor` accept either a `&DbPool` (standalone) or
+/// `&mut *tx` (as part of a larger atomic unit) - see `create_user`,
+/// `create_auth_session`, etc. Not every function has been converted yet;
+/// only the ones in flows known to have a partial-write hazard (account
+/// creation + OAuth link, auth session + refresh token issuance) have been
+/// so far. `DbPool::begin()` already gives a `Tx` directly, so this alias
+/// exists purely to give the pattern a name at call sites.
+pub type DbTx<'c> = sqlx::Transaction<'c, Postgres>;
+
 /// Initialize database tables (run migrations)
 pub async fn init_database(pool: &DbPool) -> Result<(), sqlx::Error> {
     schema::run_migrations(pool).await
 }
+
+/// Roll back the last `steps` applied migrations - see
+/// [`schema::migrate_down`].
+pub async fn migrate_down(pool: &DbPool, steps: usize) -> Result<(), sqlx::Error> {
+    schema::migrate_down(pool, steps).await
+}
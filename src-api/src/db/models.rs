@@ -19,6 +19,15 @@ pub struct User {
     pub total_xp: i32,
     pub last_login_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// Random per-user value embedded in every JWT claim; rotating it
+    /// invalidates every token issued before the rotation.
+    #[serde(skip_serializing)]
+    pub security_stamp: String,
+    /// Coarse permission level ("user", "admin", ...), embedded in the JWT so
+    /// `require_role` can check it without a DB round-trip. Defaults to
+    /// "user" in the database; promoting an account is a manual DB update for
+    /// now, same as there's no self-service way to set it at registration.
+    pub role: String,
 }
 
 /// Data for creating a new user (password or OAuth registration)
@@ -27,6 +36,7 @@ pub struct NewUser {
     pub email: String,
     pub username: Option<String>,
     pub password_hash: String, // Empty string for OAuth users
+    pub security_stamp: String,
 }
 
 /// OAuth connection linking a user to an OAuth provider
@@ -38,6 +48,11 @@ pub struct OAuthConnection {
     pub provider: String,
     pub provider_user_id: String,
     pub provider_email: Option<String>,
+    /// Wall-clock expiry of the stored access token - see
+    /// `refresh_oauth_connection`/`refresh_expiring_oauth_connections`,
+    /// which renew it before it lapses. `None` for providers/flows that
+    /// don't track expiry.
+    pub access_token_expires_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -51,6 +66,26 @@ pub struct NewOAuthConnection {
     pub provider_email: Option<String>,
     pub access_token: Option<String>,
     pub refresh_token: Option<String>,
+    /// Wall-clock expiry of `access_token`, converted from the provider's
+    /// monotonic `TokenSet::expires_at`. `None` for providers/flows that
+    /// don't track expiry (treated as effectively non-expiring).
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Space-delimited scopes the provider granted, as returned in the token
+    /// response. Stored as-is; parse with `oauth::ScopeSet` to check it.
+    pub scopes: Option<String>,
+}
+
+/// A connection that's due for its OAuth access token to be refreshed.
+/// Deliberately a separate, narrower type from [`OAuthConnection`] - which
+/// never fetches `access_token`/`refresh_token` - since the refresh routine
+/// is the one legitimate place that needs the stored refresh token back.
+#[derive(Debug, Clone, FromRow)]
+pub struct ExpiringOAuthConnection {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 /// Email verification or password reset token
@@ -74,16 +109,236 @@ pub struct NewEmailToken {
     pub expires_at: DateTime<Utc>,
 }
 
+/// Single-use invite required to register when `AuthState::require_invite` is
+/// on. Not tied to a `user_id` like [`EmailToken`] - the invitee doesn't have
+/// an account yet, so the row carries the invited email directly and
+/// `register` cross-checks it against the email being registered.
+/// Note: token_hash is stored/compared in DB but not fetched (security)
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Invite {
+    pub id: Uuid,
+    pub email: String,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Data for creating a new invite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewInvite {
+    pub email: String,
+    pub token_hash: String,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A multi-use, human-typed registration code (distinct from [`Invite`]'s
+/// single-use, emailed-link model) - e.g. a closed-beta code handed out for
+/// a new level pack. `uses` is bumped atomically against `max_uses` by
+/// `redeem_invite_code` rather than through a separate check-then-update,
+/// so concurrent redemptions can't oversell a code's `max_uses`.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct InviteCode {
+    pub id: Uuid,
+    pub code: String,
+    pub created_by: Option<Uuid>,
+    pub email: Option<String>,
+    pub max_uses: i32,
+    pub uses: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redeemed_by: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Data for creating a new [`InviteCode`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewInviteCode {
+    pub code: String,
+    pub created_by: Option<Uuid>,
+    pub email: Option<String>,
+    pub max_uses: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
 /// Stored game session with full game state
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct Session {
     pub id: Uuid,
     pub device_id: String,
     pub game_state: serde_json::Value,
+    /// Optimistic-concurrency counter, incremented on every write - see
+    /// `update_user_session_state`/`update_session_state_by_device_id`.
+    pub version: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// A logged-in device/browser, tracked by the jti of the token it was
+/// issued. Unrelated to `Session` above, which is a saved game state keyed
+/// by device - this is an auth concept ("which devices is this account
+/// signed in from"), so it gets its own table rather than overloading that
+/// one.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AuthSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub jti: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Data for creating a new auth session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewAuthSession {
+    pub user_id: Uuid,
+    pub jti: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+/// A rotatable refresh token, exchanged at `/api/auth/refresh` for a new
+/// (short-lived) access token without re-authenticating. Separate from
+/// `AuthSession`: a session tracks "is this device still logged in", while a
+/// refresh token is the actual rotating credential that keeps it that way.
+/// Note: token_hash is stored/compared in DB but not fetched (security)
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Data for creating a new refresh token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewRefreshToken {
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A registered WebAuthn/passkey credential, linked to the same account
+/// identity OAuth logins resolve to.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct WebAuthnCredential {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Base64url-encoded credential ID, as returned by the authenticator
+    pub credential_id: String,
+    /// Serialized `webauthn_rs::prelude::Passkey` (public key + metadata)
+    pub public_key: serde_json::Value,
+    pub sign_count: i64,
+    pub user_handle: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Data for creating a new WebAuthn credential
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewWebAuthnCredential {
+    pub user_id: Uuid,
+    pub credential_id: String,
+    pub public_key: serde_json::Value,
+    pub sign_count: i64,
+    pub user_handle: Uuid,
+}
+
+/// A registered client install (desktop/mobile/web). `id` is minted on first
+/// registration and is the persistent "device id" the client stores and
+/// presents on later requests - not to be confused with the synthetic
+/// `device_id` strings on `Session`/`SaveSlot`, which stay user-keyed by
+/// design. This table only backs device listing and the command queue below.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Device {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: Option<String>,
+    pub platform: Option<String>,
+    pub push_endpoint: Option<String>,
+    pub last_seen_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Data for registering a new device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewDevice {
+    pub user_id: Uuid,
+    pub name: Option<String>,
+    pub platform: Option<String>,
+    pub push_endpoint: Option<String>,
+}
+
+/// A queued remote command (e.g. "force resync", "sign out") waiting to be
+/// drained by its target device's next poll. `delivered_at` marks a command
+/// as handed out, not as acknowledged - same trust model as a push
+/// notification.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DeviceCommand {
+    pub id: Uuid,
+    pub device_id: Uuid,
+    pub command: serde_json::Value,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Data for enqueuing a new device command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewDeviceCommand {
+    pub device_id: Uuid,
+    pub command: serde_json::Value,
+}
+
+/// One row of `top_users_by_xp`: a player's rank-relevant stats, joined from
+/// `users` and `level_participants` rather than carried on `User` itself so
+/// a plain profile fetch doesn't pay for a `COUNT(DISTINCT ...)` it doesn't
+/// need.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub username: Option<String>,
+    pub total_xp: i32,
+    pub levels_completed: i64,
+}
+
+/// One row of `level_leaderboard_by_duration`: a player's cumulative
+/// completion time for one level, fastest first.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LevelLeaderboardEntry {
+    pub username: Option<String>,
+    pub total_duration_ms: i64,
+}
+
+/// One row of `level_leaderboard`: a level_session_participants' best XP at
+/// one level, highest first.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LevelXpLeaderboardEntry {
+    pub username: Option<String>,
+    pub best_xp: i32,
+}
+
+/// A single user's fastest recorded clear of one level, as returned by
+/// `get_fastest_completions`.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct FastestCompletion {
+    pub username: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// A user's TOTP second factor. `secret` is the base32 shared secret, not a
+/// hash - see the schema comment on `totp_credentials` for why this one
+/// table intentionally breaks the "store a digest, not the value" pattern.
+#[derive(Debug, Clone, FromRow)]
+pub struct TotpCredential {
+    pub user_id: Uuid,
+    pub secret: String,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
 /// Save slot for Save/Load feature
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct SaveSlot {
@@ -98,3 +353,145 @@ pub struct SaveSlot {
     pub updated_at: DateTime<Utc>,
 }
 
+/// One recorded TEST-or-SUBMIT attempt at a level or quest, so a player can
+/// review their submission history or restore a previous attempt's code.
+/// `quest_id` is `None` for a level-wide (non-quest) submission.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Submission {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub level_id: String,
+    pub quest_id: Option<String>,
+    pub code: String,
+    pub passed_count: i32,
+    pub total_count: i32,
+    pub execution_time_ms: Option<i64>,
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// A co-op room: several players work `level_id` together through one
+/// shared `GameState`, instead of each having their own progress for it.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Room {
+    pub id: Uuid,
+    pub level_id: String,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One ban/suspension entry against a user, superseding the old
+/// `users.is_suspended`/`is_blacklisted` booleans for `ban_check_middleware`
+/// - a row is active when `revoked_at IS NULL AND (expires_at IS NULL OR
+/// expires_at > NOW())`. `expires_at: None` is a permanent blacklist;
+/// `Some(_)` is a timed suspension that lapses on its own once the deadline
+/// passes, with no admin action needed. See [`get_active_ban`].
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Ban {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub issued_by: Option<Uuid>,
+    pub reason: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Data for issuing a new ban (see [`Ban`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewBan {
+    pub user_id: Uuid,
+    pub issued_by: Option<Uuid>,
+    pub reason: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A grant of read (or read+copy) access to one player's `SaveSlot`s to
+/// another ("trusted contact") account - e.g. a parent or mentor checking in
+/// on a learner's progress. Follows the same invite-then-accept shape as
+/// [`Invite`]: addressed to `grantee_email` before the grantee's identity is
+/// known, with `grantee_id`/`accepted_at` filled in once it's accepted (see
+/// `accept_trusted_contact_grants`, also run automatically on registration).
+/// `wait_days` delays `get_shared_save_slots` from returning a freshly
+/// accepted grant's rows until that many days after `accepted_at`, so the
+/// owner has a window to notice and the grantee can't get instant access.
+/// `owner_id`/`grantee_id` both carry `ON DELETE CASCADE`, so deleting
+/// either account removes the grant row outright rather than leaving it
+/// dangling for the join in `get_shared_save_slots` to trip over.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SaveSlotGrant {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub grantee_email: String,
+    pub grantee_id: Option<Uuid>,
+    pub access_level: String,
+    pub wait_days: i32,
+    pub created_at: DateTime<Utc>,
+    pub accepted_at: Option<DateTime<Utc>>,
+}
+
+/// Data for creating a new trusted-contact grant (see [`SaveSlotGrant`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewSaveSlotGrant {
+    pub owner_id: Uuid,
+    pub grantee_email: String,
+    pub access_level: String,
+    pub wait_days: i32,
+}
+
+/// One save slot visible to a grantee via an active `SaveSlotGrant` - the
+/// same fields as `SaveSlot`, plus which owner it belongs to and what the
+/// grantee is allowed to do with it, as returned by `get_shared_save_slots`.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SharedSaveSlot {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub owner_email: String,
+    pub slot_name: String,
+    pub save_data: serde_json::Value,
+    pub total_xp: i32,
+    pub levels_completed: i32,
+    pub current_level: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub access_level: String,
+}
+
+/// One row of `list_users_overview`: the fields the admin moderation API
+/// needs per user, with their current ban (if any, per [`get_active_ban`])
+/// already joined in rather than making the caller fetch it per row.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct UserOverview {
+    pub id: Uuid,
+    pub email: String,
+    pub username: Option<String>,
+    pub total_xp: i32,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub ban_id: Option<Uuid>,
+    pub ban_reason: Option<String>,
+    pub ban_expires_at: Option<DateTime<Utc>>,
+}
+
+/// A browser's Web Push subscription (one per device/browser a player has
+/// granted notification permission on), as handed to us by the
+/// `PushManager.subscribe()` result on the client. `p256dh`/`auth` are the
+/// subscriber's ECDH public key and auth secret, both base64url-encoded by
+/// the browser - `push::webpush` decodes them at send time, they're opaque
+/// to everything else.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PushSubscription {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Data for creating a new push subscription (see [`PushSubscription`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewPushSubscription {
+    pub user_id: Uuid,
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+}
+
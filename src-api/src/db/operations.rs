@@ -1,27 +1,41 @@
 //! Database CRUD operations for sessions, player progress, and authentication
 
 use super::models::{
-    EmailToken, NewEmailToken, NewOAuthConnection, NewUser,
-    OAuthConnection, SaveSlot, Session, User,
+    AuthSession, Ban, Device, DeviceCommand, EmailToken, ExpiringOAuthConnection,
+    FastestCompletion, Invite, InviteCode, LeaderboardEntry, LevelLeaderboardEntry,
+    LevelXpLeaderboardEntry, NewAuthSession, NewBan, NewDevice, NewDeviceCommand, NewEmailToken,
+    NewInvite, NewInviteCode, NewOAuthConnection, NewPushSubscription, NewRefreshToken,
+    NewSaveSlotGrant, NewUser, NewWebAuthnCredential, OAuthConnection, PushSubscription,
+    RefreshToken, Room, SaveSlot, SaveSlotGrant, Session, SharedSaveSlot, Submission,
+    TotpCredential, User, UserOverview, WebAuthnCredential,
 };
 use super::DbPool;
 
-use serde_json::Value;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::collections::HashSet;
 use uuid::Uuid;
 
-/// Create a new user with password
-pub async fn create_user(pool: &DbPool, user: &NewUser) -> Result<User, sqlx::Error> {
+/// Create a new user with password. Takes a generic executor (a `&DbPool` or
+/// a `&mut` transaction) so callers that also create a dependent row in the
+/// same logical request - an OAuth connection, an auth session - can run
+/// both inside one transaction instead of risking an orphaned half-write.
+pub async fn create_user<'e, E>(executor: E, user: &NewUser) -> Result<User, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     sqlx::query_as::<_, User>(
         r#"
-        INSERT INTO users (email, username, password_hash)
-        VALUES ($1, $2, $3)
-        RETURNING id, email, username, password_hash, email_verified, is_suspended, is_blacklisted, total_xp, last_login_at, created_at
+        INSERT INTO users (email, username, password_hash, security_stamp)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, email, username, password_hash, email_verified, is_suspended, is_blacklisted, total_xp, last_login_at, created_at, security_stamp, role
         "#,
     )
     .bind(&user.email)
     .bind(&user.username)
     .bind(&user.password_hash)
-    .fetch_one(pool)
+    .bind(&user.security_stamp)
+    .fetch_one(executor)
     .await
 }
 
@@ -29,7 +43,7 @@ pub async fn create_user(pool: &DbPool, user: &NewUser) -> Result<User, sqlx::Er
 pub async fn get_user_by_id(pool: &DbPool, user_id: Uuid) -> Result<Option<User>, sqlx::Error> {
     sqlx::query_as::<_, User>(
         r#"
-        SELECT id, email, username, password_hash, email_verified, is_suspended, is_blacklisted, total_xp, last_login_at, created_at
+        SELECT id, email, username, password_hash, email_verified, is_suspended, is_blacklisted, total_xp, last_login_at, created_at, security_stamp, role
         FROM users
         WHERE id = $1
         "#,
@@ -43,7 +57,7 @@ pub async fn get_user_by_id(pool: &DbPool, user_id: Uuid) -> Result<Option<User>
 pub async fn get_user_by_email(pool: &DbPool, email: &str) -> Result<Option<User>, sqlx::Error> {
     sqlx::query_as::<_, User>(
         r#"
-        SELECT id, email, username, password_hash, email_verified, is_suspended, is_blacklisted, total_xp, last_login_at, created_at
+        SELECT id, email, username, password_hash, email_verified, is_suspended, is_blacklisted, total_xp, last_login_at, created_at, security_stamp, role
         FROM users
         WHERE LOWER(email) = LOWER($1)
         "#,
@@ -60,7 +74,7 @@ pub async fn get_user_by_username(
 ) -> Result<Option<User>, sqlx::Error> {
     sqlx::query_as::<_, User>(
         r#"
-        SELECT id, email, username, password_hash, email_verified, is_suspended, is_blacklisted, total_xp, last_login_at, created_at
+        SELECT id, email, username, password_hash, email_verified, is_suspended, is_blacklisted, total_xp, last_login_at, created_at, security_stamp, role
         FROM users
         WHERE LOWER(username) = LOWER($1)
         "#,
@@ -70,6 +84,107 @@ pub async fn get_user_by_username(
     .await
 }
 
+/// Get a user's current security stamp, for validating a JWT claim without
+/// fetching (and discarding) the whole `User` row.
+pub async fn get_security_stamp(
+    pool: &DbPool,
+    user_id: Uuid,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT security_stamp
+        FROM users
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Rotate a user's security stamp to `new_stamp`. Every JWT minted before
+/// this call carries the old stamp and will fail validation against it.
+pub async fn rotate_security_stamp(
+    pool: &DbPool,
+    user_id: Uuid,
+    new_stamp: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET security_stamp = $2
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(new_stamp)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Update a user's password hash and rotate their security stamp in the
+/// same transaction, so a reset link can't land while a session minted
+/// under the old password is still mid-flight.
+pub async fn update_password_and_rotate_stamp(
+    pool: &DbPool,
+    user_id: Uuid,
+    password_hash: &str,
+    new_stamp: &str,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET password_hash = $2
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(password_hash)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET security_stamp = $2
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(new_stamp)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+/// Replace a user's password hash in place, without rotating their security
+/// stamp or touching any other column. Used to transparently migrate a hash
+/// onto stronger Argon2 parameters after a successful login - unlike
+/// `update_password_and_rotate_stamp`, this isn't a credential change the
+/// user asked for, so it shouldn't sign out their other sessions.
+pub async fn update_password_hash(
+    pool: &DbPool,
+    user_id: Uuid,
+    password_hash: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET password_hash = $2
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(password_hash)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 /// Update user's last login timestamp
 pub async fn update_last_login(pool: &DbPool, user_id: Uuid) -> Result<(), sqlx::Error> {
     sqlx::query(
@@ -85,8 +200,12 @@ pub async fn update_last_login(pool: &DbPool, user_id: Uuid) -> Result<(), sqlx:
     Ok(())
 }
 
-/// Mark user's email as verified
-pub async fn verify_user_email(pool: &DbPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+/// Mark user's email as verified. Generic over the executor so the OAuth
+/// new-account path can run this in the same transaction as `create_user`.
+pub async fn verify_user_email<'e, E>(executor: E, user_id: Uuid) -> Result<(), sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     sqlx::query(
         r#"
         UPDATE users
@@ -95,6 +214,28 @@ pub async fn verify_user_email(pool: &DbPool, user_id: Uuid) -> Result<(), sqlx:
         "#,
     )
     .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Update a user's email (as part of the change-email confirm flow) and
+/// re-mark it verified, since confirming the token already proves ownership
+/// of the new address.
+pub async fn update_user_email(
+    pool: &DbPool,
+    user_id: Uuid,
+    new_email: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET email = $2, email_verified = TRUE
+        WHERE id = $1
+        "#,
+    )
+    .bind(user_id)
+    .bind(new_email)
     .execute(pool)
     .await?;
     Ok(())
@@ -121,15 +262,21 @@ pub async fn update_user_password(
 }
 
 /// Create a new OAuth connection
-pub async fn create_oauth_connection(
-    pool: &DbPool,
+/// Create a new OAuth connection. Generic over the executor for the same
+/// reason as `create_user` - `find_or_create_oauth_user` runs this and a
+/// fresh `create_user` in one transaction when linking a brand-new account.
+pub async fn create_oauth_connection<'e, E>(
+    executor: E,
     conn: &NewOAuthConnection,
-) -> Result<OAuthConnection, sqlx::Error> {
+) -> Result<OAuthConnection, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
     sqlx::query_as::<_, OAuthConnection>(
         r#"
-        INSERT INTO oauth_connections (user_id, provider, provider_user_id, provider_email, access_token, refresh_token)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        RETURNING id, user_id, provider, provider_user_id, provider_email, access_token, refresh_token, created_at, updated_at
+        INSERT INTO oauth_connections (user_id, provider, provider_user_id, provider_email, access_token, refresh_token, expires_at, scopes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, user_id, provider, provider_user_id, provider_email, expires_at AS access_token_expires_at, created_at, updated_at
         "#,
     )
     .bind(conn.user_id)
@@ -138,7 +285,9 @@ pub async fn create_oauth_connection(
     .bind(&conn.provider_email)
     .bind(&conn.access_token)
     .bind(&conn.refresh_token)
-    .fetch_one(pool)
+    .bind(conn.expires_at)
+    .bind(&conn.scopes)
+    .fetch_one(executor)
     .await
 }
 
@@ -150,7 +299,7 @@ pub async fn get_oauth_connection(
 ) -> Result<Option<OAuthConnection>, sqlx::Error> {
     sqlx::query_as::<_, OAuthConnection>(
         r#"
-        SELECT id, user_id, provider, provider_user_id, provider_email, created_at, updated_at
+        SELECT id, user_id, provider, provider_user_id, provider_email, expires_at AS access_token_expires_at, created_at, updated_at
         FROM oauth_connections
         WHERE provider = $1 AND provider_user_id = $2
         "#,
@@ -161,6 +310,152 @@ pub async fn get_oauth_connection(
     .await
 }
 
+/// Get every provider identity linked to a user's account
+pub async fn get_oauth_connections_for_user(
+    pool: &DbPool,
+    user_id: Uuid,
+) -> Result<Vec<OAuthConnection>, sqlx::Error> {
+    sqlx::query_as::<_, OAuthConnection>(
+        r#"
+        SELECT id, user_id, provider, provider_user_id, provider_email, expires_at AS access_token_expires_at, created_at, updated_at
+        FROM oauth_connections
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Write back a refreshed access/refresh token pair after exchanging an
+/// expiring OAuth connection's refresh token at the provider
+pub async fn update_oauth_tokens(
+    pool: &DbPool,
+    connection_id: Uuid,
+    access_token: &str,
+    refresh_token: Option<&str>,
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE oauth_connections
+        SET access_token = $2, refresh_token = COALESCE($3, refresh_token), expires_at = $4, updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(connection_id)
+    .bind(access_token)
+    .bind(refresh_token)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// List OAuth connections whose access token expires before `before` and
+/// that have a refresh token on file to renew it with
+pub async fn get_expiring_oauth_connections(
+    pool: &DbPool,
+    before: DateTime<Utc>,
+) -> Result<Vec<ExpiringOAuthConnection>, sqlx::Error> {
+    sqlx::query_as::<_, ExpiringOAuthConnection>(
+        r#"
+        SELECT id, user_id, provider, refresh_token, expires_at
+        FROM oauth_connections
+        WHERE expires_at IS NOT NULL AND expires_at < $1 AND refresh_token IS NOT NULL
+        "#,
+    )
+    .bind(before)
+    .fetch_all(pool)
+    .await
+}
+
+/// Look up one user's connection to `provider` for an on-demand refresh
+/// (`refresh_oauth_connection`) - same shape as [`ExpiringOAuthConnection`]
+/// but fetched regardless of expiry, since the caller decides whether a
+/// refresh is actually due.
+pub async fn get_oauth_connection_for_refresh(
+    pool: &DbPool,
+    user_id: Uuid,
+    provider: &str,
+) -> Result<Option<ExpiringOAuthConnection>, sqlx::Error> {
+    sqlx::query_as::<_, ExpiringOAuthConnection>(
+        r#"
+        SELECT id, user_id, provider, refresh_token, expires_at
+        FROM oauth_connections
+        WHERE user_id = $1 AND provider = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(provider)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Resync `provider_email` after a token refresh re-fetches the provider's
+/// profile - the provider account's email may have changed since the
+/// connection was first linked.
+pub async fn update_oauth_profile(
+    pool: &DbPool,
+    connection_id: Uuid,
+    provider_email: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE oauth_connections
+        SET provider_email = $2, updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(connection_id)
+    .bind(provider_email)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Backfill a user's `username` from their OAuth profile if they don't
+/// already have one - never overwrites a name the player already set.
+pub async fn backfill_username(
+    pool: &DbPool,
+    user_id: Uuid,
+    username: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET username = $2
+        WHERE id = $1 AND username IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(username)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Unlink a provider identity from a user's account
+pub async fn delete_oauth_connection(
+    pool: &DbPool,
+    user_id: Uuid,
+    provider: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM oauth_connections
+        WHERE user_id = $1 AND provider = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(provider)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 /// Create a new email token (verification or reset)
 pub async fn create_email_token(
     pool: &DbPool,
@@ -227,6 +522,22 @@ pub async fn mark_email_token_used(pool: &DbPool, token_id: Uuid) -> Result<(),
     Ok(())
 }
 
+/// Delete email tokens that are no longer useful - either past their expiry
+/// or already consumed. `get_email_token_by_hash` already excludes these from
+/// lookups, so this is pure housekeeping, not a correctness fix; it just
+/// keeps the table from growing forever. Returns the number of rows removed.
+pub async fn delete_stale_email_tokens(pool: &DbPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM email_tokens
+        WHERE expires_at < NOW() OR used_at IS NOT NULL
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
 /// Get session by user ID
 pub async fn get_session_by_user_id(
     pool: &DbPool,
@@ -234,7 +545,7 @@ pub async fn get_session_by_user_id(
 ) -> Result<Option<Session>, sqlx::Error> {
     sqlx::query_as::<_, Session>(
         r#"
-        SELECT id, device_id, game_state, created_at, updated_at
+        SELECT id, device_id, game_state, version, created_at, updated_at
         FROM sessions
         WHERE user_id = $1
         "#,
@@ -244,24 +555,55 @@ pub async fn get_session_by_user_id(
     .await
 }
 
-/// Update session state for a user
+/// Why a compare-and-swap session write didn't go through.
+#[derive(Debug, thiserror::Error)]
+pub enum SessionUpdateError {
+    /// `expected_version` didn't match the row's current `version` - another
+    /// client (or another device of the same account) wrote in between. The
+    /// session as it actually stands in the DB is attached so the caller can
+    /// merge instead of just failing the save outright.
+    #[error("session was updated by another client")]
+    Conflict(Session),
+
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Update session state for a user, but only if `expected_version` still
+/// matches what's stored - see `SessionUpdateError::Conflict`. Succeeds with
+/// the freshly-bumped `Session` (so the caller can hand the new version back
+/// to the client for its next save) or fails with the row as it currently
+/// stands so the two can be merged instead of one silently clobbering the
+/// other.
 pub async fn update_user_session_state(
     pool: &DbPool,
     user_id: Uuid,
     state: &Value,
-) -> Result<(), sqlx::Error> {
-    sqlx::query(
+    expected_version: i32,
+) -> Result<Session, SessionUpdateError> {
+    let updated = sqlx::query_as::<_, Session>(
         r#"
         UPDATE sessions
-        SET game_state = $2, updated_at = NOW()
-        WHERE user_id = $1
+        SET game_state = $2, version = version + 1, updated_at = NOW()
+        WHERE user_id = $1 AND version = $3
+        RETURNING id, device_id, game_state, version, created_at, updated_at
         "#,
     )
     .bind(user_id)
     .bind(state)
-    .execute(pool)
+    .bind(expected_version)
+    .fetch_optional(pool)
     .await?;
-    Ok(())
+
+    match updated {
+        Some(session) => Ok(session),
+        None => {
+            let current = get_session_by_user_id(pool, user_id)
+                .await?
+                .ok_or(sqlx::Error::RowNotFound)?;
+            Err(SessionUpdateError::Conflict(current))
+        }
+    }
 }
 
 // ============================================================================
@@ -283,8 +625,8 @@ pub async fn upsert_session_by_user_id(
         INSERT INTO sessions (user_id, device_id, game_state)
         VALUES ($1, $2, $3)
         ON CONFLICT (device_id)
-        DO UPDATE SET user_id = $1, game_state = $3, updated_at = NOW()
-        RETURNING id, device_id, game_state, created_at, updated_at
+        DO UPDATE SET user_id = $1, game_state = $3, version = sessions.version + 1, updated_at = NOW()
+        RETURNING id, device_id, game_state, version, created_at, updated_at
         "#,
     )
     .bind(user_id)
@@ -294,65 +636,235 @@ pub async fn upsert_session_by_user_id(
     .await
 }
 
-/// List all save slots for a user (authenticated)
-pub async fn list_save_slots_by_user_id(
+/// Get a session by its raw `device_id`, e.g. a room's synthetic
+/// `"room-{id}"` key - see [`SessionScope`](crate) usage in main.rs.
+pub async fn get_session_by_device_id(
     pool: &DbPool,
-    user_id: Uuid,
-) -> Result<Vec<SaveSlot>, sqlx::Error> {
-    sqlx::query_as::<_, SaveSlot>(
+    device_id: &str,
+) -> Result<Option<Session>, sqlx::Error> {
+    sqlx::query_as::<_, Session>(
         r#"
-        SELECT id, device_id, slot_name, save_data, total_xp, levels_completed, current_level, created_at, updated_at
-        FROM save_slots
-        WHERE user_id = $1
-        ORDER BY updated_at DESC
+        SELECT id, device_id, game_state, version, created_at, updated_at
+        FROM sessions
+        WHERE device_id = $1
         "#,
     )
-    .bind(user_id)
-    .fetch_all(pool)
+    .bind(device_id)
+    .fetch_optional(pool)
     .await
 }
 
-/// Get a specific save slot for a user (authenticated)
-pub async fn get_save_slot_by_user_id(
+/// Create or update a session keyed by a raw `device_id` rather than a
+/// single owning user - used for a co-op room's shared session, where
+/// `user_id` is `None` since no one player owns it.
+pub async fn upsert_session_by_device_id(
     pool: &DbPool,
-    user_id: Uuid,
-    slot_name: &str,
-) -> Result<Option<SaveSlot>, sqlx::Error> {
-    sqlx::query_as::<_, SaveSlot>(
+    device_id: &str,
+    user_id: Option<Uuid>,
+    game_state: &Value,
+) -> Result<Session, sqlx::Error> {
+    sqlx::query_as::<_, Session>(
         r#"
-        SELECT id, device_id, slot_name, save_data, total_xp, levels_completed, current_level, created_at, updated_at
-        FROM save_slots
-        WHERE user_id = $1 AND slot_name = $2
+        INSERT INTO sessions (user_id, device_id, game_state)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (device_id)
+        DO UPDATE SET game_state = $3, version = sessions.version + 1, updated_at = NOW()
+        RETURNING id, device_id, game_state, version, created_at, updated_at
         "#,
     )
     .bind(user_id)
-    .bind(slot_name)
-    .fetch_optional(pool)
+    .bind(device_id)
+    .bind(game_state)
+    .fetch_one(pool)
     .await
 }
 
-/// Create or update a save slot for a user (authenticated)
-pub async fn upsert_save_slot_for_user(
+/// Update session state by raw `device_id` (see
+/// [`upsert_session_by_device_id`]), compare-and-swapping on
+/// `expected_version` the same way [`update_user_session_state`] does.
+pub async fn update_session_state_by_device_id(
     pool: &DbPool,
-    user_id: Uuid,
-    slot_name: &str,
-    save_data: &Value,
-    total_xp: i32,
-    levels_completed: i32,
-    current_level: Option<&str>,
-) -> Result<SaveSlot, sqlx::Error> {
-    // Use a synthetic device_id for the constraint
-    let synthetic_device_id = format!("user-{}", user_id);
-
-    sqlx::query_as::<_, SaveSlot>(
+    device_id: &str,
+    state: &Value,
+    expected_version: i32,
+) -> Result<Session, SessionUpdateError> {
+    let updated = sqlx::query_as::<_, Session>(
         r#"
-        INSERT INTO save_slots (user_id, device_id, slot_name, save_data, total_xp, levels_completed, current_level)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
-        ON CONFLICT (device_id, slot_name)
-        DO UPDATE SET
-            user_id = $1,
-            save_data = $4,
-            total_xp = $5,
+        UPDATE sessions
+        SET game_state = $2, version = version + 1, updated_at = NOW()
+        WHERE device_id = $1 AND version = $3
+        RETURNING id, device_id, game_state, version, created_at, updated_at
+        "#,
+    )
+    .bind(device_id)
+    .bind(state)
+    .bind(expected_version)
+    .fetch_optional(pool)
+    .await?;
+
+    match updated {
+        Some(session) => Ok(session),
+        None => {
+            let current = get_session_by_device_id(pool, device_id)
+                .await?
+                .ok_or(sqlx::Error::RowNotFound)?;
+            Err(SessionUpdateError::Conflict(current))
+        }
+    }
+}
+
+/// Attach an anonymous device's session to `user_id`, carrying its progress
+/// over - the `POST /auth/link-device` path for a player who started
+/// anonymously and then registered/logged in. If the account already has its
+/// own session, the device's progress is merged into it first (union of
+/// `completed_levels`, the higher of the two `total_xp`) rather than
+/// overwritten, so playing a level anonymously before linking isn't wasted
+/// work. Runs in one transaction, row-locking both sessions first, so a
+/// concurrent claim or sync can't interleave with the merge.
+pub async fn claim_session(
+    pool: &DbPool,
+    device_id: &str,
+    user_id: Uuid,
+) -> Result<Session, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let device_session = sqlx::query_as::<_, Session>(
+        r#"
+        SELECT id, device_id, game_state, version, created_at, updated_at
+        FROM sessions
+        WHERE device_id = $1
+        FOR UPDATE
+        "#,
+    )
+    .bind(device_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let account_session = sqlx::query_as::<_, Session>(
+        r#"
+        SELECT id, device_id, game_state, version, created_at, updated_at
+        FROM sessions
+        WHERE user_id = $1 AND device_id != $2
+        FOR UPDATE
+        "#,
+    )
+    .bind(user_id)
+    .bind(device_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let merged_state = match &account_session {
+        Some(account_session) => merge_progression(&device_session.game_state, &account_session.game_state),
+        None => device_session.game_state.clone(),
+    };
+
+    let claimed = sqlx::query_as::<_, Session>(
+        r#"
+        UPDATE sessions
+        SET user_id = $1, game_state = $2, version = version + 1, updated_at = NOW()
+        WHERE device_id = $3
+        RETURNING id, device_id, game_state, version, created_at, updated_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(&merged_state)
+    .bind(device_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if let Some(account_session) = account_session {
+        sqlx::query("DELETE FROM sessions WHERE id = $1")
+            .bind(account_session.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(claimed)
+}
+
+/// Fold `account`'s `progression.total_xp`/`completed_levels` into `device`'s,
+/// keeping every other field (player position, world, current level, ...)
+/// from `device` - it's the session the player was actually just in.
+fn merge_progression(device: &Value, account: &Value) -> Value {
+    let mut merged = device.clone();
+
+    let device_xp = device["progression"]["total_xp"].as_u64().unwrap_or(0);
+    let account_xp = account["progression"]["total_xp"].as_u64().unwrap_or(0);
+
+    let device_levels: HashSet<String> =
+        serde_json::from_value(device["progression"]["completed_levels"].clone()).unwrap_or_default();
+    let account_levels: HashSet<String> =
+        serde_json::from_value(account["progression"]["completed_levels"].clone()).unwrap_or_default();
+    let completed_levels: HashSet<String> = device_levels.union(&account_levels).cloned().collect();
+
+    if let Some(progression) = merged.get_mut("progression") {
+        progression["total_xp"] = json!(device_xp.max(account_xp));
+        progression["completed_levels"] = json!(completed_levels);
+    }
+
+    merged
+}
+
+/// List all save slots for a user (authenticated)
+pub async fn list_save_slots_by_user_id(
+    pool: &DbPool,
+    user_id: Uuid,
+) -> Result<Vec<SaveSlot>, sqlx::Error> {
+    sqlx::query_as::<_, SaveSlot>(
+        r#"
+        SELECT id, device_id, slot_name, save_data, total_xp, levels_completed, current_level, created_at, updated_at
+        FROM save_slots
+        WHERE user_id = $1
+        ORDER BY updated_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Get a specific save slot for a user (authenticated)
+pub async fn get_save_slot_by_user_id(
+    pool: &DbPool,
+    user_id: Uuid,
+    slot_name: &str,
+) -> Result<Option<SaveSlot>, sqlx::Error> {
+    sqlx::query_as::<_, SaveSlot>(
+        r#"
+        SELECT id, device_id, slot_name, save_data, total_xp, levels_completed, current_level, created_at, updated_at
+        FROM save_slots
+        WHERE user_id = $1 AND slot_name = $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(slot_name)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Create or update a save slot for a user (authenticated)
+pub async fn upsert_save_slot_for_user(
+    pool: &DbPool,
+    user_id: Uuid,
+    slot_name: &str,
+    save_data: &Value,
+    total_xp: i32,
+    levels_completed: i32,
+    current_level: Option<&str>,
+) -> Result<SaveSlot, sqlx::Error> {
+    // Use a synthetic device_id for the constraint
+    let synthetic_device_id = format!("user-{}", user_id);
+
+    sqlx::query_as::<_, SaveSlot>(
+        r#"
+        INSERT INTO save_slots (user_id, device_id, slot_name, save_data, total_xp, levels_completed, current_level)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (device_id, slot_name)
+        DO UPDATE SET
+            user_id = $1,
+            save_data = $4,
+            total_xp = $5,
             levels_completed = $6,
             current_level = $7,
             updated_at = NOW()
@@ -370,6 +882,83 @@ pub async fn upsert_save_slot_for_user(
     .await
 }
 
+/// Create a new WebAuthn credential for a user
+pub async fn create_webauthn_credential(
+    pool: &DbPool,
+    credential: &NewWebAuthnCredential,
+) -> Result<WebAuthnCredential, sqlx::Error> {
+    sqlx::query_as::<_, WebAuthnCredential>(
+        r#"
+        INSERT INTO webauthn_credentials (user_id, credential_id, public_key, sign_count, user_handle)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, user_id, credential_id, public_key, sign_count, user_handle, created_at
+        "#,
+    )
+    .bind(credential.user_id)
+    .bind(&credential.credential_id)
+    .bind(&credential.public_key)
+    .bind(credential.sign_count)
+    .bind(credential.user_handle)
+    .fetch_one(pool)
+    .await
+}
+
+/// Get all WebAuthn credentials enrolled for a user
+pub async fn get_webauthn_credentials_for_user(
+    pool: &DbPool,
+    user_id: Uuid,
+) -> Result<Vec<WebAuthnCredential>, sqlx::Error> {
+    sqlx::query_as::<_, WebAuthnCredential>(
+        r#"
+        SELECT id, user_id, credential_id, public_key, sign_count, user_handle, created_at
+        FROM webauthn_credentials
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Get a single WebAuthn credential by its credential ID (used during login,
+/// before we know which user is authenticating)
+pub async fn get_webauthn_credential_by_id(
+    pool: &DbPool,
+    credential_id: &str,
+) -> Result<Option<WebAuthnCredential>, sqlx::Error> {
+    sqlx::query_as::<_, WebAuthnCredential>(
+        r#"
+        SELECT id, user_id, credential_id, public_key, sign_count, user_handle, created_at
+        FROM webauthn_credentials
+        WHERE credential_id = $1
+        "#,
+    )
+    .bind(credential_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Update the stored signature counter after a successful authentication
+pub async fn update_webauthn_sign_count(
+    pool: &DbPool,
+    credential_id: &str,
+    sign_count: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE webauthn_credentials
+        SET sign_count = $2
+        WHERE credential_id = $1
+        "#,
+    )
+    .bind(credential_id)
+    .bind(sign_count)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
 /// Delete a save slot for a user (authenticated)
 pub async fn delete_save_slot_for_user(
     pool: &DbPool,
@@ -389,3 +978,1245 @@ pub async fn delete_save_slot_for_user(
 
     Ok(())
 }
+
+/// Record a new logged-in device/browser for a user. Generic over the
+/// executor so `start_session` can create this row and the paired refresh
+/// token in one transaction - a session with no usable refresh token is as
+/// much an orphaned write as the reverse.
+pub async fn create_auth_session<'e, E>(
+    executor: E,
+    session: &NewAuthSession,
+) -> Result<AuthSession, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, AuthSession>(
+        r#"
+        INSERT INTO auth_sessions (user_id, jti, user_agent, ip_address)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, user_id, jti, user_agent, ip_address, created_at, last_seen_at, revoked_at
+        "#,
+    )
+    .bind(session.user_id)
+    .bind(session.jti)
+    .bind(&session.user_agent)
+    .bind(&session.ip_address)
+    .fetch_one(executor)
+    .await
+}
+
+/// List a user's sessions that haven't been revoked, most recently seen first
+pub async fn list_active_auth_sessions(
+    pool: &DbPool,
+    user_id: Uuid,
+) -> Result<Vec<AuthSession>, sqlx::Error> {
+    sqlx::query_as::<_, AuthSession>(
+        r#"
+        SELECT id, user_id, jti, user_agent, ip_address, created_at, last_seen_at, revoked_at
+        FROM auth_sessions
+        WHERE user_id = $1 AND revoked_at IS NULL
+        ORDER BY last_seen_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Bump a session's `last_seen_at` and report whether it's still active.
+/// Called on every authenticated request, so a revoked session's jti starts
+/// failing immediately and "last seen" stays accurate without a second query.
+pub async fn touch_auth_session(pool: &DbPool, jti: Uuid) -> Result<bool, sqlx::Error> {
+    let touched: Option<(Uuid,)> = sqlx::query_as(
+        r#"
+        UPDATE auth_sessions
+        SET last_seen_at = NOW()
+        WHERE jti = $1 AND revoked_at IS NULL
+        RETURNING id
+        "#,
+    )
+    .bind(jti)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(touched.is_some())
+}
+
+/// Revoke one of a user's sessions by its id. Returns `false` if there was no
+/// matching, still-active session owned by `user_id` (already revoked,
+/// belongs to someone else, or never existed).
+pub async fn revoke_auth_session(
+    pool: &DbPool,
+    user_id: Uuid,
+    session_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let revoked: Option<(Uuid,)> = sqlx::query_as(
+        r#"
+        UPDATE auth_sessions
+        SET revoked_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+        RETURNING id
+        "#,
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(revoked.is_some())
+}
+
+/// Revoke a single session by the jti of the token that created it (logout)
+pub async fn revoke_auth_session_by_jti(pool: &DbPool, jti: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE auth_sessions
+        SET revoked_at = NOW()
+        WHERE jti = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(jti)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Revoke every active session for a user (logout-all / "sign out other devices")
+pub async fn revoke_all_auth_sessions(pool: &DbPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE auth_sessions
+        SET revoked_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Permanently delete a user and every dependent row. `email_tokens`,
+/// `oauth_connections`, `auth_sessions`, and `webauthn_credentials` all carry
+/// `ON DELETE CASCADE` to `users`, so deleting the `users` row clears those.
+/// `sessions` (saved game state) doesn't cascade and is explicitly removed
+/// per the request; `player_progress`/`save_slots` are device-scoped rather
+/// than account-scoped, so they're just unlinked instead of deleted.
+pub async fn delete_user(pool: &DbPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM sessions WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE player_progress SET user_id = NULL WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE save_slots SET user_id = NULL WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
+/// Create a new invite. Unlike `create_email_token`, prior invites for the
+/// same email are left alone - an admin may deliberately re-send or stack
+/// invites, and there's no existing user row to scope a "delete the old one"
+/// query to.
+pub async fn create_invite(pool: &DbPool, invite: &NewInvite) -> Result<Invite, sqlx::Error> {
+    sqlx::query_as::<_, Invite>(
+        r#"
+        INSERT INTO invites (email, token_hash, invited_by, expires_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, email, invited_by, expires_at, used_at, created_at
+        "#,
+    )
+    .bind(&invite.email)
+    .bind(&invite.token_hash)
+    .bind(invite.invited_by)
+    .bind(invite.expires_at)
+    .fetch_one(pool)
+    .await
+}
+
+/// Look up an unused, unexpired invite by its token hash
+pub async fn get_invite_by_token_hash(
+    pool: &DbPool,
+    token_hash: &str,
+) -> Result<Option<Invite>, sqlx::Error> {
+    sqlx::query_as::<_, Invite>(
+        r#"
+        SELECT id, email, invited_by, expires_at, used_at, created_at
+        FROM invites
+        WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+        "#,
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Mark an invite as consumed
+pub async fn mark_invite_used(pool: &DbPool, invite_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE invites
+        SET used_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(invite_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Create a new refresh token (initial issuance or rotation). Generic over
+/// the executor - see `create_auth_session`.
+pub async fn create_refresh_token<'e, E>(
+    executor: E,
+    token: &NewRefreshToken,
+) -> Result<RefreshToken, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, RefreshToken>(
+        r#"
+        INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
+        VALUES ($1, $2, $3)
+        RETURNING id, user_id, expires_at, revoked_at, created_at
+        "#,
+    )
+    .bind(token.user_id)
+    .bind(&token.token_hash)
+    .bind(token.expires_at)
+    .fetch_one(executor)
+    .await
+}
+
+/// Look up a refresh token by its hash, revoked or not - the caller needs to
+/// see a revoked-but-presented row to detect reuse, not just get `None`.
+pub async fn get_refresh_token_by_hash(
+    pool: &DbPool,
+    token_hash: &str,
+) -> Result<Option<RefreshToken>, sqlx::Error> {
+    sqlx::query_as::<_, RefreshToken>(
+        r#"
+        SELECT id, user_id, expires_at, revoked_at, created_at
+        FROM refresh_tokens
+        WHERE token_hash = $1
+        "#,
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Revoke a single refresh token by id (rotation retires the old token)
+pub async fn revoke_refresh_token(pool: &DbPool, token_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = NOW()
+        WHERE id = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(token_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Revoke every refresh token for a user - used both for logout-all and as
+/// the theft response when a revoked token gets reused.
+pub async fn revoke_all_for_user(pool: &DbPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Register a new device for a user, minting its persistent device id
+pub async fn register_device(pool: &DbPool, device: &NewDevice) -> Result<Device, sqlx::Error> {
+    sqlx::query_as::<_, Device>(
+        r#"
+        INSERT INTO devices (user_id, name, platform, push_endpoint)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, user_id, name, platform, push_endpoint, last_seen_at, created_at
+        "#,
+    )
+    .bind(device.user_id)
+    .bind(&device.name)
+    .bind(&device.platform)
+    .bind(&device.push_endpoint)
+    .fetch_one(pool)
+    .await
+}
+
+/// Look up a device owned by a given user, so callers can reject commands
+/// aimed at a device id that exists but belongs to someone else
+pub async fn get_device_for_user(
+    pool: &DbPool,
+    user_id: Uuid,
+    device_id: Uuid,
+) -> Result<Option<Device>, sqlx::Error> {
+    sqlx::query_as::<_, Device>(
+        r#"
+        SELECT id, user_id, name, platform, push_endpoint, last_seen_at, created_at
+        FROM devices
+        WHERE id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(device_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// List every device registered for a user, most recently seen first
+pub async fn list_devices_for_user(pool: &DbPool, user_id: Uuid) -> Result<Vec<Device>, sqlx::Error> {
+    sqlx::query_as::<_, Device>(
+        r#"
+        SELECT id, user_id, name, platform, push_endpoint, last_seen_at, created_at
+        FROM devices
+        WHERE user_id = $1
+        ORDER BY last_seen_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Bump a device's `last_seen_at`, e.g. each time it polls for commands
+pub async fn touch_device(pool: &DbPool, device_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE devices
+        SET last_seen_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(device_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Queue a remote command for a device to pick up on its next poll
+pub async fn enqueue_device_command(
+    pool: &DbPool,
+    command: &NewDeviceCommand,
+) -> Result<DeviceCommand, sqlx::Error> {
+    sqlx::query_as::<_, DeviceCommand>(
+        r#"
+        INSERT INTO device_commands (device_id, command)
+        VALUES ($1, $2)
+        RETURNING id, device_id, command, delivered_at, created_at
+        "#,
+    )
+    .bind(command.device_id)
+    .bind(&command.command)
+    .fetch_one(pool)
+    .await
+}
+
+/// List a device's undelivered commands, oldest first
+pub async fn list_pending_device_commands(
+    pool: &DbPool,
+    device_id: Uuid,
+) -> Result<Vec<DeviceCommand>, sqlx::Error> {
+    sqlx::query_as::<_, DeviceCommand>(
+        r#"
+        SELECT id, device_id, command, delivered_at, created_at
+        FROM device_commands
+        WHERE device_id = $1 AND delivered_at IS NULL
+        ORDER BY created_at ASC
+        "#,
+    )
+    .bind(device_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Mark a batch of commands as delivered once a device has polled them
+pub async fn mark_device_commands_delivered(
+    pool: &DbPool,
+    command_ids: &[Uuid],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE device_commands
+        SET delivered_at = NOW()
+        WHERE id = ANY($1) AND delivered_at IS NULL
+        "#,
+    )
+    .bind(command_ids)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Record a level clear: one row in `level_completions` (kept even on a
+/// replay, so the fastest attempt is never lost to a slower later one) and a
+/// first-clear-only row in `level_participants` (`ON CONFLICT DO NOTHING`
+/// so a replay doesn't bump the original clear time).
+pub async fn record_level_completion(
+    pool: &DbPool,
+    level_id: &str,
+    user_id: Uuid,
+    duration_ms: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO level_completions (level_id, user_id, duration_ms)
+        VALUES ($1, $2, $3)
+        "#,
+    )
+    .bind(level_id)
+    .bind(user_id)
+    .bind(duration_ms)
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO level_participants (level_id, user_id)
+        VALUES ($1, $2)
+        ON CONFLICT (level_id, user_id) DO NOTHING
+        "#,
+    )
+    .bind(level_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Ranked leaderboard page: username, total XP, and how many distinct
+/// levels the player has cleared (from `level_participants`, not
+/// `level_completions`, so a replayed level only counts once).
+pub async fn top_users_by_xp(
+    pool: &DbPool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<LeaderboardEntry>, sqlx::Error> {
+    sqlx::query_as::<_, LeaderboardEntry>(
+        r#"
+        SELECT
+            u.username,
+            u.total_xp,
+            COUNT(DISTINCT lp.level_id) AS levels_completed
+        FROM users u
+        LEFT JOIN level_participants lp ON lp.user_id = u.id
+        GROUP BY u.id
+        ORDER BY u.total_xp DESC, u.id
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+}
+
+/// This user's 1-based rank in the global XP leaderboard. A cheap
+/// `COUNT(*)` of strictly-higher totals rather than scanning the whole
+/// ranked list, so a player far outside the top-N is still answerable
+/// without paging through everyone ahead of them.
+pub async fn get_user_xp_rank(pool: &DbPool, user_id: Uuid) -> Result<Option<i64>, sqlx::Error> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        r#"
+        SELECT (SELECT COUNT(*) FROM users WHERE total_xp > u.total_xp) + 1
+        FROM users u
+        WHERE u.id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(rank,)| rank))
+}
+
+/// Ranked page of a level's completions by fastest cumulative
+/// `duration_ms` summed across all of a user's clears of that level
+/// (unlike `get_fastest_completions`, which ranks single clears, this
+/// rewards consistently fast replays over one lucky run). Attempts with no
+/// recorded duration don't contribute to the sum, and a user with none at
+/// all is excluded rather than ranked with an empty total.
+pub async fn level_leaderboard_by_duration(
+    pool: &DbPool,
+    level_id: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<LevelLeaderboardEntry>, sqlx::Error> {
+    sqlx::query_as::<_, LevelLeaderboardEntry>(
+        r#"
+        SELECT u.username, SUM(lc.duration_ms) AS total_duration_ms
+        FROM level_completions lc
+        JOIN users u ON u.id = lc.user_id
+        WHERE lc.level_id = $1 AND lc.duration_ms IS NOT NULL
+        GROUP BY u.id, u.username
+        ORDER BY total_duration_ms ASC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(level_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+}
+
+/// This user's 1-based rank within one level's duration leaderboard (see
+/// [`level_leaderboard_by_duration`]), or `None` if they have no timed
+/// completions of that level to rank.
+pub async fn get_user_level_duration_rank(
+    pool: &DbPool,
+    level_id: &str,
+    user_id: Uuid,
+) -> Result<Option<i64>, sqlx::Error> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        r#"
+        WITH totals AS (
+            SELECT user_id, SUM(duration_ms) AS total_duration_ms
+            FROM level_completions
+            WHERE level_id = $1 AND duration_ms IS NOT NULL
+            GROUP BY user_id
+        )
+        SELECT (SELECT COUNT(*) FROM totals t2 WHERE t2.total_duration_ms < t1.total_duration_ms) + 1
+        FROM totals t1
+        WHERE t1.user_id = $2
+        "#,
+    )
+    .bind(level_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(rank,)| rank))
+}
+
+/// Join a level as a co-op/competitive participant - a row that lives from
+/// `join_level` to `leave_level`, separate from `level_participants`'s
+/// permanent first-clear record. Idempotent: joining twice keeps the
+/// original `joined_at` and `best_xp`.
+pub async fn join_level(pool: &DbPool, level_id: &str, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO level_session_participants (level_id, user_id)
+        VALUES ($1, $2)
+        ON CONFLICT (level_id, user_id) DO NOTHING
+        "#,
+    )
+    .bind(level_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Leave a level - deletes the participant row outright, same
+/// no-retained-history choice as `leave_room`.
+pub async fn leave_level(pool: &DbPool, level_id: &str, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM level_session_participants
+        WHERE level_id = $1 AND user_id = $2
+        "#,
+    )
+    .bind(level_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Record a fresh clear's XP against this participant's running best for
+/// `level_id`, keeping the higher of the stored value and `xp_earned`.
+/// Called alongside `record_level_completion` once a submission actually
+/// clears the level; inserts the row if the player never explicitly joined.
+pub async fn upsert_level_best_xp(
+    pool: &DbPool,
+    level_id: &str,
+    user_id: Uuid,
+    xp_earned: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO level_session_participants (level_id, user_id, best_xp)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (level_id, user_id)
+        DO UPDATE SET best_xp = GREATEST(level_session_participants.best_xp, $3)
+        "#,
+    )
+    .bind(level_id)
+    .bind(user_id)
+    .bind(xp_earned)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Ranked co-op/competitive leaderboard for one level, by each participant's
+/// best XP - the `level_session_participants` analogue of
+/// [`level_leaderboard_by_duration`].
+pub async fn level_leaderboard(
+    pool: &DbPool,
+    level_id: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<LevelXpLeaderboardEntry>, sqlx::Error> {
+    sqlx::query_as::<_, LevelXpLeaderboardEntry>(
+        r#"
+        SELECT u.username, lsp.best_xp
+        FROM level_session_participants lsp
+        JOIN users u ON u.id = lsp.user_id
+        WHERE lsp.level_id = $1
+        ORDER BY lsp.best_xp DESC, u.id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(level_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+}
+
+/// This user's 1-based rank within one level's XP leaderboard (see
+/// [`level_leaderboard`]), or `None` if they haven't joined it.
+pub async fn get_user_level_xp_rank(
+    pool: &DbPool,
+    level_id: &str,
+    user_id: Uuid,
+) -> Result<Option<i64>, sqlx::Error> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        r#"
+        SELECT (SELECT COUNT(*) FROM level_session_participants p2
+                WHERE p2.level_id = $1 AND p2.best_xp > p1.best_xp) + 1
+        FROM level_session_participants p1
+        WHERE p1.level_id = $1 AND p1.user_id = $2
+        "#,
+    )
+    .bind(level_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(rank,)| rank))
+}
+
+/// A level's fastest recorded clears, fastest first. NULL-duration attempts
+/// (legacy output-based challenges that didn't report a time) sort last.
+pub async fn get_fastest_completions(
+    pool: &DbPool,
+    level_id: &str,
+    limit: i64,
+) -> Result<Vec<FastestCompletion>, sqlx::Error> {
+    sqlx::query_as::<_, FastestCompletion>(
+        r#"
+        SELECT u.username, lc.duration_ms, lc.completed_at
+        FROM level_completions lc
+        JOIN users u ON u.id = lc.user_id
+        WHERE lc.level_id = $1
+        ORDER BY lc.duration_ms ASC NULLS LAST, lc.completed_at ASC
+        LIMIT $2
+        "#,
+    )
+    .bind(level_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Start (or restart) TOTP enrollment for a user. `enabled` starts false -
+/// call [`enable_totp_credential`] once the player proves they scanned the
+/// QR code correctly. Re-enrolling overwrites any previous (unconfirmed or
+/// confirmed) secret, same as re-registering a passkey would replace a
+/// pending ceremony.
+pub async fn upsert_totp_credential(
+    pool: &DbPool,
+    user_id: Uuid,
+    secret: &str,
+) -> Result<TotpCredential, sqlx::Error> {
+    sqlx::query_as::<_, TotpCredential>(
+        r#"
+        INSERT INTO totp_credentials (user_id, secret, enabled)
+        VALUES ($1, $2, FALSE)
+        ON CONFLICT (user_id) DO UPDATE SET secret = $2, enabled = FALSE
+        RETURNING user_id, secret, enabled, created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(secret)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn get_totp_credential(
+    pool: &DbPool,
+    user_id: Uuid,
+) -> Result<Option<TotpCredential>, sqlx::Error> {
+    sqlx::query_as::<_, TotpCredential>(
+        r#"SELECT user_id, secret, enabled, created_at FROM totp_credentials WHERE user_id = $1"#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Mark a pending enrollment confirmed, after the player has proven they
+/// can generate a valid code with it.
+pub async fn enable_totp_credential(pool: &DbPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"UPDATE totp_credentials SET enabled = TRUE WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_totp_credential(pool: &DbPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"DELETE FROM totp_credentials WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    sqlx::query(r#"DELETE FROM totp_backup_codes WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Replace a user's backup codes with a freshly generated set (called
+/// alongside [`upsert_totp_credential`] on enrollment, or on request if the
+/// player suspects theirs have leaked). `code_hashes` are `hash_token`
+/// digests of the raw codes shown to the player exactly once.
+pub async fn replace_totp_backup_codes(
+    pool: &DbPool,
+    user_id: Uuid,
+    code_hashes: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"DELETE FROM totp_backup_codes WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    for hash in code_hashes {
+        sqlx::query(
+            r#"INSERT INTO totp_backup_codes (user_id, code_hash) VALUES ($1, $2)"#,
+        )
+        .bind(user_id)
+        .bind(hash)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Consume a backup code if it exists, is unused, and belongs to this user.
+/// Returns whether a code was consumed.
+pub async fn consume_totp_backup_code(
+    pool: &DbPool,
+    user_id: Uuid,
+    code_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE totp_backup_codes
+        SET used_at = NOW()
+        WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record one TEST-or-SUBMIT attempt at a level or quest. `quest_id` is
+/// `None` for a level-wide (non-quest) submission. Unlike
+/// [`record_level_completion`], this records every attempt regardless of
+/// pass/fail, so a player's history shows their improvement over time.
+pub async fn insert_submission(
+    pool: &DbPool,
+    user_id: Uuid,
+    level_id: &str,
+    quest_id: Option<&str>,
+    code: &str,
+    passed_count: i32,
+    total_count: i32,
+    execution_time_ms: Option<i64>,
+) -> Result<Submission, sqlx::Error> {
+    sqlx::query_as::<_, Submission>(
+        r#"
+        INSERT INTO submissions (user_id, level_id, quest_id, code, passed_count, total_count, execution_time_ms)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, user_id, level_id, quest_id, code, passed_count, total_count, execution_time_ms, submitted_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(level_id)
+    .bind(quest_id)
+    .bind(code)
+    .bind(passed_count)
+    .bind(total_count)
+    .bind(execution_time_ms)
+    .fetch_one(pool)
+    .await
+}
+
+/// A user's submission history for one level (or, if `quest_id` is given,
+/// one quest within it), most recent first.
+pub async fn list_submissions_by_user_level(
+    pool: &DbPool,
+    user_id: Uuid,
+    level_id: &str,
+    quest_id: Option<&str>,
+) -> Result<Vec<Submission>, sqlx::Error> {
+    sqlx::query_as::<_, Submission>(
+        r#"
+        SELECT id, user_id, level_id, quest_id, code, passed_count, total_count, execution_time_ms, submitted_at
+        FROM submissions
+        WHERE user_id = $1 AND level_id = $2 AND quest_id IS NOT DISTINCT FROM $3
+        ORDER BY submitted_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .bind(level_id)
+    .bind(quest_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Create a room for `level_id` and add its creator as the first
+/// participant. Not wrapped in a transaction - same precedent as
+/// `record_level_completion`'s two sequential inserts - a crash between
+/// the two leaves an orphaned room with no participants, which is
+/// harmless and just looks empty if ever queried.
+pub async fn create_room(pool: &DbPool, level_id: &str, created_by: Uuid) -> Result<Room, sqlx::Error> {
+    let room = sqlx::query_as::<_, Room>(
+        r#"
+        INSERT INTO rooms (level_id, created_by)
+        VALUES ($1, $2)
+        RETURNING id, level_id, created_by, created_at
+        "#,
+    )
+    .bind(level_id)
+    .bind(created_by)
+    .fetch_one(pool)
+    .await?;
+
+    sqlx::query(
+        r#"INSERT INTO room_participants (room_id, user_id) VALUES ($1, $2)"#,
+    )
+    .bind(room.id)
+    .bind(created_by)
+    .execute(pool)
+    .await?;
+
+    Ok(room)
+}
+
+pub async fn get_room(pool: &DbPool, room_id: Uuid) -> Result<Option<Room>, sqlx::Error> {
+    sqlx::query_as::<_, Room>(
+        r#"SELECT id, level_id, created_by, created_at FROM rooms WHERE id = $1"#,
+    )
+    .bind(room_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Add a user to a room. Idempotent - joining twice is a no-op, same as
+/// `record_level_completion`'s `level_participants` upsert.
+pub async fn join_room(pool: &DbPool, room_id: Uuid, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO room_participants (room_id, user_id)
+        VALUES ($1, $2)
+        ON CONFLICT (room_id, user_id) DO NOTHING
+        "#,
+    )
+    .bind(room_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn leave_room(pool: &DbPool, room_id: Uuid, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"DELETE FROM room_participants WHERE room_id = $1 AND user_id = $2"#,
+    )
+    .bind(room_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Whether `user_id` is currently a participant of `room_id` - gates
+/// access to a room's shared session (submit, stream) the same way
+/// `get_device_for_user` gates a device's commands to its owner.
+pub async fn is_room_participant(
+    pool: &DbPool,
+    room_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let row: Option<(i32,)> = sqlx::query_as(
+        r#"SELECT 1 FROM room_participants WHERE room_id = $1 AND user_id = $2"#,
+    )
+    .bind(room_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.is_some())
+}
+
+/// Every user_id currently in a room, for broadcasting updates to
+/// participants.
+pub async fn list_room_participant_ids(
+    pool: &DbPool,
+    room_id: Uuid,
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    let rows: Vec<(Uuid,)> = sqlx::query_as(
+        r#"SELECT user_id FROM room_participants WHERE room_id = $1"#,
+    )
+    .bind(room_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// Issue a ban (permanent if `new_ban.expires_at` is `None`, else a timed
+/// suspension) - see the `Ban` model doc comment for what makes it active.
+pub async fn create_ban(pool: &DbPool, new_ban: &NewBan) -> Result<Ban, sqlx::Error> {
+    sqlx::query_as::<_, Ban>(
+        r#"
+        INSERT INTO bans (user_id, issued_by, reason, expires_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, user_id, issued_by, reason, issued_at, expires_at, revoked_at
+        "#,
+    )
+    .bind(new_ban.user_id)
+    .bind(new_ban.issued_by)
+    .bind(&new_ban.reason)
+    .bind(new_ban.expires_at)
+    .fetch_one(pool)
+    .await
+}
+
+/// Lift a ban early, independent of its `expires_at`.
+pub async fn revoke_ban(pool: &DbPool, ban_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"UPDATE bans SET revoked_at = NOW() WHERE id = $1"#)
+        .bind(ban_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The user's current active ban, if any - a row where `revoked_at IS NULL
+/// AND (expires_at IS NULL OR expires_at > NOW())`. When more than one is
+/// active (shouldn't normally happen, but isn't prevented), the permanent
+/// one wins, then the one that expires furthest out, so a user can't be let
+/// back in early by a shorter, more recent suspension shadowing an older
+/// permanent ban.
+pub async fn get_active_ban(pool: &DbPool, user_id: Uuid) -> Result<Option<Ban>, sqlx::Error> {
+    sqlx::query_as::<_, Ban>(
+        r#"
+        SELECT id, user_id, issued_by, reason, issued_at, expires_at, revoked_at
+        FROM bans
+        WHERE user_id = $1
+          AND revoked_at IS NULL
+          AND (expires_at IS NULL OR expires_at > NOW())
+        ORDER BY (expires_at IS NULL) DESC, expires_at DESC NULLS FIRST
+        LIMIT 1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Admin: paginated page of every user with their current ban state
+/// left-joined in, ordered newest-signup-first (the order an operator
+/// working a moderation queue wants, unlike `top_users_by_xp`'s
+/// leaderboard ordering).
+pub async fn list_users_overview(
+    pool: &DbPool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<UserOverview>, sqlx::Error> {
+    sqlx::query_as::<_, UserOverview>(
+        r#"
+        SELECT
+            u.id,
+            u.email,
+            u.username,
+            u.total_xp,
+            u.last_login_at,
+            b.id AS ban_id,
+            b.reason AS ban_reason,
+            b.expires_at AS ban_expires_at
+        FROM users u
+        LEFT JOIN bans b
+            ON b.user_id = u.id
+            AND b.revoked_at IS NULL
+            AND (b.expires_at IS NULL OR b.expires_at > NOW())
+        ORDER BY u.created_at DESC, u.id
+        LIMIT $1 OFFSET $2
+        "#,
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+}
+
+/// Admin: wipe a device's saved progress - every `save_slots` row and its
+/// `sessions` row, keyed the same way `claim_session`/`sync_game` key them.
+/// Runs as one transaction so a crash between the two deletes can't leave a
+/// session without its save slots or vice versa.
+pub async fn reset_progress_for_device(pool: &DbPool, device_id: &str) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM save_slots WHERE device_id = $1")
+        .bind(device_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM sessions WHERE device_id = $1")
+        .bind(device_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Create a trusted-contact grant over the caller's own save slots. The
+/// grant starts unaccepted (`accepted_at IS NULL`) until the invited email
+/// either registers an account (see `accept_trusted_contact_grants`, called
+/// from `register`) or an explicit accept flow completes for an
+/// already-registered grantee.
+pub async fn invite_trusted_contact(
+    pool: &DbPool,
+    grant: &NewSaveSlotGrant,
+) -> Result<SaveSlotGrant, sqlx::Error> {
+    sqlx::query_as::<_, SaveSlotGrant>(
+        r#"
+        INSERT INTO save_slot_grants (owner_id, grantee_email, access_level, wait_days)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, owner_id, grantee_email, grantee_id, access_level, wait_days, created_at, accepted_at
+        "#,
+    )
+    .bind(grant.owner_id)
+    .bind(&grant.grantee_email)
+    .bind(&grant.access_level)
+    .bind(grant.wait_days)
+    .fetch_one(pool)
+    .await
+}
+
+/// Bind every pending grant addressed to `grantee_email` to the newly
+/// created account and mark it accepted, so a trusted contact who registers
+/// after being invited doesn't have to separately click an accept link.
+/// Returns the number of grants accepted.
+pub async fn accept_trusted_contact_grants(
+    pool: &DbPool,
+    grantee_id: Uuid,
+    grantee_email: &str,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE save_slot_grants
+        SET grantee_id = $1, accepted_at = NOW()
+        WHERE grantee_email = $2 AND accepted_at IS NULL
+        "#,
+    )
+    .bind(grantee_id)
+    .bind(grantee_email)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Every save slot a grantee currently has access to - grants the caller
+/// has accepted, and whose `wait_days` waiting period (the delay a grantor
+/// can impose before emergency/mentor access actually kicks in) has
+/// elapsed.
+pub async fn get_shared_save_slots(
+    pool: &DbPool,
+    grantee_id: Uuid,
+) -> Result<Vec<SharedSaveSlot>, sqlx::Error> {
+    sqlx::query_as::<_, SharedSaveSlot>(
+        r#"
+        SELECT
+            s.id,
+            g.owner_id,
+            u.email AS owner_email,
+            s.slot_name,
+            s.save_data,
+            s.total_xp,
+            s.levels_completed,
+            s.current_level,
+            s.updated_at,
+            g.access_level
+        FROM save_slot_grants g
+        JOIN save_slots s ON s.user_id = g.owner_id
+        JOIN users u ON u.id = g.owner_id
+        WHERE g.grantee_id = $1
+          AND g.accepted_at IS NOT NULL
+          AND g.accepted_at + make_interval(days => g.wait_days) <= NOW()
+        ORDER BY s.updated_at DESC
+        "#,
+    )
+    .bind(grantee_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Create a new multi-use invite code. Generic over the executor - see
+/// `create_auth_session`.
+pub async fn create_invite_code<'e, E>(
+    executor: E,
+    invite: &NewInviteCode,
+) -> Result<InviteCode, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, InviteCode>(
+        r#"
+        INSERT INTO invite_codes (code, created_by, email, max_uses, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, code, created_by, email, max_uses, uses, expires_at, redeemed_by, created_at
+        "#,
+    )
+    .bind(&invite.code)
+    .bind(invite.created_by)
+    .bind(&invite.email)
+    .bind(invite.max_uses)
+    .bind(invite.expires_at)
+    .fetch_one(executor)
+    .await
+}
+
+/// Atomically redeem an invite code for `user_id`: bumps `uses` and appends
+/// to `redeemed_by` in one `UPDATE ... WHERE ... RETURNING`, so two
+/// concurrent redemptions of the same near-exhausted code can't both
+/// succeed and oversell `max_uses` the way a separate check-then-update
+/// would. Returns `None` if the code doesn't exist, is expired, is already
+/// at `max_uses`, or is bound to a different email than `email`.
+pub async fn redeem_invite_code<'e, E>(
+    executor: E,
+    code: &str,
+    email: &str,
+    user_id: Uuid,
+) -> Result<Option<InviteCode>, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_as::<_, InviteCode>(
+        r#"
+        UPDATE invite_codes
+        SET uses = uses + 1, redeemed_by = array_append(redeemed_by, $3)
+        WHERE code = $1
+          AND uses < max_uses
+          AND (expires_at IS NULL OR expires_at > NOW())
+          AND (email IS NULL OR email = $2)
+        RETURNING id, code, created_by, email, max_uses, uses, expires_at, redeemed_by, created_at
+        "#,
+    )
+    .bind(code)
+    .bind(email)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Register a push subscription for `user_id`. `ON CONFLICT` on
+/// `(user_id, endpoint)` so re-subscribing the same browser (e.g. after the
+/// push service rotates its own keys but keeps the same endpoint) refreshes
+/// `p256dh`/`auth` in place instead of accumulating duplicate rows.
+pub async fn create_push_subscription(
+    pool: &DbPool,
+    subscription: &NewPushSubscription,
+) -> Result<PushSubscription, sqlx::Error> {
+    sqlx::query_as::<_, PushSubscription>(
+        r#"
+        INSERT INTO push_subscriptions (user_id, endpoint, p256dh, auth)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id, endpoint) DO UPDATE
+        SET p256dh = EXCLUDED.p256dh, auth = EXCLUDED.auth
+        RETURNING id, user_id, endpoint, p256dh, auth, created_at
+        "#,
+    )
+    .bind(subscription.user_id)
+    .bind(&subscription.endpoint)
+    .bind(&subscription.p256dh)
+    .bind(&subscription.auth)
+    .fetch_one(pool)
+    .await
+}
+
+/// Remove a push subscription, e.g. when the browser reports the endpoint
+/// as gone (410/404 from the push service) or the player explicitly
+/// disables notifications.
+pub async fn delete_push_subscription(
+    pool: &DbPool,
+    user_id: Uuid,
+    endpoint: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM push_subscriptions WHERE user_id = $1 AND endpoint = $2")
+        .bind(user_id)
+        .bind(endpoint)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// All of `user_id`'s subscribed browsers/devices, to fan a notification
+/// out to every one of them.
+pub async fn get_push_subscriptions(
+    pool: &DbPool,
+    user_id: Uuid,
+) -> Result<Vec<PushSubscription>, sqlx::Error> {
+    sqlx::query_as::<_, PushSubscription>(
+        r#"
+        SELECT id, user_id, endpoint, p256dh, auth, created_at
+        FROM push_subscriptions
+        WHERE user_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
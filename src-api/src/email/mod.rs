@@ -1,9 +1,12 @@
 //! Email service module for Code Warrior
 //!
-//! Uses Resend API for transactional emails (verification, password reset).
+//! Sends transactional emails (verification, password reset) through a
+//! pluggable [`transport`] backend - Resend by default, or direct SMTP.
 
+mod queue;
 pub mod sender;
 mod templates;
+mod transport;
 
 pub use sender::OptionalEmailService;
 
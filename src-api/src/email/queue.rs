@@ -0,0 +1,259 @@
+//! Durable retry queue for outbound email.
+//!
+//! [`EmailService::send`] still attempts delivery immediately; this only
+//! catches what that attempt misses. On failure the message is handed here
+//! for background redelivery with exponential backoff, so a transient 5xx
+//! or network blip doesn't silently drop a verification/reset email -
+//! mirroring how production mail relays decouple submission from delivery.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Mutex, Weak};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::sender::EmailService;
+use super::templates::EmailTemplate;
+
+/// One queued send, with enough state to compute backoff and re-render on
+/// retry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueuedEmail {
+    to: String,
+    template: EmailTemplate,
+    created_at: DateTime<Utc>,
+    attempts: u32,
+    next_attempt_at: DateTime<Utc>,
+}
+
+/// Tunables for [`EmailQueue`]'s retry behavior.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Backoff unit: after `n` failed attempts a message waits
+    /// `base * 2^n` (before `cap` and jitter), so the first retry comes
+    /// `2 * base` after the initial failed send.
+    pub base: Duration,
+    /// Upper bound on the backoff delay, regardless of attempt count.
+    pub cap: Duration,
+    /// Attempts (including the initial one) before a message is moved to
+    /// the dead-letter list.
+    pub max_attempts: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_secs(30),
+            cap: Duration::from_secs(15 * 60),
+            max_attempts: 8,
+        }
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: VecDeque<QueuedEmail>,
+    dead_letters: Vec<QueuedEmail>,
+}
+
+/// A bounded, best-effort-durable retry queue for outbound email. Holds
+/// messages [`EmailService::send`] failed to deliver on the first attempt,
+/// retrying with exponential backoff until `policy.max_attempts` is
+/// reached, at which point the message moves to the dead-letter list.
+///
+/// Pending messages are persisted to `persist_path` (JSON) after every
+/// change, so a restart doesn't lose mail that's still in flight - this is
+/// best-effort, not a durability guarantee; a crash between send and
+/// persist can still lose a message.
+pub struct EmailQueue {
+    service: Weak<EmailService>,
+    policy: RetryPolicy,
+    capacity: usize,
+    persist_path: Option<PathBuf>,
+    state: Mutex<QueueState>,
+}
+
+impl EmailQueue {
+    /// Builds a queue that redelivers through `service`, loading any
+    /// messages left over from a prior run at `persist_path` (if given).
+    pub fn new(
+        service: Weak<EmailService>,
+        policy: RetryPolicy,
+        capacity: usize,
+        persist_path: Option<PathBuf>,
+    ) -> Self {
+        let pending = persist_path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str::<VecDeque<QueuedEmail>>(&json).ok())
+            .unwrap_or_default();
+
+        Self {
+            service,
+            policy,
+            capacity,
+            persist_path,
+            state: Mutex::new(QueueState {
+                pending,
+                dead_letters: Vec::new(),
+            }),
+        }
+    }
+
+    /// The default persistence path: `code_warrior_email_queue.json` under the system
+    /// temp dir, so a restart doesn't lose mail still awaiting retry.
+    pub fn default_persist_path() -> PathBuf {
+        std::env::temp_dir().join("code_warrior_email_queue.json")
+    }
+
+    /// Queues `template` for background redelivery to `to`, counting the
+    /// caller's own failed attempt as the first one. Drops the oldest
+    /// pending message if already at `capacity`, so a sustained outage
+    /// can't grow the queue without bound.
+    pub(super) fn enqueue(&self, to: String, template: EmailTemplate) {
+        let now = Utc::now();
+        let attempts = 1;
+        let delay = self.backoff_for(attempts);
+
+        let mut state = self.state.lock().unwrap();
+        if state.pending.len() >= self.capacity {
+            tracing::warn!(
+                "email retry queue at capacity ({}); dropping oldest pending message",
+                self.capacity
+            );
+            state.pending.pop_front();
+        }
+        state.pending.push_back(QueuedEmail {
+            to,
+            template,
+            created_at: now,
+            attempts,
+            next_attempt_at: now + to_chrono_duration(delay),
+        });
+        self.persist(&state);
+    }
+
+    /// Number of messages still awaiting delivery.
+    pub fn pending_count(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    /// `(recipient, attempts)` for every message that exhausted
+    /// `policy.max_attempts`.
+    pub fn dead_letters(&self) -> Vec<(String, u32)> {
+        self.state
+            .lock()
+            .unwrap()
+            .dead_letters
+            .iter()
+            .map(|message| (message.to.clone(), message.attempts))
+            .collect()
+    }
+
+    /// Redelivers every message whose backoff has elapsed. Successes are
+    /// dropped; failures are requeued with a longer backoff or, past
+    /// `policy.max_attempts`, moved to the dead-letter list.
+    async fn drain_due(&self) {
+        let Some(service) = self.service.upgrade() else {
+            return;
+        };
+
+        let due: Vec<QueuedEmail> = {
+            let mut state = self.state.lock().unwrap();
+            let now = Utc::now();
+            let (due, still_pending): (Vec<QueuedEmail>, VecDeque<QueuedEmail>) =
+                state.pending.drain(..).partition(|message| message.next_attempt_at <= now);
+            state.pending = still_pending;
+            due
+        };
+
+        for mut message in due {
+            match service.deliver(&message.to, &message.template).await {
+                Ok(_) => tracing::info!(
+                    "queued email to {} delivered on attempt {}",
+                    message.to,
+                    message.attempts + 1
+                ),
+                Err(e) => {
+                    message.attempts += 1;
+                    if message.attempts >= self.policy.max_attempts {
+                        tracing::error!(
+                            "giving up on email to {} after {} attempts: {e}",
+                            message.to,
+                            message.attempts
+                        );
+                        self.state.lock().unwrap().dead_letters.push(message);
+                    } else {
+                        let delay = self.backoff_for(message.attempts);
+                        message.next_attempt_at = Utc::now() + to_chrono_duration(delay);
+                        tracing::warn!(
+                            "retrying email to {} in {:?} (attempt {} failed: {e})",
+                            message.to,
+                            delay,
+                            message.attempts
+                        );
+                        self.state.lock().unwrap().pending.push_back(message);
+                    }
+                }
+            }
+        }
+
+        self.persist(&self.state.lock().unwrap());
+    }
+
+    /// `min(base * 2^attempts, cap)` plus up to 20% jitter, so a burst of
+    /// simultaneously-due retries doesn't all hit the transport at once.
+    fn backoff_for(&self, attempts: u32) -> Duration {
+        let exp = self
+            .policy
+            .base
+            .saturating_mul(1u32.checked_shl(attempts).unwrap_or(u32::MAX));
+        let capped = exp.min(self.policy.cap);
+        let jitter_ceiling = (capped.as_millis() as u64 / 5).max(1);
+        let jitter = Duration::from_millis(rand::thread_rng().gen_range(0..=jitter_ceiling));
+        capped + jitter
+    }
+
+    /// Best-effort: writes the pending list to `persist_path`, logging
+    /// (not failing) on any IO error. Dead letters aren't persisted - once
+    /// given up on, they're for observability only.
+    fn persist(&self, state: &QueueState) {
+        let Some(path) = &self.persist_path else {
+            return;
+        };
+        match serde_json::to_string(&state.pending) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(path, json) {
+                    tracing::warn!("failed to persist email queue to {}: {e}", path.display());
+                }
+            }
+            Err(e) => tracing::warn!("failed to serialize email queue: {e}"),
+        }
+    }
+
+    /// Spawns a background task that calls [`Self::drain_due`] on
+    /// `interval`. The task holds its own `Arc` to the queue and loops until
+    /// it is aborted through the returned handle or the runtime shuts down;
+    /// dropping the handle does not stop it. Must be called from within a
+    /// Tokio runtime.
+    pub fn spawn_worker(self: &std::sync::Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let queue = std::sync::Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                queue.drain_due().await;
+            }
+        })
+    }
+}
+
+/// `chrono::Duration::from_std` only fails for durations too large to
+/// represent, which `backoff_for`'s `cap` field should never produce -
+/// falls back to zero delay rather than panicking if it ever does.
+fn to_chrono_duration(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero())
+}
@@ -1,106 +1,120 @@
-//! Email sending service using Resend API
+//! Email sending service - renders templates and hands them to whichever
+//! [`MailTransport`] backend `EMAIL_BACKEND` selects (Resend by default, or
+//! direct SMTP for self-hosters).
 
+use super::queue::{EmailQueue, RetryPolicy};
 use super::templates::EmailTemplate;
-use serde::{Deserialize, Serialize};
+use super::transport::{MailTransport, ResendTransport, SmtpTransport};
+use crate::config::EmailBranding;
 use std::sync::Arc;
+use std::time::Duration;
 
-/// Email service configuration
-#[derive(Clone)]
+/// How often the background retry queue checks for due messages.
+const QUEUE_DRAIN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Renders templates with [`EmailBranding`] and delivers them through a
+/// [`MailTransport`] backend. On a failed [`Self::send`], hands the message
+/// to `queue` for background retry with backoff, if one is configured.
 pub struct EmailService {
-    api_key: String,
     from_email: String,
     from_name: String,
-    client: reqwest::Client,
-}
-
-/// Resend API request payload
-#[derive(Serialize)]
-struct ResendEmailRequest {
-    from: String,
-    to: Vec<String>,
-    subject: String,
-    html: String,
-    text: String,
-}
-
-/// Resend API response
-#[derive(Deserialize)]
-struct ResendEmailResponse {
-    id: String,
-}
-
-/// Resend API error response
-#[derive(Deserialize)]
-struct ResendErrorResponse {
-    message: String,
+    branding: EmailBranding,
+    transport: Box<dyn MailTransport>,
+    queue: Option<Arc<EmailQueue>>,
 }
 
 impl EmailService {
-    /// Create a new email service instance
-    pub fn new() -> Option<Self> {
-        let api_key = std::env::var("RESEND_API_KEY").ok()?;
+    /// Create a new email service instance, picking its backend from
+    /// `EMAIL_BACKEND` (`"resend"`, the default, or `"smtp"`). Returns
+    /// `None` if the selected backend's required env vars aren't set
+    /// (`RESEND_API_KEY` for Resend, `SMTP_HOST`/`SMTP_PORT` for SMTP).
+    ///
+    /// Also spawns the background retry-queue worker, so the returned
+    /// `Arc` must be constructed from within a Tokio runtime.
+    pub fn new(branding: EmailBranding) -> Option<Arc<Self>> {
         let from_email =
             std::env::var("EMAIL_FROM").unwrap_or_else(|_| "noreply@codewarrior.dev".to_string());
         let from_name =
             std::env::var("EMAIL_FROM_NAME").unwrap_or_else(|_| "Code Warrior".to_string());
 
-        Some(Self {
-            api_key,
-            from_email,
-            from_name,
-            client: reqwest::Client::new(),
-        })
+        let backend = std::env::var("EMAIL_BACKEND").unwrap_or_else(|_| "resend".to_string());
+        let transport: Box<dyn MailTransport> = match backend.as_str() {
+            "smtp" => Box::new(SmtpTransport::from_env()?),
+            _ => Box::new(ResendTransport::new(std::env::var("RESEND_API_KEY").ok()?)),
+        };
+
+        Some(Arc::new_cyclic(|weak| {
+            let queue = Arc::new(EmailQueue::new(
+                weak.clone(),
+                RetryPolicy::default(),
+                1_000,
+                Some(EmailQueue::default_persist_path()),
+            ));
+            queue.spawn_worker(QUEUE_DRAIN_INTERVAL);
+
+            Self {
+                from_email,
+                from_name,
+                branding,
+                transport,
+                queue: Some(queue),
+            }
+        }))
     }
 
-    /// Create a new email service from explicit config (for testing)
-    pub fn with_config(api_key: String, from_email: String, from_name: String) -> Self {
+    /// Create a new email service from an explicit transport (for
+    /// testing). Has no retry queue - a failed send is just an error.
+    pub fn with_config(
+        from_email: String,
+        from_name: String,
+        branding: EmailBranding,
+        transport: Box<dyn MailTransport>,
+    ) -> Self {
         Self {
-            api_key,
             from_email,
             from_name,
-            client: reqwest::Client::new(),
+            branding,
+            transport,
+            queue: None,
         }
     }
 
-    /// Send an email using a template
-    pub async fn send(&self, to: &str, template: EmailTemplate) -> Result<String, String> {
+    /// Renders `template` and attempts delivery via `self.transport`, with
+    /// no retry bookkeeping - used both by [`Self::send`] for the first
+    /// attempt and by [`EmailQueue`] for redelivery.
+    pub(super) async fn deliver(&self, to: &str, template: &EmailTemplate) -> Result<String, String> {
         let from = format!("{} <{}>", self.from_name, self.from_email);
-
-        let request = ResendEmailRequest {
-            from,
-            to: vec![to.to_string()],
-            subject: template.subject().to_string(),
-            html: template.render_html(),
-            text: template.render_text(),
-        };
-
-        let response = self
-            .client
-            .post("https://api.resend.com/emails")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
+        self.transport
+            .send(
+                &from,
+                to,
+                &template.subject(&self.branding),
+                &template.render_html(&self.branding),
+                &template.render_text(&self.branding),
+            )
             .await
-            .map_err(|e| format!("Failed to send email: {}", e))?;
-
-        if response.status().is_success() {
-            let result: ResendEmailResponse = response
-                .json()
-                .await
-                .map_err(|e| format!("Failed to parse response: {}", e))?;
-            tracing::info!("Email sent successfully to {}, id: {}", to, result.id);
-            Ok(result.id)
-        } else {
-            let error: ResendErrorResponse = response
-                .json()
-                .await
-                .unwrap_or(ResendErrorResponse {
-                    message: "Unknown error".to_string(),
-                });
-            tracing::error!("Failed to send email to {}: {}", to, error.message);
-            Err(format!("Email send failed: {}", error.message))
+    }
+
+    /// Send an email using a template. On failure, hands the message off
+    /// to the retry queue (if configured) for background redelivery with
+    /// exponential backoff, before returning the original error.
+    pub async fn send(&self, to: &str, template: EmailTemplate) -> Result<String, String> {
+        let result = self.deliver(to, &template).await;
+        if let (Err(e), Some(queue)) = (&result, &self.queue) {
+            tracing::warn!("immediate send to {} failed ({e}); queuing for retry", to);
+            queue.enqueue(to.to_string(), template);
         }
+        result
+    }
+
+    /// Messages still awaiting delivery in the retry queue.
+    pub fn pending_count(&self) -> usize {
+        self.queue.as_ref().map(|q| q.pending_count()).unwrap_or(0)
+    }
+
+    /// `(recipient, attempts)` for messages the retry queue gave up on.
+    pub fn dead_letters(&self) -> Vec<(String, u32)> {
+        self.queue.as_ref().map(|q| q.dead_letters()).unwrap_or_default()
     }
 
     /// Send verification email
@@ -140,20 +154,96 @@ impl EmailService {
         let template = EmailTemplate::Welcome { username };
         self.send(to, template).await
     }
+
+    /// Send an invite-only-registration invite email
+    pub async fn send_invite(
+        &self,
+        to: &str,
+        token: &str,
+        frontend_url: &str,
+    ) -> Result<String, String> {
+        let invite_link = format!("{}/register?invite={}", frontend_url, token);
+        let template = EmailTemplate::Invite { invite_link };
+        self.send(to, template).await
+    }
+
+    /// Send an account-deletion confirmation email. `expiry_hours` should
+    /// match the lifetime of the token backing `token`.
+    pub async fn send_delete_confirmation(
+        &self,
+        to: &str,
+        username: Option<String>,
+        token: &str,
+        frontend_url: &str,
+        expiry_hours: u32,
+    ) -> Result<String, String> {
+        let confirm_link = format!("{}/delete-account?token={}", frontend_url, token);
+        let template = EmailTemplate::DeleteAccount {
+            username,
+            confirm_link,
+            expiry_hours,
+        };
+        self.send(to, template).await
+    }
+
+    /// Send the new-address confirmation email for a pending email change
+    pub async fn send_confirm_email_change(
+        &self,
+        to: &str,
+        username: Option<String>,
+        token: &str,
+        frontend_url: &str,
+    ) -> Result<String, String> {
+        let confirm_link = format!("{}/confirm-email-change?token={}", frontend_url, token);
+        let template = EmailTemplate::ConfirmEmailChange {
+            username,
+            confirm_link,
+        };
+        self.send(to, template).await
+    }
+
+    /// Send the old-address notice for a pending email change. `to` is the
+    /// old (current) address, which doubles as `old_email` in the template
+    /// so the recipient sees both sides of the change.
+    pub async fn send_email_change_notice(
+        &self,
+        to: &str,
+        username: Option<String>,
+        new_email: &str,
+    ) -> Result<String, String> {
+        let template = EmailTemplate::EmailChangeNotice {
+            username,
+            old_email: to.to_string(),
+            new_email: new_email.to_string(),
+        };
+        self.send(to, template).await
+    }
 }
 
 /// Wrapper for optional email service (allows running without email in dev)
 pub struct OptionalEmailService(pub Option<Arc<EmailService>>);
 
 impl OptionalEmailService {
-    pub fn new() -> Self {
-        Self(EmailService::new().map(Arc::new))
+    pub fn new(branding: EmailBranding) -> Self {
+        Self(EmailService::new(branding))
     }
 
     pub fn is_available(&self) -> bool {
         self.0.is_some()
     }
 
+    /// Messages still awaiting delivery in the retry queue, or 0 if email
+    /// isn't configured.
+    pub fn pending_count(&self) -> usize {
+        self.0.as_ref().map(|s| s.pending_count()).unwrap_or(0)
+    }
+
+    /// `(recipient, attempts)` for messages the retry queue gave up on, or
+    /// empty if email isn't configured.
+    pub fn dead_letters(&self) -> Vec<(String, u32)> {
+        self.0.as_ref().map(|s| s.dead_letters()).unwrap_or_default()
+    }
+
     pub async fn send_verification(
         &self,
         to: &str,
@@ -211,5 +301,87 @@ impl OptionalEmailService {
             }
         }
     }
+
+    pub async fn send_invite(&self, to: &str, token: &str, frontend_url: &str) -> Result<String, String> {
+        match &self.0 {
+            Some(service) => service.send_invite(to, token, frontend_url).await,
+            None => {
+                tracing::warn!(
+                    "Email service not configured. Invite link: {}/register?invite={}",
+                    frontend_url,
+                    token
+                );
+                Ok("email-disabled".to_string())
+            }
+        }
+    }
+
+    pub async fn send_delete_confirmation(
+        &self,
+        to: &str,
+        username: Option<String>,
+        token: &str,
+        frontend_url: &str,
+        expiry_hours: u32,
+    ) -> Result<String, String> {
+        match &self.0 {
+            Some(service) => {
+                service
+                    .send_delete_confirmation(to, username, token, frontend_url, expiry_hours)
+                    .await
+            }
+            None => {
+                tracing::warn!(
+                    "Email service not configured. Delete-confirm link: {}/delete-account?token={}",
+                    frontend_url,
+                    token
+                );
+                Ok("email-disabled".to_string())
+            }
+        }
+    }
+
+    pub async fn send_confirm_email_change(
+        &self,
+        to: &str,
+        username: Option<String>,
+        token: &str,
+        frontend_url: &str,
+    ) -> Result<String, String> {
+        match &self.0 {
+            Some(service) => {
+                service
+                    .send_confirm_email_change(to, username, token, frontend_url)
+                    .await
+            }
+            None => {
+                tracing::warn!(
+                    "Email service not configured. Confirm-email-change link: {}/confirm-email-change?token={}",
+                    frontend_url,
+                    token
+                );
+                Ok("email-disabled".to_string())
+            }
+        }
+    }
+
+    pub async fn send_email_change_notice(
+        &self,
+        to: &str,
+        username: Option<String>,
+        new_email: &str,
+    ) -> Result<String, String> {
+        match &self.0 {
+            Some(service) => service.send_email_change_notice(to, username, new_email).await,
+            None => {
+                tracing::warn!(
+                    "Email service not configured. Would notify {} of pending change to {}",
+                    to,
+                    new_email
+                );
+                Ok("email-disabled".to_string())
+            }
+        }
+    }
 }
 
@@ -1,6 +1,11 @@
 //! Email templates for transactional emails
 
+use crate::config::EmailBranding;
+use serde::{Deserialize, Serialize};
+
 /// Email template types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
 pub enum EmailTemplate {
     /// Email verification after registration
     Verification {
@@ -14,49 +19,148 @@ pub enum EmailTemplate {
     },
     /// Welcome email after verification
     Welcome { username: Option<String> },
+    /// Invite to register on an invite-only deployment
+    Invite { invite_link: String },
+    /// Confirm a requested account deletion
+    DeleteAccount {
+        username: Option<String>,
+        confirm_link: String,
+        expiry_hours: u32,
+    },
+    /// Sent to the NEW address, to confirm the requester controls it
+    ConfirmEmailChange {
+        username: Option<String>,
+        confirm_link: String,
+    },
+    /// Sent to the OLD address, notifying the account owner of a pending
+    /// change. Shows both addresses so the recipient can recognize - and
+    /// challenge - a change they didn't request.
+    EmailChangeNotice {
+        username: Option<String>,
+        old_email: String,
+        new_email: String,
+    },
 }
 
 impl EmailTemplate {
-    /// Get the subject line for this template
-    pub fn subject(&self) -> &'static str {
+    /// Get the subject line for this template, branded per `branding`.
+    pub fn subject(&self, branding: &EmailBranding) -> String {
+        let app_name = &branding.app_name;
         match self {
-            EmailTemplate::Verification { .. } => "Verify your Code Warrior account",
-            EmailTemplate::PasswordReset { .. } => "Reset your Code Warrior password",
-            EmailTemplate::Welcome { .. } => "Welcome to Code Warrior!",
+            EmailTemplate::Verification { .. } => format!("Verify your {app_name} account"),
+            EmailTemplate::PasswordReset { .. } => format!("Reset your {app_name} password"),
+            EmailTemplate::Welcome { .. } => format!("Welcome to {app_name}!"),
+            EmailTemplate::Invite { .. } => format!("You're invited to {app_name}"),
+            EmailTemplate::DeleteAccount { .. } => "Confirm account deletion".to_string(),
+            EmailTemplate::ConfirmEmailChange { .. } => {
+                "Confirm your new email address".to_string()
+            }
+            EmailTemplate::EmailChangeNotice { .. } => {
+                format!("Your {app_name} email is changing")
+            }
         }
     }
 
-    /// Render the HTML body for this template
-    pub fn render_html(&self) -> String {
+    /// Render the HTML body for this template, branded per `branding`.
+    pub fn render_html(&self, branding: &EmailBranding) -> String {
         match self {
             EmailTemplate::Verification {
                 username,
                 verification_link,
-            } => self.render_verification_html(username.as_deref(), verification_link),
+            } => self.render_verification_html(username.as_deref(), verification_link, branding),
             EmailTemplate::PasswordReset {
                 username,
                 reset_link,
-            } => self.render_password_reset_html(username.as_deref(), reset_link),
-            EmailTemplate::Welcome { username } => self.render_welcome_html(username.as_deref()),
+            } => self.render_password_reset_html(username.as_deref(), reset_link, branding),
+            EmailTemplate::Welcome { username } => {
+                self.render_welcome_html(username.as_deref(), branding)
+            }
+            EmailTemplate::Invite { invite_link } => {
+                self.render_invite_html(invite_link, branding)
+            }
+            EmailTemplate::DeleteAccount {
+                username,
+                confirm_link,
+                expiry_hours,
+            } => self.render_delete_account_html(
+                username.as_deref(),
+                confirm_link,
+                *expiry_hours,
+                branding,
+            ),
+            EmailTemplate::ConfirmEmailChange {
+                username,
+                confirm_link,
+            } => {
+                self.render_confirm_email_change_html(username.as_deref(), confirm_link, branding)
+            }
+            EmailTemplate::EmailChangeNotice {
+                username,
+                old_email,
+                new_email,
+            } => self.render_email_change_notice_html(
+                username.as_deref(),
+                old_email,
+                new_email,
+                branding,
+            ),
         }
     }
 
-    /// Render plain text version for this template
-    pub fn render_text(&self) -> String {
+    /// Render plain text version for this template, branded per `branding`.
+    pub fn render_text(&self, branding: &EmailBranding) -> String {
         match self {
             EmailTemplate::Verification {
                 username,
                 verification_link,
-            } => self.render_verification_text(username.as_deref(), verification_link),
+            } => self.render_verification_text(username.as_deref(), verification_link, branding),
             EmailTemplate::PasswordReset {
                 username,
                 reset_link,
-            } => self.render_password_reset_text(username.as_deref(), reset_link),
-            EmailTemplate::Welcome { username } => self.render_welcome_text(username.as_deref()),
+            } => self.render_password_reset_text(username.as_deref(), reset_link, branding),
+            EmailTemplate::Welcome { username } => {
+                self.render_welcome_text(username.as_deref(), branding)
+            }
+            EmailTemplate::Invite { invite_link } => {
+                self.render_invite_text(invite_link, branding)
+            }
+            EmailTemplate::DeleteAccount {
+                username,
+                confirm_link,
+                expiry_hours,
+            } => self.render_delete_account_text(
+                username.as_deref(),
+                confirm_link,
+                *expiry_hours,
+                branding,
+            ),
+            EmailTemplate::ConfirmEmailChange {
+                username,
+                confirm_link,
+            } => {
+                self.render_confirm_email_change_text(username.as_deref(), confirm_link, branding)
+            }
+            EmailTemplate::EmailChangeNotice {
+                username,
+                old_email,
+                new_email,
+            } => self.render_email_change_notice_text(
+                username.as_deref(),
+                old_email,
+                new_email,
+                branding,
+            ),
         }
     }
 
-    fn render_verification_html(&self, username: Option<&str>, link: &str) -> String {
+    fn render_verification_html(&self, username: Option<&str>, link: &str, branding: &EmailBranding) -> String {
+        let app_name = &branding.app_name;
+        let app_name_upper = branding.app_name.to_uppercase();
+        let logo_emoji = &branding.logo_emoji;
+        let primary_color = &branding.primary_color;
+        let primary_color_dark = &branding.primary_color_dark;
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
         let greeting = username
             .map(|u| format!("Hey {},", u))
             .unwrap_or_else(|| "Hey there,".to_string());
@@ -85,7 +189,7 @@ impl EmailTemplate {
             overflow: hidden;
         }}
         .header {{
-            background: linear-gradient(135deg, #f59e0b 0%, #d97706 100%);
+            background: linear-gradient(135deg, {primary_color} 0%, {primary_color_dark} 100%);
             padding: 32px;
             text-align: center;
         }}
@@ -139,12 +243,12 @@ impl EmailTemplate {
 <body>
     <div class="container">
         <div class="header">
-            <h1>⚔️ CODE WARRIOR</h1>
+            <h1>{logo_emoji} {app_name_upper}</h1>
         </div>
         <div class="content">
             <p class="greeting">{greeting}</p>
             <p class="message">
-                Thanks for joining Code Warrior! Before you can start your journey to master C programming,
+                Thanks for joining {app_name}! Before you can start your journey to master C programming,
                 please verify your email address by clicking the button below.
             </p>
             <p style="text-align: center; margin: 32px 0;">
@@ -159,7 +263,7 @@ impl EmailTemplate {
             </p>
         </div>
         <div class="footer">
-            <p>© 2024 Code Warrior. Master C programming through adventure.</p>
+            <p>© {year} {app_name}. {tagline}</p>
         </div>
     </div>
 </body>
@@ -167,28 +271,37 @@ impl EmailTemplate {
         )
     }
 
-    fn render_verification_text(&self, username: Option<&str>, link: &str) -> String {
+    fn render_verification_text(&self, username: Option<&str>, link: &str, branding: &EmailBranding) -> String {
+        let app_name = &branding.app_name;
+        let app_name_upper = branding.app_name.to_uppercase();
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
         let greeting = username
             .map(|u| format!("Hey {},", u))
             .unwrap_or_else(|| "Hey there,".to_string());
 
         format!(
-            r#"CODE WARRIOR - Verify Your Email
+            r#"{app_name_upper} - Verify Your Email
 
 {greeting}
 
-Thanks for joining Code Warrior! Before you can start your journey to master C programming, please verify your email address by visiting the link below:
+Thanks for joining {app_name}! Before you can start your journey to master C programming, please verify your email address by visiting the link below:
 
 {link}
 
 This link will expire in 24 hours. If you didn't create an account, you can safely ignore this email.
 
 ---
-© 2024 Code Warrior. Master C programming through adventure."#
+© {year} {app_name}. {tagline}"#
         )
     }
 
-    fn render_password_reset_html(&self, username: Option<&str>, link: &str) -> String {
+    fn render_password_reset_html(&self, username: Option<&str>, link: &str, branding: &EmailBranding) -> String {
+        let app_name = &branding.app_name;
+        let primary_color = &branding.primary_color;
+        let primary_color_dark = &branding.primary_color_dark;
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
         let greeting = username
             .map(|u| format!("Hey {},", u))
             .unwrap_or_else(|| "Hey there,".to_string());
@@ -242,7 +355,7 @@ This link will expire in 24 hours. If you didn't create an account, you can safe
         }}
         .button {{
             display: inline-block;
-            background: linear-gradient(135deg, #f59e0b 0%, #d97706 100%);
+            background: linear-gradient(135deg, {primary_color} 0%, {primary_color_dark} 100%);
             color: #0f172a !important;
             text-decoration: none;
             padding: 14px 32px;
@@ -282,7 +395,7 @@ This link will expire in 24 hours. If you didn't create an account, you can safe
         <div class="content">
             <p class="greeting">{greeting}</p>
             <p class="message">
-                We received a request to reset your Code Warrior password. Click the button below to create a new password.
+                We received a request to reset your {app_name} password. Click the button below to create a new password.
             </p>
             <p style="text-align: center; margin: 32px 0;">
                 <a href="{link}" class="button">Reset Password</a>
@@ -299,7 +412,7 @@ This link will expire in 24 hours. If you didn't create an account, you can safe
             </p>
         </div>
         <div class="footer">
-            <p>© 2024 Code Warrior. Master C programming through adventure.</p>
+            <p>© {year} {app_name}. {tagline}</p>
         </div>
     </div>
 </body>
@@ -307,17 +420,21 @@ This link will expire in 24 hours. If you didn't create an account, you can safe
         )
     }
 
-    fn render_password_reset_text(&self, username: Option<&str>, link: &str) -> String {
+    fn render_password_reset_text(&self, username: Option<&str>, link: &str, branding: &EmailBranding) -> String {
+        let app_name = &branding.app_name;
+        let app_name_upper = branding.app_name.to_uppercase();
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
         let greeting = username
             .map(|u| format!("Hey {},", u))
             .unwrap_or_else(|| "Hey there,".to_string());
 
         format!(
-            r#"CODE WARRIOR - Password Reset
+            r#"{app_name_upper} - Password Reset
 
 {greeting}
 
-We received a request to reset your Code Warrior password. Visit the link below to create a new password:
+We received a request to reset your {app_name} password. Visit the link below to create a new password:
 
 {link}
 
@@ -326,11 +443,19 @@ This link will expire in 1 hour for security reasons.
 ⚠️ If you didn't request a password reset, please ignore this email. Your password will remain unchanged.
 
 ---
-© 2024 Code Warrior. Master C programming through adventure."#
+© {year} {app_name}. {tagline}"#
         )
     }
 
-    fn render_welcome_html(&self, username: Option<&str>) -> String {
+    fn render_welcome_html(&self, username: Option<&str>, branding: &EmailBranding) -> String {
+        let app_name = &branding.app_name;
+        let app_name_upper = branding.app_name.to_uppercase();
+        let logo_emoji = &branding.logo_emoji;
+        let primary_color = &branding.primary_color;
+        let primary_color_dark = &branding.primary_color_dark;
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
+        let base_url = &branding.base_url;
         let greeting = username
             .map(|u| format!("Welcome, {}!", u))
             .unwrap_or_else(|| "Welcome, Warrior!".to_string());
@@ -341,7 +466,7 @@ This link will expire in 1 hour for security reasons.
 <head>
     <meta charset="utf-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Welcome to Code Warrior</title>
+    <title>Welcome to {app_name}</title>
     <style>
         body {{
             font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif;
@@ -397,7 +522,7 @@ This link will expire in 1 hour for security reasons.
         }}
         .button {{
             display: inline-block;
-            background: linear-gradient(135deg, #f59e0b 0%, #d97706 100%);
+            background: linear-gradient(135deg, {primary_color} 0%, {primary_color_dark} 100%);
             color: #0f172a !important;
             text-decoration: none;
             padding: 14px 32px;
@@ -417,7 +542,7 @@ This link will expire in 1 hour for security reasons.
 <body>
     <div class="container">
         <div class="header">
-            <h1>⚔️ CODE WARRIOR</h1>
+            <h1>{logo_emoji} {app_name_upper}</h1>
         </div>
         <div class="content">
             <p class="greeting">{greeting}</p>
@@ -438,11 +563,406 @@ This link will expire in 1 hour for security reasons.
                 <span class="feature-text">Earn XP and unlock new levels as you progress</span>
             </div>
             <p style="text-align: center; margin: 32px 0;">
-                <a href="https://code-warrior-seven.vercel.app" class="button">Start Your Adventure</a>
+                <a href="{base_url}" class="button">Start Your Adventure</a>
+            </p>
+        </div>
+        <div class="footer">
+            <p>© {year} {app_name}. {tagline}</p>
+        </div>
+    </div>
+</body>
+</html>"#
+        )
+    }
+
+    fn render_invite_html(&self, link: &str, branding: &EmailBranding) -> String {
+        let app_name = &branding.app_name;
+        let app_name_upper = branding.app_name.to_uppercase();
+        let logo_emoji = &branding.logo_emoji;
+        let primary_color = &branding.primary_color;
+        let primary_color_dark = &branding.primary_color_dark;
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>You're invited to {app_name}</title>
+    <style>
+        body {{
+            font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif;
+            background: linear-gradient(135deg, #0f172a 0%, #1e293b 100%);
+            color: #e2e8f0;
+            margin: 0;
+            padding: 40px 20px;
+        }}
+        .container {{
+            max-width: 560px;
+            margin: 0 auto;
+            background: #1e293b;
+            border-radius: 12px;
+            border: 1px solid #334155;
+            overflow: hidden;
+        }}
+        .header {{
+            background: linear-gradient(135deg, {primary_color} 0%, {primary_color_dark} 100%);
+            padding: 32px;
+            text-align: center;
+        }}
+        .header h1 {{
+            margin: 0;
+            color: #0f172a;
+            font-size: 28px;
+            font-weight: 700;
+            letter-spacing: 2px;
+        }}
+        .content {{
+            padding: 32px;
+        }}
+        .message {{
+            color: #94a3b8;
+            line-height: 1.6;
+            margin-bottom: 24px;
+        }}
+        .button {{
+            display: inline-block;
+            background: linear-gradient(135deg, #22c55e 0%, #16a34a 100%);
+            color: #fff !important;
+            text-decoration: none;
+            padding: 14px 32px;
+            border-radius: 8px;
+            font-weight: 600;
+            font-size: 16px;
+        }}
+        .footer {{
+            padding: 24px 32px;
+            background: #0f172a;
+            text-align: center;
+            color: #64748b;
+            font-size: 12px;
+        }}
+        .link-fallback {{
+            color: #64748b;
+            font-size: 12px;
+            word-break: break-all;
+            margin-top: 16px;
+        }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>{logo_emoji} {app_name_upper}</h1>
+        </div>
+        <div class="content">
+            <p class="message">
+                You've been invited to join {app_name}, a game that teaches C programming through adventure.
+                Click the button below to create your account.
+            </p>
+            <p style="text-align: center; margin: 32px 0;">
+                <a href="{link}" class="button">Accept Invite</a>
+            </p>
+            <p class="message">
+                This invite will expire in 72 hours.
+            </p>
+            <p class="link-fallback">
+                If the button doesn't work, copy and paste this link into your browser:<br>
+                {link}
+            </p>
+        </div>
+        <div class="footer">
+            <p>© {year} {app_name}. {tagline}</p>
+        </div>
+    </div>
+</body>
+</html>"#
+        )
+    }
+
+    fn render_invite_text(&self, link: &str, branding: &EmailBranding) -> String {
+        let app_name = &branding.app_name;
+        let app_name_upper = branding.app_name.to_uppercase();
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
+        format!(
+            r#"{app_name_upper} - You're Invited
+
+You've been invited to join {app_name}, a game that teaches C programming through adventure. Visit the link below to create your account:
+
+{link}
+
+This invite will expire in 72 hours.
+
+---
+© {year} {app_name}. {tagline}"#
+        )
+    }
+
+    fn render_delete_account_html(
+        &self,
+        username: Option<&str>,
+        link: &str,
+        expiry_hours: u32,
+        branding: &EmailBranding,
+    ) -> String {
+        let app_name = &branding.app_name;
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
+        let greeting = username
+            .map(|u| format!("Hey {},", u))
+            .unwrap_or_else(|| "Hey there,".to_string());
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Confirm account deletion</title>
+    <style>
+        body {{
+            font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif;
+            background: linear-gradient(135deg, #0f172a 0%, #1e293b 100%);
+            color: #e2e8f0;
+            margin: 0;
+            padding: 40px 20px;
+        }}
+        .container {{
+            max-width: 560px;
+            margin: 0 auto;
+            background: #1e293b;
+            border-radius: 12px;
+            border: 1px solid #334155;
+            overflow: hidden;
+        }}
+        .header {{
+            background: linear-gradient(135deg, #ef4444 0%, #dc2626 100%);
+            padding: 32px;
+            text-align: center;
+        }}
+        .header h1 {{
+            margin: 0;
+            color: #fff;
+            font-size: 28px;
+            font-weight: 700;
+            letter-spacing: 2px;
+        }}
+        .content {{
+            padding: 32px;
+        }}
+        .greeting {{
+            font-size: 18px;
+            margin-bottom: 16px;
+        }}
+        .message {{
+            color: #94a3b8;
+            line-height: 1.6;
+            margin-bottom: 24px;
+        }}
+        .button {{
+            display: inline-block;
+            background: linear-gradient(135deg, #ef4444 0%, #dc2626 100%);
+            color: #fff !important;
+            text-decoration: none;
+            padding: 14px 32px;
+            border-radius: 8px;
+            font-weight: 600;
+            font-size: 16px;
+        }}
+        .footer {{
+            padding: 24px 32px;
+            background: #0f172a;
+            text-align: center;
+            color: #64748b;
+            font-size: 12px;
+        }}
+        .link-fallback {{
+            color: #64748b;
+            font-size: 12px;
+            word-break: break-all;
+            margin-top: 16px;
+        }}
+        .warning {{
+            background: #451a03;
+            border: 1px solid #92400e;
+            border-radius: 8px;
+            padding: 12px 16px;
+            color: #fbbf24;
+            font-size: 13px;
+            margin-top: 24px;
+        }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>⚠️ DELETE ACCOUNT</h1>
+        </div>
+        <div class="content">
+            <p class="greeting">{greeting}</p>
+            <p class="message">
+                We received a request to permanently delete your {app_name} account. Click the button below to confirm.
+            </p>
+            <p style="text-align: center; margin: 32px 0;">
+                <a href="{link}" class="button">Confirm Deletion</a>
+            </p>
+            <div class="warning">
+                ⚠️ This link expires in {expiry_hours} hour(s) and cannot be undone. If you didn't request this, ignore this email and your account will stay as-is.
+            </div>
+            <p class="link-fallback">
+                If the button doesn't work, copy and paste this link into your browser:<br>
+                {link}
+            </p>
+        </div>
+        <div class="footer">
+            <p>© {year} {app_name}. {tagline}</p>
+        </div>
+    </div>
+</body>
+</html>"#
+        )
+    }
+
+    fn render_delete_account_text(
+        &self,
+        username: Option<&str>,
+        link: &str,
+        expiry_hours: u32,
+        branding: &EmailBranding,
+    ) -> String {
+        let app_name = &branding.app_name;
+        let app_name_upper = branding.app_name.to_uppercase();
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
+        let greeting = username
+            .map(|u| format!("Hey {},", u))
+            .unwrap_or_else(|| "Hey there,".to_string());
+
+        format!(
+            r#"{app_name_upper} - Confirm Account Deletion
+
+{greeting}
+
+We received a request to permanently delete your {app_name} account. Visit the link below to confirm:
+
+{link}
+
+⚠️ This link expires in {expiry_hours} hour(s) and cannot be undone. If you didn't request this, ignore this email and your account will stay as-is.
+
+---
+© {year} {app_name}. {tagline}"#
+        )
+    }
+
+    fn render_confirm_email_change_html(&self, username: Option<&str>, link: &str, branding: &EmailBranding) -> String {
+        let app_name = &branding.app_name;
+        let app_name_upper = branding.app_name.to_uppercase();
+        let logo_emoji = &branding.logo_emoji;
+        let primary_color = &branding.primary_color;
+        let primary_color_dark = &branding.primary_color_dark;
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
+        let greeting = username
+            .map(|u| format!("Hey {},", u))
+            .unwrap_or_else(|| "Hey there,".to_string());
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Confirm your new email</title>
+    <style>
+        body {{
+            font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif;
+            background: linear-gradient(135deg, #0f172a 0%, #1e293b 100%);
+            color: #e2e8f0;
+            margin: 0;
+            padding: 40px 20px;
+        }}
+        .container {{
+            max-width: 560px;
+            margin: 0 auto;
+            background: #1e293b;
+            border-radius: 12px;
+            border: 1px solid #334155;
+            overflow: hidden;
+        }}
+        .header {{
+            background: linear-gradient(135deg, {primary_color} 0%, {primary_color_dark} 100%);
+            padding: 32px;
+            text-align: center;
+        }}
+        .header h1 {{
+            margin: 0;
+            color: #0f172a;
+            font-size: 28px;
+            font-weight: 700;
+            letter-spacing: 2px;
+        }}
+        .content {{
+            padding: 32px;
+        }}
+        .greeting {{
+            font-size: 18px;
+            margin-bottom: 16px;
+        }}
+        .message {{
+            color: #94a3b8;
+            line-height: 1.6;
+            margin-bottom: 24px;
+        }}
+        .button {{
+            display: inline-block;
+            background: linear-gradient(135deg, #22c55e 0%, #16a34a 100%);
+            color: #fff !important;
+            text-decoration: none;
+            padding: 14px 32px;
+            border-radius: 8px;
+            font-weight: 600;
+            font-size: 16px;
+        }}
+        .footer {{
+            padding: 24px 32px;
+            background: #0f172a;
+            text-align: center;
+            color: #64748b;
+            font-size: 12px;
+        }}
+        .link-fallback {{
+            color: #64748b;
+            font-size: 12px;
+            word-break: break-all;
+            margin-top: 16px;
+        }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>{logo_emoji} {app_name_upper}</h1>
+        </div>
+        <div class="content">
+            <p class="greeting">{greeting}</p>
+            <p class="message">
+                Confirm that this is your email address to finish moving your {app_name} account to it.
+            </p>
+            <p style="text-align: center; margin: 32px 0;">
+                <a href="{link}" class="button">Confirm New Email</a>
+            </p>
+            <p class="message">
+                This link will expire in 1 hour. If you didn't request this change, you can safely ignore this email.
+            </p>
+            <p class="link-fallback">
+                If the button doesn't work, copy and paste this link into your browser:<br>
+                {link}
             </p>
         </div>
         <div class="footer">
-            <p>© 2024 Code Warrior. Master C programming through adventure.</p>
+            <p>© {year} {app_name}. {tagline}</p>
         </div>
     </div>
 </body>
@@ -450,13 +970,175 @@ This link will expire in 1 hour for security reasons.
         )
     }
 
-    fn render_welcome_text(&self, username: Option<&str>) -> String {
+    fn render_confirm_email_change_text(&self, username: Option<&str>, link: &str, branding: &EmailBranding) -> String {
+        let app_name = &branding.app_name;
+        let app_name_upper = branding.app_name.to_uppercase();
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
+        let greeting = username
+            .map(|u| format!("Hey {},", u))
+            .unwrap_or_else(|| "Hey there,".to_string());
+
+        format!(
+            r#"{app_name_upper} - Confirm Your New Email
+
+{greeting}
+
+Confirm that this is your email address to finish moving your {app_name} account to it. Visit the link below:
+
+{link}
+
+This link will expire in 1 hour. If you didn't request this change, you can safely ignore this email.
+
+---
+© {year} {app_name}. {tagline}"#
+        )
+    }
+
+    fn render_email_change_notice_html(
+        &self,
+        username: Option<&str>,
+        old_email: &str,
+        new_email: &str,
+        branding: &EmailBranding,
+    ) -> String {
+        let app_name = &branding.app_name;
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
+        let greeting = username
+            .map(|u| format!("Hey {},", u))
+            .unwrap_or_else(|| "Hey there,".to_string());
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Your email is changing</title>
+    <style>
+        body {{
+            font-family: 'Segoe UI', Tahoma, Geneva, Verdana, sans-serif;
+            background: linear-gradient(135deg, #0f172a 0%, #1e293b 100%);
+            color: #e2e8f0;
+            margin: 0;
+            padding: 40px 20px;
+        }}
+        .container {{
+            max-width: 560px;
+            margin: 0 auto;
+            background: #1e293b;
+            border-radius: 12px;
+            border: 1px solid #334155;
+            overflow: hidden;
+        }}
+        .header {{
+            background: linear-gradient(135deg, #ef4444 0%, #dc2626 100%);
+            padding: 32px;
+            text-align: center;
+        }}
+        .header h1 {{
+            margin: 0;
+            color: #fff;
+            font-size: 28px;
+            font-weight: 700;
+            letter-spacing: 2px;
+        }}
+        .content {{
+            padding: 32px;
+        }}
+        .greeting {{
+            font-size: 18px;
+            margin-bottom: 16px;
+        }}
+        .message {{
+            color: #94a3b8;
+            line-height: 1.6;
+            margin-bottom: 24px;
+        }}
+        .footer {{
+            padding: 24px 32px;
+            background: #0f172a;
+            text-align: center;
+            color: #64748b;
+            font-size: 12px;
+        }}
+        .warning {{
+            background: #451a03;
+            border: 1px solid #92400e;
+            border-radius: 8px;
+            padding: 12px 16px;
+            color: #fbbf24;
+            font-size: 13px;
+            margin-top: 24px;
+        }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>⚠️ EMAIL CHANGE REQUESTED</h1>
+        </div>
+        <div class="content">
+            <p class="greeting">{greeting}</p>
+            <p class="message">
+                Someone requested to change the email on your {app_name} account from <strong>{old_email}</strong> to <strong>{new_email}</strong>.
+                The change only takes effect once that address is confirmed.
+            </p>
+            <div class="warning">
+                ⚠️ If this wasn't you, reset your password immediately - someone else may have access to your account.
+            </div>
+        </div>
+        <div class="footer">
+            <p>© {year} {app_name}. {tagline}</p>
+        </div>
+    </div>
+</body>
+</html>"#
+        )
+    }
+
+    fn render_email_change_notice_text(
+        &self,
+        username: Option<&str>,
+        old_email: &str,
+        new_email: &str,
+        branding: &EmailBranding,
+    ) -> String {
+        let app_name = &branding.app_name;
+        let app_name_upper = branding.app_name.to_uppercase();
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
+        let greeting = username
+            .map(|u| format!("Hey {},", u))
+            .unwrap_or_else(|| "Hey there,".to_string());
+
+        format!(
+            r#"{app_name_upper} - Email Change Requested
+
+{greeting}
+
+Someone requested to change the email on your {app_name} account from {old_email} to {new_email}. The change only takes effect once that address is confirmed.
+
+⚠️ If this wasn't you, reset your password immediately - someone else may have access to your account.
+
+---
+© {year} {app_name}. {tagline}"#
+        )
+    }
+
+    fn render_welcome_text(&self, username: Option<&str>, branding: &EmailBranding) -> String {
+        let app_name = &branding.app_name;
+        let app_name_upper = branding.app_name.to_uppercase();
+        let tagline = &branding.tagline;
+        let year = branding.copyright_year;
+        let base_url = &branding.base_url;
         let greeting = username
             .map(|u| format!("Welcome, {}!", u))
             .unwrap_or_else(|| "Welcome, Warrior!".to_string());
 
         format!(
-            r#"CODE WARRIOR - Welcome!
+            r#"{app_name_upper} - Welcome!
 
 {greeting}
 
@@ -468,10 +1150,10 @@ What awaits you:
 🏰 Explore a world where memory management is magic
 ⭐ Earn XP and unlock new levels as you progress
 
-Start your adventure: https://code-warrior-seven.vercel.app
+Start your adventure: {base_url}
 
 ---
-© 2024 Code Warrior. Master C programming through adventure."#
+© {year} {app_name}. {tagline}"#
         )
     }
 }
@@ -0,0 +1,27 @@
+//! Pluggable email delivery backends.
+//!
+//! `EmailService` talks to whichever [`MailTransport`] it's built with -
+//! the Resend HTTP API by default, or direct SMTP for self-hosters who
+//! can't (or don't want to) depend on a third-party relay. Picked at
+//! startup by `EmailService::new` based on `EMAIL_BACKEND`.
+
+mod resend;
+mod smtp;
+
+pub use resend::ResendTransport;
+pub use smtp::SmtpTransport;
+
+use async_trait::async_trait;
+
+/// A backend capable of actually delivering a rendered email. Implementors
+/// own their own transport-level concerns (HTTP client, SMTP connection,
+/// TLS, auth); `EmailService` only ever talks to this trait, so adding a
+/// new backend never touches the template/branding code above it.
+#[async_trait]
+pub trait MailTransport: Send + Sync {
+    /// Sends one email. `from` is a full `"Name <address>"` header value;
+    /// `to` is a bare address. Returns a backend-specific identifier for
+    /// the sent message on success (e.g. the Resend message id).
+    async fn send(&self, from: &str, to: &str, subject: &str, html: &str, text: &str)
+        -> Result<String, String>;
+}
@@ -0,0 +1,92 @@
+//! Resend HTTP API backend - the default, zero-setup transport for hosted
+//! deployments.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::MailTransport;
+
+/// Resend API request payload
+#[derive(Serialize)]
+struct ResendEmailRequest {
+    from: String,
+    to: Vec<String>,
+    subject: String,
+    html: String,
+    text: String,
+}
+
+/// Resend API response
+#[derive(Deserialize)]
+struct ResendEmailResponse {
+    id: String,
+}
+
+/// Resend API error response
+#[derive(Deserialize)]
+struct ResendErrorResponse {
+    message: String,
+}
+
+/// Delivers email via the Resend HTTP API.
+pub struct ResendTransport {
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl ResendTransport {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            api_key,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl MailTransport for ResendTransport {
+    async fn send(
+        &self,
+        from: &str,
+        to: &str,
+        subject: &str,
+        html: &str,
+        text: &str,
+    ) -> Result<String, String> {
+        let request = ResendEmailRequest {
+            from: from.to_string(),
+            to: vec![to.to_string()],
+            subject: subject.to_string(),
+            html: html.to_string(),
+            text: text.to_string(),
+        };
+
+        let response = self
+            .client
+            .post("https://api.resend.com/emails")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send email: {}", e))?;
+
+        if response.status().is_success() {
+            let result: ResendEmailResponse = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))?;
+            tracing::info!("Email sent successfully to {}, id: {}", to, result.id);
+            Ok(result.id)
+        } else {
+            let error: ResendErrorResponse = response
+                .json()
+                .await
+                .unwrap_or(ResendErrorResponse {
+                    message: "Unknown error".to_string(),
+                });
+            tracing::error!("Failed to send email to {}: {}", to, error.message);
+            Err(format!("Email send failed: {}", error.message))
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Direct SMTP backend, for self-hosters who'd rather point at their own
+//! mail server than depend on a third-party relay.
+
+use async_trait::async_trait;
+use lettre::message::{MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use super::MailTransport;
+
+/// Delivers email over SMTP, optionally authenticated, with the TLS mode
+/// chosen by `SMTP_TLS` (`starttls`, the default, or `implicit`).
+pub struct SmtpTransport {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    /// Builds a transport from `SMTP_HOST`/`SMTP_PORT` (both required) and
+    /// optional `SMTP_USER`/`SMTP_PASS` for authenticated relays. Returns
+    /// `None` if the host/port aren't set or the TLS config can't be built.
+    pub fn from_env() -> Option<Self> {
+        let host = std::env::var("SMTP_HOST").ok()?;
+        let port: u16 = std::env::var("SMTP_PORT").ok()?.parse().ok()?;
+        let implicit_tls = std::env::var("SMTP_TLS")
+            .map(|v| v.eq_ignore_ascii_case("implicit"))
+            .unwrap_or(false);
+
+        let tls_parameters = TlsParameters::new(host.clone()).ok()?;
+        let tls = if implicit_tls {
+            Tls::Wrapper(tls_parameters)
+        } else {
+            Tls::Required(tls_parameters)
+        };
+
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host)
+            .port(port)
+            .tls(tls);
+
+        if let (Ok(user), Ok(pass)) = (std::env::var("SMTP_USER"), std::env::var("SMTP_PASS")) {
+            builder = builder.credentials(Credentials::new(user, pass));
+        }
+
+        Some(Self {
+            mailer: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl MailTransport for SmtpTransport {
+    async fn send(
+        &self,
+        from: &str,
+        to: &str,
+        subject: &str,
+        html: &str,
+        text: &str,
+    ) -> Result<String, String> {
+        let email = Message::builder()
+            .from(from.parse().map_err(|e| format!("Invalid from address: {}", e))?)
+            .to(to.parse().map_err(|e| format!("Invalid to address: {}", e))?)
+            .subject(subject)
+            .multipart(
+                MultiPart::alternative()
+                    .singlepart(SinglePart::plain(text.to_string()))
+                    .singlepart(SinglePart::html(html.to_string())),
+            )
+            .map_err(|e| format!("Failed to build email: {}", e))?;
+
+        self.mailer
+            .send(email)
+            .await
+            .map(|_| {
+                tracing::info!("Email sent successfully to {} via SMTP", to);
+                "smtp-sent".to_string()
+            })
+            .map_err(|e| {
+                tracing::error!("Failed to send email to {} via SMTP: {}", to, e);
+                format!("Email send failed: {}", e)
+            })
+    }
+}
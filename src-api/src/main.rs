@@ -1,5 +1,8 @@
 use axum::{
-    extract::{Path, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
     http::StatusCode,
     middleware,
     response::Json,
@@ -7,10 +10,11 @@ use axum::{
     Router,
 };
 use code_warrior::{
-    compiler::CCompiler,
-    game::{GamePhase, GameState, PlayerAction, RenderState},
+    compiler::{CCompiler, CoverageReport},
+    game::{GamePhase, GameState, PlayerAction, ProgressionState, RenderState},
     levels::{
-        generate_harness, LevelData, LevelInfo, LevelRegistry, TestCaseResult, TestSuiteResult,
+        diagnose_failure, run_test_suite, run_test_suite_with_coverage, LevelData, LevelInfo,
+        LevelRegistry, TestSuiteResult, TestSuiteRun,
     },
 };
 use dashmap::DashMap;
@@ -21,12 +25,16 @@ use std::sync::Arc;
 use std::time::Duration;
 use tower_http::cors::{Any, CorsLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use utoipa::OpenApi;
 use uuid::Uuid;
 
 mod auth;
 mod auth_middleware;
+mod config;
 mod db;
 mod email;
+mod openapi;
+mod push;
 
 struct AppState {
     db: Pool<Postgres>,
@@ -35,6 +43,38 @@ struct AppState {
     /// In-memory session cache keyed by device ID to avoid hitting Neon on every tick/move
     sessions: DashMap<String, GameState>,
     rate_limiter: auth_middleware::SharedRateLimiter,
+    /// Broadcasts a delta every time a level completion is recorded, so
+    /// connected clients can update rankings live instead of polling
+    /// `/leaderboard`. Lagging/absent receivers are fine - a missed delta is
+    /// just a stale leaderboard until the client's next full refetch.
+    leaderboard_tx: tokio::sync::broadcast::Sender<LeaderboardDelta>,
+    /// Broadcasts every room's updated render state whenever a participant
+    /// completes a quest, so the rest of the room sees progress live
+    /// instead of polling. One channel for every room (like
+    /// `leaderboard_tx`) rather than one per room, since `/rooms/:id/stream`
+    /// just filters by `room_id`.
+    room_tx: tokio::sync::broadcast::Sender<RoomDelta>,
+    /// Web Push notifications (streak reminders, level unlocks, achievements
+    /// earned elsewhere). Absent in dev unless `VAPID_PRIVATE_KEY`/
+    /// `VAPID_SUBJECT` are set - see `push::PushService::new`.
+    push: push::OptionalPushService,
+}
+
+/// Broadcast over `/leaderboard/stream` whenever a player clears a level.
+#[derive(Debug, Clone, Serialize)]
+struct LeaderboardDelta {
+    user_id: Uuid,
+    level_id: String,
+    xp_earned: u32,
+    total_xp: u32,
+}
+
+/// Broadcast over `/rooms/:room_id/stream` whenever a participant completes
+/// a quest in that room.
+#[derive(Debug, Clone, Serialize)]
+struct RoomDelta {
+    room_id: Uuid,
+    render_state: RenderState,
 }
 
 #[derive(Debug, Deserialize)]
@@ -45,6 +85,11 @@ struct InitGameRequest {
 #[derive(Debug, Deserialize)]
 struct SyncGameRequest {
     game_state: GameState,
+    /// The `version` the client last saw (from `GameStateResponse` or a
+    /// prior sync) - compare-and-swapped against the stored session so a
+    /// stale client (e.g. a browser tab open since before a Tauri sync)
+    /// can't silently clobber newer progress. See `db::SessionUpdateError`.
+    expected_version: i32,
 }
 
 #[derive(Debug, Serialize)]
@@ -53,6 +98,15 @@ struct InitGameResponse {
     game_state: GameState,
 }
 
+/// `GameState` plus the session row's current `version`, so the client can
+/// pass it back as `expected_version` on its next `/game/sync`.
+#[derive(Debug, Serialize)]
+struct GameStateResponse {
+    #[serde(flatten)]
+    game_state: GameState,
+    version: i32,
+}
+
 #[derive(Debug, Serialize)]
 struct LoadLevelResponse {
     level_data: LevelData,
@@ -64,6 +118,13 @@ struct SubmitCodeRequest {
     code: String,
     #[serde(default)]
     test_only: bool,
+    #[serde(default)]
+    collect_coverage: bool,
+    /// Stable test-case IDs (see [`code_warrior::levels::TestCase::stable_id`])
+    /// to re-run in isolation instead of the full suite. A proper subset
+    /// can never complete the level or award XP.
+    #[serde(default)]
+    rerun_failed: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -72,6 +133,10 @@ struct SubmitQuestCodeRequest {
     quest_id: String,
     #[serde(default)]
     test_only: bool,
+    #[serde(default)]
+    collect_coverage: bool,
+    #[serde(default)]
+    rerun_failed: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -104,6 +169,8 @@ struct SubmitCodeResponse {
     render_state: RenderState,
     #[serde(skip_serializing_if = "Option::is_none")]
     test_results: Option<TestSuiteResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coverage: Option<CoverageReport>,
 }
 
 #[derive(Debug, Serialize)]
@@ -129,6 +196,103 @@ struct SaveSlotResponse {
     empty: bool,
 }
 
+#[derive(Debug, Deserialize)]
+struct RegisterDeviceRequest {
+    name: Option<String>,
+    platform: Option<String>,
+    push_endpoint: Option<String>,
+}
+
+/// Request body for `POST /auth/link-device`.
+#[derive(Debug, Deserialize)]
+struct LinkDeviceRequest {
+    device_id: String,
+}
+
+/// The subscription object returned by the browser's
+/// `PushSubscription.toJSON()`.
+#[derive(Debug, Deserialize)]
+struct PushSubscribeRequest {
+    endpoint: String,
+    keys: PushSubscriptionKeys,
+}
+
+#[derive(Debug, Deserialize)]
+struct PushSubscriptionKeys {
+    p256dh: String,
+    auth: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PushUnsubscribeRequest {
+    endpoint: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DeviceResponse {
+    id: String,
+    name: Option<String>,
+    platform: Option<String>,
+    last_seen_at: String,
+}
+
+impl From<db::models::Device> for DeviceResponse {
+    fn from(device: db::models::Device) -> Self {
+        Self {
+            id: device.id.to_string(),
+            name: device.name,
+            platform: device.platform,
+            last_seen_at: device.last_seen_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EnqueueDeviceCommandRequest {
+    command: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct DeviceCommandResponse {
+    id: String,
+    command: serde_json::Value,
+}
+
+impl From<db::models::DeviceCommand> for DeviceCommandResponse {
+    fn from(command: db::models::DeviceCommand) -> Self {
+        Self {
+            id: command.id.to_string(),
+            command: command.command,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateRoomRequest {
+    level_id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RoomResponse {
+    id: Uuid,
+    level_id: String,
+    created_by: Uuid,
+    created_at: String,
+    participant_ids: Vec<Uuid>,
+}
+
+impl RoomResponse {
+    fn new(room: db::models::Room, participant_ids: Vec<Uuid>) -> Self {
+        Self {
+            id: room.id,
+            level_id: room.level_id,
+            created_by: room.created_by,
+            created_at: room.created_at.to_rfc3339(),
+            participant_ids,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     // Load environment variables
@@ -174,7 +338,11 @@ async fn main() {
 
     tracing::info!("Game systems initialized");
 
-    let rate_limiter = auth_middleware::create_rate_limiter();
+    let app_config = config::AppConfig::load();
+
+    let rate_limiter = auth_middleware::create_rate_limiter_with_config(app_config.rate_limit.clone());
+    let (leaderboard_tx, _) = tokio::sync::broadcast::channel(128);
+    let (room_tx, _) = tokio::sync::broadcast::channel(128);
 
     let state = Arc::new(AppState {
         db: pool,
@@ -182,6 +350,9 @@ async fn main() {
         compiler,
         sessions: DashMap::new(),
         rate_limiter,
+        leaderboard_tx,
+        room_tx,
+        push: push::OptionalPushService::new(),
     });
 
     let cors = CorsLayer::new()
@@ -195,36 +366,111 @@ async fn main() {
 
     let auth_state = Arc::new(auth::handlers::AuthState {
         db: state.db.clone(),
-        email: email::OptionalEmailService::new(),
-        google_oauth: auth::oauth::GoogleOAuth::from_env(&api_url),
-        github_oauth: auth::oauth::GitHubOAuth::from_env(&api_url),
+        email: email::OptionalEmailService::new(app_config.email.clone()),
+        oauth_providers: auth::oauth::ProviderRegistry::from_env(&api_url),
+        oauth_pkce: auth::oauth::PkceStore::new(),
+        webauthn: auth::webauthn::WebAuthnService::from_env().map(Arc::new),
         frontend_url,
+        security_stamps: DashMap::new(),
+        require_invite: std::env::var("REQUIRE_INVITE")
+            .map(|v| v == "true")
+            .unwrap_or(false),
     });
 
+    auth::handlers::spawn_oauth_token_refresh(auth_state.clone(), Duration::from_secs(300));
+    auth::handlers::spawn_email_token_cleanup(auth_state.clone(), Duration::from_secs(3600));
+
     let auth_routes = Router::new()
         .route("/register", post(auth::handlers::register))
+        .route("/invite", post(auth::handlers::create_invite))
+        .route("/invite-codes", post(auth::handlers::create_invite_code))
         .route("/login", post(auth::handlers::login))
+        .route("/refresh", post(auth::handlers::refresh))
         .route("/logout", post(auth::handlers::logout))
+        .route("/logout-all", post(auth::handlers::logout_all))
+        .route("/sessions", get(auth::handlers::list_sessions))
+        .route(
+            "/sessions/:id",
+            axum::routing::delete(auth::handlers::revoke_session),
+        )
         .route("/me", get(auth::handlers::me))
         .route("/verify-email", post(auth::handlers::verify_email))
         .route("/resend-verify", post(auth::handlers::resend_verify))
         .route("/request-reset", post(auth::handlers::request_reset))
         .route("/reset-password", post(auth::handlers::reset_password))
+        .route("/delete/request", post(auth::handlers::delete_request))
+        .route("/delete/confirm", post(auth::handlers::delete_confirm))
+        .route("/change-email", post(auth::handlers::change_email))
+        .route(
+            "/change-email/confirm",
+            post(auth::handlers::change_email_confirm),
+        )
+        .route("/oauth/:provider/start", get(auth::handlers::oauth_start))
+        .route(
+            "/oauth/:provider/callback",
+            get(auth::handlers::oauth_callback),
+        )
+        .route(
+            "/webauthn/register/start",
+            post(auth::handlers::webauthn_register_start),
+        )
+        .route(
+            "/webauthn/register/finish",
+            post(auth::handlers::webauthn_register_finish),
+        )
+        .route(
+            "/webauthn/login/start",
+            post(auth::handlers::webauthn_login_start),
+        )
+        .route(
+            "/webauthn/login/finish",
+            post(auth::handlers::webauthn_login_finish),
+        )
+        .route(
+            "/identities/:provider/link-start",
+            get(auth::handlers::link_identity_start),
+        )
         .route(
-            "/oauth/google/start",
-            get(auth::handlers::google_oauth_start),
+            "/identities/:provider",
+            axum::routing::delete(auth::handlers::unlink_identity),
         )
         .route(
-            "/oauth/google/callback",
-            get(auth::handlers::google_oauth_callback),
+            "/totp/enroll/start",
+            post(auth::handlers::totp_enroll_start),
         )
         .route(
-            "/oauth/github/start",
-            get(auth::handlers::github_oauth_start),
+            "/totp/enroll/confirm",
+            post(auth::handlers::totp_enroll_confirm),
         )
+        .route("/totp/disable", post(auth::handlers::totp_disable))
+        .route("/admin/users", get(auth::handlers::users_overview))
         .route(
-            "/oauth/github/callback",
-            get(auth::handlers::github_oauth_callback),
+            "/admin/users/:id/suspend",
+            post(auth::handlers::suspend_user),
+        )
+        .route(
+            "/admin/users/:id/blacklist",
+            post(auth::handlers::blacklist_user),
+        )
+        .route(
+            "/admin/bans/:id/revoke",
+            post(auth::handlers::admin_revoke_ban),
+        )
+        .route(
+            "/admin/users/:id/deauth",
+            post(auth::handlers::deauth_user),
+        )
+        .route(
+            "/admin/users/:id/reset-progress",
+            post(auth::handlers::reset_progress),
+        )
+        .route(
+            "/trusted-contacts",
+            post(auth::handlers::invite_trusted_contact),
+        )
+        .route(
+            "/trusted-contacts/shared",
+            get(auth::handlers::shared_save_slots),
         )
         .layer(axum::Extension(state.rate_limiter.clone()))
         .layer(middleware::from_fn(
@@ -245,12 +491,38 @@ async fn main() {
         .route("/levels/current", get(get_current_level))
         .route("/levels/current/quests", get(get_level_quests))
         .route("/levels/current/quests/:quest_id", get(get_quest))
+        .route("/levels/:id/submissions", get(get_level_submissions))
+        .route(
+            "/levels/current/quests/:quest_id/submissions",
+            get(get_quest_submissions),
+        )
         .route("/code/hint/:index", get(get_hint))
         .route("/player/progress", get(get_progress))
         .route("/saves", get(list_saves))
         .route("/saves/:slot", post(save_game))
         .route("/saves/:slot", get(load_save))
         .route("/saves/:slot", axum::routing::delete(delete_save))
+        .route("/auth/link-device", post(link_device))
+        .route("/devices/register", post(register_device))
+        .route("/devices", get(list_devices))
+        .route("/devices/:id/commands", post(enqueue_device_command))
+        .route("/devices/:id/commands/poll", post(poll_device_commands))
+        .route("/push/vapid-key", get(get_push_vapid_key))
+        .route("/push/subscribe", post(push_subscribe))
+        .route("/push/unsubscribe", post(push_unsubscribe))
+        .route("/leaderboard", get(get_leaderboard))
+        .route("/leaderboard/level/:level_id", get(get_level_leaderboard))
+        .route("/leaderboard/stream", get(leaderboard_stream))
+        .route("/levels/:id/fastest", get(get_level_fastest))
+        .route("/rooms", post(create_room))
+        .route("/rooms/:room_id", get(get_room))
+        .route("/rooms/:room_id/join", post(join_room))
+        .route("/rooms/:room_id/leave", post(leave_room))
+        .route(
+            "/rooms/:room_id/code/submit-quest",
+            post(submit_room_quest_code),
+        )
+        .route("/rooms/:room_id/stream", get(room_stream))
         .layer(axum::Extension(state.rate_limiter.clone()))
         .layer(axum::Extension(state.db.clone()))
         .layer(middleware::from_fn(auth_middleware::rate_limit_middleware))
@@ -260,10 +532,30 @@ async fn main() {
         .layer(middleware::from_fn(auth_middleware::ban_check_middleware))
         .layer(middleware::from_fn(auth_middleware::jwt_auth_middleware));
 
+    // Level participation/leaderboard routes: `optional_jwt_auth_middleware`
+    // rather than the hard-gated `jwt_auth_middleware` above, since the
+    // leaderboard itself is public - `join`/`leave` still require an
+    // identity, but reject with a normal 401 from inside the handler instead
+    // of the middleware refusing the request outright.
+    let level_participation_routes = Router::new()
+        .route("/levels/:id/join", post(join_level))
+        .route("/levels/:id/leave", post(leave_level))
+        .route("/levels/:id/leaderboard", get(get_level_xp_leaderboard))
+        .layer(axum::Extension(state.rate_limiter.clone()))
+        .layer(middleware::from_fn(auth_middleware::rate_limit_middleware))
+        .layer(middleware::from_fn(
+            auth_middleware::optional_jwt_auth_middleware,
+        ));
+
     let app = Router::new()
         .route("/health", get(health_check))
         .nest("/api/auth", auth_routes)
         .nest("/api", protected_routes)
+        .nest("/api", level_participation_routes)
+        .merge(
+            utoipa_swagger_ui::SwaggerUi::new("/api/docs")
+                .url("/api/openapi.json", openapi::ApiDoc::openapi()),
+        )
         .layer(cors)
         .with_state(state);
 
@@ -279,30 +571,52 @@ async fn main() {
         .await
         .expect("Failed to bind to address");
 
-    axum::serve(listener, app)
-        .await
-        .expect("Server failed to start");
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .expect("Server failed to start");
+}
+
+/// Which account a `GameState` belongs to: a lone player's own progress, or
+/// a shared co-op room several players work a level through together.
+/// Threaded through `get_or_create_session`/`persist_session`/
+/// `cache_session` instead of a bare `user_id` so those three stay the only
+/// place that knows how a session's cache key and DB row are derived.
+#[derive(Debug, Clone, Copy)]
+enum SessionScope {
+    User(Uuid),
+    /// A room's shared session has no single owning user, so it's stored
+    /// under a synthetic `"room-{id}"` device_id the same way a user
+    /// session is stored under `"user-{id}"` - see
+    /// `db::upsert_session_by_device_id`.
+    Room(Uuid),
+}
+
+impl SessionScope {
+    fn cache_key(&self) -> String {
+        match self {
+            SessionScope::User(id) => format!("user-{}", id),
+            SessionScope::Room(id) => format!("room-{}", id),
+        }
+    }
 }
 
 // Helper to get or create game state for a session using an in-memory cache with DB fallback
-// All progress is user-based (account-only) - no anonymous/device tracking
-async fn get_or_create_session(app: &Arc<AppState>, user_id: Uuid) -> Result<GameState, String> {
-    let cache_key = format!("user-{}", user_id);
+async fn get_or_create_session(app: &Arc<AppState>, scope: SessionScope) -> Result<GameState, String> {
+    let cache_key = scope.cache_key();
 
     // Fast path: in-memory session
     if let Some(entry) = app.sessions.get(&cache_key) {
-        let gs = entry.value();
-        tracing::info!(
-            "DEBUG get_or_create_session: cache hit for {}, completed_quests: {:?}, total_xp: {}",
-            cache_key,
-            gs.progression.completed_quests,
-            gs.progression.total_xp
-        );
-        return Ok(gs.clone());
+        return Ok(entry.value().clone());
     }
 
     // Fallback: load from database or create a new session
-    let db_session = db::get_session_by_user_id(&app.db, user_id).await;
+    let db_session = match scope {
+        SessionScope::User(user_id) => db::get_session_by_user_id(&app.db, user_id).await,
+        SessionScope::Room(_) => db::get_session_by_device_id(&app.db, &cache_key).await,
+    };
 
     match db_session {
         Ok(Some(session)) => {
@@ -328,10 +642,19 @@ async fn get_or_create_session(app: &Arc<AppState>, user_id: Uuid) -> Result<Gam
             let session_json = serde_json::to_value(&new_state)
                 .map_err(|e| format!("Failed to serialize game state: {}", e))?;
 
-            // Save new session for user
-            db::upsert_session_by_user_id(&app.db, user_id, &session_json)
-                .await
-                .map_err(|e| format!("Failed to create user session: {}", e))?;
+            // Save new session
+            match scope {
+                SessionScope::User(user_id) => {
+                    db::upsert_session_by_user_id(&app.db, user_id, &session_json)
+                        .await
+                        .map_err(|e| format!("Failed to create user session: {}", e))?;
+                }
+                SessionScope::Room(_) => {
+                    db::upsert_session_by_device_id(&app.db, &cache_key, None, &session_json)
+                        .await
+                        .map_err(|e| format!("Failed to create room session: {}", e))?;
+                }
+            }
 
             app.sessions.insert(cache_key, new_state.clone());
             Ok(new_state)
@@ -341,28 +664,172 @@ async fn get_or_create_session(app: &Arc<AppState>, user_id: Uuid) -> Result<Gam
 }
 
 // Helper to cache session state in memory only (no DB write)
-fn cache_session(app: &Arc<AppState>, user_id: Uuid, state: &GameState) {
-    let cache_key = format!("user-{}", user_id);
-    app.sessions.insert(cache_key, state.clone());
+fn cache_session(app: &Arc<AppState>, scope: SessionScope, state: &GameState) {
+    app.sessions.insert(scope.cache_key(), state.clone());
+}
+
+/// Record a level clear in the leaderboard tables and broadcast the delta to
+/// any `/leaderboard/stream` listeners. Best-effort: a DB error here is
+/// logged and swallowed rather than failing the submission response, since
+/// the player's actual XP/progress was already awarded in `game_state` by
+/// the time this is called.
+async fn record_level_completion_and_broadcast(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+    level_id: &str,
+    duration_ms: Option<i64>,
+    xp_earned: u32,
+    total_xp: u32,
+    newly_unlocked: &[String],
+) {
+    if let Err(e) = db::record_level_completion(&state.db, level_id, user_id, duration_ms).await {
+        tracing::warn!("failed to record level completion for {}: {e}", user_id);
+        return;
+    }
+
+    if let Err(e) =
+        db::upsert_level_best_xp(&state.db, level_id, user_id, xp_earned as i32).await
+    {
+        tracing::warn!("failed to upsert level best_xp for {}: {e}", user_id);
+    }
+
+    let _ = state.leaderboard_tx.send(LeaderboardDelta {
+        user_id,
+        level_id: level_id.to_string(),
+        xp_earned,
+        total_xp,
+    });
+
+    if state.push.is_available() && !newly_unlocked.is_empty() {
+        match db::get_push_subscriptions(&state.db, user_id).await {
+            Ok(subscriptions) if !subscriptions.is_empty() => {
+                for unlocked_id in newly_unlocked {
+                    let title = state
+                        .levels
+                        .get_level(unlocked_id)
+                        .map(|l| l.title.clone())
+                        .unwrap_or_else(|| unlocked_id.clone());
+                    state
+                        .push
+                        .notify_all(
+                            &state.db,
+                            user_id,
+                            &subscriptions,
+                            &push::PushPayload::LevelUnlocked {
+                                level_id: unlocked_id.clone(),
+                                level_title: title,
+                            },
+                        )
+                        .await;
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("failed to load push subscriptions for {}: {e}", user_id),
+        }
+    }
+}
+
+/// Record one TEST-or-SUBMIT attempt in the `submissions` table, so the
+/// player can review their history or restore a previous attempt's code.
+/// Best-effort like `record_level_completion_and_broadcast`: a DB error
+/// here is logged and swallowed rather than failing the response, since
+/// grading already happened by the time this is called.
+async fn record_submission(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+    level_id: &str,
+    quest_id: Option<&str>,
+    code: &str,
+    passed_count: i32,
+    total_count: i32,
+    execution_time_ms: Option<i64>,
+) {
+    if let Err(e) = db::insert_submission(
+        &state.db,
+        user_id,
+        level_id,
+        quest_id,
+        code,
+        passed_count,
+        total_count,
+        execution_time_ms,
+    )
+    .await
+    {
+        tracing::warn!("failed to record submission for {}: {e}", user_id);
+    }
+}
+
+/// Retry `update_user_session_state`'s compare-and-swap against whatever the
+/// row's current version turns out to be. Unlike `sync_game`'s
+/// client-supplied `expected_version`, this is an internal,
+/// server-authoritative write computed from session data fetched moments
+/// earlier in the same request, so a version conflict here just means
+/// another request for the same user landed in between - there's nothing to
+/// surface to a caller, it just needs the CAS to eventually land.
+async fn persist_user_session_retrying(
+    app: &Arc<AppState>,
+    user_id: Uuid,
+    state_json: &serde_json::Value,
+) -> Result<(), String> {
+    for _ in 0..5 {
+        let version = match db::get_session_by_user_id(&app.db, user_id).await {
+            Ok(Some(session)) => session.version,
+            Ok(None) => return Err("Session not found".to_string()),
+            Err(e) => return Err(format!("Database error: {}", e)),
+        };
+
+        match db::update_user_session_state(&app.db, user_id, state_json, version).await {
+            Ok(_) => return Ok(()),
+            Err(db::SessionUpdateError::Conflict(_)) => continue,
+            Err(db::SessionUpdateError::Database(e)) => return Err(format!("Database error: {}", e)),
+        }
+    }
+    Err("Failed to update user session after repeated version conflicts".to_string())
+}
+
+/// Device-keyed counterpart of [`persist_user_session_retrying`], for
+/// room-scoped sessions stored under a synthetic `"room-{id}"` device_id.
+async fn persist_device_session_retrying(
+    app: &Arc<AppState>,
+    device_id: &str,
+    state_json: &serde_json::Value,
+) -> Result<(), String> {
+    for _ in 0..5 {
+        let version = match db::get_session_by_device_id(&app.db, device_id).await {
+            Ok(Some(session)) => session.version,
+            Ok(None) => return Err("Session not found".to_string()),
+            Err(e) => return Err(format!("Database error: {}", e)),
+        };
+
+        match db::update_session_state_by_device_id(&app.db, device_id, state_json, version).await {
+            Ok(_) => return Ok(()),
+            Err(db::SessionUpdateError::Conflict(_)) => continue,
+            Err(db::SessionUpdateError::Database(e)) => return Err(format!("Database error: {}", e)),
+        }
+    }
+    Err("Failed to update room session after repeated version conflicts".to_string())
 }
 
 // Helper to persist session state to DB (and update in-memory cache)
-// All progress is user-based (account-only) - no anonymous/device tracking
 async fn persist_session(
     app: &Arc<AppState>,
-    user_id: Uuid,
+    scope: SessionScope,
     state: &GameState,
 ) -> Result<(), String> {
-    cache_session(app, user_id, state);
+    cache_session(app, scope, state);
 
     let state_json = serde_json::to_value(state)
         .map_err(|e| format!("Failed to serialize game state: {}", e))?;
 
-    db::update_user_session_state(&app.db, user_id, &state_json)
-        .await
-        .map_err(|e| format!("Failed to update user session: {}", e))?;
-
-    Ok(())
+    match scope {
+        SessionScope::User(user_id) => {
+            persist_user_session_retrying(app, user_id, &state_json).await
+        }
+        SessionScope::Room(_) => {
+            persist_device_session_retrying(app, &scope.cache_key(), &state_json).await
+        }
+    }
 }
 
 // Handler functions
@@ -393,7 +860,7 @@ async fn init_game(
     tracing::info!("Initializing new game session for user: {}", user_id);
 
     // Force create new state or reset? For now, just get/create
-    let game_state = get_or_create_session(&state, user_id)
+    let game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -409,32 +876,72 @@ async fn sync_game(
     State(state): State<Arc<AppState>>,
     axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
     Json(request): Json<SyncGameRequest>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     let user_id = auth_user.user_id;
     tracing::debug!("Syncing game state for user: {}", user_id);
 
-    persist_session(&state, user_id, &request.game_state)
+    let state_json = serde_json::to_value(&request.game_state).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": format!("Failed to serialize game state: {}", e) })),
+        )
+    })?;
+
+    match db::update_user_session_state(&state.db, user_id, &state_json, request.expected_version)
         .await
-        .map_err(|e| {
+    {
+        Ok(session) => {
+            cache_session(&state, SessionScope::User(user_id), &request.game_state);
+            Ok(Json(serde_json::json!({ "success": true, "version": session.version })))
+        }
+        Err(db::SessionUpdateError::Conflict(current)) => {
+            let current_state: GameState = serde_json::from_value(current.game_state.clone())
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({ "error": format!("Failed to parse game state: {}", e) })),
+                    )
+                })?;
+            cache_session(&state, SessionScope::User(user_id), &current_state);
+            Err((
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({
+                    "error": "session was updated by another client",
+                    "current_game_state": current_state,
+                    "current_version": current.version,
+                })),
+            ))
+        }
+        Err(db::SessionUpdateError::Database(e)) => {
             tracing::error!("Sync failed for {}: {}", user_id, e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-    Ok(Json(serde_json::json!({ "success": true })))
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "database error" })),
+            ))
+        }
+    }
 }
 
 async fn get_game_state(
     State(state): State<Arc<AppState>>,
     axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
-) -> Result<Json<GameState>, (StatusCode, String)> {
+) -> Result<Json<GameStateResponse>, (StatusCode, String)> {
     let user_id = auth_user.user_id;
     tracing::debug!("Fetching game state for user: {}", user_id);
 
-    let game_state = get_or_create_session(&state, user_id)
+    let game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
-    Ok(Json(game_state))
+    let session = db::get_session_by_user_id(&state.db, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Session not found".to_string()))?;
+
+    Ok(Json(GameStateResponse {
+        game_state,
+        version: session.version,
+    }))
 }
 
 async fn get_render_state(
@@ -444,7 +951,7 @@ async fn get_render_state(
     let user_id = auth_user.user_id;
     tracing::debug!("Fetching render state for user: {}", user_id);
 
-    let game_state = get_or_create_session(&state, user_id)
+    let game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -459,7 +966,7 @@ async fn process_action(
     let user_id = auth_user.user_id;
     tracing::info!("Processing action for user: {}", user_id);
 
-    let mut game_state = get_or_create_session(&state, user_id)
+    let mut game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -493,19 +1000,29 @@ async fn process_action(
     }
 
     // Cache updated session in memory; persistence happens on level load / code submit
-    cache_session(&state, user_id, &game_state);
+    cache_session(&state, SessionScope::User(user_id), &game_state);
 
     Ok(Json(game_state.to_render_state()))
 }
 
-async fn get_available_levels(
+/// GET /api/levels
+#[utoipa::path(
+    get,
+    path = "/api/levels",
+    responses(
+        (status = 200, description = "All levels, with lock/completion status for the caller", body = Vec<LevelInfo>),
+        (status = 500, description = "Session could not be loaded"),
+    ),
+    tag = "levels",
+)]
+pub(crate) async fn get_available_levels(
     State(state): State<Arc<AppState>>,
     axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
 ) -> Result<Json<Vec<LevelInfo>>, (StatusCode, String)> {
     let user_id = auth_user.user_id;
     tracing::info!("Fetching available levels for user: {}", user_id);
 
-    let game_state = get_or_create_session(&state, user_id)
+    let game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -567,7 +1084,7 @@ async fn load_level(
     })?;
 
     // Get or create game state
-    let mut game_state = get_or_create_session(&state, user_id)
+    let mut game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -587,7 +1104,7 @@ async fn load_level(
     game_state.update_unlocked_levels(state.levels.get_prerequisites());
 
     // Save updated state (persist to DB and cache)
-    persist_session(&state, user_id, &game_state)
+    persist_session(&state, SessionScope::User(user_id), &game_state)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -610,7 +1127,7 @@ async fn submit_code(
     );
 
     // Get game state
-    let mut game_state = get_or_create_session(&state, user_id)
+    let mut game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -644,6 +1161,8 @@ async fn submit_code(
             user_id,
             &payload.code,
             payload.test_only,
+            payload.collect_coverage,
+            payload.rerun_failed.clone(),
             &level,
             &level_id,
             &mut game_state,
@@ -652,11 +1171,22 @@ async fn submit_code(
     }
 
     // Legacy output-based challenge
-    let execution_result = state
-        .compiler
-        .compile_and_run(&payload.code)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let (execution_result, coverage) = if payload.collect_coverage {
+        state
+            .compiler
+            .compile_and_run_with_coverage(&payload.code, None)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+    } else {
+        (
+            state
+                .compiler
+                .compile_and_run(&payload.code)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            None,
+        )
+    };
 
     // Validate output
     let success = level.validate_output(&execution_result);
@@ -672,7 +1202,25 @@ async fn submit_code(
         xp_earned = Some(xp);
 
         // Update unlocked levels
+        let previously_unlocked = game_state.progression.unlocked_levels.clone();
         game_state.update_unlocked_levels(state.levels.get_prerequisites());
+        let newly_unlocked: Vec<String> = game_state
+            .progression
+            .unlocked_levels
+            .difference(&previously_unlocked)
+            .cloned()
+            .collect();
+
+        record_level_completion_and_broadcast(
+            &state,
+            user_id,
+            &level_id,
+            Some(execution_result.execution_time_ms as i64),
+            xp,
+            game_state.total_xp,
+            &newly_unlocked,
+        )
+        .await;
 
         format!(
             "Success! Your code produced the correct output. You earned {} XP!",
@@ -682,8 +1230,24 @@ async fn submit_code(
         "Output doesn't match expected result. Try again!".to_string()
     };
 
+    record_submission(
+        &state,
+        user_id,
+        &level_id,
+        None,
+        &payload.code,
+        if success { 1 } else { 0 },
+        1,
+        Some(execution_result.execution_time_ms as i64),
+    )
+    .await;
+
+    if !success {
+        game_state.record_failed_attempt(&level_id);
+    }
+
     // Save updated state
-    persist_session(&state, user_id, &game_state)
+    persist_session(&state, SessionScope::User(user_id), &game_state)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -699,6 +1263,7 @@ async fn submit_code(
         doors_unlocked: success,
         render_state: game_state.to_render_state(),
         test_results: None,
+        coverage,
     }))
 }
 
@@ -708,6 +1273,8 @@ async fn run_function_based_challenge(
     user_id: Uuid,
     code: &str,
     test_only: bool,
+    collect_coverage: bool,
+    rerun_failed: Option<Vec<String>>,
     level: &LevelData,
     level_id: &str,
     game_state: &mut GameState,
@@ -722,12 +1289,28 @@ async fn run_function_based_challenge(
     })?;
 
     // Filter test cases: sample only for TEST, all for SUBMIT
-    let test_cases: Vec<_> = level
+    let eligible_cases: Vec<_> = level
         .test_cases
         .iter()
         .filter(|tc| !test_only || tc.sample)
         .collect();
 
+    // A `rerun_failed` subset lets the learner re-check just the cases they
+    // fixed without waiting on the whole suite, but since not every case
+    // ran, it can never complete the level or award XP on its own.
+    let is_partial_rerun = rerun_failed.as_ref().is_some_and(|ids| {
+        eligible_cases
+            .iter()
+            .any(|tc| !ids.iter().any(|id| *id == tc.stable_id()))
+    });
+    let test_cases: Vec<_> = match &rerun_failed {
+        Some(ids) => eligible_cases
+            .into_iter()
+            .filter(|tc| ids.iter().any(|id| *id == tc.stable_id()))
+            .collect(),
+        None => eligible_cases,
+    };
+
     if test_cases.is_empty() {
         return Err((
             StatusCode::BAD_REQUEST,
@@ -735,41 +1318,47 @@ async fn run_function_based_challenge(
         ));
     }
 
-    let mut results: Vec<TestCaseResult> = Vec::new();
-    let mut total_time_ms = 0u64;
-
-    // Run each test case
-    for test_case in &test_cases {
-        let harness = generate_harness(code, signature, test_case).map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to generate test harness: {}", e),
-            )
-        })?;
-
-        let execution_result = state
-            .compiler
-            .compile_and_run(&harness)
+    let (run, coverage) = if collect_coverage {
+        run_test_suite_with_coverage(&state.compiler, code, signature, &test_cases)
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+    } else {
+        (
+            run_test_suite(&state.compiler, code, signature, &test_cases)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            None,
+        )
+    };
 
-        total_time_ms += execution_result.execution_time_ms;
+    let (results, total_time_ms) = match run {
+        TestSuiteRun::CompileError { message, stderr, total_time_ms } => {
+            let test_suite = TestSuiteResult::from_compile_error(test_cases.len(), message.clone());
+
+            record_submission(
+                state,
+                user_id,
+                level_id,
+                None,
+                code,
+                0,
+                test_cases.len() as i32,
+                Some(total_time_ms as i64),
+            )
+            .await;
 
-        // Check for compilation error
-        if let Some(ref err) = execution_result.compile_error {
-            let test_suite = TestSuiteResult {
-                passed: false,
-                total: test_cases.len(),
-                passed_count: 0,
-                results: vec![],
-                compilation_error: Some(err.clone()),
-            };
+            if !test_only {
+                game_state.record_failed_attempt(level_id);
+                persist_session(state, SessionScope::User(user_id), game_state)
+                    .await
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+            }
 
             return Ok(Json(SubmitCodeResponse {
                 success: false,
                 stdout: String::new(),
-                stderr: execution_result.stderr,
-                compile_error: Some(err.clone()),
+                stderr,
+                compile_error: Some(message),
                 execution_time_ms: total_time_ms,
                 feedback: "Code failed to compile. Check for syntax errors.".to_string(),
                 hint: None,
@@ -777,43 +1366,69 @@ async fn run_function_based_challenge(
                 doors_unlocked: false,
                 render_state: game_state.to_render_state(),
                 test_results: Some(test_suite),
+                coverage,
             }));
         }
-
-        let actual = execution_result.stdout.trim().to_string();
-        let expected = test_case.expected.trim().to_string();
-        let passed = actual == expected;
-
-        results.push(TestCaseResult {
-            input: test_case.input.clone(),
-            expected: expected.clone(),
-            actual,
-            passed,
-        });
-    }
-
-    let passed_count = results.iter().filter(|r| r.passed).count();
-    let all_passed = passed_count == results.len();
-
-    let test_suite = TestSuiteResult {
-        passed: all_passed,
-        total: results.len(),
-        passed_count,
-        results,
-        compilation_error: None,
+        TestSuiteRun::Ran { results, total_time_ms } => (results, total_time_ms),
     };
 
+    let test_suite = TestSuiteResult::from_results(results);
+    let passed_count = test_suite.passed_count;
+    let all_passed = test_suite.passed;
+
     let mut xp_earned = None;
 
-    // Only complete level on SUBMIT (not TEST) and if all passed
-    if all_passed && !test_only {
+    if !all_passed && !test_only {
+        game_state.record_failed_attempt(level_id);
+    }
+
+    // Only complete level on SUBMIT (not TEST) and if all passed, and only
+    // when every case actually ran (a `rerun_failed` subset can't unlock
+    // doors or earn XP, since it skipped cases that might still be broken).
+    if all_passed && !test_only && !is_partial_rerun {
         let xp = game_state.complete_level(level.xp_reward);
         xp_earned = Some(xp);
 
+        let previously_unlocked = game_state.progression.unlocked_levels.clone();
         game_state.update_unlocked_levels(state.levels.get_prerequisites());
+        let newly_unlocked: Vec<String> = game_state
+            .progression
+            .unlocked_levels
+            .difference(&previously_unlocked)
+            .cloned()
+            .collect();
+
+        record_level_completion_and_broadcast(
+            state,
+            user_id,
+            level_id,
+            Some(total_time_ms as i64),
+            xp,
+            game_state.total_xp,
+            &newly_unlocked,
+        )
+        .await;
+    } else if !test_only && !is_partial_rerun {
+        // Partial credit on SUBMIT even without a full pass.
+        let xp = game_state.award_partial_xp(level.xp_reward, passed_count, test_suite.total);
+        xp_earned = Some(xp);
     }
 
-    let feedback = if all_passed {
+    let feedback = if is_partial_rerun {
+        if all_passed {
+            format!(
+                "{}/{} re-run tests passed! Submit the full suite to complete the level.",
+                passed_count, test_suite.total
+            )
+        } else if let Some(diagnostic) = diagnose_failure(&test_suite.results) {
+            diagnostic
+        } else {
+            format!(
+                "{}/{} re-run tests passed. Check your logic and try again!",
+                passed_count, test_suite.total
+            )
+        }
+    } else if all_passed {
         if test_only {
             format!(
                 "All {} sample tests passed! Click SUBMIT to complete.",
@@ -830,15 +1445,34 @@ async fn run_function_based_challenge(
                 test_suite.total
             )
         }
-    } else {
+    } else if let Some(diagnostic) = diagnose_failure(&test_suite.results) {
+        diagnostic
+    } else if let Some(xp) = xp_earned.filter(|&x| x > 0) {
+        format!(
+            "{}/{} tests passed. +{} XP partial credit! Keep going to complete the level.",
+            passed_count, test_suite.total, xp
+        )
+    } else {
         format!(
             "{}/{} tests passed. Check your logic and try again!",
             passed_count, test_suite.total
         )
     };
 
+    record_submission(
+        state,
+        user_id,
+        level_id,
+        None,
+        code,
+        passed_count as i32,
+        test_suite.total as i32,
+        Some(total_time_ms as i64),
+    )
+    .await;
+
     // Save updated state
-    persist_session(state, user_id, game_state)
+    persist_session(state, SessionScope::User(user_id), game_state)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -851,9 +1485,10 @@ async fn run_function_based_challenge(
         feedback,
         hint: None,
         xp_earned,
-        doors_unlocked: all_passed && !test_only,
+        doors_unlocked: all_passed && !test_only && !is_partial_rerun,
         render_state: game_state.to_render_state(),
         test_results: Some(test_suite),
+        coverage,
     }))
 }
 
@@ -862,7 +1497,7 @@ async fn get_current_level(
     axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
 ) -> Result<Json<LevelData>, (StatusCode, String)> {
     let user_id = auth_user.user_id;
-    let game_state = get_or_create_session(&state, user_id)
+    let game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -888,7 +1523,7 @@ async fn get_level_quests(
     axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
 ) -> Result<Json<Vec<QuestInfoResponse>>, (StatusCode, String)> {
     let user_id = auth_user.user_id;
-    let game_state = get_or_create_session(&state, user_id)
+    let game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -937,7 +1572,7 @@ async fn get_quest(
     Path(quest_id): Path<String>,
 ) -> Result<Json<QuestInfoResponse>, (StatusCode, String)> {
     let user_id = auth_user.user_id;
-    let game_state = get_or_create_session(&state, user_id)
+    let game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -987,6 +1622,54 @@ async fn submit_quest_code(
     Json(payload): Json<SubmitQuestCodeRequest>,
 ) -> Result<Json<SubmitCodeResponse>, (StatusCode, String)> {
     let user_id = auth_user.user_id;
+    submit_quest_code_for_scope(&state, user_id, SessionScope::User(user_id), payload)
+        .await
+        .map(Json)
+}
+
+/// POST /api/rooms/:room_id/code/submit-quest - grades `payload` the same
+/// way as `submit_quest_code`, but against the room's shared `GameState`
+/// rather than the caller's own, so any participant's submission advances
+/// the room together. Requires the caller to currently be a participant.
+/// On a quest completion, broadcasts the room's updated render state to
+/// `/rooms/:room_id/stream` listeners, the same live-update pattern
+/// `record_level_completion_and_broadcast` uses for the leaderboard.
+async fn submit_room_quest_code(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Path(room_id): Path<Uuid>,
+    Json(payload): Json<SubmitQuestCodeRequest>,
+) -> Result<Json<SubmitCodeResponse>, (StatusCode, String)> {
+    require_room_participant(&state, room_id, auth_user.user_id).await?;
+
+    let response = submit_quest_code_for_scope(
+        &state,
+        auth_user.user_id,
+        SessionScope::Room(room_id),
+        payload,
+    )
+    .await?;
+
+    if response.success && response.xp_earned.is_some() {
+        let _ = state.room_tx.send(RoomDelta {
+            room_id,
+            render_state: response.render_state.clone(),
+        });
+    }
+
+    Ok(Json(response))
+}
+
+/// Shared grading logic behind `submit_quest_code` and
+/// `submit_room_quest_code` - `user_id` is always the caller (for
+/// submission history), while `scope` picks whose `GameState` is graded
+/// and persisted.
+async fn submit_quest_code_for_scope(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+    scope: SessionScope,
+    payload: SubmitQuestCodeRequest,
+) -> Result<SubmitCodeResponse, (StatusCode, String)> {
     tracing::info!(
         "Submitting quest code for user: {}, quest: {}, test_only: {}",
         user_id,
@@ -998,7 +1681,7 @@ async fn submit_quest_code(
         payload.quest_id
     );
 
-    let mut game_state = get_or_create_session(&state, user_id)
+    let mut game_state = get_or_create_session(state, scope)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -1034,12 +1717,28 @@ async fn submit_quest_code(
         .clone();
 
     // Filter test cases: sample only for TEST, all for SUBMIT
-    let test_cases: Vec<_> = quest
+    let eligible_cases: Vec<_> = quest
         .test_cases
         .iter()
         .filter(|tc| !payload.test_only || tc.sample)
         .collect();
 
+    // A `rerun_failed` subset lets the learner re-check just the cases they
+    // fixed without waiting on the whole suite, but since not every case
+    // ran, it can never complete the quest or award XP on its own.
+    let is_partial_rerun = payload.rerun_failed.as_ref().is_some_and(|ids| {
+        eligible_cases
+            .iter()
+            .any(|tc| !ids.iter().any(|id| *id == tc.stable_id()))
+    });
+    let test_cases: Vec<_> = match &payload.rerun_failed {
+        Some(ids) => eligible_cases
+            .into_iter()
+            .filter(|tc| ids.iter().any(|id| *id == tc.stable_id()))
+            .collect(),
+        None => eligible_cases,
+    };
+
     if test_cases.is_empty() {
         return Err((
             StatusCode::BAD_REQUEST,
@@ -1047,40 +1746,58 @@ async fn submit_quest_code(
         ));
     }
 
-    let mut results: Vec<TestCaseResult> = Vec::new();
-    let mut total_time_ms = 0u64;
-
-    for test_case in &test_cases {
-        let harness = generate_harness(&payload.code, &quest.function_signature, test_case)
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Failed to generate test harness: {}", e),
-                )
-            })?;
-
-        let execution_result = state
-            .compiler
-            .compile_and_run(&harness)
+    let (run, coverage) = if payload.collect_coverage {
+        run_test_suite_with_coverage(
+            &state.compiler,
+            &payload.code,
+            &quest.function_signature,
+            &test_cases,
+        )
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+    } else {
+        (
+            run_test_suite(
+                &state.compiler,
+                &payload.code,
+                &quest.function_signature,
+                &test_cases,
+            )
             .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
-
-        total_time_ms += execution_result.execution_time_ms;
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?,
+            None,
+        )
+    };
 
-        if let Some(ref err) = execution_result.compile_error {
-            let test_suite = TestSuiteResult {
-                passed: false,
-                total: test_cases.len(),
-                passed_count: 0,
-                results: vec![],
-                compilation_error: Some(err.clone()),
-            };
+    let (results, total_time_ms) = match run {
+        TestSuiteRun::CompileError { message, stderr, total_time_ms } => {
+            let test_suite = TestSuiteResult::from_compile_error(test_cases.len(), message.clone());
+
+            record_submission(
+                state,
+                user_id,
+                &level_id,
+                Some(&payload.quest_id),
+                &payload.code,
+                0,
+                test_cases.len() as i32,
+                Some(total_time_ms as i64),
+            )
+            .await;
+
+            if !payload.test_only {
+                let key = ProgressionState::quest_partial_key(&level_id, &payload.quest_id);
+                game_state.record_failed_attempt(&key);
+                persist_session(state, scope, &game_state)
+                    .await
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+            }
 
-            return Ok(Json(SubmitCodeResponse {
+            return Ok(SubmitCodeResponse {
                 success: false,
                 stdout: String::new(),
-                stderr: execution_result.stderr,
-                compile_error: Some(err.clone()),
+                stderr,
+                compile_error: Some(message),
                 execution_time_ms: total_time_ms,
                 feedback: "Code failed to compile. Check for syntax errors.".to_string(),
                 hint: quest.hints.first().cloned(),
@@ -1088,38 +1805,30 @@ async fn submit_quest_code(
                 doors_unlocked: false,
                 render_state: game_state.to_render_state(),
                 test_results: Some(test_suite),
-            }));
+                coverage,
+            });
         }
-
-        let actual = execution_result.stdout.trim().to_string();
-        let expected = test_case.expected.trim().to_string();
-        let passed = actual == expected;
-
-        results.push(TestCaseResult {
-            input: test_case.input.clone(),
-            expected: expected.clone(),
-            actual,
-            passed,
-        });
-    }
-
-    let passed_count = results.iter().filter(|r| r.passed).count();
-    let all_passed = passed_count == results.len();
-
-    let test_suite = TestSuiteResult {
-        passed: all_passed,
-        total: results.len(),
-        passed_count,
-        results,
-        compilation_error: None,
+        TestSuiteRun::Ran { results, total_time_ms } => (results, total_time_ms),
     };
 
+    let test_suite = TestSuiteResult::from_results(results);
+    let passed_count = test_suite.passed_count;
+    let all_passed = test_suite.passed;
+
     let mut xp_earned = None;
     let mut doors_unlocked = false;
     let mut quests_remaining = total_quests;
 
-    // Only complete quest on SUBMIT (not TEST) and if all passed
-    if all_passed && !payload.test_only {
+    if !all_passed && !payload.test_only {
+        let key = ProgressionState::quest_partial_key(&level_id, &payload.quest_id);
+        game_state.record_failed_attempt(&key);
+    }
+
+    // Only complete quest on SUBMIT (not TEST) and if all passed, and only
+    // when every case actually ran (a `rerun_failed` subset can't complete
+    // the quest or earn XP, since it skipped cases that might still be
+    // broken).
+    if all_passed && !payload.test_only && !is_partial_rerun {
         tracing::info!(
             "DEBUG: All tests passed, completing quest {} for level {}",
             payload.quest_id,
@@ -1152,7 +1861,7 @@ async fn submit_quest_code(
         game_state.active_quest_id = None;
 
         // Persist state
-        persist_session(&state, user_id, &game_state)
+        persist_session(state, scope, &game_state)
             .await
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
         tracing::info!(
@@ -1161,9 +1870,49 @@ async fn submit_quest_code(
             level_id,
             game_state.progression.completed_quests.get(&level_id)
         );
+    } else if !payload.test_only && !is_partial_rerun {
+        // Partial credit on SUBMIT even without a full pass.
+        let xp = game_state.award_quest_partial_xp(
+            &level_id,
+            &payload.quest_id,
+            quest.xp_reward,
+            passed_count,
+            test_suite.total,
+        );
+        xp_earned = Some(xp);
+
+        persist_session(state, scope, &game_state)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
     }
 
-    let feedback = if all_passed {
+    record_submission(
+        state,
+        user_id,
+        &level_id,
+        Some(&payload.quest_id),
+        &payload.code,
+        passed_count as i32,
+        test_suite.total as i32,
+        Some(total_time_ms as i64),
+    )
+    .await;
+
+    let feedback = if is_partial_rerun {
+        if all_passed {
+            format!(
+                "{}/{} re-run tests passed! Submit the full suite to complete the quest.",
+                passed_count, test_suite.total
+            )
+        } else if let Some(diagnostic) = diagnose_failure(&test_suite.results) {
+            diagnostic
+        } else {
+            format!(
+                "{}/{} re-run tests passed. Check your logic and try again!",
+                passed_count, test_suite.total
+            )
+        }
+    } else if all_passed {
         if payload.test_only {
             format!(
                 "All {} sample tests passed! Click SUBMIT to complete.",
@@ -1182,6 +1931,13 @@ async fn submit_quest_code(
         } else {
             "Quest already completed. Try another quest!".to_string()
         }
+    } else if let Some(diagnostic) = diagnose_failure(&test_suite.results) {
+        diagnostic
+    } else if let Some(xp) = xp_earned.filter(|&x| x > 0) {
+        format!(
+            "{}/{} tests passed. +{} XP partial credit! Keep going to complete the quest.",
+            passed_count, test_suite.total, xp
+        )
     } else {
         format!(
             "{}/{} tests passed. Check your logic and try again!",
@@ -1189,7 +1945,7 @@ async fn submit_quest_code(
         )
     };
 
-    Ok(Json(SubmitCodeResponse {
+    Ok(SubmitCodeResponse {
         success: all_passed,
         stdout: String::new(),
         stderr: String::new(),
@@ -1205,39 +1961,101 @@ async fn submit_quest_code(
         doors_unlocked,
         render_state: game_state.to_render_state(),
         test_results: Some(test_suite),
-    }))
+        coverage,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct HintQuery {
+    quest_id: Option<String>,
 }
 
+/// A revealed hint, plus enough state for the frontend to show what revealing
+/// it cost and what's still gated behind more failed attempts.
+#[derive(Debug, Serialize)]
+struct HintResponse {
+    text: String,
+    hints_revealed: usize,
+    total_hints: usize,
+    /// Percent this (and any earlier) hint deducts from the eventual
+    /// completion reward, see `ProgressionState::hint_penalty_percent`.
+    penalty_percent: u32,
+    failed_attempts: u32,
+}
+
+/// Reveal a hint for the current level (or, if `quest_id` is given, for one
+/// of its quests). Hints unlock progressively - earlier ones must be
+/// revealed first - and the final hint additionally requires a few failed
+/// submissions, since it's effectively the answer. Revealing a hint also
+/// dents the XP the level/quest pays out on completion.
 async fn get_hint(
     State(state): State<Arc<AppState>>,
     axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
     Path(index): Path<usize>,
-) -> Result<Json<String>, (StatusCode, String)> {
+    Query(query): Query<HintQuery>,
+) -> Result<Json<HintResponse>, (StatusCode, String)> {
     let user_id = auth_user.user_id;
-    let game_state = get_or_create_session(&state, user_id)
+    let mut game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
-    let level_id = game_state.current_level_id.as_ref().ok_or_else(|| {
-        (
-            StatusCode::BAD_REQUEST,
-            "No level currently loaded".to_string(),
-        )
-    })?;
+    let level_id = game_state
+        .current_level_id
+        .as_ref()
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                "No level currently loaded".to_string(),
+            )
+        })?
+        .clone();
 
-    let level = state.levels.get_level(level_id).ok_or_else(|| {
+    let level = state.levels.get_level(&level_id).ok_or_else(|| {
         (
             StatusCode::NOT_FOUND,
             format!("Level '{}' not found", level_id),
         )
     })?;
 
-    let hint = level
-        .hints
+    let (hints, key) = match &query.quest_id {
+        Some(quest_id) => {
+            let quest = level
+                .get_quests()
+                .into_iter()
+                .find(|q| &q.id == quest_id)
+                .ok_or_else(|| {
+                    (
+                        StatusCode::NOT_FOUND,
+                        format!("Quest '{}' not found in level '{}'", quest_id, level_id),
+                    )
+                })?;
+            (quest.hints, ProgressionState::quest_partial_key(&level_id, quest_id))
+        }
+        None => (level.hints.clone(), level_id),
+    };
+
+    game_state
+        .reveal_hint(&key, index, hints.len())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let text = hints
         .get(index)
+        .cloned()
         .ok_or_else(|| (StatusCode::NOT_FOUND, "No more hints available".to_string()))?;
 
-    Ok(Json(hint.clone()))
+    let response = HintResponse {
+        text,
+        hints_revealed: game_state.progression.revealed_hint_count(&key),
+        total_hints: hints.len(),
+        penalty_percent: game_state.progression.hint_penalty_percent(&key),
+        failed_attempts: game_state.progression.failed_attempt_count(&key),
+    };
+
+    persist_session(&state, SessionScope::User(user_id), &game_state)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(response))
 }
 
 async fn get_progress(
@@ -1245,7 +2063,7 @@ async fn get_progress(
     axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
 ) -> Result<Json<ProgressResponse>, (StatusCode, String)> {
     let user_id = auth_user.user_id;
-    let game_state = get_or_create_session(&state, user_id)
+    let game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -1306,7 +2124,7 @@ async fn save_game(
 ) -> Result<Json<SaveSlotResponse>, (StatusCode, String)> {
     let user_id = auth_user.user_id;
 
-    let game_state = get_or_create_session(&state, user_id)
+    let game_state = get_or_create_session(&state, SessionScope::User(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -1385,7 +2203,7 @@ async fn load_save(
     })?;
 
     // Update session with loaded state
-    persist_session(&state, user_id, &game_state)
+    persist_session(&state, SessionScope::User(user_id), &game_state)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
 
@@ -1413,6 +2231,649 @@ async fn delete_save(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Attach an anonymous device's saved progress to the caller's account
+/// (merging with any session the account already has - see
+/// `db::claim_session`), then hand back the resulting `GameState` so the
+/// client can update its local copy without a follow-up `/game/state` call.
+async fn link_device(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Json(request): Json<LinkDeviceRequest>,
+) -> Result<Json<GameState>, (StatusCode, String)> {
+    let user_id = auth_user.user_id;
+
+    let claimed = db::claim_session(&state.db, &request.device_id, user_id)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => {
+                (StatusCode::NOT_FOUND, "No session for that device".to_string())
+            }
+            e => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB error: {}", e)),
+        })?;
+
+    let game_state: GameState = serde_json::from_value(claimed.game_state).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to parse game state: {}", e),
+        )
+    })?;
+
+    cache_session(&state, SessionScope::User(user_id), &game_state);
+
+    Ok(Json(game_state))
+}
+
+async fn register_device(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Json(request): Json<RegisterDeviceRequest>,
+) -> Result<Json<DeviceResponse>, (StatusCode, String)> {
+    let device = db::register_device(
+        &state.db,
+        &db::models::NewDevice {
+            user_id: auth_user.user_id,
+            name: request.name,
+            platform: request.platform,
+            push_endpoint: request.push_endpoint,
+        },
+    )
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("DB error: {}", e),
+        )
+    })?;
+
+    Ok(Json(device.into()))
+}
+
+async fn list_devices(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+) -> Result<Json<Vec<DeviceResponse>>, (StatusCode, String)> {
+    let devices = db::list_devices_for_user(&state.db, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(Json(devices.into_iter().map(DeviceResponse::from).collect()))
+}
+
+/// Enqueue a remote command (e.g. "force resync", "sign out") for one of the
+/// caller's own devices to pick up on its next poll
+async fn enqueue_device_command(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Path(device_id): Path<Uuid>,
+    Json(request): Json<EnqueueDeviceCommandRequest>,
+) -> Result<Json<DeviceCommandResponse>, (StatusCode, String)> {
+    db::get_device_for_user(&state.db, auth_user.user_id, device_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Device not found".to_string()))?;
+
+    let command = db::enqueue_device_command(
+        &state.db,
+        &db::models::NewDeviceCommand {
+            device_id,
+            command: request.command,
+        },
+    )
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("DB error: {}", e),
+        )
+    })?;
+
+    Ok(Json(command.into()))
+}
+
+/// Drain a device's undelivered commands. Called by the device itself, so
+/// also bumps `last_seen_at` the way any other authenticated poll would.
+async fn poll_device_commands(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Path(device_id): Path<Uuid>,
+) -> Result<Json<Vec<DeviceCommandResponse>>, (StatusCode, String)> {
+    db::get_device_for_user(&state.db, auth_user.user_id, device_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Device not found".to_string()))?;
+
+    db::touch_device(&state.db, device_id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("DB error: {}", e),
+        )
+    })?;
+
+    let pending = db::list_pending_device_commands(&state.db, device_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    let ids: Vec<Uuid> = pending.iter().map(|c| c.id).collect();
+    db::mark_device_commands_delivered(&state.db, &ids)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(Json(pending.into_iter().map(DeviceCommandResponse::from).collect()))
+}
+
+/// The VAPID public key for the client to pass as `applicationServerKey` to
+/// `PushManager.subscribe()`. 404s if push isn't configured, so the client
+/// can hide the "enable notifications" toggle entirely in that deployment.
+async fn get_push_vapid_key(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    state
+        .push
+        .public_key()
+        .map(|key| Json(serde_json::json!({ "key": key })))
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "push notifications are not configured".to_string()))
+}
+
+async fn push_subscribe(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Json(request): Json<PushSubscribeRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    db::create_push_subscription(
+        &state.db,
+        &db::models::NewPushSubscription {
+            user_id: auth_user.user_id,
+            endpoint: request.endpoint,
+            p256dh: request.keys.p256dh,
+            auth: request.keys.auth,
+        },
+    )
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("DB error: {}", e),
+        )
+    })?;
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+async fn push_unsubscribe(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Json(request): Json<PushUnsubscribeRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    db::delete_push_subscription(&state.db, auth_user.user_id, &request.endpoint)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+#[derive(Debug, Deserialize)]
+struct LeaderboardQuery {
+    #[serde(default = "default_leaderboard_limit")]
+    limit: i64,
+    #[serde(default)]
+    offset: i64,
+}
+
+fn default_leaderboard_limit() -> i64 {
+    50
+}
+
+/// Leaderboard page plus the requesting player's own rank, so a player
+/// outside the top-N can still see where they stand without paging
+/// through everyone ahead of them.
+#[derive(Debug, Serialize)]
+struct LeaderboardResponse<T> {
+    entries: Vec<T>,
+    my_rank: Option<i64>,
+}
+
+/// GET /api/leaderboard - ranked page of players by total XP, plus the
+/// caller's own rank.
+async fn get_leaderboard(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<LeaderboardResponse<db::models::LeaderboardEntry>>, (StatusCode, String)> {
+    let entries = db::top_users_by_xp(&state.db, query.limit, query.offset)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    let my_rank = db::get_user_xp_rank(&state.db, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(Json(LeaderboardResponse { entries, my_rank }))
+}
+
+/// GET /api/leaderboard/level/:level_id - ranked page of a level's fastest
+/// cumulative completion times, plus the caller's own rank.
+async fn get_level_leaderboard(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Path(level_id): Path<String>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<LeaderboardResponse<db::models::LevelLeaderboardEntry>>, (StatusCode, String)> {
+    let entries =
+        db::level_leaderboard_by_duration(&state.db, &level_id, query.limit, query.offset)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("DB error: {}", e),
+                )
+            })?;
+
+    let my_rank = db::get_user_level_duration_rank(&state.db, &level_id, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(Json(LeaderboardResponse { entries, my_rank }))
+}
+
+/// POST /api/levels/:id/join - join a level as a co-op/competitive
+/// participant (see `db::join_level`). Requires a caller identity, so it
+/// 401s under `optional_jwt_auth_middleware` the same way a mandatory-auth
+/// route would.
+async fn join_level(
+    State(state): State<Arc<AppState>>,
+    auth_user: Option<axum::Extension<auth_middleware::AuthUser>>,
+    Path(level_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let auth_user =
+        auth_user.ok_or_else(|| (StatusCode::UNAUTHORIZED, "Login required".to_string()))?;
+
+    db::join_level(&state.db, &level_id, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /api/levels/:id/leave - leave a level's participant list (see
+/// `db::leave_level`).
+async fn leave_level(
+    State(state): State<Arc<AppState>>,
+    auth_user: Option<axum::Extension<auth_middleware::AuthUser>>,
+    Path(level_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let auth_user =
+        auth_user.ok_or_else(|| (StatusCode::UNAUTHORIZED, "Login required".to_string()))?;
+
+    db::leave_level(&state.db, &level_id, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/levels/:id/leaderboard - one level's co-op/competitive best-XP
+/// ranking (see `db::level_leaderboard`). Public; `my_rank` is only filled
+/// in when the caller is logged in.
+async fn get_level_xp_leaderboard(
+    State(state): State<Arc<AppState>>,
+    auth_user: Option<axum::Extension<auth_middleware::AuthUser>>,
+    Path(level_id): Path<String>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<LeaderboardResponse<db::models::LevelXpLeaderboardEntry>>, (StatusCode, String)> {
+    let entries = db::level_leaderboard(&state.db, &level_id, query.limit, query.offset)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    let my_rank = match auth_user {
+        Some(auth_user) => {
+            db::get_user_level_xp_rank(&state.db, &level_id, auth_user.user_id)
+                .await
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("DB error: {}", e),
+                    )
+                })?
+        }
+        None => None,
+    };
+
+    Ok(Json(LeaderboardResponse { entries, my_rank }))
+}
+
+#[derive(Debug, Deserialize)]
+struct FastestQuery {
+    #[serde(default = "default_fastest_limit")]
+    limit: i64,
+}
+
+fn default_fastest_limit() -> i64 {
+    10
+}
+
+/// GET /api/levels/:id/fastest - a level's quickest recorded clears
+async fn get_level_fastest(
+    State(state): State<Arc<AppState>>,
+    Path(level_id): Path<String>,
+    Query(query): Query<FastestQuery>,
+) -> Result<Json<Vec<db::models::FastestCompletion>>, (StatusCode, String)> {
+    let entries = db::get_fastest_completions(&state.db, &level_id, query.limit)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(Json(entries))
+}
+
+/// GET /api/levels/:id/submissions - the authenticated player's submission
+/// history for one level (not quest-scoped), most recent first.
+async fn get_level_submissions(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Path(level_id): Path<String>,
+) -> Result<Json<Vec<db::models::Submission>>, (StatusCode, String)> {
+    let submissions =
+        db::list_submissions_by_user_level(&state.db, auth_user.user_id, &level_id, None)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("DB error: {}", e),
+                )
+            })?;
+
+    Ok(Json(submissions))
+}
+
+/// GET /api/levels/current/quests/:quest_id/submissions - the authenticated
+/// player's submission history for one quest within their current level.
+async fn get_quest_submissions(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Path(quest_id): Path<String>,
+) -> Result<Json<Vec<db::models::Submission>>, (StatusCode, String)> {
+    let user_id = auth_user.user_id;
+    let game_state = get_or_create_session(&state, SessionScope::User(user_id))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let level_id = game_state.current_level_id.as_ref().ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            "No level currently loaded".to_string(),
+        )
+    })?;
+
+    let submissions =
+        db::list_submissions_by_user_level(&state.db, user_id, level_id, Some(&quest_id))
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("DB error: {}", e),
+                )
+            })?;
+
+    Ok(Json(submissions))
+}
+
+/// GET /api/leaderboard/stream - upgrades to a WebSocket that forwards every
+/// `LeaderboardDelta` broadcast by a level completion so a connected client
+/// can update rankings live instead of re-polling `/leaderboard`.
+async fn leaderboard_stream(
+    State(state): State<Arc<AppState>>,
+    ws: WebSocketUpgrade,
+) -> axum::response::Response {
+    ws.on_upgrade(move |socket| forward_leaderboard_deltas(socket, state))
+}
+
+async fn forward_leaderboard_deltas(mut socket: WebSocket, state: Arc<AppState>) {
+    let mut rx = state.leaderboard_tx.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(delta) => {
+                let Ok(payload) = serde_json::to_string(&delta) else {
+                    continue;
+                };
+                if socket.send(Message::Text(payload)).await.is_err() {
+                    break;
+                }
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Checks that `user_id` is currently a participant of `room_id`, 403-ing
+/// otherwise - the same gate `get_device_for_user` applies to a device's
+/// commands, just expressed as a membership table instead of ownership.
+async fn require_room_participant(
+    state: &Arc<AppState>,
+    room_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), (StatusCode, String)> {
+    let is_participant = db::is_room_participant(&state.db, room_id, user_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    if !is_participant {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Not a participant in this room".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// POST /api/rooms - create a co-op room for `level_id`, adding the caller
+/// as its first participant.
+async fn create_room(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Json(request): Json<CreateRoomRequest>,
+) -> Result<Json<RoomResponse>, (StatusCode, String)> {
+    let room = db::create_room(&state.db, &request.level_id, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(Json(RoomResponse::new(room, vec![auth_user.user_id])))
+}
+
+/// GET /api/rooms/:room_id - a room's details and current participants.
+/// Open to any authenticated player (not participant-gated) so someone can
+/// look a room up before deciding to join it.
+async fn get_room(
+    State(state): State<Arc<AppState>>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Json<RoomResponse>, (StatusCode, String)> {
+    let room = db::get_room(&state.db, room_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Room not found".to_string()))?;
+
+    let participant_ids = db::list_room_participant_ids(&state.db, room_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(Json(RoomResponse::new(room, participant_ids)))
+}
+
+/// POST /api/rooms/:room_id/join - add the caller to a room's participants.
+async fn join_room(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Json<RoomResponse>, (StatusCode, String)> {
+    let room = db::get_room(&state.db, room_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "Room not found".to_string()))?;
+
+    db::join_room(&state.db, room_id, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    let participant_ids = db::list_room_participant_ids(&state.db, room_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(Json(RoomResponse::new(room, participant_ids)))
+}
+
+/// POST /api/rooms/:room_id/leave - drop the caller from a room's
+/// participants. The room and its shared session are left intact for
+/// whoever remains.
+async fn leave_room(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    db::leave_room(&state.db, room_id, auth_user.user_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("DB error: {}", e),
+            )
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// GET /api/rooms/:room_id/stream - upgrades to a WebSocket that forwards
+/// `RoomDelta`s for this room, so every participant sees the shared
+/// `render_state` update live when anyone completes a quest.
+async fn room_stream(
+    State(state): State<Arc<AppState>>,
+    axum::Extension(auth_user): axum::Extension<auth_middleware::AuthUser>,
+    Path(room_id): Path<Uuid>,
+    ws: WebSocketUpgrade,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    require_room_participant(&state, room_id, auth_user.user_id).await?;
+
+    Ok(ws.on_upgrade(move |socket| forward_room_deltas(socket, state, room_id)))
+}
+
+async fn forward_room_deltas(mut socket: WebSocket, state: Arc<AppState>, room_id: Uuid) {
+    let mut rx = state.room_tx.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(delta) if delta.room_id == room_id => {
+                let Ok(payload) = serde_json::to_string(&delta) else {
+                    continue;
+                };
+                if socket.send(Message::Text(payload)).await.is_err() {
+                    break;
+                }
+            }
+            Ok(_) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::db;
@@ -0,0 +1,60 @@
+//! OpenAPI spec aggregation for the auth and level API surface - see each
+//! handler's `#[utoipa::path(...)]` annotation for the per-endpoint
+//! documentation this assembles. Served as JSON at `/api/openapi.json` and
+//! as Swagger UI at `/api/docs`.
+
+use utoipa::OpenApi;
+
+use crate::auth::{
+    handlers::{
+        login, logout, me, refresh, register, request_reset, resend_verify, reset_password,
+        verify_email,
+    },
+    AuthResponse, ChangeEmailRequest, ConfirmChangeEmailRequest, ConfirmDeleteRequest,
+    CreateInviteCodeRequest, CreateInviteRequest, InviteTrustedContactRequest, LoginRequest,
+    RefreshRequest, RegisterRequest, RequestDeleteRequest, RequestResetRequest,
+    ResendVerifyRequest, ResetPasswordRequest, TotpConfirmRequest, TotpDisableRequest,
+    UserResponse, VerifyEmailRequest,
+};
+use crate::get_available_levels;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        register,
+        login,
+        refresh,
+        logout,
+        me,
+        verify_email,
+        resend_verify,
+        request_reset,
+        reset_password,
+        get_available_levels,
+    ),
+    components(schemas(
+        AuthResponse,
+        UserResponse,
+        RegisterRequest,
+        CreateInviteRequest,
+        CreateInviteCodeRequest,
+        InviteTrustedContactRequest,
+        LoginRequest,
+        RefreshRequest,
+        VerifyEmailRequest,
+        ResendVerifyRequest,
+        RequestResetRequest,
+        ResetPasswordRequest,
+        RequestDeleteRequest,
+        ConfirmDeleteRequest,
+        ChangeEmailRequest,
+        ConfirmChangeEmailRequest,
+        TotpConfirmRequest,
+        TotpDisableRequest,
+    )),
+    tags(
+        (name = "auth", description = "Registration, login, and session management"),
+        (name = "levels", description = "Level catalog and progression"),
+    )
+)]
+pub struct ApiDoc;
@@ -0,0 +1,145 @@
+//! Web Push notifications - quest reminders, newly-unlockable levels, and
+//! achievements earned on another device, delivered to whichever
+//! browsers/devices the player has subscribed.
+//!
+//! Split the same way `email` is: this module owns subscription management
+//! and what payloads we send; `webpush` owns the wire protocol.
+
+mod webpush;
+
+pub use webpush::PushError;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use p256::ecdsa::SigningKey;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::db::models::PushSubscription;
+
+/// A notification to fan out to every subscription a player has.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum PushPayload {
+    /// Sent when a player hasn't logged in for a while and is about to lose
+    /// their daily streak.
+    StreakReminder { streak_days: u32 },
+    /// Sent when clearing a level unlocks a new one.
+    LevelUnlocked { level_id: String, level_title: String },
+    /// Sent when an achievement is earned on a different device than the
+    /// one currently in front of the player.
+    AchievementEarned { achievement_id: String, title: String },
+}
+
+/// Holds the server's VAPID keypair (see RFC 8292) and signs/sends
+/// notifications on its behalf. One instance per process, loaded from env
+/// at startup - there's nothing per-request to configure.
+pub struct PushService {
+    vapid_private_key: SigningKey,
+    vapid_subject: String,
+    client: reqwest::Client,
+}
+
+impl PushService {
+    /// Build a service from `VAPID_PRIVATE_KEY` (a base64url-encoded P-256
+    /// scalar, the same format the `web-push` ecosystem generates) and
+    /// `VAPID_SUBJECT` (a `mailto:` or `https:` contact URI, required by
+    /// RFC 8292 so a push service can reach out if it needs to rate-limit
+    /// or block us). Returns `None` if either is unset, mirroring
+    /// `EmailService::new`.
+    pub fn new() -> Option<Self> {
+        let key_b64 = std::env::var("VAPID_PRIVATE_KEY").ok()?;
+        let subject = std::env::var("VAPID_SUBJECT").ok()?;
+
+        let key_bytes = URL_SAFE_NO_PAD.decode(key_b64).ok()?;
+        let vapid_private_key = SigningKey::from_slice(&key_bytes).ok()?;
+
+        Some(Self {
+            vapid_private_key,
+            vapid_subject: subject,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// The VAPID public key, base64url-encoded, for the client to pass as
+    /// `applicationServerKey` to `PushManager.subscribe()`.
+    pub fn public_key(&self) -> String {
+        let point = self.vapid_private_key.verifying_key().to_encoded_point(false);
+        URL_SAFE_NO_PAD.encode(point.as_bytes())
+    }
+
+    /// Encrypt and deliver `payload` to one subscription.
+    pub async fn notify(
+        &self,
+        subscription: &PushSubscription,
+        payload: &PushPayload,
+    ) -> Result<(), PushError> {
+        let body = serde_json::to_vec(payload)
+            .map_err(|e| PushError::Encryption(format!("failed to serialize payload: {e}")))?;
+
+        webpush::send(
+            &self.vapid_private_key,
+            &self.vapid_subject,
+            subscription,
+            &body,
+            &self.client,
+        )
+        .await
+    }
+}
+
+/// Wrapper for optional push service (allows running without push
+/// configured in dev) - mirrors `email::OptionalEmailService`.
+pub struct OptionalPushService(pub Option<Arc<PushService>>);
+
+impl OptionalPushService {
+    pub fn new() -> Self {
+        Self(PushService::new().map(Arc::new))
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub fn public_key(&self) -> Option<String> {
+        self.0.as_ref().map(|s| s.public_key())
+    }
+
+    /// Best-effort fan-out to every one of `subscriptions`. A dead
+    /// subscription (`PushError::Gone`) is deleted so it stops being tried;
+    /// any other failure is logged and swallowed, same as `email`'s
+    /// best-effort sends - a missed reminder isn't worth failing the
+    /// request that triggered it.
+    pub async fn notify_all(
+        &self,
+        db: &crate::db::DbPool,
+        user_id: uuid::Uuid,
+        subscriptions: &[PushSubscription],
+        payload: &PushPayload,
+    ) {
+        let Some(service) = &self.0 else {
+            tracing::debug!("push service not configured; skipping notify for {user_id}");
+            return;
+        };
+
+        for subscription in subscriptions {
+            match service.notify(subscription, payload).await {
+                Ok(()) => {}
+                Err(PushError::Gone) => {
+                    if let Err(e) =
+                        crate::db::delete_push_subscription(db, user_id, &subscription.endpoint)
+                            .await
+                    {
+                        tracing::warn!(
+                            "failed to delete stale push subscription {}: {e}",
+                            subscription.id
+                        );
+                    }
+                }
+                Err(e) => tracing::warn!(
+                    "failed to push to subscription {}: {e}",
+                    subscription.id
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Web Push wire protocol: message encryption (RFC 8291, built on the
+//! `aes128gcm` content encoding from RFC 8188) and VAPID request
+//! authentication (RFC 8292). `mod.rs` owns what we send and to whom;
+//! everything here is just "turn a JSON payload and a subscription into an
+//! HTTP request the push service will accept."
+
+use aes_gcm::{aead::Aead, Aes128Gcm, KeyInit, Nonce};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hkdf::Hkdf;
+use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+use p256::{ecdh::EphemeralSecret, PublicKey};
+use rand::RngCore;
+use sha2::Sha256;
+
+use crate::db::models::PushSubscription;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PushError {
+    #[error("subscription key is malformed: {0}")]
+    MalformedKey(String),
+
+    #[error("encryption failed: {0}")]
+    Encryption(String),
+
+    #[error("failed to build VAPID key: {0}")]
+    VapidKey(String),
+
+    #[error("push request failed: {0}")]
+    Request(String),
+
+    /// The push service reports the subscription no longer exists (HTTP 404
+    /// or 410) - the caller should delete it rather than retry.
+    #[error("subscription is gone")]
+    Gone,
+}
+
+const RECORD_SIZE: u32 = 4096;
+
+/// Encrypt `payload` for `subscription` per RFC 8291 and POST it to the
+/// subscription's push service endpoint, authenticated with a VAPID JWT
+/// signed by `vapid_private_key` (a P-256 ES256 signing key, as built by
+/// `PushService::new` from a raw base64url-encoded scalar).
+pub async fn send(
+    vapid_private_key: &SigningKey,
+    vapid_subject: &str,
+    subscription: &PushSubscription,
+    payload: &[u8],
+    client: &reqwest::Client,
+) -> Result<(), PushError> {
+    let body = encrypt(subscription, payload)?;
+    let authorization = vapid_header(vapid_private_key, vapid_subject, &subscription.endpoint)?;
+
+    let response = client
+        .post(&subscription.endpoint)
+        .header("Content-Encoding", "aes128gcm")
+        .header("Content-Type", "application/octet-stream")
+        .header("TTL", "86400")
+        .header("Authorization", authorization)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| PushError::Request(e.to_string()))?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND
+        || response.status() == reqwest::StatusCode::GONE
+    {
+        return Err(PushError::Gone);
+    }
+    if !response.status().is_success() {
+        return Err(PushError::Request(format!(
+            "push service returned {}",
+            response.status()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Encrypt `payload` into a single `aes128gcm` record (RFC 8188 section 2),
+/// with the ephemeral ECDH public key as the record's `keyid` so the
+/// receiving push service doesn't need a separate `Crypto-Key` header.
+fn encrypt(subscription: &PushSubscription, payload: &[u8]) -> Result<Vec<u8>, PushError> {
+    let client_public = decode_public_key(&subscription.p256dh)?;
+    let auth_secret = URL_SAFE_NO_PAD
+        .decode(&subscription.auth)
+        .map_err(|e| PushError::MalformedKey(format!("auth secret: {e}")))?;
+
+    let ephemeral_secret = EphemeralSecret::random(&mut rand::thread_rng());
+    let ephemeral_public = ephemeral_secret.public_key();
+    let shared_secret = ephemeral_secret.diffie_hellman(&client_public);
+
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+
+    let (cek, nonce) = derive_keys(
+        &shared_secret.raw_secret_bytes()[..],
+        &auth_secret,
+        &client_public,
+        &ephemeral_public,
+        &salt,
+    )?;
+
+    // RFC 8188 ends each record's content with a delimiter (0x02 for the
+    // last record), followed by padding; we only ever send one record, so
+    // padding is empty.
+    let mut plaintext = payload.to_vec();
+    plaintext.push(0x02);
+
+    let cipher = Aes128Gcm::new_from_slice(&cek)
+        .map_err(|e| PushError::Encryption(format!("bad content-encryption key: {e}")))?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+        .map_err(|e| PushError::Encryption(e.to_string()))?;
+
+    // aes128gcm header: salt(16) || record size(4, big-endian) || keyid
+    // length(1) || keyid (uncompressed ephemeral public key, 65 bytes).
+    let keyid = ephemeral_public.to_encoded_point(false);
+    let keyid = keyid.as_bytes();
+
+    let mut record = Vec::with_capacity(16 + 4 + 1 + keyid.len() + ciphertext.len());
+    record.extend_from_slice(&salt);
+    record.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    record.push(keyid.len() as u8);
+    record.extend_from_slice(keyid);
+    record.extend_from_slice(&ciphertext);
+
+    Ok(record)
+}
+
+/// Derive the content-encryption key and nonce per RFC 8291 section 3.3/3.4:
+/// PRK from the ECDH secret salted with the subscriber's auth secret,
+/// expanded with a "WebPush: info" block (client and server public keys)
+/// into the input keying material; then an HKDF over that, salted with the
+/// record salt, expanded twice with the RFC 8188 `aes128gcm` CEK and nonce
+/// info strings.
+fn derive_keys(
+    shared_secret: &[u8],
+    auth_secret: &[u8],
+    client_public: &PublicKey,
+    ephemeral_public: &PublicKey,
+    salt: &[u8],
+) -> Result<([u8; 16], [u8; 12]), PushError> {
+    let prk = Hkdf::<Sha256>::new(Some(auth_secret), shared_secret);
+
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(client_public.to_encoded_point(false).as_bytes());
+    key_info.extend_from_slice(ephemeral_public.to_encoded_point(false).as_bytes());
+
+    let mut ikm = [0u8; 32];
+    prk.expand(&key_info, &mut ikm)
+        .map_err(|e| PushError::Encryption(format!("PRK expand: {e}")))?;
+
+    let content_prk = Hkdf::<Sha256>::new(Some(salt), &ikm);
+
+    let mut cek = [0u8; 16];
+    content_prk
+        .expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .map_err(|e| PushError::Encryption(format!("CEK expand: {e}")))?;
+
+    let mut nonce = [0u8; 12];
+    content_prk
+        .expand(b"Content-Encoding: nonce\0", &mut nonce)
+        .map_err(|e| PushError::Encryption(format!("nonce expand: {e}")))?;
+
+    Ok((cek, nonce))
+}
+
+fn decode_public_key(p256dh: &str) -> Result<PublicKey, PushError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(p256dh)
+        .map_err(|e| PushError::MalformedKey(format!("p256dh: {e}")))?;
+    PublicKey::from_sec1_bytes(&bytes)
+        .map_err(|e| PushError::MalformedKey(format!("p256dh is not a valid EC point: {e}")))
+}
+
+/// Build the `Authorization: vapid t=<jwt>, k=<public key>` header per
+/// RFC 8292. `aud` is the push service's origin (scheme+host), not the full
+/// endpoint URL - required by the spec, and most push services reject a
+/// JWT with a mismatched audience.
+fn vapid_header(
+    private_key: &SigningKey,
+    subject: &str,
+    endpoint: &str,
+) -> Result<String, PushError> {
+    let endpoint_url =
+        url::Url::parse(endpoint).map_err(|e| PushError::VapidKey(format!("bad endpoint: {e}")))?;
+    let aud = format!(
+        "{}://{}",
+        endpoint_url.scheme(),
+        endpoint_url.host_str().unwrap_or_default()
+    );
+
+    let header = URL_SAFE_NO_PAD.encode(br#"{"typ":"JWT","alg":"ES256"}"#);
+    let exp = (chrono::Utc::now() + chrono::Duration::hours(12)).timestamp();
+    let claims = serde_json::json!({ "aud": aud, "exp": exp, "sub": subject });
+    let claims = URL_SAFE_NO_PAD.encode(claims.to_string());
+
+    let signing_input = format!("{header}.{claims}");
+    let signature: Signature = private_key.sign(signing_input.as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(signature.to_bytes());
+
+    let public_key = private_key.verifying_key().to_encoded_point(false);
+    let public_key = URL_SAFE_NO_PAD.encode(public_key.as_bytes());
+
+    Ok(format!(
+        "vapid t={signing_input}.{signature}, k={public_key}"
+    ))
+}
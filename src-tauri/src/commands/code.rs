@@ -3,8 +3,12 @@ use tauri::{AppHandle, Emitter, State};
 use tracing::{debug, info};
 
 use crate::GameStateWrapper;
-use code_warrior::compiler::CCompiler;
-use code_warrior::levels::{generate_harness, LevelRegistry, TestCaseResult, TestSuiteResult};
+use code_warrior::compiler::{CCompiler, CoverageReport};
+use code_warrior::game::ProgressionState;
+use code_warrior::levels::{
+    diagnose_failure, run_test_suite, run_test_suite_with_coverage, LevelRegistry, TestSuiteResult,
+    TestSuiteRun,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeResult {
@@ -21,6 +25,23 @@ pub struct CodeResult {
     /// Test results for function-based challenges
     #[serde(skip_serializing_if = "Option::is_none")]
     pub test_results: Option<TestSuiteResult>,
+    /// Per-line execution counts, when the submission opted into coverage
+    /// collection via `collect_coverage`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageReport>,
+}
+
+/// A revealed hint, plus enough state for the frontend to show what revealing
+/// it cost and what's still gated behind more failed attempts.
+#[derive(Debug, Clone, Serialize)]
+pub struct HintResult {
+    pub text: String,
+    pub hints_revealed: usize,
+    pub total_hints: usize,
+    /// Percent this (and any earlier) hint deducts from the eventual
+    /// completion reward, see `ProgressionState::hint_penalty_percent`.
+    pub penalty_percent: u32,
+    pub failed_attempts: u32,
 }
 
 /// Event emitted when a level is completed
@@ -45,17 +66,24 @@ pub struct QuestCompleteEvent {
 pub async fn submit_code(
     code: String,
     #[allow(unused_variables)] test_only: Option<bool>,
+    collect_coverage: Option<bool>,
+    rerun_failed: Option<Vec<String>>,
     state: State<'_, GameStateWrapper>,
     levels: State<'_, LevelRegistry>,
     compiler: State<'_, CCompiler>,
     app: AppHandle,
 ) -> Result<CodeResult, String> {
     let test_only = test_only.unwrap_or(false);
-    debug!("submit_code command received, test_only={}", test_only);
-
-    // Get level data before await to avoid holding MutexGuard across await
+    let collect_coverage = collect_coverage.unwrap_or(false);
+    debug!(
+        "submit_code command received, test_only={}, collect_coverage={}, rerun_failed={:?}",
+        test_only, collect_coverage, rerun_failed
+    );
+
+    // Get level data before the compile/run await so the lock isn't held
+    // for the duration of that call, letting other commands run concurrently
     let (level_data, level_id) = {
-        let game_state = state.0.lock().map_err(|e| e.to_string())?;
+        let game_state = state.0.read().await;
         let level_id = game_state
             .current_level_id
             .as_ref()
@@ -77,6 +105,8 @@ pub async fn submit_code(
         return run_function_based_challenge(
             code,
             test_only,
+            collect_coverage,
+            rerun_failed,
             level_data,
             level_id,
             state,
@@ -89,7 +119,11 @@ pub async fn submit_code(
 
     // Legacy output-based challenge
     debug!("Running legacy output-based challenge");
-    let execution_result = compiler.compile_and_run(&code).await?;
+    let (execution_result, coverage) = if collect_coverage {
+        compiler.compile_and_run_with_coverage(&code, None).await?
+    } else {
+        (compiler.compile_and_run(&code).await?, None)
+    };
     debug!(success = execution_result.run_success(), "Compiler returned");
     let success = level_data.validate_output(&execution_result);
 
@@ -100,7 +134,7 @@ pub async fn submit_code(
 
     // If code is successful, complete the level
     if success {
-        let mut game_state = state.0.lock().map_err(|e| e.to_string())?;
+        let mut game_state = state.0.write().await;
 
         // Get previously unlocked levels
         let previously_unlocked: std::collections::HashSet<_> =
@@ -126,6 +160,9 @@ pub async fn submit_code(
 
         // Determine next level based on registry order
         next_level_id = levels.get_next_level(&level_id);
+    } else {
+        let mut game_state = state.0.write().await;
+        game_state.record_failed_attempt(&level_id);
     }
 
     let feedback = if execution_result.compile_error.is_some() {
@@ -155,7 +192,7 @@ pub async fn submit_code(
 
     // Get current render state for response
     let render_state = {
-        let game_state = state.0.lock().map_err(|e| e.to_string())?;
+        let game_state = state.0.read().await;
         game_state.to_render_state()
     };
 
@@ -171,6 +208,7 @@ pub async fn submit_code(
         doors_unlocked,
         render_state,
         test_results: None,
+        coverage,
     })
 }
 
@@ -178,6 +216,8 @@ pub async fn submit_code(
 async fn run_function_based_challenge(
     code: String,
     test_only: bool,
+    collect_coverage: bool,
+    rerun_failed: Option<Vec<String>>,
     level_data: code_warrior::levels::LevelData,
     level_id: String,
     state: State<'_, GameStateWrapper>,
@@ -193,47 +233,55 @@ async fn run_function_based_challenge(
         .ok_or("Function signature missing")?;
 
     // Filter test cases: sample only for TEST, all for SUBMIT
-    let test_cases: Vec<_> = level_data
+    let eligible_cases: Vec<_> = level_data
         .test_cases
         .iter()
         .filter(|tc| !test_only || tc.sample)
         .collect();
 
+    // A `rerun_failed` subset lets the learner re-check just the cases they
+    // fixed without waiting on the whole suite, but since not every case
+    // ran, it can never complete the level or award XP on its own.
+    let is_partial_rerun = rerun_failed.as_ref().is_some_and(|ids| {
+        eligible_cases
+            .iter()
+            .any(|tc| !ids.iter().any(|id| *id == tc.stable_id()))
+    });
+    let test_cases: Vec<_> = match &rerun_failed {
+        Some(ids) => eligible_cases
+            .into_iter()
+            .filter(|tc| ids.iter().any(|id| *id == tc.stable_id()))
+            .collect(),
+        None => eligible_cases,
+    };
+
     if test_cases.is_empty() {
         return Err("No test cases defined for this level".to_string());
     }
 
-    let mut results: Vec<TestCaseResult> = Vec::new();
-    let mut total_time_ms = 0u64;
-
-    // Run each test case
-    for test_case in &test_cases {
-        let harness = generate_harness(&code, signature, test_case)
-            .map_err(|e| format!("Failed to generate test harness: {}", e))?;
-
-        let execution_result = compiler.compile_and_run(&harness).await?;
-        total_time_ms += execution_result.execution_time_ms;
-
-        // Check for compilation error
-        if let Some(ref err) = execution_result.compile_error {
-            let test_suite = TestSuiteResult {
-                passed: false,
-                total: test_cases.len(),
-                passed_count: 0,
-                results: vec![],
-                compilation_error: Some(err.clone()),
-            };
+    let (run, coverage) = if collect_coverage {
+        run_test_suite_with_coverage(&compiler, &code, signature, &test_cases).await?
+    } else {
+        (run_test_suite(&compiler, &code, signature, &test_cases).await?, None)
+    };
+
+    let (results, total_time_ms) = match run {
+        TestSuiteRun::CompileError { message, stderr, total_time_ms } => {
+            let test_suite = TestSuiteResult::from_compile_error(test_cases.len(), message.clone());
 
             let render_state = {
-                let game_state = state.0.lock().map_err(|e| e.to_string())?;
+                let mut game_state = state.0.write().await;
+                if !test_only {
+                    game_state.record_failed_attempt(&level_id);
+                }
                 game_state.to_render_state()
             };
 
             return Ok(CodeResult {
                 success: false,
                 stdout: String::new(),
-                stderr: execution_result.stderr,
-                compile_error: Some(err.clone()),
+                stderr,
+                compile_error: Some(message),
                 execution_time_ms: total_time_ms,
                 feedback: "Code failed to compile. Check for syntax errors.".to_string(),
                 hint: None,
@@ -241,38 +289,29 @@ async fn run_function_based_challenge(
                 doors_unlocked: false,
                 render_state,
                 test_results: Some(test_suite),
+                coverage: None,
             });
         }
-
-        let actual = execution_result.stdout.trim().to_string();
-        let expected = test_case.expected.trim().to_string();
-        let passed = actual == expected;
-
-        results.push(TestCaseResult {
-            input: test_case.input.clone(),
-            expected: expected.clone(),
-            actual,
-            passed,
-        });
-    }
-
-    let passed_count = results.iter().filter(|r| r.passed).count();
-    let all_passed = passed_count == results.len();
-
-    let test_suite = TestSuiteResult {
-        passed: all_passed,
-        total: results.len(),
-        passed_count,
-        results,
-        compilation_error: None,
+        TestSuiteRun::Ran { results, total_time_ms } => (results, total_time_ms),
     };
 
+    let test_suite = TestSuiteResult::from_results(results);
+    let passed_count = test_suite.passed_count;
+    let all_passed = test_suite.passed;
+
     let mut xp_earned = 0;
     let mut doors_unlocked = false;
 
-    // Only complete level on SUBMIT (not TEST) and if all passed
-    if all_passed && !test_only {
-        let mut game_state = state.0.lock().map_err(|e| e.to_string())?;
+    if !all_passed && !test_only {
+        let mut game_state = state.0.write().await;
+        game_state.record_failed_attempt(&level_id);
+    }
+
+    // Only complete level on SUBMIT (not TEST) and if all passed, and only
+    // when every case actually ran (a `rerun_failed` subset can't unlock
+    // doors or earn XP, since it skipped cases that might still be broken).
+    if all_passed && !test_only && !is_partial_rerun {
+        let mut game_state = state.0.write().await;
 
         let previously_unlocked: std::collections::HashSet<_> =
             game_state.progression.unlocked_levels.clone();
@@ -302,9 +341,28 @@ async fn run_function_based_challenge(
             newly_unlocked,
         };
         let _ = app.emit("level_complete", event);
+    } else if !test_only && !is_partial_rerun {
+        // Partial credit on SUBMIT even without a full pass.
+        let mut game_state = state.0.write().await;
+        xp_earned =
+            game_state.award_partial_xp(level_data.xp_reward, passed_count, test_suite.total);
     }
 
-    let feedback = if all_passed {
+    let feedback = if is_partial_rerun {
+        if all_passed {
+            format!(
+                "{}/{} re-run tests passed! Submit the full suite to complete the level.",
+                passed_count, test_suite.total
+            )
+        } else if let Some(diagnostic) = diagnose_failure(&test_suite.results) {
+            diagnostic
+        } else {
+            format!(
+                "{}/{} re-run tests passed. Check your logic and try again!",
+                passed_count, test_suite.total
+            )
+        }
+    } else if all_passed {
         if test_only {
             format!("All {} sample tests passed! Click SUBMIT to complete.", passed_count)
         } else if xp_earned > 0 {
@@ -312,12 +370,19 @@ async fn run_function_based_challenge(
         } else {
             format!("All {} tests passed! Doors have been unlocked! (Level already completed)", test_suite.total)
         }
+    } else if let Some(diagnostic) = diagnose_failure(&test_suite.results) {
+        diagnostic
+    } else if xp_earned > 0 {
+        format!(
+            "{}/{} tests passed. +{} XP partial credit! Keep going to complete the level.",
+            passed_count, test_suite.total, xp_earned
+        )
     } else {
         format!("{}/{} tests passed. Check your logic and try again!", passed_count, test_suite.total)
     };
 
     let render_state = {
-        let game_state = state.0.lock().map_err(|e| e.to_string())?;
+        let game_state = state.0.read().await;
         game_state.to_render_state()
     };
 
@@ -333,30 +398,68 @@ async fn run_function_based_challenge(
         doors_unlocked,
         render_state,
         test_results: Some(test_suite),
+        coverage,
     })
 }
 
+/// Reveal a hint for the current level (or, if `quest_id` is given, for one
+/// of its quests). Hints unlock progressively - earlier ones must be
+/// revealed first - and the final hint additionally requires a few failed
+/// submissions, since it's effectively the answer. Revealing a hint also
+/// dents the XP the level/quest pays out on completion; see
+/// `ProgressionState::hint_penalty_percent`.
 #[tauri::command]
 pub async fn get_hint(
     hint_index: usize,
+    quest_id: Option<String>,
     state: State<'_, GameStateWrapper>,
     levels: State<'_, LevelRegistry>,
-) -> Result<String, String> {
-    let game_state = state.0.lock().map_err(|e| e.to_string())?;
+) -> Result<HintResult, String> {
+    let mut game_state = state.0.write().await;
     let level_id = game_state
         .current_level_id
         .as_ref()
-        .ok_or("No level currently loaded")?;
+        .ok_or("No level currently loaded")?
+        .clone();
 
     let level = levels
-        .get_level(level_id)
+        .get_level(&level_id)
         .ok_or_else(|| format!("Level {} not found", level_id))?;
 
-    level
-        .hints
+    let (hints, key) = match &quest_id {
+        Some(quest_id) => {
+            let quest = level
+                .get_quests()
+                .into_iter()
+                .find(|q| &q.id == quest_id)
+                .ok_or_else(|| format!("Quest {} not found in level {}", quest_id, level_id))?;
+            (quest.hints, ProgressionState::quest_partial_key(&level_id, quest_id))
+        }
+        None => (level.hints.clone(), level_id),
+    };
+
+    game_state.reveal_hint(&key, hint_index, hints.len())?;
+
+    let text = hints
         .get(hint_index)
         .cloned()
-        .ok_or_else(|| "No more hints available".to_string())
+        .ok_or_else(|| "No more hints available".to_string())?;
+
+    Ok(HintResult {
+        text,
+        hints_revealed: game_state.progression.revealed_hint_count(&key),
+        total_hints: hints.len(),
+        penalty_percent: game_state.progression.hint_penalty_percent(&key),
+        failed_attempts: game_state.progression.failed_attempt_count(&key),
+    })
+}
+
+/// Clear the compiler's on-disk compile-and-run cache, forcing the next
+/// submissions to recompile from scratch. Mainly useful for freeing disk
+/// space or ruling out a stale cached result while debugging.
+#[tauri::command]
+pub async fn clean_cache(compiler: State<'_, CCompiler>) -> Result<(), String> {
+    compiler.clean_cache().map_err(|e| e.to_string())
 }
 
 /// Submit code for a specific quest in a multi-quest level
@@ -365,17 +468,23 @@ pub async fn submit_quest_code(
     code: String,
     quest_id: String,
     test_only: Option<bool>,
+    collect_coverage: Option<bool>,
+    rerun_failed: Option<Vec<String>>,
     state: State<'_, GameStateWrapper>,
     levels: State<'_, LevelRegistry>,
     compiler: State<'_, CCompiler>,
     app: AppHandle,
 ) -> Result<CodeResult, String> {
     let test_only = test_only.unwrap_or(false);
-    debug!("submit_quest_code: quest_id={}, test_only={}", quest_id, test_only);
+    let collect_coverage = collect_coverage.unwrap_or(false);
+    debug!(
+        "submit_quest_code: quest_id={}, test_only={}, collect_coverage={}, rerun_failed={:?}",
+        quest_id, test_only, collect_coverage, rerun_failed
+    );
 
     // Get level and quest data
     let (level_id, quest, total_quests) = {
-        let game_state = state.0.lock().map_err(|e| e.to_string())?;
+        let game_state = state.0.read().await;
         let level_id = game_state
             .current_level_id
             .as_ref()
@@ -398,47 +507,59 @@ pub async fn submit_quest_code(
     };
 
     // Filter test cases: sample only for TEST, all for SUBMIT
-    let test_cases: Vec<_> = quest
+    let eligible_cases: Vec<_> = quest
         .test_cases
         .iter()
         .filter(|tc| !test_only || tc.sample)
         .collect();
 
+    // A `rerun_failed` subset lets the learner re-check just the cases they
+    // fixed without waiting on the whole suite, but since not every case
+    // ran, it can never complete the quest or award XP on its own.
+    let is_partial_rerun = rerun_failed.as_ref().is_some_and(|ids| {
+        eligible_cases
+            .iter()
+            .any(|tc| !ids.iter().any(|id| *id == tc.stable_id()))
+    });
+    let test_cases: Vec<_> = match &rerun_failed {
+        Some(ids) => eligible_cases
+            .into_iter()
+            .filter(|tc| ids.iter().any(|id| *id == tc.stable_id()))
+            .collect(),
+        None => eligible_cases,
+    };
+
     if test_cases.is_empty() {
         return Err("No test cases defined for this quest".to_string());
     }
 
-    let mut results: Vec<TestCaseResult> = Vec::new();
-    let mut total_time_ms = 0u64;
-
-    // Run each test case
-    for test_case in &test_cases {
-        let harness = generate_harness(&code, &quest.function_signature, test_case)
-            .map_err(|e| format!("Failed to generate test harness: {}", e))?;
-
-        let execution_result = compiler.compile_and_run(&harness).await?;
-        total_time_ms += execution_result.execution_time_ms;
-
-        // Check for compilation error
-        if let Some(ref err) = execution_result.compile_error {
-            let test_suite = TestSuiteResult {
-                passed: false,
-                total: test_cases.len(),
-                passed_count: 0,
-                results: vec![],
-                compilation_error: Some(err.clone()),
-            };
+    let (run, coverage) = if collect_coverage {
+        run_test_suite_with_coverage(&compiler, &code, &quest.function_signature, &test_cases).await?
+    } else {
+        (
+            run_test_suite(&compiler, &code, &quest.function_signature, &test_cases).await?,
+            None,
+        )
+    };
+
+    let (results, total_time_ms) = match run {
+        TestSuiteRun::CompileError { message, stderr, total_time_ms } => {
+            let test_suite = TestSuiteResult::from_compile_error(test_cases.len(), message.clone());
 
             let render_state = {
-                let game_state = state.0.lock().map_err(|e| e.to_string())?;
+                let mut game_state = state.0.write().await;
+                if !test_only {
+                    let key = ProgressionState::quest_partial_key(&level_id, &quest_id);
+                    game_state.record_failed_attempt(&key);
+                }
                 game_state.to_render_state()
             };
 
             return Ok(CodeResult {
                 success: false,
                 stdout: String::new(),
-                stderr: execution_result.stderr,
-                compile_error: Some(err.clone()),
+                stderr,
+                compile_error: Some(message),
                 execution_time_ms: total_time_ms,
                 feedback: "Code failed to compile. Check for syntax errors.".to_string(),
                 hint: quest.hints.first().cloned(),
@@ -446,39 +567,32 @@ pub async fn submit_quest_code(
                 doors_unlocked: false,
                 render_state,
                 test_results: Some(test_suite),
+                coverage: None,
             });
         }
-
-        let actual = execution_result.stdout.trim().to_string();
-        let expected = test_case.expected.trim().to_string();
-        let passed = actual == expected;
-
-        results.push(TestCaseResult {
-            input: test_case.input.clone(),
-            expected: expected.clone(),
-            actual,
-            passed,
-        });
-    }
-
-    let passed_count = results.iter().filter(|r| r.passed).count();
-    let all_passed = passed_count == results.len();
-
-    let test_suite = TestSuiteResult {
-        passed: all_passed,
-        total: results.len(),
-        passed_count,
-        results,
-        compilation_error: None,
+        TestSuiteRun::Ran { results, total_time_ms } => (results, total_time_ms),
     };
 
+    let test_suite = TestSuiteResult::from_results(results);
+    let passed_count = test_suite.passed_count;
+    let all_passed = test_suite.passed;
+
     let mut xp_earned = 0;
     let mut doors_unlocked = false;
     let mut quests_remaining = total_quests;
 
-    // Only complete quest on SUBMIT (not TEST) and if all passed
-    if all_passed && !test_only {
-        let mut game_state = state.0.lock().map_err(|e| e.to_string())?;
+    if !all_passed && !test_only {
+        let mut game_state = state.0.write().await;
+        let key = ProgressionState::quest_partial_key(&level_id, &quest_id);
+        game_state.record_failed_attempt(&key);
+    }
+
+    // Only complete quest on SUBMIT (not TEST) and if all passed, and only
+    // when every case actually ran (a `rerun_failed` subset can't complete
+    // the quest or earn XP, since it skipped cases that might still be
+    // broken).
+    if all_passed && !test_only && !is_partial_rerun {
+        let mut game_state = state.0.write().await;
 
         // Complete the quest (awards XP only if not already completed)
         xp_earned = game_state.complete_quest(&level_id, &quest_id, quest.xp_reward);
@@ -519,9 +633,33 @@ pub async fn submit_quest_code(
             remaining = quests_remaining,
             "Quest completed"
         );
+    } else if !test_only && !is_partial_rerun {
+        // Partial credit on SUBMIT even without a full pass.
+        let mut game_state = state.0.write().await;
+        xp_earned = game_state.award_quest_partial_xp(
+            &level_id,
+            &quest_id,
+            quest.xp_reward,
+            passed_count,
+            test_suite.total,
+        );
     }
 
-    let feedback = if all_passed {
+    let feedback = if is_partial_rerun {
+        if all_passed {
+            format!(
+                "{}/{} re-run tests passed! Submit the full suite to complete the quest.",
+                passed_count, test_suite.total
+            )
+        } else if let Some(diagnostic) = diagnose_failure(&test_suite.results) {
+            diagnostic
+        } else {
+            format!(
+                "{}/{} re-run tests passed. Check your logic and try again!",
+                passed_count, test_suite.total
+            )
+        }
+    } else if all_passed {
         if test_only {
             format!("All {} sample tests passed! Click SUBMIT to complete.", passed_count)
         } else if quests_remaining == 0 {
@@ -537,6 +675,13 @@ pub async fn submit_quest_code(
         } else {
             "Quest already completed. Try another quest!".to_string()
         }
+    } else if let Some(diagnostic) = diagnose_failure(&test_suite.results) {
+        diagnostic
+    } else if xp_earned > 0 {
+        format!(
+            "{}/{} tests passed. +{} XP partial credit! Keep going to complete the quest.",
+            passed_count, test_suite.total, xp_earned
+        )
     } else {
         format!(
             "{}/{} tests passed. Check your logic and try again!",
@@ -545,7 +690,7 @@ pub async fn submit_quest_code(
     };
 
     let render_state = {
-        let game_state = state.0.lock().map_err(|e| e.to_string())?;
+        let game_state = state.0.read().await;
         game_state.to_render_state()
     };
 
@@ -561,5 +706,6 @@ pub async fn submit_quest_code(
         doors_unlocked,
         render_state,
         test_results: Some(test_suite),
+        coverage,
     })
 }
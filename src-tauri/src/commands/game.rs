@@ -14,7 +14,7 @@ pub struct ProgressInfo {
 
 #[tauri::command]
 pub async fn init_game(state: State<'_, GameStateWrapper>) -> Result<RenderState, String> {
-    let mut game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let mut game_state = state.0.write().await;
     *game_state = GameState::default();
     // Start in playing mode so player can move
     game_state.game_phase = GamePhase::Playing;
@@ -23,13 +23,13 @@ pub async fn init_game(state: State<'_, GameStateWrapper>) -> Result<RenderState
 
 #[tauri::command]
 pub async fn get_game_state(state: State<'_, GameStateWrapper>) -> Result<GameState, String> {
-    let game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let game_state = state.0.read().await;
     Ok(game_state.clone())
 }
 
 #[tauri::command]
 pub async fn get_render_state(state: State<'_, GameStateWrapper>) -> Result<RenderState, String> {
-    let game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let game_state = state.0.read().await;
     Ok(game_state.to_render_state())
 }
 
@@ -38,7 +38,7 @@ pub async fn process_action(
     action: PlayerAction,
     state: State<'_, GameStateWrapper>,
 ) -> Result<RenderState, String> {
-    let mut game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let mut game_state = state.0.write().await;
 
     match action {
         PlayerAction::Move { direction } => {
@@ -66,7 +66,7 @@ pub async fn process_action(
 
 #[tauri::command]
 pub async fn get_progress(state: State<'_, GameStateWrapper>) -> Result<ProgressInfo, String> {
-    let game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let game_state = state.0.read().await;
 
     Ok(ProgressInfo {
         total_xp: game_state.progression.total_xp,
@@ -1,4 +1,5 @@
-use tauri::State;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
 use tracing::warn;
 
 use crate::GameStateWrapper;
@@ -10,7 +11,7 @@ pub async fn get_available_levels(
     state: State<'_, GameStateWrapper>,
     levels: State<'_, LevelRegistry>,
 ) -> Result<Vec<LevelInfo>, String> {
-    let game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let game_state = state.0.read().await;
 
     // Get level info with actual locked/completed status from progression
     let all_levels: Vec<LevelInfo> = levels
@@ -53,7 +54,7 @@ pub async fn load_level(
         .get_level(&level_id)
         .ok_or_else(|| format!("Level {} not found", level_id))?;
 
-    let mut game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let mut game_state = state.0.write().await;
 
     // Check if level is unlocked
     if !game_state.is_level_unlocked(&level_id) {
@@ -83,7 +84,7 @@ pub async fn get_level_data(
     state: State<'_, GameStateWrapper>,
     levels: State<'_, LevelRegistry>,
 ) -> Result<LevelData, String> {
-    let game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let game_state = state.0.read().await;
     let level_id = game_state
         .current_level_id
         .as_ref()
@@ -107,7 +108,7 @@ pub async fn get_level_quests(
         .get_level(&level_id)
         .ok_or_else(|| format!("Level {} not found", level_id))?;
 
-    let game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let game_state = state.0.read().await;
 
     let quests = level.get_quests();
     let quest_infos: Vec<QuestInfo> = quests
@@ -146,3 +147,53 @@ pub async fn load_quest(
 
     Ok(quest)
 }
+
+/// Event emitted every time a hot-reloaded level's `World` is swapped in
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldReloadedEvent {
+    pub level_id: String,
+    pub render_state: code_warrior::game::RenderState,
+}
+
+/// Watch a loose level JSON file on disk and live-swap its `World` into the
+/// running game every time it changes, instead of requiring a restart - a
+/// level designer's edit-and-see-live loop, as an alternative to the
+/// bundled, compile-time `assets/levels.json`.
+#[tauri::command]
+pub async fn start_level_hot_reload(path: String, app: AppHandle) -> Result<(), String> {
+    use code_warrior::levels::watch_level;
+
+    let reloads = watch_level(std::path::PathBuf::from(path)).map_err(|e| e.to_string())?;
+
+    std::thread::spawn(move || {
+        for level in reloads {
+            let world = match &level.map_file {
+                Some(map_path) => match load_map_file(map_path) {
+                    Ok(map_data) => map_data.to_world(),
+                    Err(e) => {
+                        warn!(map = %map_path, error = %e, "Failed to load map, using world_config");
+                        World::from_config(&level.world_config)
+                    }
+                },
+                None => World::from_config(&level.world_config),
+            };
+
+            let state = app.state::<GameStateWrapper>();
+            let render_state = {
+                let mut game_state = state.0.blocking_write();
+                game_state.apply_reloaded_world(world);
+                game_state.to_render_state()
+            };
+
+            let _ = app.emit(
+                "world_reloaded",
+                WorldReloadedEvent {
+                    level_id: level.id.clone(),
+                    render_state,
+                },
+            );
+        }
+    });
+
+    Ok(())
+}
@@ -18,7 +18,7 @@ pub async fn complete_level(
     state: State<'_, GameStateWrapper>,
     levels: State<'_, LevelRegistry>,
 ) -> Result<LevelCompleteResult, String> {
-    let mut game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let mut game_state = state.0.write().await;
     let level_id = game_state
         .current_level_id
         .clone()
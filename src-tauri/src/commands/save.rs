@@ -22,7 +22,7 @@ pub async fn save_game(
     state: State<'_, GameStateWrapper>,
     save_manager: State<'_, SaveManager>,
 ) -> Result<(), String> {
-    let game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let game_state = state.0.read().await;
 
     let mut save_data = SaveData::new(slot_id.clone());
     save_data.progression = game_state.progression.clone();
@@ -46,7 +46,7 @@ pub async fn load_game(
 ) -> Result<RenderState, String> {
     let save_data = save_manager.load(&slot_id)?;
 
-    let mut game_state = state.0.lock().map_err(|e| e.to_string())?;
+    let mut game_state = state.0.write().await;
 
     // Restore progression state
     game_state.progression = save_data.progression;
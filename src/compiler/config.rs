@@ -0,0 +1,194 @@
+//! Hot-reloadable tunables for [`super::CCompiler`] - timeout, max code
+//! size, the sandbox's resource limits, and the dangerous-function policy
+//! rule - so an operator can retune them by editing a file instead of
+//! redeploying. The sandbox *mode* (seccomp/bubblewrap/fallback) stays
+//! fixed at startup: which backend is available is a security property
+//! detected once, not a tunable.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use super::policy::{self, PolicyEngine};
+
+/// How often [`watch_config`] polls the file's mtime for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CompilerConfig {
+    /// Execution timeout in seconds.
+    pub timeout_secs: u64,
+    /// Max accepted submission size in bytes.
+    pub max_code_size: usize,
+    /// Sandbox memory limit in bytes (0 = unlimited under bubblewrap; the
+    /// seccomp backend falls back to `ExecutionBudgetProfile`'s default).
+    pub memory_limit: u64,
+    /// Sandbox `RLIMIT_NPROC` (0 = unlimited).
+    pub max_processes: u64,
+    /// Rule evaluated by the fallback sandbox's dangerous-function check
+    /// (see `super::policy`). Must compile - validated on load.
+    pub deny_rule: String,
+}
+
+impl Default for CompilerConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 5,
+            max_code_size: 10240,
+            memory_limit: 64 * 1024 * 1024,
+            max_processes: 32,
+            deny_rule: policy::DEFAULT_RULE.to_string(),
+        }
+    }
+}
+
+impl CompilerConfig {
+    /// Range checks plus compiling `deny_rule`, returning the compiled
+    /// engine so a caller that already needs it (every successful load)
+    /// doesn't pay for a second compile. Applied before a loaded config
+    /// replaces the live one, so an operator's typo can never leave the
+    /// compiler running with a broken policy.
+    fn validate(&self) -> Result<PolicyEngine, String> {
+        if self.timeout_secs == 0 {
+            return Err("timeout_secs must be greater than 0".to_string());
+        }
+        if self.max_code_size == 0 {
+            return Err("max_code_size must be greater than 0".to_string());
+        }
+        PolicyEngine::compile(&self.deny_rule).map_err(|e| format!("deny_rule is invalid: {e}"))
+    }
+
+    fn load_from_path(path: &Path) -> Result<(Self, PolicyEngine), String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        let config: Self =
+            toml::from_str(&text).map_err(|e| format!("failed to parse {}: {e}", path.display()))?;
+        let policy = config.validate()?;
+        Ok((config, policy))
+    }
+}
+
+/// A [`CompilerConfig`] together with its pre-compiled deny-rule
+/// [`PolicyEngine`], swapped in as one unit so the two are always in sync.
+pub struct LoadedCompilerConfig {
+    pub settings: CompilerConfig,
+    pub policy: PolicyEngine,
+}
+
+/// Live-reloadable holder for [`LoadedCompilerConfig`]. Cloning is cheap
+/// (an `Arc` bump); [`Self::current`] always returns whatever the most
+/// recent successful reload swapped in.
+#[derive(Clone)]
+pub struct SharedCompilerConfig(Arc<RwLock<Arc<LoadedCompilerConfig>>>);
+
+impl SharedCompilerConfig {
+    /// Builds a holder from an already-valid config. Panics if `config`'s
+    /// `deny_rule` doesn't compile - callers passing anything other than
+    /// [`CompilerConfig::default`] should validate first (see
+    /// [`watch_config`], which does).
+    pub fn new(config: CompilerConfig) -> Self {
+        let policy = PolicyEngine::compile(&config.deny_rule)
+            .expect("CompilerConfig passed to SharedCompilerConfig::new must have a valid deny_rule");
+        Self::from_loaded(config, policy)
+    }
+
+    fn from_loaded(settings: CompilerConfig, policy: PolicyEngine) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(LoadedCompilerConfig { settings, policy }))))
+    }
+
+    /// The config as of the most recent successful load or reload.
+    pub fn current(&self) -> Arc<LoadedCompilerConfig> {
+        Arc::clone(&self.0.read().unwrap())
+    }
+
+    fn set(&self, settings: CompilerConfig, policy: PolicyEngine) {
+        *self.0.write().unwrap() = Arc::new(LoadedCompilerConfig { settings, policy });
+    }
+}
+
+/// Loads `path` once synchronously - falling back to [`CompilerConfig::default`]
+/// and logging a warning if it's missing or invalid - then spawns a
+/// background task that re-reads the file whenever its mtime changes,
+/// atomically swapping in the new config. A reload that fails to parse or
+/// validate logs a warning and leaves the previously-live config in place.
+/// Must be called from within a Tokio runtime.
+pub fn watch_config(path: PathBuf) -> SharedCompilerConfig {
+    let shared = match CompilerConfig::load_from_path(&path) {
+        Ok((config, policy)) => SharedCompilerConfig::from_loaded(config, policy),
+        Err(e) => {
+            eprintln!(
+                "WARNING: failed to load compiler config from {}: {e}; using defaults",
+                path.display()
+            );
+            SharedCompilerConfig::new(CompilerConfig::default())
+        }
+    };
+
+    let watched = shared.clone();
+    tokio::spawn(async move {
+        let mut last_modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+
+            let Ok(modified) = std::fs::metadata(&path).and_then(|m| m.modified()) else {
+                continue;
+            };
+            if Some(modified) == last_modified {
+                continue;
+            }
+            last_modified = Some(modified);
+
+            match CompilerConfig::load_from_path(&path) {
+                Ok((config, policy)) => {
+                    eprintln!("INFO: reloaded compiler config from {}", path.display());
+                    watched.set(config, policy);
+                }
+                Err(e) => eprintln!(
+                    "WARNING: failed to reload compiler config from {}: {e}; keeping previous config",
+                    path.display()
+                ),
+            }
+        }
+    });
+
+    shared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_validates() {
+        assert!(CompilerConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_zero_timeout_rejected() {
+        let mut config = CompilerConfig::default();
+        config.timeout_secs = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_invalid_deny_rule_rejected() {
+        let mut config = CompilerConfig::default();
+        config.deny_rule = "functions &&".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_shared_config_reflects_latest_set() {
+        let shared = SharedCompilerConfig::new(CompilerConfig::default());
+        assert_eq!(shared.current().settings.timeout_secs, 5);
+
+        let mut updated = CompilerConfig::default();
+        updated.timeout_secs = 10;
+        let policy = updated.validate().unwrap();
+        shared.set(updated, policy);
+        assert_eq!(shared.current().settings.timeout_secs, 10);
+    }
+}
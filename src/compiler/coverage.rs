@@ -0,0 +1,146 @@
+//! Per-line execution coverage for a compiled submission, derived from
+//! `gcov` after running a `--coverage`-instrumented binary.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Per-line hit counts for a submission's C source, so the frontend can
+/// highlight which lines the test suite actually exercised (and flag dead
+/// code it never reached).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CoverageReport {
+    /// 1-indexed source line -> number of times it executed. Only lines
+    /// `gcov` considers executable are present; blank lines, comments, and
+    /// declarations without code are omitted.
+    pub line_hits: HashMap<u32, u32>,
+    /// Count of executable lines that were hit at least once.
+    pub lines_covered: usize,
+    /// Count of executable lines `gcov` reported on, hit or not.
+    pub lines_total: usize,
+}
+
+impl CoverageReport {
+    /// Fold another run's coverage into this one by summing hit counts
+    /// (useful when several harnesses compiled from the same user code -
+    /// one per test case - each produce their own report), then
+    /// recomputing the covered/total summary from the merged line set.
+    pub fn merge(&mut self, other: &CoverageReport) {
+        for (&line, &hits) in &other.line_hits {
+            *self.line_hits.entry(line).or_insert(0) += hits;
+        }
+        self.lines_total = self.line_hits.len();
+        self.lines_covered = self.line_hits.values().filter(|&&hits| hits > 0).count();
+    }
+}
+
+/// Run `gcov` against the `.gcno`/`.gcda` pair left next to
+/// `dir/source_file_name` and parse its annotated output into a
+/// [`CoverageReport`].
+pub async fn collect_coverage(dir: &Path, source_file_name: &str) -> Result<CoverageReport, String> {
+    let output = tokio::process::Command::new("gcov")
+        .arg(source_file_name)
+        .current_dir(dir)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run gcov: {}", e))?;
+
+    let gcov_path = dir.join(format!("{}.gcov", source_file_name));
+    let text = std::fs::read_to_string(&gcov_path).map_err(|e| {
+        format!(
+            "Failed to read gcov output: {} (gcov stderr: {})",
+            e,
+            String::from_utf8_lossy(&output.stderr)
+        )
+    })?;
+
+    Ok(parse_gcov(&text))
+}
+
+/// Parse `gcov`'s annotated-source (`.gcov`) format: each line is
+/// `<count>:<line_no>:<source text>`, where `<count>` is a number, `-` for
+/// a line with no executable code, or `#####`/`=====` for a line that was
+/// never executed.
+fn parse_gcov(text: &str) -> CoverageReport {
+    let mut report = CoverageReport::default();
+
+    for line in text.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(count_field), Some(line_field)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+
+        let Ok(line_no) = line_field.trim().parse::<u32>() else {
+            continue;
+        };
+        // gcov's line 0 is the per-file header ("Source:code.c" etc), not a
+        // real source line.
+        if line_no == 0 {
+            continue;
+        }
+
+        let count_field = count_field.trim();
+        if count_field == "-" {
+            continue; // not executable (blank line, comment, declaration)
+        }
+
+        let hits = if count_field == "#####" || count_field == "=====" {
+            0
+        } else {
+            count_field.parse::<u32>().unwrap_or(0)
+        };
+
+        report.lines_total += 1;
+        if hits > 0 {
+            report.lines_covered += 1;
+        }
+        report.line_hits.insert(line_no, hits);
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gcov_distinguishes_hit_dead_and_non_executable_lines() {
+        let sample = concat!(
+            "        -:    0:Source:code.c\n",
+            "        -:    1:#include <stdio.h>\n",
+            "        3:    2:int main() {\n",
+            "    #####:    3:    if (0) { return 1; }\n",
+            "        3:    4:    return 0;\n",
+            "        -:    5:}\n",
+        );
+
+        let report = parse_gcov(sample);
+        assert_eq!(report.line_hits.get(&1), None);
+        assert_eq!(report.line_hits.get(&2), Some(&3));
+        assert_eq!(report.line_hits.get(&3), Some(&0));
+        assert_eq!(report.line_hits.get(&4), Some(&3));
+        assert_eq!(report.lines_total, 3);
+        assert_eq!(report.lines_covered, 2);
+    }
+
+    #[test]
+    fn test_merge_sums_hits_across_runs() {
+        let mut a = CoverageReport::default();
+        a.line_hits.insert(2, 1);
+        a.line_hits.insert(3, 0);
+        a.lines_total = 2;
+        a.lines_covered = 1;
+
+        let mut b = CoverageReport::default();
+        b.line_hits.insert(2, 2);
+        b.line_hits.insert(3, 5);
+
+        a.merge(&b);
+
+        assert_eq!(a.line_hits.get(&2), Some(&3));
+        assert_eq!(a.line_hits.get(&3), Some(&5));
+        assert_eq!(a.lines_total, 2);
+        assert_eq!(a.lines_covered, 2);
+    }
+}
@@ -5,19 +5,28 @@
 //! 2. bubblewrap (Linux, requires namespace support)
 //! 3. Fallback (development only, NOT SECURE)
 
+mod cache;
+mod config;
+mod coverage;
+mod policy;
 mod sandbox;
 
 #[cfg(all(target_os = "linux", feature = "seccomp"))]
 mod seccomp_sandbox;
 
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 
-pub use sandbox::{SandboxConfig, SandboxResult};
+pub use cache::CompilationCache;
+pub use config::{CompilerConfig, LoadedCompilerConfig, SharedCompilerConfig, watch_config};
+pub use coverage::CoverageReport;
+pub use policy::{Facts, PolicyEngine};
+pub use sandbox::{SandboxConfig, SandboxResult, SandboxViolation};
 
 #[cfg(all(target_os = "linux", feature = "seccomp"))]
-use seccomp_sandbox::{is_seccomp_available, seccomp_execute};
+use seccomp_sandbox::{is_seccomp_available, seccomp_execute, ExecutionBudgetProfile, FilesystemPolicy};
 
 /// Available sandbox modes, in order of preference
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -117,6 +126,17 @@ pub struct ExecutionOutput {
     pub exit_code: Option<i32>,
     pub execution_time_ms: u64,
     pub timed_out: bool,
+    /// Peak resident set size in KB, when the sandbox backend reports it.
+    pub peak_memory_kb: Option<u64>,
+    /// Total CPU time (user + system) consumed, when the sandbox backend reports it.
+    pub cpu_time_ms: Option<u64>,
+    /// Signal that terminated the program, if it crashed instead of exiting
+    /// normally (e.g. `SIGSEGV`, `SIGFPE`).
+    pub term_signal: Option<i32>,
+    /// Name (and reason) of a syscall the sandbox blocked, if the seccomp
+    /// backend's ptrace supervisor identified one - see
+    /// [`sandbox::SandboxResult::blocked_syscall`].
+    pub blocked_syscall: Option<String>,
 }
 
 impl ExecutionOutput {
@@ -134,12 +154,38 @@ impl ExecutionOutput {
 
 pub struct CCompiler {
     temp_dir: String,
-    timeout_secs: u64,
-    max_code_size: usize,
     sandbox_mode: SandboxMode,
-    sandbox_config: SandboxConfig,
+    /// Max concurrent `compile_and_run` invocations a caller (e.g. a test
+    /// suite runner) should have in flight at once. Defaults to the number
+    /// of available CPUs.
+    parallelism: usize,
+    /// Content-addressed cache of past compile-and-run results, keyed on
+    /// the exact source/flags/stdin. A hit skips both the `gcc` invocation
+    /// and the sandboxed execution entirely.
+    cache: CompilationCache,
+    /// Timeout, code-size limit, sandbox resource limits, and dangerous-
+    /// function policy - read once per `compile_and_run*` call via
+    /// [`SharedCompilerConfig::current`] so a single submission always sees
+    /// a consistent snapshot even if an operator edits the config file
+    /// mid-request. Defaults to [`CompilerConfig::default`]; call
+    /// [`Self::with_config_file`] to make it hot-reloadable.
+    config: SharedCompilerConfig,
 }
 
+/// Flags that affect the compiled binary's behavior, used both to compile
+/// and to derive the cache key (the `-o <path>` output path doesn't belong
+/// in the key - it names a scratch file, not a property of the program).
+const GCC_FLAGS: &[&str] = &["-Wall", "-lpthread"];
+
+/// Max cached compile-and-run results kept on disk before the
+/// least-recently-used ones are evicted.
+const DEFAULT_CACHE_ENTRIES: usize = 500;
+
+/// Monotonic counter mixed into each sandbox directory name so concurrent
+/// `compile_and_run` calls (e.g. from a parallel test-suite runner) never
+/// collide, even if they land in the same millisecond.
+static SANDBOX_SEQ: AtomicU64 = AtomicU64::new(0);
+
 impl Default for CCompiler {
     fn default() -> Self {
         Self::new()
@@ -175,36 +221,126 @@ impl CCompiler {
             }
         }
 
+        let cache_dir = std::env::temp_dir().join("code_warrior_compile_cache");
+
         Self {
             temp_dir: std::env::temp_dir().to_string_lossy().to_string(),
-            timeout_secs: 5,
-            max_code_size: 10240,
             sandbox_mode,
-            sandbox_config: SandboxConfig::default(),
+            parallelism: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4),
+            cache: CompilationCache::new(cache_dir, DEFAULT_CACHE_ENTRIES),
+            config: SharedCompilerConfig::new(CompilerConfig::default()),
         }
     }
 
+    /// Make the compiler's timeout, code-size limit, sandbox resource
+    /// limits, and dangerous-function policy hot-reloadable from a TOML
+    /// file at `path` - see [`config::watch_config`]. Must be called from
+    /// within a Tokio runtime.
+    pub fn with_config_file(mut self, path: PathBuf) -> Self {
+        self.config = config::watch_config(path);
+        self
+    }
+
     /// Get the current sandbox mode
     pub fn sandbox_mode(&self) -> SandboxMode {
         self.sandbox_mode
     }
 
-    /// Check for dangerous C functions in source code (used in fallback mode)
-    fn check_dangerous_functions(&self, source: &str) -> Result<(), String> {
-        const DANGEROUS_FUNCS: &[&str] = &["system(", "exec(", "popen(", "fork("];
+    /// Max concurrent `compile_and_run` invocations a test-suite runner
+    /// should keep in flight at once.
+    pub fn parallelism(&self) -> usize {
+        self.parallelism
+    }
 
-        for func in DANGEROUS_FUNCS {
-            if source.contains(func) {
-                return Err(format!(
-                    "Dangerous function '{}' is not allowed",
-                    func.trim_end_matches('(')
-                ));
-            }
+    /// Override the default parallelism (number of available CPUs).
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Clear every cached compile-and-run result. Exposed to callers (e.g.
+    /// the `clean_cache` Tauri command) who want to force fresh compiles,
+    /// or reclaim disk space.
+    pub fn clean_cache(&self) -> std::io::Result<()> {
+        self.cache.clear()
+    }
+
+    /// Check source against `config.policy` (used in fallback mode)
+    fn check_dangerous_functions(&self, source: &str, config: &LoadedCompilerConfig) -> Result<(), String> {
+        let facts = Facts::extract(source);
+        if config.policy.evaluate(&facts) {
+            return Err("Use of a disallowed function or pattern is not permitted".to_string());
+        }
+        Ok(())
+    }
+
+    /// Reject oversized or (in fallback mode) obviously dangerous source
+    /// before spending a compile on it. Shared by every `compile_and_run*`
+    /// entry point so they reject the same way.
+    fn validate_source(&self, source: &str, config: &LoadedCompilerConfig) -> Result<(), String> {
+        if source.len() > config.settings.max_code_size {
+            return Err(format!(
+                "Code size exceeds maximum limit of {} bytes",
+                config.settings.max_code_size
+            ));
+        }
+
+        // Only check dangerous functions in fallback mode
+        // (seccomp and bwrap block them at OS level)
+        if self.sandbox_mode == SandboxMode::Fallback {
+            self.check_dangerous_functions(source, config)?;
         }
 
         Ok(())
     }
 
+    /// Run the already-compiled binary at `binary_str` (living in
+    /// `sandbox_dir`) under whichever sandbox backend is active.
+    #[allow(unused_variables)] // stdin_input only used with seccomp on Linux
+    async fn execute_binary(
+        &self,
+        binary_str: &str,
+        sandbox_dir: &Path,
+        stdin_input: Option<&str>,
+        config: &CompilerConfig,
+    ) -> Result<SandboxResult, String> {
+        match self.sandbox_mode {
+            #[cfg(all(target_os = "linux", feature = "seccomp"))]
+            SandboxMode::Seccomp => {
+                let budget = ExecutionBudgetProfile {
+                    max_cpu_seconds: config.timeout_secs,
+                    max_address_space_bytes: if config.memory_limit > 0 {
+                        config.memory_limit
+                    } else {
+                        ExecutionBudgetProfile::default().max_address_space_bytes
+                    },
+                    ..Default::default()
+                };
+                let fs_policy = FilesystemPolicy::confined_to(sandbox_dir);
+                seccomp_execute(binary_str, stdin_input, config.timeout_secs, &budget, &fs_policy).await
+            }
+
+            #[cfg(not(all(target_os = "linux", feature = "seccomp")))]
+            SandboxMode::Seccomp => {
+                // Should never happen - detect_sandbox_mode wouldn't return Seccomp
+                Err("Seccomp not available on this platform".to_string())
+            }
+
+            SandboxMode::Bubblewrap => {
+                let sandbox_config = SandboxConfig {
+                    timeout_secs: config.timeout_secs,
+                    memory_limit: config.memory_limit,
+                    max_processes: config.max_processes,
+                };
+                sandbox::sandbox_execute(&sandbox_config, binary_str, &[], sandbox_dir).await
+            }
+
+            SandboxMode::Fallback => sandbox::fallback_execute(binary_str, &[], config.timeout_secs).await,
+        }
+    }
+
     /// Compile and run C code with security protections.
     ///
     /// Sandbox priority:
@@ -216,36 +352,36 @@ impl CCompiler {
     }
 
     /// Compile and run C code with optional stdin input
-    #[allow(unused_variables)] // stdin_input only used with seccomp on Linux
     pub async fn compile_and_run_with_input(
         &self,
         source: &str,
         stdin_input: Option<&str>,
     ) -> Result<ExecutionOutput, String> {
         let start = Instant::now();
+        let config = self.config.current();
 
-        // Check code size limit
-        if source.len() > self.max_code_size {
+        if let Err(e) = self.validate_source(source, &config) {
             return Ok(ExecutionOutput {
-                compile_error: Some(format!(
-                    "Code size exceeds maximum limit of {} bytes",
-                    self.max_code_size
-                )),
+                compile_error: Some(e),
                 execution_time_ms: start.elapsed().as_millis() as u64,
                 ..Default::default()
             });
         }
 
-        // Only check dangerous functions in fallback mode
-        // (seccomp and bwrap block them at OS level)
-        if self.sandbox_mode == SandboxMode::Fallback {
-            if let Err(e) = self.check_dangerous_functions(source) {
-                return Ok(ExecutionOutput {
-                    compile_error: Some(e),
-                    execution_time_ms: start.elapsed().as_millis() as u64,
-                    ..Default::default()
-                });
-            }
+        // Cache hit: reuse the full prior result (including its exit code,
+        // stdout/stderr, and timing) instead of compiling and executing
+        // again. Safe because the key covers source, flags, stdin, and the
+        // live timeout/resource limits - anything that could change the
+        // outcome changes the key.
+        let limits = format!(
+            "limits:{}:{}:{}",
+            config.settings.timeout_secs, config.settings.memory_limit, config.settings.max_processes
+        );
+        let mut key_flags: Vec<&str> = GCC_FLAGS.to_vec();
+        key_flags.push(&limits);
+        let cache_key = cache::hash_key(source, &key_flags, stdin_input);
+        if let Some(cached) = self.cache.get(&cache_key) {
+            return Ok(cached);
         }
 
         let timestamp = std::time::SystemTime::now()
@@ -253,8 +389,11 @@ impl CCompiler {
             .unwrap_or_default()
             .as_millis();
 
-        // Create working directory
-        let sandbox_dir = Path::new(&self.temp_dir).join(format!("sandbox_{}", timestamp));
+        // Create working directory. The sequence number guarantees
+        // uniqueness even when multiple calls race within the same
+        // millisecond (e.g. a parallel test-suite run).
+        let seq = SANDBOX_SEQ.fetch_add(1, Ordering::Relaxed);
+        let sandbox_dir = Path::new(&self.temp_dir).join(format!("sandbox_{}_{}", timestamp, seq));
         std::fs::create_dir_all(&sandbox_dir)
             .map_err(|e| format!("Failed to create sandbox dir: {}", e))?;
 
@@ -269,12 +408,10 @@ impl CCompiler {
 
         // COMPILE PHASE
         // Compilation is safe - just run gcc directly (no untrusted code execution)
-        let compile_result = sandbox::fallback_execute(
-            "gcc",
-            &[&source_str, "-o", &binary_str, "-Wall", "-lpthread"],
-            self.timeout_secs,
-        )
-        .await?;
+        let mut compile_args: Vec<&str> = vec![&source_str, "-o", &binary_str];
+        compile_args.extend_from_slice(GCC_FLAGS);
+        let compile_result =
+            sandbox::fallback_execute("gcc", &compile_args, config.settings.timeout_secs).await?;
 
         if compile_result.exit_code != Some(0) {
             let _ = std::fs::remove_dir_all(&sandbox_dir);
@@ -286,49 +423,143 @@ impl CCompiler {
         }
 
         // EXECUTION PHASE - This is where sandboxing matters
-        let run_result = match self.sandbox_mode {
-            #[cfg(all(target_os = "linux", feature = "seccomp"))]
-            SandboxMode::Seccomp => {
-                seccomp_execute(&binary_str, stdin_input, self.timeout_secs).await?
-            }
+        let run_result = self
+            .execute_binary(&binary_str, &sandbox_dir, stdin_input, &config.settings)
+            .await?;
 
-            #[cfg(not(all(target_os = "linux", feature = "seccomp")))]
-            SandboxMode::Seccomp => {
-                // Should never happen - detect_sandbox_mode wouldn't return Seccomp
-                return Err("Seccomp not available on this platform".to_string());
-            }
+        // Cleanup
+        let _ = std::fs::remove_dir_all(&sandbox_dir);
 
-            SandboxMode::Bubblewrap => {
-                sandbox::sandbox_execute(
-                    &self.sandbox_config,
-                    &binary_str,
-                    &[],
-                    &sandbox_dir,
-                )
-                .await?
-            }
+        let output = ExecutionOutput {
+            stdout: run_result.stdout,
+            stderr: run_result.stderr,
+            exit_code: run_result.exit_code,
+            execution_time_ms: start.elapsed().as_millis() as u64,
+            timed_out: run_result.timed_out,
+            peak_memory_kb: run_result.peak_memory_kb,
+            cpu_time_ms: run_result.cpu_time_ms,
+            term_signal: run_result.term_signal,
+            runtime_error: if run_result.timed_out {
+                Some("Execution timed out".to_string())
+            } else {
+                run_result.blocked_syscall.as_ref().map(|s| format!("Blocked syscall: {s}"))
+            },
+            blocked_syscall: run_result.blocked_syscall,
+            ..Default::default()
+        };
 
-            SandboxMode::Fallback => {
-                sandbox::fallback_execute(&binary_str, &[], self.timeout_secs).await?
+        self.cache.put(&cache_key, &output);
+        Ok(output)
+    }
+
+    /// Compile `source` with gcov instrumentation (`--coverage`), run it
+    /// once, then feed the resulting `.gcno`/`.gcda` through `gcov` for a
+    /// per-line hit-count breakdown.
+    ///
+    /// Slower than [`Self::compile_and_run`] - an instrumented binary runs
+    /// measurably slower, and `gcov` has to parse the emitted data
+    /// afterwards - so callers should only take this path when a learner
+    /// explicitly asked for coverage feedback. Bypasses the compile cache:
+    /// the source is the same as the plain path, but compiling it with
+    /// different flags would otherwise collide on the same cache key, and
+    /// a cached hit couldn't have emitted coverage data anyway. Each call
+    /// gets its own sandbox directory, so instrumentation output from one
+    /// submission never leaks into another's coverage report.
+    pub async fn compile_and_run_with_coverage(
+        &self,
+        source: &str,
+        stdin_input: Option<&str>,
+    ) -> Result<(ExecutionOutput, Option<CoverageReport>), String> {
+        let start = Instant::now();
+        let config = self.config.current();
+
+        if let Err(e) = self.validate_source(source, &config) {
+            return Ok((
+                ExecutionOutput {
+                    compile_error: Some(e),
+                    execution_time_ms: start.elapsed().as_millis() as u64,
+                    ..Default::default()
+                },
+                None,
+            ));
+        }
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        let seq = SANDBOX_SEQ.fetch_add(1, Ordering::Relaxed);
+        let sandbox_dir = Path::new(&self.temp_dir).join(format!("coverage_{}_{}", timestamp, seq));
+        std::fs::create_dir_all(&sandbox_dir)
+            .map_err(|e| format!("Failed to create sandbox dir: {}", e))?;
+
+        let source_file_name = "code.c";
+        let source_file = sandbox_dir.join(source_file_name);
+        let binary_file = sandbox_dir.join("program");
+
+        std::fs::write(&source_file, source)
+            .map_err(|e| format!("Failed to write source: {}", e))?;
+
+        let source_str = source_file.to_string_lossy().to_string();
+        let binary_str = binary_file.to_string_lossy().to_string();
+
+        // COMPILE PHASE, instrumented for coverage in addition to the
+        // usual flags.
+        let mut compile_args: Vec<&str> = vec![&source_str, "-o", &binary_str, "--coverage"];
+        compile_args.extend_from_slice(GCC_FLAGS);
+        let compile_result =
+            sandbox::fallback_execute("gcc", &compile_args, config.settings.timeout_secs).await?;
+
+        if compile_result.exit_code != Some(0) {
+            let _ = std::fs::remove_dir_all(&sandbox_dir);
+            return Ok((
+                ExecutionOutput {
+                    compile_error: Some(compile_result.stderr),
+                    execution_time_ms: start.elapsed().as_millis() as u64,
+                    ..Default::default()
+                },
+                None,
+            ));
+        }
+
+        let run_result = self
+            .execute_binary(&binary_str, &sandbox_dir, stdin_input, &config.settings)
+            .await?;
+
+        // gcov needs the .gcda/.gcno pair the run just produced, so collect
+        // coverage before cleaning up. A parse failure isn't fatal - the
+        // submission still ran and gets a real result, it just comes back
+        // without line-hit data.
+        let coverage = match coverage::collect_coverage(&sandbox_dir, source_file_name).await {
+            Ok(report) => Some(report),
+            Err(e) => {
+                eprintln!("WARNING: failed to collect coverage: {}", e);
+                None
             }
         };
 
-        // Cleanup
         let _ = std::fs::remove_dir_all(&sandbox_dir);
 
-        Ok(ExecutionOutput {
+        let output = ExecutionOutput {
             stdout: run_result.stdout,
             stderr: run_result.stderr,
             exit_code: run_result.exit_code,
             execution_time_ms: start.elapsed().as_millis() as u64,
             timed_out: run_result.timed_out,
+            peak_memory_kb: run_result.peak_memory_kb,
+            cpu_time_ms: run_result.cpu_time_ms,
+            term_signal: run_result.term_signal,
             runtime_error: if run_result.timed_out {
                 Some("Execution timed out".to_string())
             } else {
-                None
+                run_result.blocked_syscall.as_ref().map(|s| format!("Blocked syscall: {s}"))
             },
+            blocked_syscall: run_result.blocked_syscall,
             ..Default::default()
-        })
+        };
+
+        Ok((output, coverage))
     }
 }
 
@@ -347,14 +578,15 @@ mod tests {
     fn test_dangerous_function_check() {
         let compiler = CCompiler {
             temp_dir: "/tmp".to_string(),
-            timeout_secs: 5,
-            max_code_size: 10240,
             sandbox_mode: SandboxMode::Fallback,
-            sandbox_config: SandboxConfig::default(),
+            parallelism: 4,
+            cache: CompilationCache::new("/tmp/code_warrior_cache_test", DEFAULT_CACHE_ENTRIES),
+            config: SharedCompilerConfig::new(CompilerConfig::default()),
         };
+        let config = compiler.config.current();
 
-        assert!(compiler.check_dangerous_functions("int main() { return 0; }").is_ok());
-        assert!(compiler.check_dangerous_functions("system(\"ls\");").is_err());
-        assert!(compiler.check_dangerous_functions("popen(\"cmd\", \"r\");").is_err());
+        assert!(compiler.check_dangerous_functions("int main() { return 0; }", &config).is_ok());
+        assert!(compiler.check_dangerous_functions("system(\"ls\");", &config).is_err());
+        assert!(compiler.check_dangerous_functions("popen(\"cmd\", \"r\");", &config).is_err());
     }
 }
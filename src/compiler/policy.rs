@@ -0,0 +1,568 @@
+//! Small rule-expression engine for the fallback sandbox's dangerous-code
+//! check, replacing a brittle `source.contains("system(")` scan with
+//! something a deployment can tune without a recompile.
+//!
+//! A rule is a boolean expression over facts extracted from a submission
+//! (`source`, `functions`, `includes`, `code_size`); evaluating to `true`
+//! means "deny". Pipeline: [`tokenize`] -> shunting-yard (`to_rpn`) ->
+//! [`PolicyEngine::evaluate`] walks the resulting RPN with a small value
+//! stack - no AST needed for a grammar this small.
+
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Default rule, reproducing the hardcoded list this engine replaces.
+pub const DEFAULT_RULE: &str =
+    r#"any(functions,"system") || any(functions,"exec") || any(functions,"popen") || any(functions,"fork")"#;
+
+/// Facts extracted from one submission, consulted by a compiled rule.
+#[derive(Debug, Clone, Default)]
+pub struct Facts {
+    pub source: String,
+    pub functions: Vec<String>,
+    pub includes: Vec<String>,
+    pub code_size: usize,
+}
+
+impl Facts {
+    /// Extracts facts from raw C source: `functions` are identifiers
+    /// followed by `(`, optionally after whitespace (so `foo(` and `foo (`
+    /// count, `int foo;` doesn't),
+    /// `includes` are the header names named by `#include`.
+    pub fn extract(source: &str) -> Self {
+        let func_re = Regex::new(r"\b([A-Za-z_][A-Za-z0-9_]*)\s*\(").unwrap();
+        let functions = func_re.captures_iter(source).map(|c| c[1].to_string()).collect();
+
+        let include_re = Regex::new(r#"#\s*include\s*[<"]([^>"]+)[>"]"#).unwrap();
+        let includes = include_re.captures_iter(source).map(|c| c[1].to_string()).collect();
+
+        Self {
+            source: source.to_string(),
+            functions,
+            includes,
+            code_size: source.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Str(String),
+    Num(f64),
+    Ident(String),
+    And,
+    Or,
+    Not,
+    Eq,
+    Ne,
+    Tilde,
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(rule: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = rule.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            '"' => {
+                let mut s = String::new();
+                let mut j = i + 1;
+                let mut closed = false;
+                while j < chars.len() {
+                    match chars[j] {
+                        '"' => {
+                            closed = true;
+                            j += 1;
+                            break;
+                        }
+                        '\\' if j + 1 < chars.len() => {
+                            s.push(chars[j + 1]);
+                            j += 2;
+                        }
+                        c => {
+                            s.push(c);
+                            j += 1;
+                        }
+                    }
+                }
+                if !closed {
+                    return Err("unterminated string literal in rule".to_string());
+                }
+                tokens.push(Token::Str(s));
+                i = j;
+            }
+            '&' if chars.get(i + 1) == Some(&'&') => {
+                tokens.push(Token::And);
+                i += 2;
+            }
+            '|' if chars.get(i + 1) == Some(&'|') => {
+                tokens.push(Token::Or);
+                i += 2;
+            }
+            '!' if chars.get(i + 1) == Some(&'=') => {
+                tokens.push(Token::Ne);
+                i += 2;
+            }
+            '!' => {
+                tokens.push(Token::Not);
+                i += 1;
+            }
+            '=' if chars.get(i + 1) == Some(&'=') => {
+                tokens.push(Token::Eq);
+                i += 2;
+            }
+            '=' if chars.get(i + 1) == Some(&'~') => {
+                tokens.push(Token::Tilde);
+                i += 2;
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let num = text
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid number literal '{text}' in rule"))?;
+                tokens.push(Token::Num(num));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            other => return Err(format!("unexpected character '{other}' in rule")),
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    And,
+    Or,
+    Not,
+    Eq,
+    Ne,
+    Tilde,
+}
+
+impl Op {
+    fn precedence(self) -> u8 {
+        match self {
+            Op::Or => 1,
+            Op::And => 2,
+            Op::Eq | Op::Ne | Op::Tilde => 3,
+            Op::Not => 4,
+        }
+    }
+
+    fn is_unary(self) -> bool {
+        matches!(self, Op::Not)
+    }
+}
+
+/// One instruction in the compiled rule's postfix form.
+#[derive(Debug, Clone)]
+enum Rpn {
+    Str(String),
+    Num(f64),
+    Var(String),
+    Op(Op),
+    Call(String, usize),
+}
+
+enum StackItem {
+    Op(Op),
+    Group,
+    Call(String),
+}
+
+/// Infix tokens -> postfix (RPN) via shunting-yard. `!` binds tightest and
+/// is right-associative (prefix); `==`/`!=`/`=~` bind next; `&&` then
+/// `||` bind loosest, both left-associative. Function calls are tracked as
+/// `StackItem::Call` fences so `,` and `)` know where an argument list
+/// starts.
+fn to_rpn(tokens: &[Token]) -> Result<Vec<Rpn>, String> {
+    fn should_pop(top: Op, incoming: Op) -> bool {
+        if incoming.is_unary() {
+            top.precedence() > incoming.precedence()
+        } else {
+            top.precedence() >= incoming.precedence()
+        }
+    }
+
+    fn pop_op(stack: &mut Vec<StackItem>, output: &mut Vec<Rpn>) {
+        if let Some(StackItem::Op(op)) = stack.pop() {
+            output.push(Rpn::Op(op));
+        }
+    }
+
+    let mut output = Vec::new();
+    let mut stack: Vec<StackItem> = Vec::new();
+    let mut arg_counts: Vec<usize> = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Str(s) => {
+                output.push(Rpn::Str(s.clone()));
+                i += 1;
+            }
+            Token::Num(n) => {
+                output.push(Rpn::Num(*n));
+                i += 1;
+            }
+            Token::Ident(name) => {
+                if matches!(tokens.get(i + 1), Some(Token::LParen)) {
+                    stack.push(StackItem::Call(name.clone()));
+                    arg_counts.push(1);
+                    i += 2; // consume the ident and its opening paren
+                } else {
+                    output.push(Rpn::Var(name.clone()));
+                    i += 1;
+                }
+            }
+            Token::And | Token::Or | Token::Eq | Token::Ne | Token::Tilde | Token::Not => {
+                let op = match tokens[i] {
+                    Token::And => Op::And,
+                    Token::Or => Op::Or,
+                    Token::Eq => Op::Eq,
+                    Token::Ne => Op::Ne,
+                    Token::Tilde => Op::Tilde,
+                    Token::Not => Op::Not,
+                    _ => unreachable!(),
+                };
+                while let Some(StackItem::Op(top)) = stack.last() {
+                    if should_pop(*top, op) {
+                        pop_op(&mut stack, &mut output);
+                    } else {
+                        break;
+                    }
+                }
+                stack.push(StackItem::Op(op));
+                i += 1;
+            }
+            Token::LParen => {
+                stack.push(StackItem::Group);
+                i += 1;
+            }
+            Token::RParen => {
+                loop {
+                    match stack.pop() {
+                        Some(StackItem::Op(op)) => output.push(Rpn::Op(op)),
+                        Some(StackItem::Group) => break,
+                        Some(StackItem::Call(name)) => {
+                            let argc = arg_counts.pop().unwrap_or(0);
+                            output.push(Rpn::Call(name, argc));
+                            break;
+                        }
+                        None => return Err("mismatched parentheses in rule".to_string()),
+                    }
+                }
+                i += 1;
+            }
+            Token::Comma => {
+                loop {
+                    match stack.last() {
+                        Some(StackItem::Op(_)) => pop_op(&mut stack, &mut output),
+                        Some(StackItem::Call(_)) => {
+                            *arg_counts.last_mut().unwrap() += 1;
+                            break;
+                        }
+                        _ => return Err("',' outside of a function call in rule".to_string()),
+                    }
+                }
+                i += 1;
+            }
+        }
+    }
+
+    while let Some(item) = stack.pop() {
+        match item {
+            StackItem::Op(op) => output.push(Rpn::Op(op)),
+            StackItem::Group | StackItem::Call(_) => {
+                return Err("mismatched parentheses in rule".to_string())
+            }
+        }
+    }
+
+    if output.is_empty() {
+        return Err("empty rule".to_string());
+    }
+
+    Ok(output)
+}
+
+/// A value on the evaluator's stack - either a literal/variable operand or
+/// the boolean result of an operator.
+#[derive(Debug, Clone)]
+enum Value {
+    Str(String),
+    Num(f64),
+    List(Vec<String>),
+    Bool(bool),
+}
+
+impl Value {
+    fn truthy(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            Value::Str(s) => !s.is_empty(),
+            Value::Num(n) => *n != 0.0,
+            Value::List(l) => !l.is_empty(),
+        }
+    }
+
+    fn as_str(&self) -> String {
+        match self {
+            Value::Str(s) => s.clone(),
+            Value::Num(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::List(l) => l.join(","),
+        }
+    }
+
+    fn as_list(&self) -> Vec<String> {
+        match self {
+            Value::List(l) => l.clone(),
+            other => vec![other.as_str()],
+        }
+    }
+
+    fn loose_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Num(a), Value::Num(b)) => a == b,
+            _ => self.as_str() == other.as_str(),
+        }
+    }
+}
+
+/// `source`/`functions`/`includes`/`code_size` resolve to their fact;
+/// anything else - a typo, a future fact not yet wired up - evaluates to
+/// an empty string rather than erroring, per the engine's invariant that
+/// unknown variables are harmless no-ops.
+fn resolve_var(name: &str, facts: &Facts) -> Value {
+    match name {
+        "source" => Value::Str(facts.source.clone()),
+        "functions" => Value::List(facts.functions.clone()),
+        "includes" => Value::List(facts.includes.clone()),
+        "code_size" => Value::Num(facts.code_size as f64),
+        _ => Value::Str(String::new()),
+    }
+}
+
+/// A compiled rule, ready to evaluate against a submission's [`Facts`]
+/// without re-tokenizing or re-parsing, and with every `=~`/`matches(...)`
+/// regex pre-compiled so a bad pattern is caught at [`Self::compile`] time
+/// rather than on a learner's submission.
+pub struct PolicyEngine {
+    rpn: Vec<Rpn>,
+    regex_cache: HashMap<String, Regex>,
+}
+
+impl PolicyEngine {
+    /// Parses `rule` and pre-compiles every regex literal it references.
+    /// Fails on any syntax error, arity mismatch, or invalid regex - there
+    /// is no partial-success mode, since a rule that can't be fully
+    /// validated shouldn't be trusted to gate code execution.
+    pub fn compile(rule: &str) -> Result<Self, String> {
+        let tokens = tokenize(rule)?;
+        let rpn = to_rpn(&tokens)?;
+
+        // Every operator/builtin needs its operands and the rule must reduce to one value.
+        let mut depth = 0usize;
+        for token in &rpn {
+            let needs = match token {
+                Rpn::Str(_) | Rpn::Num(_) | Rpn::Var(_) => 0,
+                Rpn::Op(Op::Not) => 1,
+                Rpn::Op(_) => 2,
+                Rpn::Call(name, argc) if *argc != 2 && matches!(name.as_str(), "contains" | "any" | "matches") => return Err(format!("{name}(...) takes 2 arguments, got {argc}")),
+                Rpn::Call(_, argc) => *argc,
+            };
+            depth = depth.checked_sub(needs).ok_or("operator is missing an operand in rule")? + 1;
+        }
+        (depth == 1).then_some(()).ok_or("rule does not reduce to a single value")?;
+
+        let mut regex_cache: HashMap<String, Regex> = HashMap::new();
+        for (i, token) in rpn.iter().enumerate() {
+            let needs_pattern = matches!(token, Rpn::Op(Op::Tilde))
+                || matches!(token, Rpn::Call(name, argc) if name == "matches" && *argc == 2);
+            if !needs_pattern {
+                continue;
+            }
+
+            let pattern = match i.checked_sub(1).and_then(|j| rpn.get(j)) {
+                Some(Rpn::Str(s)) => s.clone(),
+                _ => {
+                    return Err(
+                        "=~ and matches(...) require a literal string regex pattern".to_string()
+                    )
+                }
+            };
+
+            if !regex_cache.contains_key(&pattern) {
+                let re = Regex::new(&pattern).map_err(|e| format!("invalid regex '{pattern}': {e}"))?;
+                regex_cache.insert(pattern, re);
+            }
+        }
+
+        Ok(Self { rpn, regex_cache })
+    }
+
+    /// Evaluates the compiled rule against `facts`. Returns `true` for
+    /// "deny" - callers should reject the submission when this is `true`.
+    /// Any stack-shape inconsistency (which [`Self::compile`] should have
+    /// already ruled out) fails closed, i.e. also denies.
+    pub fn evaluate(&self, facts: &Facts) -> bool {
+        let mut stack: Vec<Value> = Vec::new();
+
+        for token in &self.rpn {
+            match token {
+                Rpn::Str(s) => stack.push(Value::Str(s.clone())),
+                Rpn::Num(n) => stack.push(Value::Num(*n)),
+                Rpn::Var(name) => stack.push(resolve_var(name, facts)),
+                Rpn::Op(Op::Not) => {
+                    let Some(v) = stack.pop() else { return true };
+                    stack.push(Value::Bool(!v.truthy()));
+                }
+                Rpn::Op(op @ (Op::And | Op::Or)) => {
+                    let (Some(b), Some(a)) = (stack.pop(), stack.pop()) else { return true };
+                    let result = if *op == Op::And {
+                        a.truthy() && b.truthy()
+                    } else {
+                        a.truthy() || b.truthy()
+                    };
+                    stack.push(Value::Bool(result));
+                }
+                Rpn::Op(op @ (Op::Eq | Op::Ne)) => {
+                    let (Some(b), Some(a)) = (stack.pop(), stack.pop()) else { return true };
+                    let eq = a.loose_eq(&b);
+                    stack.push(Value::Bool(if *op == Op::Eq { eq } else { !eq }));
+                }
+                Rpn::Op(Op::Tilde) => {
+                    let (Some(pattern), Some(text)) = (stack.pop(), stack.pop()) else { return true };
+                    let Some(re) = self.regex_cache.get(&pattern.as_str()) else { return true };
+                    stack.push(Value::Bool(re.is_match(&text.as_str())));
+                }
+                Rpn::Call(name, argc) => {
+                    if stack.len() < *argc {
+                        return true;
+                    }
+                    let args = stack.split_off(stack.len() - argc);
+                    let result = match (name.as_str(), args.as_slice()) {
+                        ("contains", [haystack, needle]) => haystack.as_str().contains(&needle.as_str()),
+                        ("any", [list, needle]) => {
+                            let needle = needle.as_str();
+                            list.as_list().iter().any(|item| *item == needle)
+                        }
+                        ("matches", [text, pattern]) => match self.regex_cache.get(&pattern.as_str()) {
+                            Some(re) => re.is_match(&text.as_str()),
+                            None => return true,
+                        },
+                        _ => return true, // unknown function or wrong arity: fail closed
+                    };
+                    stack.push(Value::Bool(result));
+                }
+            }
+        }
+
+        match stack.pop() {
+            Some(v) => v.truthy(),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_rule_matches_today_behavior() {
+        let policy = PolicyEngine::compile(DEFAULT_RULE).unwrap();
+
+        assert!(policy.evaluate(&Facts::extract("int main() { system(\"ls\"); return 0; }")));
+        assert!(policy.evaluate(&Facts::extract("popen(\"cmd\", \"r\");")));
+        assert!(!policy.evaluate(&Facts::extract("int main() { return 0; }")));
+    }
+
+    #[test]
+    fn test_any_matches_exact_identifier_not_substring() {
+        let policy = PolicyEngine::compile(DEFAULT_RULE).unwrap();
+        // A false positive the old substring scan would have triggered on.
+        assert!(!policy.evaluate(&Facts::extract("void systemCheck() {}")));
+    }
+
+    #[test]
+    fn test_contains_and_not_operators() {
+        let policy = PolicyEngine::compile(r#"contains(source, "/bin/sh") && !contains(source, "safe_mode")"#).unwrap();
+        assert!(policy.evaluate(&Facts::extract("char *p = \"/bin/sh\";")));
+        assert!(!policy.evaluate(&Facts::extract("char *p = \"/bin/sh\"; // safe_mode")));
+    }
+
+    #[test]
+    fn test_matches_uses_cached_regex() {
+        let policy = PolicyEngine::compile(r##"matches(source, "#include\\s*<unistd\\.h>")"##).unwrap();
+        assert!(policy.evaluate(&Facts::extract("#include <unistd.h>\nint main(){}")));
+        assert!(!policy.evaluate(&Facts::extract("#include <stdio.h>\nint main(){}")));
+    }
+
+    #[test]
+    fn test_includes_fact_extraction() {
+        let policy = PolicyEngine::compile(r#"any(includes, "unistd.h")"#).unwrap();
+        assert!(policy.evaluate(&Facts::extract("#include <unistd.h>\n")));
+        assert!(!policy.evaluate(&Facts::extract("#include <stdio.h>\n")));
+    }
+
+    #[test]
+    fn test_unknown_variable_is_empty_string() {
+        let policy = PolicyEngine::compile(r#"nonexistent == "danger""#).unwrap();
+        assert!(!policy.evaluate(&Facts::extract("anything")));
+    }
+
+    #[test]
+    fn test_invalid_regex_fails_at_compile_time() {
+        let err = PolicyEngine::compile(r#"matches(source, "(unclosed")"#).unwrap_err();
+        assert!(err.contains("invalid regex"));
+    }
+
+    #[test]
+    fn test_malformed_rule_fails_to_compile() {
+        assert!(PolicyEngine::compile("functions &&").is_err());
+        assert!(PolicyEngine::compile("(functions").is_err());
+        assert!(PolicyEngine::compile("").is_err());
+    }
+
+    #[test]
+    fn test_and_binds_tighter_than_or() {
+        // Correct precedence: (malloc && system) || exec. With only exec(),
+        // the left `&&` clause is false but the `||` still makes it true -
+        // a naive left-to-right evaluator that grouped this as
+        // malloc && (system || exec) would wrongly come out false.
+        let policy =
+            PolicyEngine::compile(r#"any(functions,"malloc") && any(functions,"system") || any(functions,"exec")"#)
+                .unwrap();
+        assert!(policy.evaluate(&Facts::extract("exec(\"ls\");")));
+    }
+}
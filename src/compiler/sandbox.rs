@@ -3,17 +3,26 @@
 //! Provides OS-level isolation via Linux namespaces (PID, NET, IPC, mount).
 //! Bubblewrap is simpler than nsjail and available in Alpine repos.
 
+use std::os::unix::process::ExitStatusExt;
 use std::path::Path;
 use std::process::Command;
 use std::time::Instant;
 use tokio::time::{timeout, Duration};
 
+#[cfg(all(target_os = "linux", feature = "seccomp"))]
+use super::seccomp_sandbox::{build_seccomp_bpf_bytes, SeccompDefaultAction};
+
 /// Configuration for the bubblewrap sandbox.
 pub struct SandboxConfig {
     /// Execution timeout in seconds
     pub timeout_secs: u64,
     /// Memory limit in bytes (0 = unlimited)
     pub memory_limit: u64,
+    /// Max number of processes/threads (`RLIMIT_NPROC`, applied via
+    /// `prlimit --nproc`) the sandboxed program and its descendants may
+    /// hold open at once (0 = unlimited). Bounds a fork-bomb even though
+    /// bwrap's own PID namespace already isolates it from the host.
+    pub max_processes: u64,
 }
 
 impl Default for SandboxConfig {
@@ -21,10 +30,45 @@ impl Default for SandboxConfig {
         Self {
             timeout_secs: 5,
             memory_limit: 64 * 1024 * 1024, // 64MB
+            max_processes: 32,
         }
     }
 }
 
+/// A resource-limit or sandbox-policy kill distinguishable from an ordinary
+/// crash by the signal that ended the process. Best-effort: [`OutOfMemory`](SandboxViolation::OutOfMemory)
+/// in particular is inferred (a `SIGSEGV` while a memory budget was
+/// configured), since exceeding `RLIMIT_AS` just fails the allocation
+/// syscall rather than delivering a signal of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxViolation {
+    /// Likely killed for exceeding `memory_limit` (inferred from `SIGSEGV`
+    /// with a memory limit configured - see the caveat above).
+    OutOfMemory,
+    /// Killed after exceeding the CPU time budget (`SIGXCPU`, from
+    /// `prlimit --cpu`), a harder backstop than the outer async timeout.
+    CpuLimitExceeded,
+    /// Terminated by `SIGSYS`: executed a syscall outside the seccomp
+    /// whitelist passed to bwrap via `--seccomp`.
+    SeccompViolation,
+}
+
+/// Best-effort classification of why a sandboxed process was killed, from
+/// the raw termination signal. Returns `None` for a normal exit or a signal
+/// that doesn't map to one of the budgets `SandboxConfig` enforces.
+pub(crate) fn classify_violation(term_signal: Option<i32>, memory_limit: u64) -> Option<SandboxViolation> {
+    const SIGSEGV: i32 = 11;
+    const SIGXCPU: i32 = 24;
+    const SIGSYS: i32 = 31;
+
+    match term_signal {
+        Some(SIGSYS) => Some(SandboxViolation::SeccompViolation),
+        Some(SIGXCPU) => Some(SandboxViolation::CpuLimitExceeded),
+        Some(SIGSEGV) if memory_limit > 0 => Some(SandboxViolation::OutOfMemory),
+        _ => None,
+    }
+}
+
 /// Result of sandbox execution.
 pub struct SandboxResult {
     pub stdout: String,
@@ -32,6 +76,29 @@ pub struct SandboxResult {
     pub exit_code: Option<i32>,
     pub timed_out: bool,
     pub execution_time_ms: u64,
+    /// Peak resident set size in KB, if the sandbox backend captured it
+    /// (currently only the seccomp backend, via `getrusage`).
+    pub peak_memory_kb: Option<u64>,
+    /// Total CPU time (user + system) consumed by the child, if the sandbox
+    /// backend captured it.
+    pub cpu_time_ms: Option<u64>,
+    /// Signal that terminated the child, if it didn't exit normally (e.g.
+    /// `SIGSEGV`, `SIGFPE`, `SIGABRT`). `None` when the process exited via
+    /// `exit`/`exit_group` or was killed by the outer timeout.
+    pub term_signal: Option<i32>,
+    /// Best-effort classification of `term_signal` against the budgets in
+    /// [`SandboxConfig`]/[`ExecutionBudgetProfile`](super::seccomp_sandbox::ExecutionBudgetProfile),
+    /// e.g. telling a student "your program used too much memory" instead
+    /// of just "terminated by signal 11". `None` for a normal exit, a
+    /// timeout (already called out via `timed_out`), or a signal that
+    /// doesn't map to an enforced budget.
+    pub violation: Option<SandboxViolation>,
+    /// Name of the syscall that got the program killed, e.g. `"socket
+    /// (network access is not permitted)"`, if the seccomp backend's ptrace
+    /// supervisor caught one. `None` for a normal exit/crash, a timeout, or
+    /// when no supervisor is running (bwrap, fallback, or a non-x86_64
+    /// seccomp build - see [`super::seccomp_sandbox`]).
+    pub blocked_syscall: Option<String>,
 }
 
 /// Check if bubblewrap (bwrap) is available on the system.
@@ -48,6 +115,35 @@ pub fn is_nsjail_available() -> bool {
     is_bwrap_available()
 }
 
+/// Check if `prlimit` (util-linux) is available. `sandbox_execute` shells
+/// out to it to enforce `SandboxConfig`'s memory/process/CPU budgets on the
+/// bwrap-launched process, since bwrap itself has no such flags.
+pub fn is_prlimit_available() -> bool {
+    Command::new("which")
+        .arg("prlimit")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Write a compiled seccomp-bpf program into an anonymous, non-`CLOEXEC`
+/// `memfd` so its file descriptor survives into the child bwrap spawns,
+/// for use with bwrap's `--seccomp <fd>` flag.
+#[cfg(all(target_os = "linux", feature = "seccomp"))]
+fn seccomp_memfd(bytes: &[u8]) -> std::io::Result<std::fs::File> {
+    use std::io::Write;
+    use std::os::fd::FromRawFd;
+
+    let name = std::ffi::CString::new("cw-sandbox-seccomp").expect("literal has no NUL byte");
+    let fd = unsafe { libc::memfd_create(name.as_ptr(), 0) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut file = unsafe { std::fs::File::from_raw_fd(fd) };
+    file.write_all(bytes)?;
+    Ok(file)
+}
+
 /// Execute a command inside bubblewrap sandbox.
 ///
 /// # Arguments
@@ -71,12 +167,35 @@ pub async fn sandbox_execute(
     let cmd_str = command.to_string();
     let args_vec: Vec<String> = args.iter().map(|s| s.to_string()).collect();
     let timeout_secs = config.timeout_secs;
+    let memory_limit = config.memory_limit;
+    let max_processes = config.max_processes;
+    let use_prlimit = is_prlimit_available();
 
     // Spawn in blocking thread with timeout
     let result = timeout(
         Duration::from_secs(timeout_secs),
         tokio::task::spawn_blocking(move || {
-            let mut cmd = Command::new("bwrap");
+            // `bwrap` itself has no resource-limit flags, so when `prlimit`
+            // is on the host we launch bwrap underneath it - the limits
+            // apply to bwrap and are inherited across its own exec, so
+            // they still bound the sandboxed program.
+            let mut cmd = if use_prlimit {
+                let mut c = Command::new("prlimit");
+                if memory_limit > 0 {
+                    c.arg(format!("--as={}", memory_limit));
+                }
+                if max_processes > 0 {
+                    c.arg(format!("--nproc={}", max_processes));
+                }
+                c.arg(format!("--cpu={}", timeout_secs));
+                c.arg("--").arg("bwrap");
+                c
+            } else {
+                eprintln!(
+                    "WARNING: prlimit not available - bwrap sandbox running without memory/process/CPU rlimits enforced"
+                );
+                Command::new("bwrap")
+            };
 
             // Namespace isolation
             cmd.arg("--unshare-net") // No network access
@@ -110,6 +229,31 @@ pub async fn sandbox_execute(
             cmd.arg("--dev").arg("/dev");
             cmd.arg("--proc").arg("/proc");
 
+            // Syscall filtering on top of namespace isolation - the same
+            // whitelist the seccomp-primary backend uses, applied here via
+            // bwrap's `--seccomp <fd>`. Kept alive until after `.output()`
+            // so the fd isn't closed before bwrap execs.
+            #[cfg(all(target_os = "linux", feature = "seccomp"))]
+            let _seccomp_fd_guard = {
+                use std::os::fd::AsRawFd;
+
+                match build_seccomp_bpf_bytes(SeccompDefaultAction::Kill)
+                    .and_then(|bytes| seccomp_memfd(&bytes).map_err(|e| e.to_string()))
+                {
+                    Ok(file) => {
+                        cmd.arg("--seccomp").arg(file.as_raw_fd().to_string());
+                        Some(file)
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "WARNING: failed to build seccomp filter for bwrap sandbox, continuing with namespace isolation only: {}",
+                            e
+                        );
+                        None
+                    }
+                }
+            };
+
             // The command to run
             cmd.arg("--").arg(&cmd_str);
 
@@ -124,13 +268,21 @@ pub async fn sandbox_execute(
     .await;
 
     match result {
-        Ok(Ok(Ok(output))) => Ok(SandboxResult {
-            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-            exit_code: output.status.code(),
-            timed_out: false,
-            execution_time_ms: start.elapsed().as_millis() as u64,
-        }),
+        Ok(Ok(Ok(output))) => {
+            let term_signal = output.status.signal();
+            Ok(SandboxResult {
+                stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+                exit_code: output.status.code(),
+                timed_out: false,
+                execution_time_ms: start.elapsed().as_millis() as u64,
+                peak_memory_kb: None,
+                cpu_time_ms: None,
+                term_signal,
+                violation: classify_violation(term_signal, memory_limit),
+                blocked_syscall: None,
+            })
+        }
         Ok(Ok(Err(e))) => Err(format!("Failed to spawn sandbox: {}", e)),
         Ok(Err(e)) => Err(format!("Task panicked: {}", e)),
         Err(_) => Ok(SandboxResult {
@@ -139,6 +291,11 @@ pub async fn sandbox_execute(
             exit_code: None,
             timed_out: true,
             execution_time_ms: timeout_secs * 1000,
+            peak_memory_kb: None,
+            cpu_time_ms: None,
+            term_signal: None,
+            violation: None,
+            blocked_syscall: None,
         }),
     }
 }
@@ -177,6 +334,11 @@ pub async fn fallback_execute(
             exit_code: output.status.code(),
             timed_out: false,
             execution_time_ms: start.elapsed().as_millis() as u64,
+            peak_memory_kb: None,
+            cpu_time_ms: None,
+            term_signal: output.status.signal(),
+            violation: None,
+            blocked_syscall: None,
         }),
         Ok(Ok(Err(e))) => Err(format!("Failed to execute: {}", e)),
         Ok(Err(e)) => Err(format!("Task panicked: {}", e)),
@@ -186,6 +348,11 @@ pub async fn fallback_execute(
             exit_code: None,
             timed_out: true,
             execution_time_ms: timeout_secs * 1000,
+            peak_memory_kb: None,
+            cpu_time_ms: None,
+            term_signal: None,
+            violation: None,
+            blocked_syscall: None,
         }),
     }
 }
@@ -199,4 +366,20 @@ mod tests {
         // This test just verifies the function runs without panic
         let _ = is_nsjail_available();
     }
+
+    #[test]
+    fn test_default_sandbox_config_has_enforced_limits() {
+        let config = SandboxConfig::default();
+        assert!(config.memory_limit > 0);
+        assert!(config.max_processes > 0);
+    }
+
+    #[test]
+    fn test_classify_violation() {
+        assert_eq!(classify_violation(Some(31), 0), Some(SandboxViolation::SeccompViolation));
+        assert_eq!(classify_violation(Some(24), 0), Some(SandboxViolation::CpuLimitExceeded));
+        assert_eq!(classify_violation(Some(11), 64 * 1024 * 1024), Some(SandboxViolation::OutOfMemory));
+        assert_eq!(classify_violation(Some(11), 0), None);
+        assert_eq!(classify_violation(None, 64 * 1024 * 1024), None);
+    }
 }
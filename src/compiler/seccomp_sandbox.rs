@@ -18,12 +18,19 @@ use seccompiler::{
 #[cfg(all(target_os = "linux", feature = "seccomp"))]
 use std::collections::BTreeMap;
 
-use std::io::Write;
+#[cfg(all(target_os = "linux", feature = "landlock"))]
+use landlock::{
+    AccessFs, PathBeneath, PathFd, Ruleset, RulesetAttr, RulesetCreatedAttr, ABI,
+};
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::os::unix::process::CommandExt;
 use std::process::{Command, Stdio};
 use std::time::Instant;
 use tokio::time::{timeout, Duration};
 
+use super::sandbox::classify_violation;
 use super::SandboxResult;
 
 /// Check if seccomp is available on this system.
@@ -41,18 +48,88 @@ pub fn is_seccomp_available() -> bool {
     false
 }
 
+/// Check if the Landlock LSM is usable on this system, by attempting to
+/// build (but not apply) a minimal ruleset. Unlike seccomp, Landlock has no
+/// namespace or `CAP_SYS_ADMIN` requirement, so this works on Railway/Docker
+/// the same as bare-metal — it just gracefully returns `false` on kernels
+/// built without `CONFIG_SECURITY_LANDLOCK` or too old to support it (< 5.13).
+#[cfg(all(target_os = "linux", feature = "landlock"))]
+pub fn is_landlock_available() -> bool {
+    Ruleset::default()
+        .handle_access(AccessFs::from_all(ABI::V1))
+        .and_then(|r| r.create())
+        .is_ok()
+}
+
+#[cfg(not(all(target_os = "linux", feature = "landlock")))]
+pub fn is_landlock_available() -> bool {
+    false
+}
+
+/// The architecture seccompiler should compile the filter for, resolved at
+/// compile time from the build target so cross-compiled binaries (e.g.
+/// aarch64 Docker images on Apple Silicon CI, Railway's arm64 runners) get a
+/// filter with the right syscall numbers instead of a silently-wrong x86_64
+/// one.
+#[cfg(all(target_os = "linux", feature = "seccomp", target_arch = "x86_64"))]
+const TARGET_ARCH: TargetArch = TargetArch::x86_64;
+
+#[cfg(all(target_os = "linux", feature = "seccomp", target_arch = "aarch64"))]
+const TARGET_ARCH: TargetArch = TargetArch::aarch64;
+
+/// Action taken for a syscall that isn't on the whitelist.
+///
+/// [`SeccompAction::KillProcess`] is the historical behavior: the process
+/// just vanishes, with no `exit_code` and no explanation. In diagnostic mode
+/// we instead use [`SeccompAction::Trap`] (`SECCOMP_RET_TRAP`), which
+/// delivers `SIGSYS` to the child — the wait path can then detect
+/// `WIFSIGNALED`+`SIGSYS` and surface a "blocked syscall" message in
+/// [`SandboxResult::stderr`] instead of a silent kill.
+///
+/// `Trap` gives the always-on diagnostic signal (exit-status inspection);
+/// `Trace` additionally names the exact offending syscall through the
+/// `ptrace` supervisor in [`run_under_ptrace_supervisor`] (x86_64 only).
+/// There is no per-syscall `Errno` soft-fail subset: every syscall off the
+/// whitelist gets the same default action.
+#[cfg(all(target_os = "linux", feature = "seccomp"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeccompDefaultAction {
+    /// `SECCOMP_RET_KILL_PROCESS` — process vanishes, no diagnosis possible.
+    Kill,
+    /// `SECCOMP_RET_TRAP` — delivers `SIGSYS`, diagnosable from the exit status.
+    Trap,
+    /// `SECCOMP_RET_TRACE` — stops the tracee via `PTRACE_EVENT_SECCOMP`
+    /// instead of terminating it, so [`run_under_ptrace_supervisor`] can
+    /// read the blocked syscall number off its registers before killing it
+    /// itself. Requires the child to call `PTRACE_TRACEME` before applying
+    /// the filter (see `execute_with_seccomp_sync`). x86_64 only - the
+    /// supervisor reads `orig_rax`, which has no aarch64 equivalent wired up
+    /// here.
+    Trace,
+}
+
 /// Build the seccomp filter for sandboxed execution.
 ///
 /// Policy:
-/// - Default: KILL (whitelist approach)
+/// - Default: `default_action` for any syscall not on the whitelist
 /// - Allow safe syscalls for basic C programs + threading
 /// - Block dangerous syscalls explicitly
+///
+/// The syscall whitelist itself is mostly arch-independent (`libc::SYS_*`
+/// already resolves to the right number for the compiled target), except
+/// for a handful of legacy syscalls (`dup2`, `pipe`, `access`, `readlink`,
+/// `arch_prctl`) that only exist on x86_64 — aarch64 glibc/musl always go
+/// through their `*at`/`dup3`/`pipe2` replacements instead, so those extra
+/// entries are gated behind `target_arch = "x86_64"`.
 #[cfg(all(target_os = "linux", feature = "seccomp"))]
-fn build_seccomp_filter() -> Result<BpfProgram, String> {
+fn build_seccomp_filter(default_action: SeccompDefaultAction) -> Result<BpfProgram, String> {
 
     // Whitelist of allowed syscalls
     // These are needed for basic C programs with threading support
-    let allowed_syscalls: Vec<i64> = vec![
+    // `mut` is only exercised on x86_64 (see the arch-gated `extend_from_slice`
+    // below); harmless no-op on other architectures.
+    #[allow(unused_mut)]
+    let mut allowed_syscalls: Vec<i64> = vec![
         // Process execution (needed for pre_exec to work)
         // Note: execve is needed because seccomp filter is applied in pre_exec
         // BEFORE the binary starts. The binary is already compiled and in a
@@ -67,20 +144,16 @@ fn build_seccomp_filter() -> Result<BpfProgram, String> {
         libc::SYS_fstat,
         libc::SYS_lseek,
         libc::SYS_dup,
-        libc::SYS_dup2,
         libc::SYS_dup3,
-        libc::SYS_pipe,
         libc::SYS_pipe2,
 
         // File operations (needed by musl/glibc for basic operations)
         libc::SYS_openat,     // Used instead of open on modern Linux
         libc::SYS_newfstatat, // Used for fstat in musl
         libc::SYS_readlinkat, // Used for /proc/self/exe resolution
-        libc::SYS_access,
         libc::SYS_faccessat,
         libc::SYS_faccessat2,
         libc::SYS_statx,
-        libc::SYS_readlink,
         libc::SYS_getcwd,
 
         // Memory management
@@ -124,7 +197,6 @@ fn build_seccomp_filter() -> Result<BpfProgram, String> {
         libc::SYS_exit_group,
 
         // Misc required for glibc/musl
-        libc::SYS_arch_prctl,
         libc::SYS_getrandom,
         libc::SYS_clock_gettime,
         libc::SYS_gettimeofday,
@@ -148,6 +220,17 @@ fn build_seccomp_filter() -> Result<BpfProgram, String> {
         libc::SYS_madvise,
     ];
 
+    // Legacy syscalls that only exist on x86_64; aarch64 glibc/musl always
+    // use the `*at`/`dup3`/`pipe2` equivalents already in the list above.
+    #[cfg(target_arch = "x86_64")]
+    allowed_syscalls.extend_from_slice(&[
+        libc::SYS_dup2,
+        libc::SYS_pipe,
+        libc::SYS_access,
+        libc::SYS_readlink,
+        libc::SYS_arch_prctl,
+    ]);
+
     // Build rules - each allowed syscall gets an empty rule (no conditions = always allow)
     let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
     for syscall in allowed_syscalls {
@@ -171,12 +254,17 @@ fn build_seccomp_filter() -> Result<BpfProgram, String> {
         ],
     );
 
-    // Build the filter with KILL as default action
+    let default = match default_action {
+        SeccompDefaultAction::Kill => SeccompAction::KillProcess,
+        SeccompDefaultAction::Trap => SeccompAction::Trap,
+        SeccompDefaultAction::Trace => SeccompAction::Trace(0),
+    };
+
     let filter = SeccompFilter::new(
         rules,
-        SeccompAction::KillProcess,  // Default: kill if syscall not in whitelist
-        SeccompAction::Allow,        // Match action: allow if rule matches
-        TargetArch::x86_64,
+        default,              // Default: applied if syscall not in whitelist
+        SeccompAction::Allow, // Match action: allow if rule matches
+        TARGET_ARCH,
     ).map_err(|e| format!("Failed to create seccomp filter: {:?}", e))?;
 
     // Compile to BPF program
@@ -186,6 +274,265 @@ fn build_seccomp_filter() -> Result<BpfProgram, String> {
     Ok(bpf)
 }
 
+/// `ptrace` options/events not (yet) exposed by the `libc` crate version
+/// this repo pins - see `man ptrace(2)`.
+#[cfg(all(target_os = "linux", feature = "seccomp", target_arch = "x86_64"))]
+const PTRACE_O_TRACESECCOMP: libc::c_int = 0x0080;
+#[cfg(all(target_os = "linux", feature = "seccomp", target_arch = "x86_64"))]
+const PTRACE_EVENT_SECCOMP: libc::c_int = 7;
+/// Also trace threads spawned via the `clone(CLONE_THREAD)` path the filter
+/// allows for `pthread_create` - without this, a secondary thread hitting a
+/// denied syscall is untraced, and `SECCOMP_RET_TRACE` quietly degrades to
+/// `-ENOSYS` instead of stopping it for the supervisor to see.
+#[cfg(all(target_os = "linux", feature = "seccomp", target_arch = "x86_64"))]
+const PTRACE_O_TRACECLONE: libc::c_int = 0x0008;
+
+/// x86_64 syscall numbers worth naming for a learner - not an exhaustive
+/// syscall table, just the ones most likely to be why a program got
+/// blocked (networking, spawning processes, tracing, module/namespace
+/// manipulation). Anything else reports as `"syscall #<nr>"`.
+#[cfg(all(target_os = "linux", feature = "seccomp", target_arch = "x86_64"))]
+fn describe_blocked_syscall(nr: i64) -> String {
+    let (name, reason): (&str, &str) = match nr {
+        41 => ("socket", "network access is not permitted"),
+        42 => ("connect", "network access is not permitted"),
+        43 => ("accept", "network access is not permitted"),
+        49 => ("bind", "network access is not permitted"),
+        50 => ("listen", "network access is not permitted"),
+        57 => ("fork", "spawning processes is not permitted"),
+        58 => ("vfork", "spawning processes is not permitted"),
+        101 => ("ptrace", "debugging/tracing other processes is not permitted"),
+        165 => ("mount", "mounting filesystems is not permitted"),
+        166 => ("umount2", "mounting filesystems is not permitted"),
+        169 => ("reboot", "rebooting the system is not permitted"),
+        175 => ("init_module", "loading kernel modules is not permitted"),
+        176 => ("delete_module", "loading kernel modules is not permitted"),
+        161 => ("chroot", "changing the filesystem root is not permitted"),
+        272 => ("unshare", "creating new namespaces is not permitted"),
+        308 => ("setns", "joining other namespaces is not permitted"),
+        _ => return format!("syscall #{nr}"),
+    };
+    format!("{name} ({reason})")
+}
+
+/// Runs an already-spawned tracee under a minimal ptrace supervisor loop.
+/// The tracee must have called `PTRACE_TRACEME` in `pre_exec` before its
+/// seccomp filter (built with [`SeccompDefaultAction::Trace`]) was applied,
+/// so a syscall outside the whitelist stops it via `PTRACE_EVENT_SECCOMP`
+/// instead of killing it directly - giving this loop a chance to read the
+/// offending syscall number off its registers before killing it itself. Any
+/// other stop (a real signal, e.g. `SIGSEGV` from an actual crash) is just
+/// forwarded so normal crash behavior is unaffected.
+///
+/// `PTRACE_O_TRACECLONE` is set alongside `PTRACE_O_TRACESECCOMP`, and the
+/// wait loop reaps any thread in `pid`'s thread group (`__WALL`, tid -1),
+/// not just `pid` itself - the filter lets student programs spawn threads
+/// via `pthread_create`, and an untraced thread's seccomp stop would
+/// otherwise silently resolve to `-ENOSYS` instead of stopping for us.
+///
+/// Returns the main thread's final `wait4` status, the blocked-syscall
+/// description (if a seccomp violation was the reason the tracee died), and
+/// the `rusage` accumulated by the process (and its reaped children) at
+/// that point.
+#[cfg(all(target_os = "linux", feature = "seccomp", target_arch = "x86_64"))]
+fn run_under_ptrace_supervisor(pid: libc::pid_t) -> (libc::c_int, Option<String>, libc::rusage) {
+    let mut status: libc::c_int = 0;
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+
+    // Initial stop: the execve that PTRACE_TRACEME arms delivers SIGTRAP
+    // before the new image runs a single instruction.
+    if unsafe { libc::wait4(pid, &mut status, 0, &mut rusage) } < 0 {
+        return (status, None, rusage);
+    }
+    unsafe {
+        libc::ptrace(
+            libc::PTRACE_SETOPTIONS,
+            pid,
+            std::ptr::null_mut::<libc::c_void>(),
+            (PTRACE_O_TRACESECCOMP | PTRACE_O_TRACECLONE) as *mut libc::c_void,
+        );
+        // The execve stop above parked the tracee in ptrace-stop; it won't
+        // run another instruction (or hit its first seccomp check) until
+        // it's resumed.
+        libc::ptrace(
+            libc::PTRACE_CONT,
+            pid,
+            std::ptr::null_mut::<libc::c_void>(),
+            std::ptr::null_mut::<libc::c_void>(),
+        );
+    }
+
+    let mut blocked_syscall = None;
+    let mut main_status = status;
+    loop {
+        // -1 with __WALL: any thread of this process (or any other tracee,
+        // but we only ever supervise one process tree at a time) that's
+        // changed state, not just the original tid.
+        let stopped_pid = unsafe { libc::wait4(-1, &mut status, libc::__WALL, &mut rusage) };
+        if stopped_pid < 0 {
+            break;
+        }
+        if stopped_pid == pid {
+            main_status = status;
+        }
+
+        if libc::WIFSTOPPED(status) {
+            let mut resume_signal: libc::c_int = 0;
+            let event = (status >> 16) & 0xff;
+            if libc::WSTOPSIG(status) == libc::SIGTRAP && event == PTRACE_EVENT_SECCOMP {
+                let mut regs: libc::user_regs_struct = unsafe { std::mem::zeroed() };
+                let got_regs = unsafe {
+                    libc::ptrace(
+                        libc::PTRACE_GETREGS,
+                        stopped_pid,
+                        std::ptr::null_mut::<libc::c_void>(),
+                        &mut regs as *mut _ as *mut libc::c_void,
+                    )
+                } == 0;
+                blocked_syscall = Some(if got_regs {
+                    describe_blocked_syscall(regs.orig_rax as i64)
+                } else {
+                    "unknown syscall".to_string()
+                });
+                // SIGKILL delivered to any thread terminates the whole
+                // thread group, so killing the offending thread is enough.
+                unsafe { libc::kill(stopped_pid, libc::SIGKILL) };
+            } else if libc::WSTOPSIG(status) == libc::SIGTRAP && event != 0 {
+                // A non-seccomp ptrace event stop (e.g. PTRACE_EVENT_CLONE
+                // for a newly spawned thread) - nothing to forward, just
+                // let it continue running.
+            } else {
+                // Not a ptrace event - a real signal the tracee would
+                // otherwise have received. Forward it on resume.
+                resume_signal = libc::WSTOPSIG(status);
+            }
+
+            unsafe {
+                libc::ptrace(
+                    libc::PTRACE_CONT,
+                    stopped_pid,
+                    std::ptr::null_mut::<libc::c_void>(),
+                    resume_signal as *mut libc::c_void,
+                );
+            }
+            continue;
+        }
+
+        if stopped_pid == pid {
+            // The main thread exited or was terminated by a signal -
+            // nothing left to supervise.
+            break;
+        }
+    }
+
+    (main_status, blocked_syscall, rusage)
+}
+
+/// Per-execution resource budget applied via `setrlimit` right before the
+/// seccomp filter, so a runaway student program is killed by the kernel
+/// (SIGKILL/SIGXCPU/SIGXFSZ) instead of only by the outer async `timeout`.
+#[derive(Debug, Clone)]
+pub struct ExecutionBudgetProfile {
+    /// `RLIMIT_AS`: max virtual address space, in bytes.
+    pub max_address_space_bytes: u64,
+    /// `RLIMIT_CPU`: max CPU time, in seconds.
+    pub max_cpu_seconds: u64,
+    /// `RLIMIT_NOFILE`: max open file descriptors.
+    pub max_open_fds: u64,
+    /// `RLIMIT_FSIZE`: max bytes a single write can grow a file/pipe to.
+    pub max_output_bytes: u64,
+}
+
+impl Default for ExecutionBudgetProfile {
+    fn default() -> Self {
+        Self {
+            max_address_space_bytes: 256 * 1024 * 1024, // 256MB
+            max_cpu_seconds: 5,
+            max_open_fds: 64,
+            max_output_bytes: 10 * 1024 * 1024, // 10MB
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "seccomp"))]
+impl ExecutionBudgetProfile {
+    /// Apply every limit via `setrlimit`. Must run in the child, after fork
+    /// and before exec (i.e. inside `pre_exec`).
+    fn apply(&self) -> std::io::Result<()> {
+        set_rlimit(libc::RLIMIT_AS, self.max_address_space_bytes)?;
+        set_rlimit(libc::RLIMIT_CPU, self.max_cpu_seconds)?;
+        set_rlimit(libc::RLIMIT_NOFILE, self.max_open_fds)?;
+        set_rlimit(libc::RLIMIT_FSIZE, self.max_output_bytes)?;
+        Ok(())
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "seccomp"))]
+fn set_rlimit(resource: libc::c_int, value: u64) -> std::io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: value,
+        rlim_max: value,
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Filesystem confinement layer complementing seccomp.
+///
+/// Seccomp can't inspect `openat`/`newfstatat`/`readlinkat`'s path argument,
+/// so those syscalls are unconditionally whitelisted and a sandboxed
+/// program can otherwise open any file the container's user can read. This
+/// applies a Landlock ruleset (in `pre_exec`, before the seccomp filter) that
+/// restricts the process to read/execute access under `allowed_dir` — its
+/// own temp directory — and denies everything else.
+#[derive(Debug, Clone)]
+pub struct FilesystemPolicy {
+    pub allowed_dir: PathBuf,
+}
+
+impl FilesystemPolicy {
+    /// Confine the process to read/execute access under `allowed_dir`.
+    pub fn confined_to(allowed_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            allowed_dir: allowed_dir.into(),
+        }
+    }
+
+    #[cfg(all(target_os = "linux", feature = "landlock"))]
+    fn apply(&self) -> std::io::Result<()> {
+        fn landlock_err<E: std::fmt::Debug>(e: E) -> std::io::Error {
+            std::io::Error::new(std::io::ErrorKind::Other, format!("Landlock error: {:?}", e))
+        }
+
+        let abi = ABI::V1;
+        let access = AccessFs::from_read(abi) | AccessFs::Execute;
+
+        let path_fd = PathFd::new(&self.allowed_dir).map_err(landlock_err)?;
+
+        // Best-effort: if the running kernel lacks Landlock support,
+        // `restrict_self()` no-ops (reports partial enforcement) rather than
+        // erroring, so we don't break execution on older kernels.
+        Ruleset::default()
+            .handle_access(AccessFs::from_all(abi))
+            .map_err(landlock_err)?
+            .create()
+            .map_err(landlock_err)?
+            .add_rule(PathBeneath::new(path_fd, access))
+            .map_err(landlock_err)?
+            .restrict_self()
+            .map_err(landlock_err)?;
+
+        Ok(())
+    }
+
+    #[cfg(not(all(target_os = "linux", feature = "landlock")))]
+    fn apply(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 /// Execute a binary with seccomp sandboxing.
 ///
 /// This forks a child process, applies the seccomp filter, then executes the binary.
@@ -206,14 +553,49 @@ pub async fn seccomp_execute(
     binary_path: &str,
     stdin_data: Option<&str>,
     timeout_secs: u64,
+    budget: &ExecutionBudgetProfile,
+    fs_policy: &FilesystemPolicy,
+) -> Result<SandboxResult, String> {
+    seccomp_execute_with_output_mode(binary_path, stdin_data, timeout_secs, budget, fs_policy, false).await
+}
+
+/// Same as [`seccomp_execute`], but with control over whether raw ANSI
+/// escape sequences are passed through untouched (`allow_raw_ansi = true`)
+/// instead of stripped — for levels that intentionally teach terminal
+/// coloring/cursor control.
+#[cfg(all(target_os = "linux", feature = "seccomp"))]
+pub async fn seccomp_execute_with_output_mode(
+    binary_path: &str,
+    stdin_data: Option<&str>,
+    timeout_secs: u64,
+    budget: &ExecutionBudgetProfile,
+    fs_policy: &FilesystemPolicy,
+    allow_raw_ansi: bool,
 ) -> Result<SandboxResult, String> {
     let binary = binary_path.to_string();
     let input = stdin_data.map(|s| s.to_string());
+    let budget = budget.clone();
+    let fs_policy = fs_policy.clone();
+
+    // The ptrace supervisor that names a blocked syscall only knows how to
+    // read x86_64 registers; elsewhere we fall back to the plain SIGSYS
+    // diagnostic `execute_with_seccomp_sync` already reports.
+    #[cfg(target_arch = "x86_64")]
+    let default_action = SeccompDefaultAction::Trace;
+    #[cfg(not(target_arch = "x86_64"))]
+    let default_action = SeccompDefaultAction::Trap;
 
     let result = timeout(
         Duration::from_secs(timeout_secs),
         tokio::task::spawn_blocking(move || {
-            execute_with_seccomp_sync(&binary, input.as_deref())
+            execute_with_seccomp_sync(
+                &binary,
+                input.as_deref(),
+                &budget,
+                &fs_policy,
+                default_action,
+                allow_raw_ansi,
+            )
         }),
     )
     .await;
@@ -228,16 +610,34 @@ pub async fn seccomp_execute(
             exit_code: None,
             timed_out: true,
             execution_time_ms: timeout_secs * 1000,
+            peak_memory_kb: None,
+            cpu_time_ms: None,
+            term_signal: None,
+            violation: None,
+            blocked_syscall: None,
         }),
     }
 }
 
 #[cfg(all(target_os = "linux", feature = "seccomp"))]
-fn execute_with_seccomp_sync(binary_path: &str, stdin_data: Option<&str>) -> Result<SandboxResult, String> {
+fn execute_with_seccomp_sync(
+    binary_path: &str,
+    stdin_data: Option<&str>,
+    budget: &ExecutionBudgetProfile,
+    fs_policy: &FilesystemPolicy,
+    default_action: SeccompDefaultAction,
+    allow_raw_ansi: bool,
+) -> Result<SandboxResult, String> {
     let start = Instant::now();
 
     // Build the seccomp filter before forking
-    let bpf = build_seccomp_filter()?;
+    let bpf = build_seccomp_filter(default_action)?;
+    let budget = budget.clone();
+    let fs_policy = fs_policy.clone();
+    #[cfg(target_arch = "x86_64")]
+    let trace_syscalls = default_action == SeccompDefaultAction::Trace;
+    #[cfg(not(target_arch = "x86_64"))]
+    let trace_syscalls = false;
 
     let mut child = unsafe {
         Command::new(binary_path)
@@ -245,11 +645,29 @@ fn execute_with_seccomp_sync(binary_path: &str, stdin_data: Option<&str>) -> Res
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .pre_exec(move || {
-                // Set NO_NEW_PRIVS - required for seccomp without CAP_SYS_ADMIN
+                // Arm ptrace-event-stops for the seccomp filter below, so
+                // run_under_ptrace_supervisor can read the blocked syscall
+                // before it terminates the tracee. Must happen before the
+                // filter is applied.
+                #[cfg(target_arch = "x86_64")]
+                if trace_syscalls && libc::ptrace(libc::PTRACE_TRACEME, 0, 0, 0) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+
+                // Apply resource budgets before the seccomp filter so the
+                // kernel (not just the async timeout) kills a runaway program.
+                budget.apply()?;
+
+                // Set NO_NEW_PRIVS - required for seccomp without CAP_SYS_ADMIN,
+                // and a prerequisite for Landlock's `restrict_self()` below.
                 if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
                     return Err(std::io::Error::last_os_error());
                 }
 
+                // Confine the filesystem before the seccomp filter locks down
+                // the syscalls Landlock itself needs to set up its ruleset.
+                fs_policy.apply()?;
+
                 // Apply seccomp filter to all threads
                 seccompiler::apply_filter_all_threads(&bpf)
                     .map_err(|e| std::io::Error::new(
@@ -270,29 +688,211 @@ fn execute_with_seccomp_sync(binary_path: &str, stdin_data: Option<&str>) -> Res
         }
     }
 
-    // Wait for completion
-    let output = child.wait_with_output()
-        .map_err(|e| format!("Failed to wait for process: {}", e))?;
+    // Drain stdout/stderr on their own threads (same reasoning as
+    // `Command::wait_with_output`'s internal impl) so a chatty child can't
+    // deadlock on a full pipe while we're blocked below.
+    let mut stdout_pipe = child.stdout.take();
+    let mut stderr_pipe = child.stderr.take();
+    let stdout_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(pipe) = stdout_pipe.as_mut() {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    });
+    let stderr_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(pipe) = stderr_pipe.as_mut() {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    });
+
+    // Wait via wait4/getrusage (instead of `wait_with_output`) so we can
+    // report peak RSS and CPU time alongside the exit status. In trace mode,
+    // the ptrace supervisor runs its own wait4 loop instead, so it can
+    // intercept the PTRACE_EVENT_SECCOMP stop before the tracee dies.
+    let pid = child.id() as libc::pid_t;
+    #[cfg(target_arch = "x86_64")]
+    let (wstatus, blocked_syscall, rusage) = if trace_syscalls {
+        run_under_ptrace_supervisor(pid)
+    } else {
+        let mut wstatus: libc::c_int = 0;
+        let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+        if unsafe { libc::wait4(pid, &mut wstatus, 0, &mut rusage) } < 0 {
+            return Err(format!(
+                "Failed to wait for process: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        (wstatus, None, rusage)
+    };
+    #[cfg(not(target_arch = "x86_64"))]
+    let (wstatus, blocked_syscall, rusage): (libc::c_int, Option<String>, libc::rusage) = {
+        let mut wstatus: libc::c_int = 0;
+        let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+        if unsafe { libc::wait4(pid, &mut wstatus, 0, &mut rusage) } < 0 {
+            return Err(format!(
+                "Failed to wait for process: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        (wstatus, None, rusage)
+    };
+
+    let stdout = stdout_reader.join().unwrap_or_default();
+    let stderr = stderr_reader.join().unwrap_or_default();
+
+    let exit_code = if libc::WIFEXITED(wstatus) {
+        Some(libc::WEXITSTATUS(wstatus))
+    } else {
+        None
+    };
+
+    // The ptrace supervisor kills a blocked tracee with SIGKILL (the only
+    // signal guaranteed to take effect on a ptrace-stopped process), but
+    // that's an implementation detail - report it the same way the
+    // Trap-based path's SIGSYS would, so `classify_violation` still
+    // recognizes it as a seccomp violation.
+    let term_signal = if blocked_syscall.is_some() {
+        Some(libc::SIGSYS)
+    } else if libc::WIFSIGNALED(wstatus) {
+        Some(libc::WTERMSIG(wstatus))
+    } else {
+        None
+    };
+
+    let cpu_time_ms = (rusage.ru_utime.tv_sec as u64 * 1000 + rusage.ru_utime.tv_usec as u64 / 1000)
+        + (rusage.ru_stime.tv_sec as u64 * 1000 + rusage.ru_stime.tv_usec as u64 / 1000);
+
+    let mut stderr = String::from_utf8_lossy(&stderr).to_string();
+
+    if let Some(blocked) = &blocked_syscall {
+        if !stderr.is_empty() {
+            stderr.push('\n');
+        }
+        stderr.push_str(&format!("Blocked syscall: {blocked}"));
+    } else if default_action == SeccompDefaultAction::Trap
+        && libc::WIFSIGNALED(wstatus)
+        && libc::WTERMSIG(wstatus) == libc::SIGSYS
+    {
+        // Diagnostic mode without a ptrace supervisor (non-x86_64): a
+        // denied syscall delivers SIGSYS (SECCOMP_RET_TRAP) instead of
+        // silently killing the process, so surface that much at least.
+        if !stderr.is_empty() {
+            stderr.push('\n');
+        }
+        stderr.push_str(
+            "Program terminated by SIGSYS: attempted a syscall outside the sandbox's allowed list (blocked syscall)",
+        );
+    }
 
     Ok(SandboxResult {
-        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-        exit_code: output.status.code(),
+        stdout: sanitize_output(&String::from_utf8_lossy(&stdout), allow_raw_ansi),
+        stderr: sanitize_output(&stderr, allow_raw_ansi),
+        exit_code,
         timed_out: false,
         execution_time_ms: start.elapsed().as_millis() as u64,
+        peak_memory_kb: Some(rusage.ru_maxrss as u64),
+        cpu_time_ms: Some(cpu_time_ms),
+        violation: classify_violation(term_signal, budget.max_address_space_bytes),
+        term_signal,
+        blocked_syscall,
     })
 }
 
+/// Serialize a compiled seccomp-bpf program into the raw `sock_filter` byte
+/// layout the kernel (and therefore bwrap's `--seccomp <fd>`) expects, so
+/// the bubblewrap backend in `sandbox.rs` can apply the same syscall
+/// whitelist as this, the seccomp-primary backend, instead of relying on
+/// namespace isolation alone.
+#[cfg(all(target_os = "linux", feature = "seccomp"))]
+pub(crate) fn build_seccomp_bpf_bytes(default_action: SeccompDefaultAction) -> Result<Vec<u8>, String> {
+    let program = build_seccomp_filter(default_action)?;
+    let mut bytes = Vec::with_capacity(program.len() * 8);
+    for insn in &program {
+        bytes.extend_from_slice(&insn.code.to_ne_bytes());
+        bytes.push(insn.jt);
+        bytes.push(insn.jf);
+        bytes.extend_from_slice(&insn.k.to_ne_bytes());
+    }
+    Ok(bytes)
+}
+
 /// Fallback for non-Linux systems - seccomp not available
 #[cfg(not(all(target_os = "linux", feature = "seccomp")))]
 pub async fn seccomp_execute(
     _binary_path: &str,
     _stdin_data: Option<&str>,
     _timeout_secs: u64,
+    _budget: &ExecutionBudgetProfile,
+    _fs_policy: &FilesystemPolicy,
 ) -> Result<SandboxResult, String> {
     Err("Seccomp sandbox is only available on Linux".to_string())
 }
 
+/// Strip output a sandboxed program could use to corrupt or spoof the
+/// frontend terminal.
+///
+/// By default only tab, newline, and printable ASCII/UTF-8 survive; CSI
+/// (`ESC [ ...`) and OSC (`ESC ] ... BEL`/`ESC \`) escape sequences are
+/// dropped entirely rather than just having their `ESC` stripped, since a
+/// bare leftover sequence body (e.g. `31mh`) would itself render as noise.
+/// `allow_raw_ansi` passes bytes through untouched, for levels that
+/// intentionally teach terminal coloring/cursor control.
+fn sanitize_output(input: &str, allow_raw_ansi: bool) -> String {
+    if allow_raw_ansi {
+        return input.to_string();
+    }
+
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            match chars.peek() {
+                // CSI: ESC [ ... <final byte in 0x40..=0x7e>
+                Some('[') => {
+                    chars.next();
+                    for next in chars.by_ref() {
+                        if ('\u{40}'..='\u{7e}').contains(&next) {
+                            break;
+                        }
+                    }
+                }
+                // OSC: ESC ] ... terminated by BEL or ESC \
+                Some(']') => {
+                    chars.next();
+                    while let Some(&next) = chars.peek() {
+                        if next == '\u{7}' {
+                            chars.next();
+                            break;
+                        }
+                        if next == '\u{1b}' {
+                            chars.next();
+                            if chars.peek() == Some(&'\\') {
+                                chars.next();
+                            }
+                            break;
+                        }
+                        chars.next();
+                    }
+                }
+                // Any other escape sequence: drop just the ESC byte.
+                _ => {}
+            }
+            continue;
+        }
+
+        if c == '\t' || c == '\n' || (!c.is_control()) {
+            out.push(c);
+        }
+        // Other control characters (e.g. bare \r, \x07, \x08) are dropped.
+    }
+
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -307,7 +907,65 @@ mod tests {
     #[cfg(all(target_os = "linux", feature = "seccomp"))]
     #[test]
     fn test_build_filter() {
-        let filter = build_seccomp_filter();
+        let filter = build_seccomp_filter(SeccompDefaultAction::Kill);
         assert!(filter.is_ok(), "Failed to build filter: {:?}", filter.err());
+
+        let diagnostic_filter = build_seccomp_filter(SeccompDefaultAction::Trap);
+        assert!(diagnostic_filter.is_ok(), "Failed to build diagnostic filter: {:?}", diagnostic_filter.err());
+    }
+
+    // Real end-to-end runs of `execute_with_seccomp_sync` under
+    // `SeccompDefaultAction::Trace` - the ptrace supervisor path only the
+    // production default-action wiring in `seccomp_execute_with_output_mode`
+    // exercises. `test_build_filter` above only builds a filter; it never
+    // runs the supervisor loop, which is exactly the gap that let a missing
+    // `PTRACE_CONT` hang every sandboxed run.
+    #[cfg(all(target_os = "linux", feature = "seccomp", target_arch = "x86_64"))]
+    #[test]
+    fn test_trace_supervisor_allows_normal_execution() {
+        let budget = ExecutionBudgetProfile::default();
+        let fs_policy = FilesystemPolicy::confined_to("/");
+
+        let result = execute_with_seccomp_sync(
+            "/bin/true",
+            None,
+            &budget,
+            &fs_policy,
+            SeccompDefaultAction::Trace,
+            false,
+        );
+
+        let result = result.expect("supervisor run should not error");
+        assert!(!result.timed_out, "supervisor should not hang a well-behaved program");
+        assert_eq!(result.exit_code, Some(0));
+        assert!(result.blocked_syscall.is_none());
+    }
+
+    #[cfg(all(target_os = "linux", feature = "seccomp", target_arch = "x86_64"))]
+    #[test]
+    fn test_trace_supervisor_reports_blocked_syscall() {
+        let budget = ExecutionBudgetProfile::default();
+        let fs_policy = FilesystemPolicy::confined_to("/");
+
+        // `/bin/sh` with no args reads commands from stdin; running an
+        // external command forks a child via a plain `clone()` (no
+        // CLONE_THREAD), which the filter denies - a deterministic way to
+        // trip the supervisor without depending on a networked tool.
+        let result = execute_with_seccomp_sync(
+            "/bin/sh",
+            Some("/bin/date\n"),
+            &budget,
+            &fs_policy,
+            SeccompDefaultAction::Trace,
+            false,
+        );
+
+        let result = result.expect("supervisor run should not error");
+        assert!(!result.timed_out);
+        assert!(
+            result.blocked_syscall.is_some(),
+            "expected the denied fork/clone to be caught and named, got: {:?}",
+            result
+        );
     }
 }
@@ -0,0 +1,281 @@
+//! Pheromone-based ("stigmergic") roaming AI for [`ObjectType::Npc`] map
+//! objects. Rather than follow a scripted path, each NPC deposits a
+//! decaying trail on the tile it's standing on and picks its next step by
+//! blending the trail's gradient with a little randomness - the same
+//! emergent-movement trick real ants use to find food without anyone
+//! drawing them a map.
+
+use rand::Rng;
+
+use super::constants::TILE_SIZE;
+use super::map::{ObjectType, TileMap};
+use super::state::Position;
+use super::world::World;
+
+/// How much pheromone a single step deposits on the NPC's current tile.
+const DEPOSIT_AMOUNT: f32 = 1.0;
+/// Fraction of every pheromone cell that survives one [`PheromoneField::evaporate`] call.
+const EVAPORATION_RATE: f32 = 0.9;
+/// Upper bound of the random term blended into each neighbor's weight,
+/// so NPCs don't move in perfectly deterministic lockstep with the trail.
+const RANDOM_WEIGHT: f32 = 0.2;
+/// How many of an NPC's most recent tiles count against it when picking
+/// a next step, discouraging it from immediately doubling back.
+const HISTORY_LIMIT: usize = 6;
+/// Multiplier applied to a neighbor's weight if it's in the NPC's recent history.
+const HISTORY_PENALTY: f32 = 0.25;
+
+/// Two `f32` grids - "explore" and "return" - one cell per tile, stored
+/// flat and row-major like [`TileMap::tiles`]. NPCs lay down "explore"
+/// trail while roaming and "return" trail on the tile where they find a
+/// goal, so the gradient pulls other NPCs toward goals already found.
+/// Also tracks each NPC's short movement history, indexed the same way
+/// as [`TileMap::objects`].
+#[derive(Debug, Clone, Default)]
+pub struct PheromoneField {
+    width: usize,
+    height: usize,
+    explore: Vec<f32>,
+    return_trail: Vec<f32>,
+    histories: Vec<Vec<(usize, usize)>>,
+}
+
+impl PheromoneField {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            explore: vec![0.0; width * height],
+            return_trail: vec![0.0; width * height],
+            histories: Vec::new(),
+        }
+    }
+
+    fn xy_idx(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    pub fn explore_at(&self, x: usize, y: usize) -> f32 {
+        self.explore
+            .get(self.xy_idx(x, y))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn return_at(&self, x: usize, y: usize) -> f32 {
+        self.return_trail
+            .get(self.xy_idx(x, y))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    fn deposit_explore(&mut self, x: usize, y: usize) {
+        let index = self.xy_idx(x, y);
+        if let Some(cell) = self.explore.get_mut(index) {
+            *cell += DEPOSIT_AMOUNT;
+        }
+    }
+
+    fn deposit_return(&mut self, x: usize, y: usize) {
+        let index = self.xy_idx(x, y);
+        if let Some(cell) = self.return_trail.get_mut(index) {
+            *cell += DEPOSIT_AMOUNT;
+        }
+    }
+
+    /// Fades every pheromone cell by [`EVAPORATION_RATE`], so trails
+    /// nobody has refreshed recently stop influencing movement.
+    pub fn evaporate(&mut self) {
+        for cell in self.explore.iter_mut().chain(self.return_trail.iter_mut()) {
+            *cell *= EVAPORATION_RATE;
+        }
+    }
+
+    fn history(&mut self, npc_index: usize) -> &mut Vec<(usize, usize)> {
+        if npc_index >= self.histories.len() {
+            self.histories.resize(npc_index + 1, Vec::new());
+        }
+        &mut self.histories[npc_index]
+    }
+}
+
+fn tile_center(x: usize, y: usize) -> Position {
+    Position::new(
+        x as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+        y as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+    )
+}
+
+/// A tile counts as "found" for an NPC's purposes if a collectible sits
+/// on it. There's no player-position tracking wired into [`World`] for
+/// NPCs to chase yet - `world` is threaded through mainly so movement can
+/// be double-checked against it (it's what the frontend actually renders
+/// collision against), and is the natural extension point once a
+/// last-seen-player-position gets tracked there.
+fn is_goal_tile(map: &TileMap, x: usize, y: usize) -> bool {
+    map.get_object_at(tile_center(x, y))
+        .is_some_and(|obj| obj.object_type == ObjectType::Collectible)
+}
+
+fn walkable_neighbors(map: &TileMap, world: &World, x: usize, y: usize) -> Vec<(usize, usize)> {
+    let candidates = [
+        (x.checked_sub(1), Some(y)),
+        (x.checked_add(1), Some(y)),
+        (Some(x), y.checked_sub(1)),
+        (Some(x), y.checked_add(1)),
+    ];
+
+    candidates
+        .into_iter()
+        .filter_map(|(cx, cy)| {
+            let (cx, cy) = (cx?, cy?);
+            if cx >= map.width as usize || cy >= map.height as usize {
+                return None;
+            }
+            let walkable = world.is_walkable(cx, cy) && map.is_position_walkable(tile_center(cx, cy));
+            walkable.then_some((cx, cy))
+        })
+        .collect()
+}
+
+/// Advances every [`ObjectType::Npc`] on `map` by one pheromone-guided
+/// step: evaporate the whole field once, then for each NPC deposit a trail
+/// on its current tile (switching to the "return" trail and clearing its
+/// short history once it reaches a goal tile) and move it to a walkable
+/// neighbor chosen with probability weighted by the pheromone gradient
+/// plus a small random term. Neighbors already in the NPC's recent history are
+/// penalized so it doesn't immediately backtrack.
+pub fn step_npcs(map: &mut TileMap, world: &World, field: &mut PheromoneField) {
+    field.evaporate();
+
+    let npc_indices: Vec<usize> = map
+        .objects
+        .iter()
+        .enumerate()
+        .filter(|(_, obj)| obj.object_type == ObjectType::Npc)
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut rng = rand::thread_rng();
+    let mut moves = Vec::with_capacity(npc_indices.len());
+
+    for index in npc_indices {
+        let (tx, ty) = map.objects[index].position.tile_coords(TILE_SIZE);
+        if tx < 0 || ty < 0 {
+            continue;
+        }
+        let (x, y) = (tx as usize, ty as usize);
+
+        if is_goal_tile(map, x, y) {
+            field.deposit_return(x, y);
+            field.history(index).clear();
+        } else {
+            field.deposit_explore(x, y);
+        }
+
+        let neighbors = walkable_neighbors(map, world, x, y);
+        if neighbors.is_empty() {
+            continue;
+        }
+
+        let history = field.history(index).clone();
+        let weights: Vec<f32> = neighbors
+            .iter()
+            .map(|&(nx, ny)| {
+                let gradient = field.explore_at(nx, ny) + field.return_at(nx, ny);
+                let penalty = if history.contains(&(nx, ny)) {
+                    HISTORY_PENALTY
+                } else {
+                    1.0
+                };
+                (gradient + rng.gen_range(0.0..RANDOM_WEIGHT)) * penalty + 0.01
+            })
+            .collect();
+
+        let total: f32 = weights.iter().sum();
+        let mut pick = rng.gen_range(0.0..total);
+        let mut chosen = neighbors[0];
+        for (&candidate, &weight) in neighbors.iter().zip(weights.iter()) {
+            if pick < weight {
+                chosen = candidate;
+                break;
+            }
+            pick -= weight;
+        }
+
+        let history = field.history(index);
+        history.push((x, y));
+        if history.len() > HISTORY_LIMIT {
+            history.remove(0);
+        }
+
+        moves.push((index, chosen));
+    }
+
+    for (index, (x, y)) in moves {
+        map.objects[index].position = tile_center(x, y);
+    }
+    map.rebuild_spatial_index();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::map::MapObject;
+
+    #[test]
+    fn test_npc_deposits_explore_pheromone_on_its_tile() {
+        let mut map = TileMap::new(10, 10);
+        map.add_object(MapObject::new(ObjectType::Npc, tile_center(5, 5)));
+        let world = World::new(10, 10);
+        let mut field = PheromoneField::new(10, 10);
+
+        step_npcs(&mut map, &world, &mut field);
+
+        assert!(field.explore_at(5, 5) > 0.0);
+    }
+
+    #[test]
+    fn test_npc_moves_to_a_walkable_neighbor() {
+        let mut map = TileMap::new(10, 10);
+        map.add_object(MapObject::new(ObjectType::Npc, tile_center(5, 5)));
+        let world = World::new(10, 10);
+        let mut field = PheromoneField::new(10, 10);
+
+        step_npcs(&mut map, &world, &mut field);
+
+        let (x, y) = map.objects[0].position.tile_coords(TILE_SIZE);
+        let moved = (x - 5).unsigned_abs() + (y - 5).unsigned_abs();
+        assert_eq!(moved, 1);
+    }
+
+    #[test]
+    fn test_reaching_goal_clears_history_and_deposits_return_trail() {
+        let mut map = TileMap::new(10, 10);
+        map.add_object(MapObject::new(
+            ObjectType::Collectible,
+            tile_center(5, 5),
+        ));
+        map.add_object(MapObject::new(ObjectType::Npc, tile_center(5, 5)));
+        let world = World::new(10, 10);
+        let mut field = PheromoneField::new(10, 10);
+
+        step_npcs(&mut map, &world, &mut field);
+
+        assert!(field.return_at(5, 5) > 0.0);
+        // Cleared on reaching the goal, then rebuilt from the goal tile it
+        // just stepped off.
+        assert_eq!(field.history(1).as_slice(), &[(5, 5)]);
+    }
+
+    #[test]
+    fn test_evaporate_fades_pheromone_over_time() {
+        let mut field = PheromoneField::new(10, 10);
+        field.deposit_explore(3, 3);
+        let before = field.explore_at(3, 3);
+
+        field.evaporate();
+
+        assert!(field.explore_at(3, 3) < before);
+    }
+}
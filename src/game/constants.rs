@@ -6,3 +6,6 @@ pub const VIEWPORT_HEIGHT: usize = 15;
 pub const PLAYER_SPEED: f32 = 200.0; // pixels per second
 pub const TICK_RATE: u64 = 20; // ticks per second
 pub const XP_PER_LEVEL: u32 = 100;
+/// Radius, in tiles, of the player's field of view - see
+/// `game::physics::compute_fov`.
+pub const FOV_RANGE: i32 = 8;
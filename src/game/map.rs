@@ -1,48 +1,159 @@
 use serde::{Deserialize, Serialize};
 
+use super::constants::TILE_SIZE;
+use super::physics::bresenham_line;
+use super::spatial::SpatialIndex;
 use super::state::Position;
-use super::world::{Tile, TileType};
+use super::world::{Tile, TileType, TileVisibility};
 
 /// Represents a complete tile map for a level
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TileMap {
     pub width: u32,
     pub height: u32,
-    pub tiles: Vec<Vec<Tile>>,
+    /// Row-major, addressed via [`Self::xy_idx`] rather than nested
+    /// `Vec<Vec<Tile>>` - avoids an extra indirection per tile lookup on
+    /// large generated maps.
+    pub tiles: Vec<Tile>,
     pub objects: Vec<MapObject>,
+    /// See [`super::world::World::revealed_tiles`].
+    #[serde(default)]
+    pub revealed_tiles: Vec<bool>,
+    /// See [`super::world::World::visible_tiles`].
+    #[serde(default)]
+    pub visible_tiles: Vec<bool>,
+    /// Blocked-tile bitmap and per-tile object index - see
+    /// [`super::spatial`]. Rebuilt whenever `tiles`/`objects` change, so
+    /// it's never serialized; reconstruct it with
+    /// [`Self::rebuild_spatial_index`] after deserializing a `TileMap`.
+    #[serde(skip)]
+    spatial: SpatialIndex,
 }
 
 impl TileMap {
     pub fn new(width: u32, height: u32) -> Self {
-        let tiles = vec![vec![Tile::floor(); width as usize]; height as usize];
-        Self {
+        let fov_len = (width * height) as usize;
+        let mut map = Self {
             width,
             height,
-            tiles,
+            tiles: vec![Tile::floor(); fov_len],
             objects: Vec::new(),
+            revealed_tiles: vec![false; fov_len],
+            visible_tiles: vec![false; fov_len],
+            spatial: SpatialIndex::default(),
+        };
+        map.rebuild_spatial_index();
+        map
+    }
+
+    /// Row-major index of tile `(x, y)` into `tiles`/`revealed_tiles`/
+    /// `visible_tiles`.
+    pub fn xy_idx(&self, x: usize, y: usize) -> usize {
+        y * self.width as usize + x
+    }
+
+    pub fn get_tile(&self, x: usize, y: usize) -> Option<&Tile> {
+        self.tiles.get(self.xy_idx(x, y))
+    }
+
+    pub fn get_tile_mut(&mut self, x: usize, y: usize) -> Option<&mut Tile> {
+        let index = self.xy_idx(x, y);
+        self.tiles.get_mut(index)
+    }
+
+    /// Rebuilds the blocked-tile bitmap and per-tile object index from
+    /// the current `tiles`/`objects` - see [`super::spatial`]. Called
+    /// automatically by [`Self::new`], [`Self::add_object`], and
+    /// [`Self::remove_object_at`]; call it directly after mutating
+    /// `tiles` in place (e.g. while generating a map) or after
+    /// deserializing a `TileMap`.
+    pub fn rebuild_spatial_index(&mut self) {
+        self.spatial = SpatialIndex::build(self);
+    }
+
+    /// Recomputes `visible_tiles`/`revealed_tiles` from `origin`, out to
+    /// `range` tiles - see [`super::physics::compute_fov`], which this
+    /// mirrors for the generation-time `TileMap` rather than the
+    /// runtime `World`.
+    pub fn compute_fov(&mut self, origin: (u32, u32), range: i32) {
+        for v in self.visible_tiles.iter_mut() {
+            *v = false;
         }
+
+        let (ox, oy) = (origin.0 as i32, origin.1 as i32);
+        if ox < 0 || oy < 0 || ox as u32 >= self.width || oy as u32 >= self.height {
+            return;
+        }
+
+        for dy in -range..=range {
+            for dx in -range..=range {
+                if dx * dx + dy * dy > range * range {
+                    continue;
+                }
+                let (tx, ty) = (ox + dx, oy + dy);
+                if tx < 0 || ty < 0 || tx as u32 >= self.width || ty as u32 >= self.height {
+                    continue;
+                }
+
+                for (x, y) in bresenham_line(ox, oy, tx, ty) {
+                    if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+                        break;
+                    }
+                    let (x, y) = (x as usize, y as usize);
+                    let index = self.xy_idx(x, y);
+                    self.visible_tiles[index] = true;
+                    self.revealed_tiles[index] = true;
+                    if self.tiles[index].is_opaque() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn visibility_at(&self, x: usize, y: usize) -> TileVisibility {
+        let index = self.xy_idx(x, y);
+        TileVisibility::from_flags(
+            self.revealed_tiles.get(index).copied().unwrap_or(false),
+            self.visible_tiles.get(index).copied().unwrap_or(false),
+        )
+    }
+
+    /// Whether `position` falls on a walkable, unoccupied tile - an O(1)
+    /// lookup against the blocked bitmap rather than a tile scan.
+    pub fn is_position_walkable(&self, position: Position) -> bool {
+        let (tx, ty) = position.tile_coords(TILE_SIZE);
+        if tx < 0 || ty < 0 || tx as u32 >= self.width || ty as u32 >= self.height {
+            return false;
+        }
+        !self.spatial.is_blocked(tx as usize, ty as usize)
     }
 
     pub fn add_object(&mut self, object: MapObject) {
         self.objects.push(object);
+        self.rebuild_spatial_index();
     }
 
     pub fn get_object_at(&self, position: Position) -> Option<&MapObject> {
-        self.objects
-            .iter()
-            .find(|obj| obj.position.x == position.x && obj.position.y == position.y)
+        let (tx, ty) = position.tile_coords(TILE_SIZE);
+        if tx < 0 || ty < 0 {
+            return None;
+        }
+        let index = self.xy_idx(tx as usize, ty as usize);
+        let object_index = *self.spatial.tile_contents.get(index)?.first()?;
+        self.objects.get(object_index)
     }
 
     pub fn remove_object_at(&mut self, position: Position) -> Option<MapObject> {
-        if let Some(index) = self
-            .objects
-            .iter()
-            .position(|obj| obj.position.x == position.x && obj.position.y == position.y)
-        {
-            Some(self.objects.remove(index))
-        } else {
-            None
+        let (tx, ty) = position.tile_coords(TILE_SIZE);
+        if tx < 0 || ty < 0 {
+            return None;
         }
+        let index = self.xy_idx(tx as usize, ty as usize);
+        let object_index = *self.spatial.tile_contents.get(index)?.first()?;
+        let removed = self.objects.remove(object_index);
+        self.rebuild_spatial_index();
+        Some(removed)
     }
 }
 
@@ -103,20 +214,30 @@ pub struct TileMapRender {
     pub width: u32,
     pub height: u32,
     pub tiles: Vec<Vec<TileType>>,
+    pub visibility: Vec<Vec<TileVisibility>>,
 }
 
 impl From<&TileMap> for TileMapRender {
     fn from(map: &TileMap) -> Self {
         let tiles = map
             .tiles
-            .iter()
+            .chunks(map.width as usize)
             .map(|row| row.iter().map(|tile| tile.tile_type).collect())
             .collect();
 
+        let visibility = (0..map.height as usize)
+            .map(|y| {
+                (0..map.width as usize)
+                    .map(|x| map.visibility_at(x, y))
+                    .collect()
+            })
+            .collect();
+
         Self {
             width: map.width,
             height: map.height,
             tiles,
+            visibility,
         }
     }
 }
@@ -130,8 +251,7 @@ mod tests {
         let map = TileMap::new(10, 10);
         assert_eq!(map.width, 10);
         assert_eq!(map.height, 10);
-        assert_eq!(map.tiles.len(), 10);
-        assert_eq!(map.tiles[0].len(), 10);
+        assert_eq!(map.tiles.len(), 100);
     }
 
     #[test]
@@ -161,4 +281,51 @@ mod tests {
         assert_eq!(removed.unwrap().object_type, ObjectType::Door);
         assert!(map.get_object_at(pos).is_none());
     }
+
+    #[test]
+    fn test_door_blocks_position_but_terminal_does_not() {
+        let mut map = TileMap::new(10, 10);
+        let pos = Position::new(5.0 * TILE_SIZE, 5.0 * TILE_SIZE);
+        assert!(map.is_position_walkable(pos));
+
+        map.add_object(MapObject::new(ObjectType::Door, pos));
+        assert!(!map.is_position_walkable(pos));
+
+        map.remove_object_at(pos);
+        assert!(map.is_position_walkable(pos));
+
+        map.add_object(MapObject::new(ObjectType::Terminal, pos));
+        assert!(map.is_position_walkable(pos));
+    }
+
+    #[test]
+    fn test_wall_tile_blocks_position() {
+        let mut map = TileMap::new(10, 10);
+        *map.get_tile_mut(5, 5).unwrap() = Tile::wall();
+        map.rebuild_spatial_index();
+
+        assert!(!map.is_position_walkable(Position::new(5.0 * TILE_SIZE, 5.0 * TILE_SIZE)));
+    }
+
+    #[test]
+    fn test_compute_fov_stops_at_wall() {
+        let mut map = TileMap::new(10, 10);
+        *map.get_tile_mut(7, 5).unwrap() = Tile::wall();
+
+        map.compute_fov((5, 5), 8);
+
+        assert_eq!(map.visibility_at(5, 5), TileVisibility::Visible);
+        assert_eq!(map.visibility_at(7, 5), TileVisibility::Visible); // the wall itself is seen
+        assert_eq!(map.visibility_at(8, 5), TileVisibility::Unseen); // nothing beyond it is
+    }
+
+    #[test]
+    fn test_visibility_falls_back_to_seen_once_out_of_view() {
+        let mut map = TileMap::new(10, 10);
+        map.compute_fov((5, 5), 8);
+        assert_eq!(map.visibility_at(5, 5), TileVisibility::Visible);
+
+        map.compute_fov((0, 0), 1);
+        assert_eq!(map.visibility_at(5, 5), TileVisibility::Seen);
+    }
 }
@@ -0,0 +1,302 @@
+//! Procedural BSP dungeon generation for [`TileMap`], for level designers
+//! who want a reproducible generated layout instead of hand-placing every
+//! tile. See `levels::map_builders` for the composable builder-chain
+//! generator the runtime `World` uses instead - this one targets the
+//! serializable `TileMap`/`MapObject` types directly.
+//!
+//! [`generate`] recursively splits the canvas with binary space
+//! partitioning, carves a room into each leaf partition, then connects
+//! sibling partitions' rooms with an L-shaped corridor as the recursion
+//! unwinds.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::map::{MapObject, ObjectType, TileMap};
+use super::state::Position;
+use super::world::Tile;
+
+/// Smallest partition BSP splitting will produce; below this a partition
+/// becomes a leaf and gets a room carved into it instead.
+const MIN_LEAF_SIZE: u32 = 8;
+
+/// An axis-aligned rectangular region of the map, in tile coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn center(&self) -> (u32, u32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+}
+
+/// Converts a tile coordinate to the pixel-space `Position` the rest of the
+/// game works in, matching `levels::map_builders::PlaceSpawn`'s convention.
+fn tile_center(x: u32, y: u32) -> Position {
+    Position::new(x as f32 * 32.0 + 16.0, y as f32 * 32.0 + 16.0)
+}
+
+/// Builds a `TileMap` via binary space partitioning. Keeps `rooms` and
+/// `corridors` around after [`MapBuilder::build`] so callers (and tests)
+/// can inspect the layout the map was derived from.
+pub struct MapBuilder {
+    width: u32,
+    height: u32,
+    rng: StdRng,
+    pub rooms: Vec<Rect>,
+    pub corridors: Vec<Vec<(u32, u32)>>,
+    /// `(room index, corridor length in tiles)` adjacency, parallel to
+    /// `rooms`, used to find the exit by corridor distance.
+    adjacency: Vec<Vec<(usize, u32)>>,
+    pub starting_point: Position,
+    pub exit_point: Position,
+}
+
+impl MapBuilder {
+    /// Runs the BSP split/carve/connect passes against a freshly seeded
+    /// RNG and returns the finished builder (map, rooms, corridors, and
+    /// the derived start/exit points all populated).
+    pub fn build(width: u32, height: u32, seed: u64) -> (Self, TileMap) {
+        let mut builder = Self {
+            width,
+            height,
+            rng: StdRng::seed_from_u64(seed),
+            rooms: Vec::new(),
+            corridors: Vec::new(),
+            adjacency: Vec::new(),
+            starting_point: Position::new(0.0, 0.0),
+            exit_point: Position::new(0.0, 0.0),
+        };
+
+        let mut map = TileMap::new(width, height);
+        for tile in map.tiles.iter_mut() {
+            *tile = Tile::wall();
+        }
+
+        let canvas = Rect { x: 0, y: 0, width, height };
+        builder.split(canvas, &mut map);
+
+        if let Some(start_room) = builder.rooms.first().copied() {
+            let (sx, sy) = start_room.center();
+            builder.starting_point = tile_center(sx, sy);
+
+            let exit_index = builder.farthest_room();
+            let (ex, ey) = builder.rooms[exit_index].center();
+            builder.exit_point = tile_center(ex, ey);
+
+            map.add_object(MapObject::new(ObjectType::Terminal, builder.exit_point));
+        }
+
+        map.rebuild_spatial_index();
+
+        (builder, map)
+    }
+
+    /// Recursively splits `area`, carving a room once a partition is too
+    /// small to split further, and connecting the two halves' rooms with a
+    /// corridor as each split unwinds. Returns the index into `self.rooms`
+    /// of a representative room for `area`, for the parent split to
+    /// connect to (`None` if `area` was too small to hold any room).
+    fn split(&mut self, area: Rect, map: &mut TileMap) -> Option<usize> {
+        let can_split_h = area.height >= MIN_LEAF_SIZE * 2;
+        let can_split_v = area.width >= MIN_LEAF_SIZE * 2;
+
+        if !can_split_h && !can_split_v {
+            return self.carve_room(area, map);
+        }
+
+        let split_horizontally = match (can_split_h, can_split_v) {
+            (true, true) => self.rng.gen_bool(0.5),
+            (true, false) => true,
+            (false, true) => false,
+            (false, false) => unreachable!(),
+        };
+
+        let (first, second) = if split_horizontally {
+            let split_at = self.rng.gen_range(MIN_LEAF_SIZE..=(area.height - MIN_LEAF_SIZE));
+            (
+                Rect { x: area.x, y: area.y, width: area.width, height: split_at },
+                Rect {
+                    x: area.x,
+                    y: area.y + split_at,
+                    width: area.width,
+                    height: area.height - split_at,
+                },
+            )
+        } else {
+            let split_at = self.rng.gen_range(MIN_LEAF_SIZE..=(area.width - MIN_LEAF_SIZE));
+            (
+                Rect { x: area.x, y: area.y, width: split_at, height: area.height },
+                Rect {
+                    x: area.x + split_at,
+                    y: area.y,
+                    width: area.width - split_at,
+                    height: area.height,
+                },
+            )
+        };
+
+        let room_a = self.split(first, map);
+        let room_b = self.split(second, map);
+
+        match (room_a, room_b) {
+            (Some(a), Some(b)) => {
+                self.carve_corridor(a, b, map);
+                Some(a)
+            }
+            (Some(a), None) => Some(a),
+            (None, Some(b)) => Some(b),
+            (None, None) => None,
+        }
+    }
+
+    /// Carves a room sized to fit `area` with a one-tile margin on every
+    /// side (so sibling rooms never share a wall) and records it. Returns
+    /// `None` if `area` is too small to hold even the smallest room.
+    fn carve_room(&mut self, area: Rect, map: &mut TileMap) -> Option<usize> {
+        let margin = 1;
+        if area.width <= margin * 2 + 1 || area.height <= margin * 2 + 1 {
+            return None;
+        }
+
+        let max_w = area.width - margin * 2;
+        let max_h = area.height - margin * 2;
+        let min_w = max_w.min(3);
+        let min_h = max_h.min(3);
+
+        let w = if max_w > min_w { self.rng.gen_range(min_w..=max_w) } else { max_w };
+        let h = if max_h > min_h { self.rng.gen_range(min_h..=max_h) } else { max_h };
+
+        let slack_x = max_w - w;
+        let slack_y = max_h - h;
+        let x = area.x + margin + if slack_x > 0 { self.rng.gen_range(0..=slack_x) } else { 0 };
+        let y = area.y + margin + if slack_y > 0 { self.rng.gen_range(0..=slack_y) } else { 0 };
+
+        let room = Rect { x, y, width: w, height: h };
+        for ry in room.y..room.y + room.height {
+            for rx in room.x..room.x + room.width {
+                *map.get_tile_mut(rx as usize, ry as usize).unwrap() = Tile::floor();
+            }
+        }
+
+        self.rooms.push(room);
+        self.adjacency.push(Vec::new());
+        Some(self.rooms.len() - 1)
+    }
+
+    /// Carves an L-shaped corridor (horizontal leg, then vertical leg)
+    /// between the centers of rooms `a` and `b`, recording the carved path
+    /// and the edge between them.
+    fn carve_corridor(&mut self, a: usize, b: usize, map: &mut TileMap) {
+        let (x0, y0) = self.rooms[a].center();
+        let (x1, y1) = self.rooms[b].center();
+        let mut path = Vec::new();
+
+        let (min_x, max_x) = (x0.min(x1), x0.max(x1));
+        for x in min_x..=max_x {
+            *map.get_tile_mut(x as usize, y0 as usize).unwrap() = Tile::floor();
+            path.push((x, y0));
+        }
+
+        let (min_y, max_y) = (y0.min(y1), y0.max(y1));
+        for y in min_y..=max_y {
+            *map.get_tile_mut(x1 as usize, y as usize).unwrap() = Tile::floor();
+            path.push((x1, y));
+        }
+
+        let length = path.len() as u32;
+        self.adjacency[a].push((b, length));
+        self.adjacency[b].push((a, length));
+        self.corridors.push(path);
+    }
+
+    /// Finds the room the farthest corridor-distance from the first room
+    /// (the `rooms`/corridor graph is a tree, so a single traversal from
+    /// the start suffices).
+    fn farthest_room(&self) -> usize {
+        let mut visited = vec![false; self.rooms.len()];
+        let mut stack = vec![(0usize, 0u32)];
+        visited[0] = true;
+
+        let mut farthest = 0;
+        let mut farthest_dist = 0;
+
+        while let Some((room, dist)) = stack.pop() {
+            if dist > farthest_dist {
+                farthest_dist = dist;
+                farthest = room;
+            }
+            for &(neighbor, weight) in &self.adjacency[room] {
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    stack.push((neighbor, dist + weight));
+                }
+            }
+        }
+
+        farthest
+    }
+}
+
+/// Generates a `TileMap` of `width` x `height` tiles via binary space
+/// partitioning, reproducibly from `seed`. Carved rooms and corridors
+/// become `Tile::floor()`, everything else stays `Tile::wall()`, and a
+/// `Terminal` is placed on the exit room (the room farthest, by corridor
+/// distance, from the first room carved).
+pub fn generate(width: u32, height: u32, seed: u64) -> TileMap {
+    MapBuilder::build(width, height, seed).1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_has_expected_dimensions() {
+        let map = generate(50, 30, 42);
+        assert_eq!(map.width, 50);
+        assert_eq!(map.height, 30);
+        assert_eq!(map.tiles.len(), 30 * 50);
+    }
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        let a = generate(50, 30, 7);
+        let b = generate(50, 30, 7);
+        assert_eq!(a.tiles.len(), b.tiles.len());
+        for (tile_a, tile_b) in a.tiles.iter().zip(b.tiles.iter()) {
+            assert_eq!(tile_a.tile_type, tile_b.tile_type);
+        }
+    }
+
+    #[test]
+    fn test_builder_tracks_rooms_and_corridors() {
+        let (builder, map) = MapBuilder::build(60, 40, 123);
+        assert!(!builder.rooms.is_empty());
+        assert!(!builder.corridors.is_empty());
+
+        // Every carved room must actually be floor on the map.
+        for room in &builder.rooms {
+            for ry in room.y..room.y + room.height {
+                for rx in room.x..room.x + room.width {
+                    assert!(map.get_tile(rx as usize, ry as usize).unwrap().walkable);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_exit_terminal_is_placed() {
+        let (builder, map) = MapBuilder::build(60, 40, 99);
+        let terminal = map
+            .objects
+            .iter()
+            .find(|obj| obj.object_type == ObjectType::Terminal);
+        assert!(terminal.is_some());
+        assert_eq!(terminal.unwrap().position, builder.exit_point);
+    }
+}
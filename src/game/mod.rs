@@ -1,11 +1,17 @@
 // Game module - core game logic and state management
 // All game types and logic should go here
 
+pub mod ai;
 pub mod constants;
 pub mod inventory;
 pub mod map;
+pub mod mapgen;
+pub mod pathfind;
 pub mod physics;
 pub mod player;
+pub mod progression;
+pub mod replay;
+pub mod spatial;
 pub mod state;
 pub mod world;
 
@@ -13,6 +19,12 @@ pub mod world;
 pub use constants::*;
 pub use inventory::{Inventory, Item, ItemType};
 pub use map::{MapObject, ObjectRender, ObjectType, TileMap, TileMapRender};
+pub use mapgen::{generate as generate_bsp_map, MapBuilder, Rect};
 pub use player::{Direction, Player};
+pub use progression::{
+    Advancement, AdvancementRewards, LevelPrerequisites, ProgressionState, QuestPrerequisites,
+    Trigger,
+};
+pub use replay::{ActionLog, ActionLogEntry};
 pub use state::{GamePhase, GameState, PlayerAction, Position, RenderState};
-pub use world::{Tile, TileType, World};
+pub use world::{CollisionShape, Tile, TileType, TileVisibility, World};
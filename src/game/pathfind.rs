@@ -0,0 +1,218 @@
+// A* pathfinding over the walkable tiles of a World, plus a helper to
+// convert a tile path into per-tick movement steps for auto-walk / NPC
+// chase behavior.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::constants::{PLAYER_SPEED, TICK_RATE, TILE_SIZE};
+use super::physics::calculate_movement;
+use super::player::Direction;
+use super::state::Position;
+use super::world::World;
+
+/// Entry in the A* open set, ordered by ascending `f = g + h` - a
+/// [`BinaryHeap`] is a max-heap, so `Ord` is reversed to pop the lowest
+/// `f` score first.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct OpenEntry {
+    f: u32,
+    position: (usize, usize),
+}
+
+impl Ord for OpenEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.f.cmp(&self.f)
+    }
+}
+
+impl PartialOrd for OpenEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn manhattan(a: (usize, usize), b: (usize, usize)) -> u32 {
+    (a.0 as i32 - b.0 as i32).unsigned_abs() + (a.1 as i32 - b.1 as i32).unsigned_abs()
+}
+
+/// Find a walkable path from `start` to `goal` using A* with a
+/// Manhattan-distance heuristic and 4-directional neighbors. Returns the
+/// tile coordinates from `start` to `goal` inclusive, or `None` if `goal`
+/// isn't walkable or no path exists.
+pub fn find_path(
+    world: &World,
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> Option<Vec<(usize, usize)>> {
+    if !world.is_walkable(goal.0, goal.1) {
+        return None;
+    }
+    if start == goal {
+        return Some(vec![start]);
+    }
+
+    let mut open = BinaryHeap::new();
+    open.push(OpenEntry {
+        f: manhattan(start, goal),
+        position: start,
+    });
+
+    let mut came_from: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    let mut g_score: HashMap<(usize, usize), u32> = HashMap::new();
+    g_score.insert(start, 0);
+
+    while let Some(OpenEntry { position, .. }) = open.pop() {
+        if position == goal {
+            return Some(reconstruct_path(&came_from, position));
+        }
+
+        let current_g = g_score[&position];
+        for neighbor in neighbors(world, position) {
+            let tentative_g = current_g + 1;
+            if tentative_g < *g_score.get(&neighbor).unwrap_or(&u32::MAX) {
+                came_from.insert(neighbor, position);
+                g_score.insert(neighbor, tentative_g);
+                open.push(OpenEntry {
+                    f: tentative_g + manhattan(neighbor, goal),
+                    position: neighbor,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+fn neighbors(world: &World, (x, y): (usize, usize)) -> Vec<(usize, usize)> {
+    [
+        (x.checked_sub(1), Some(y)),
+        (x.checked_add(1), Some(y)),
+        (Some(x), y.checked_sub(1)),
+        (Some(x), y.checked_add(1)),
+    ]
+    .into_iter()
+    .filter_map(|(cx, cy)| {
+        let (cx, cy) = (cx?, cy?);
+        world.is_walkable(cx, cy).then_some((cx, cy))
+    })
+    .collect()
+}
+
+fn reconstruct_path(
+    came_from: &HashMap<(usize, usize), (usize, usize)>,
+    mut current: (usize, usize),
+) -> Vec<(usize, usize)> {
+    let mut path = vec![current];
+    while let Some(&prev) = came_from.get(&current) {
+        current = prev;
+        path.push(current);
+    }
+    path.reverse();
+    path
+}
+
+/// Convert a tile path (as returned by [`find_path`]) into the sequence of
+/// pixel positions a mover passes through while walking it one fixed tick
+/// at a time, at [`PLAYER_SPEED`] over [`TICK_RATE`] ticks per second. Used
+/// both to auto-walk the player to a clicked terminal and to drive NPC
+/// objects toward the player, by feeding the result into the same
+/// per-tick [`calculate_movement`] step movement already uses.
+pub fn auto_walk(path: &[(usize, usize)]) -> Vec<Position> {
+    let step_distance = PLAYER_SPEED / TICK_RATE as f32;
+    let mut steps = Vec::new();
+
+    let Some(&first) = path.first() else {
+        return steps;
+    };
+    let mut current = tile_center(first);
+
+    for &tile in &path[1..] {
+        let target = tile_center(tile);
+        let direction = direction_between(current, target);
+        let mut remaining = TILE_SIZE;
+        while remaining > 0.0 {
+            let distance = step_distance.min(remaining);
+            current = calculate_movement(current, direction, distance);
+            steps.push(current);
+            remaining -= distance;
+        }
+    }
+
+    steps
+}
+
+fn tile_center(tile: (usize, usize)) -> Position {
+    Position::new(
+        tile.0 as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+        tile.1 as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+    )
+}
+
+fn direction_between(from: Position, to: Position) -> Direction {
+    if to.y < from.y {
+        Direction::Up
+    } else if to.y > from.y {
+        Direction::Down
+    } else if to.x < from.x {
+        Direction::Left
+    } else {
+        Direction::Right
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::Tile;
+
+    #[test]
+    fn test_find_path_straight_line() {
+        let world = World::new(10, 10);
+        let path = find_path(&world, (1, 1), (1, 4)).unwrap();
+        assert_eq!(path, vec![(1, 1), (1, 2), (1, 3), (1, 4)]);
+    }
+
+    #[test]
+    fn test_find_path_routes_around_wall() {
+        let mut world = World::new(10, 10);
+        for x in 0..10 {
+            world.tiles[3][x] = Tile::wall();
+        }
+        world.tiles[3][9] = Tile::floor(); // leave a gap at the edge
+
+        let path = find_path(&world, (0, 0), (0, 6)).unwrap();
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(0, 6)));
+        // The path must detour through the gap rather than crossing the wall row.
+        assert!(path.contains(&(9, 3)));
+    }
+
+    #[test]
+    fn test_find_path_returns_none_when_unreachable() {
+        let mut world = World::new(10, 10);
+        for x in 0..10 {
+            world.tiles[3][x] = Tile::wall();
+        }
+
+        assert!(find_path(&world, (0, 0), (0, 6)).is_none());
+    }
+
+    #[test]
+    fn test_find_path_returns_none_for_unwalkable_goal() {
+        let mut world = World::new(10, 10);
+        world.tiles[5][5] = Tile::wall();
+
+        assert!(find_path(&world, (1, 1), (5, 5)).is_none());
+    }
+
+    #[test]
+    fn test_auto_walk_reaches_tile_centers() {
+        let path = vec![(1, 1), (1, 2)];
+        let steps = auto_walk(&path);
+
+        let expected_steps = (TILE_SIZE / (PLAYER_SPEED / TICK_RATE as f32)).ceil() as usize;
+        assert_eq!(steps.len(), expected_steps);
+        assert_eq!(steps.last(), Some(&tile_center((1, 2))));
+    }
+}
@@ -1,7 +1,7 @@
 use super::constants::TILE_SIZE;
 use super::player::Direction;
 use super::state::Position;
-use super::world::{TileType, World};
+use super::world::{CollisionShape, TileType, World};
 
 /// Calculate new position after moving in a direction
 pub fn calculate_movement(current_pos: Position, direction: Direction, distance: f32) -> Position {
@@ -25,14 +25,79 @@ pub fn is_position_walkable(world: &World, position: Position) -> bool {
     world.is_walkable(tile_x as usize, tile_y as usize)
 }
 
-/// Check if movement from one position to another would collide with walls
-pub fn check_collision(world: &World, _from: Position, to: Position) -> bool {
-    // Check if the destination is walkable
-    if !is_position_walkable(world, to) {
-        return true; // Collision detected
+/// Which axes (if either) a movement attempt was blocked on, so the
+/// caller can slide along a wall - keeping whichever axis wasn't blocked
+/// - rather than stopping dead as soon as any part of the move collides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CollisionResult {
+    pub blocked_x: bool,
+    pub blocked_y: bool,
+}
+
+impl CollisionResult {
+    pub fn is_blocked(&self) -> bool {
+        self.blocked_x || self.blocked_y
+    }
+}
+
+/// If `position` is standing on a slope tile, clamp its Y to the slope's
+/// floor height at the player's X offset within that tile - so walking
+/// onto a slope rides its surface rather than colliding with it like a
+/// wall. No-op on any other tile.
+pub fn resolve_slope(world: &World, position: Position) -> Position {
+    let (tile_x, tile_y) = position.tile_coords(TILE_SIZE);
+    if tile_x < 0 || tile_y < 0 {
+        return position;
+    }
+
+    let Some(tile) = world.get_tile(tile_x as usize, tile_y as usize) else {
+        return position;
+    };
+
+    let shape = tile.collision_shape();
+    if !shape.is_slope() {
+        return position;
+    }
+
+    // 0.0 at the tile's left edge, 1.0 at its right edge.
+    let x_offset = ((position.x - tile_x as f32 * TILE_SIZE) / TILE_SIZE).clamp(0.0, 1.0);
+    let height_fraction = match shape {
+        CollisionShape::SlopeUpLeft => 1.0 - x_offset, // rises toward the left
+        CollisionShape::SlopeUpRight => x_offset,      // rises toward the right
+        CollisionShape::Directional { .. } => unreachable!("shape.is_slope() checked above"),
+    };
+
+    let floor_y = tile_y as f32 * TILE_SIZE + TILE_SIZE * (1.0 - height_fraction);
+    Position::new(position.x, position.y.min(floor_y))
+}
+
+/// Check whether moving the player's bounding box from `from` to `to`
+/// collides, resolving the X and Y axes of motion independently so the
+/// caller can slide along a wall instead of stopping dead. Each tile's
+/// [`CollisionShape`] is consulted against the axis and direction of
+/// travel - e.g. a tile with only `from_top` set blocks downward entry
+/// but lets the player pass through moving upward. Slope tiles never
+/// block; ride them via [`resolve_slope`] instead.
+pub fn check_collision(world: &World, from: Position, to: Position) -> CollisionResult {
+    CollisionResult {
+        blocked_x: axis_blocked(world, from, Position::new(to.x, from.y)),
+        blocked_y: axis_blocked(world, from, Position::new(from.x, to.y)),
+    }
+}
+
+/// Tests the four corners of the player's bounding box at `to` against
+/// each corner tile's `CollisionShape`, given the single axis of motion
+/// implied by `from` -> `to` (the caller holds one coordinate fixed).
+fn axis_blocked(world: &World, from: Position, to: Position) -> bool {
+    let moving_right = to.x > from.x;
+    let moving_left = to.x < from.x;
+    let moving_down = to.y > from.y;
+    let moving_up = to.y < from.y;
+
+    if !moving_right && !moving_left && !moving_down && !moving_up {
+        return false;
     }
 
-    // Check the four corners of the player's bounding box (assuming player is smaller than tile)
     let player_size = TILE_SIZE * 0.8; // Player is 80% of tile size
     let half_size = player_size / 2.0;
 
@@ -43,14 +108,109 @@ pub fn check_collision(world: &World, _from: Position, to: Position) -> bool {
         Position::new(to.x + half_size, to.y + half_size), // Bottom-right
     ];
 
-    // If any corner is not walkable, there's a collision
     for corner in &corners {
-        if !is_position_walkable(world, *corner) {
+        let (tile_x, tile_y) = corner.tile_coords(TILE_SIZE);
+        if tile_x < 0 || tile_y < 0 {
             return true;
         }
+
+        let Some(tile) = world.get_tile(tile_x as usize, tile_y as usize) else {
+            return true;
+        };
+
+        let shape = tile.collision_shape();
+        if shape.is_empty() || shape.is_slope() {
+            continue;
+        }
+
+        if let CollisionShape::Directional { from_top, from_left, from_right, from_bottom } = shape
+        {
+            let blocked = (moving_down && from_top)
+                || (moving_up && from_bottom)
+                || (moving_right && from_left)
+                || (moving_left && from_right);
+            if blocked {
+                return true;
+            }
+        }
     }
 
-    false // No collision
+    false
+}
+
+/// Recomputes `world.visible_tiles` from scratch (every tile cleared,
+/// then re-marked) by casting a ray from `origin` to every tile within
+/// `range`, marking each tile the ray crosses visible and stopping at -
+/// but including - the first opaque tile, so sight doesn't pass through
+/// walls. Every tile that becomes visible is also marked permanently in
+/// `world.revealed_tiles`, for fog-of-war dimming once it falls back out
+/// of view. Call this whenever the player's position (or the world
+/// itself) changes.
+pub fn compute_fov(world: &mut World, origin: Position, range: i32) {
+    for v in world.visible_tiles.iter_mut() {
+        *v = false;
+    }
+
+    let (ox, oy) = origin.tile_coords(TILE_SIZE);
+    if ox < 0 || oy < 0 || ox as usize >= world.width || oy as usize >= world.height {
+        return;
+    }
+
+    for dy in -range..=range {
+        for dx in -range..=range {
+            if dx * dx + dy * dy > range * range {
+                continue; // outside the circular viewshed
+            }
+            let (tx, ty) = (ox + dx, oy + dy);
+            if tx < 0 || ty < 0 || tx as usize >= world.width || ty as usize >= world.height {
+                continue;
+            }
+
+            for (x, y) in bresenham_line(ox, oy, tx, ty) {
+                if x < 0 || y < 0 || x as usize >= world.width || y as usize >= world.height {
+                    break; // ran off the map before reaching the target tile
+                }
+                let (x, y) = (x as usize, y as usize);
+                let index = y * world.width + x;
+                world.visible_tiles[index] = true;
+                world.revealed_tiles[index] = true;
+                if world.tiles[y][x].is_opaque() {
+                    break; // sight stops at the first opaque tile
+                }
+            }
+        }
+    }
+}
+
+/// Tile coordinates visited walking a straight line from `(x0, y0)` to
+/// `(x1, y1)`, via Bresenham's line algorithm. Shared by
+/// [`compute_fov`] and [`super::map::TileMap::compute_fov`].
+pub(crate) fn bresenham_line(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<(i32, i32)> {
+    let mut points = Vec::new();
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let (mut x, mut y) = (x0, y0);
+
+    loop {
+        points.push((x, y));
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+
+    points
 }
 
 /// Find the nearest interactable tile (terminal, door, etc.) within interaction range
@@ -133,7 +293,7 @@ mod tests {
         let from = Position::new(32.0, 32.0); // Tile (1, 1)
         let to = Position::new(64.0, 64.0); // Tile (2, 2) - wall
 
-        assert!(check_collision(&world, from, to));
+        assert!(check_collision(&world, from, to).is_blocked());
     }
 
     #[test]
@@ -143,6 +303,99 @@ mod tests {
         let from = Position::new(32.0, 32.0); // Tile (1, 1)
         let to = Position::new(96.0, 96.0); // Tile (3, 3) - floor
 
-        assert!(!check_collision(&world, from, to));
+        assert!(!check_collision(&world, from, to).is_blocked());
+    }
+
+    #[test]
+    fn test_directional_tile_blocks_only_from_its_side() {
+        let mut world = World::new(10, 10);
+        world.tiles[3][2] = Tile {
+            collision: Some(CollisionShape::Directional {
+                from_top: true,
+                from_left: false,
+                from_right: false,
+                from_bottom: false,
+            }),
+            ..Tile::floor()
+        };
+
+        // Moving down into the tile from above is blocked.
+        let from_above = Position::new(64.0, 64.0); // tile (2, 2)
+        let into_tile = Position::new(64.0, 96.0); // tile (2, 3)
+        assert!(check_collision(&world, from_above, into_tile).blocked_y);
+
+        // Moving up into the same tile from below is not.
+        let from_below = Position::new(64.0, 128.0); // tile (2, 4)
+        assert!(!check_collision(&world, from_below, into_tile).blocked_y);
+    }
+
+    #[test]
+    fn test_slide_axes_reported_independently() {
+        let mut world = World::new(10, 10);
+        // Wall directly to the right of tile (1,1), but the row below is open.
+        world.tiles[1][2] = Tile::wall();
+
+        let from = Position::new(32.0, 32.0); // tile (1, 1)
+        let to = Position::new(64.0, 64.0); // tile (2, 2) - open floor
+
+        let result = check_collision(&world, from, to);
+        assert!(result.blocked_x); // moving right alone hits the wall
+        assert!(!result.blocked_y); // moving down alone does not
+    }
+
+    #[test]
+    fn test_resolve_slope_clamps_to_surface() {
+        let mut world = World::new(10, 10);
+        world.tiles[2][2] = Tile {
+            collision: Some(CollisionShape::SlopeUpRight),
+            ..Tile::floor()
+        };
+
+        // At the tile's right edge the slope is at its highest (lowest Y);
+        // standing there with feet below the surface should be clamped up.
+        let deep_in_floor = Position::new(95.0, 95.0); // near right edge of tile (2,2)
+        let resolved = resolve_slope(&world, deep_in_floor);
+        assert!(resolved.y < deep_in_floor.y);
+    }
+
+    #[test]
+    fn test_compute_fov_reveals_origin_and_stops_at_wall() {
+        let mut world = World::new(10, 10);
+        world.tiles[5][7] = Tile::wall();
+
+        let origin = Position::new(5.0 * TILE_SIZE, 5.0 * TILE_SIZE);
+        compute_fov(&mut world, origin, 8);
+
+        assert!(world.visible_tiles[5 * world.width + 5]);
+        assert!(world.visible_tiles[5 * world.width + 7]); // the wall itself is seen
+        assert!(!world.visible_tiles[5 * world.width + 8]); // but nothing beyond it is
+    }
+
+    #[test]
+    fn test_compute_fov_respects_range() {
+        let mut world = World::new(20, 20);
+        let origin = Position::new(5.0 * TILE_SIZE, 5.0 * TILE_SIZE);
+        compute_fov(&mut world, origin, 3);
+
+        assert!(!world.visible_tiles[5 * world.width + 19]);
+    }
+
+    #[test]
+    fn test_compute_fov_clears_previous_visible_but_keeps_revealed() {
+        let mut world = World::new(10, 10);
+        compute_fov(&mut world, Position::new(5.0 * TILE_SIZE, 5.0 * TILE_SIZE), 8);
+        assert!(world.visible_tiles[5 * world.width + 5]);
+
+        // Move far enough that the old spot drops out of view.
+        compute_fov(&mut world, Position::new(0.0, 0.0), 1);
+        assert!(!world.visible_tiles[5 * world.width + 5]);
+        assert!(world.revealed_tiles[5 * world.width + 5]);
+    }
+
+    #[test]
+    fn test_bresenham_line_is_symmetric_endpoints() {
+        let line = bresenham_line(0, 0, 3, 3);
+        assert_eq!(line.first(), Some(&(0, 0)));
+        assert_eq!(line.last(), Some(&(3, 3)));
     }
 }
@@ -0,0 +1,99 @@
+//! Deterministic record-and-replay of player actions.
+//!
+//! Every tick's `delta` (fed to `GameState::update`) and the `PlayerAction`
+//! applied during it are appended to an `ActionLog`. Because `update` advances
+//! in fixed steps (see `FIXED_TIMESTEP`) and `apply_action` is a pure function
+//! of state, replaying a log from the same starting `GameState` reproduces the
+//! exact same final state — enabling misprediction-free playback, reproducible
+//! bug reports, and server-side verification of submitted solutions.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::state::{GameState, PlayerAction};
+
+/// One recorded tick: the `delta` passed to `GameState::update`, plus the
+/// `PlayerAction` applied during it (if any).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionLogEntry {
+    /// `delta` for this tick, in milliseconds.
+    pub delta_ms: u64,
+    pub action: Option<PlayerAction>,
+}
+
+/// A timestamped, serializable recording of ticks and actions. Serializes
+/// through the same serde derives as `GameState`, so it can be saved
+/// alongside a game save and replayed later.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActionLog {
+    pub entries: Vec<ActionLogEntry>,
+}
+
+impl ActionLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append one tick's delta and the action (if any) applied during it.
+    pub fn record(&mut self, delta: Duration, action: Option<PlayerAction>) {
+        self.entries.push(ActionLogEntry {
+            delta_ms: delta.as_millis() as u64,
+            action,
+        });
+    }
+}
+
+impl GameState {
+    /// Deterministically replay `log` onto this state, tick by tick.
+    ///
+    /// For a reproducible result the caller must start from the same
+    /// baseline the log was recorded from (e.g. right after `start_level`).
+    pub fn apply_replay(&mut self, log: &ActionLog) {
+        for entry in &log.entries {
+            self.update(Duration::from_millis(entry.delta_ms));
+            if let Some(action) = entry.action.clone() {
+                self.apply_action(action);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::player::Direction;
+    use crate::game::world::World;
+
+    #[test]
+    fn replay_reproduces_live_state() {
+        let world = World::new(20, 15);
+
+        let mut live = GameState::default();
+        live.start_level("test_level".to_string(), world.clone());
+
+        let mut log = ActionLog::new();
+        let delta = Duration::from_millis(16);
+
+        let actions = [
+            Some(PlayerAction::Move { direction: Direction::Down }),
+            None,
+            Some(PlayerAction::Move { direction: Direction::Right }),
+        ];
+
+        for action in actions {
+            live.update(delta);
+            if let Some(action) = action.clone() {
+                live.apply_action(action);
+            }
+            log.record(delta, action);
+        }
+
+        let mut replayed = GameState::default();
+        replayed.start_level("test_level".to_string(), world);
+        replayed.apply_replay(&log);
+
+        assert_eq!(replayed.player.position, live.player.position);
+        assert_eq!(replayed.player.facing, live.player.facing);
+        assert_eq!(replayed.tick_accumulator, live.tick_accumulator);
+    }
+}
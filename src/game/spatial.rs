@@ -0,0 +1,74 @@
+//! Tile-indexed occupancy data for a [`TileMap`]: a `blocked` bitmap and a
+//! per-tile index of which `MapObject`s sit where. Rebuilding this from
+//! scratch on every `TileMap` mutation (see
+//! [`TileMap::rebuild_spatial_index`]) trades a little work on writes for
+//! O(1) blocked/occupant lookups on reads, which matters far more: per-tick
+//! collision checks and interaction lookups vastly outnumber the handful of
+//! times a map's tiles or objects change.
+
+use super::constants::TILE_SIZE;
+use super::map::{ObjectType, TileMap};
+
+/// Rebuilds the `blocked` bitmap for `map` from each tile's walkability
+/// plus any blocking objects placed on top (doors, NPCs - terminals and
+/// collectibles don't block movement). Row-major, addressed the same way
+/// as `TileMap::xy_idx`.
+pub fn populate_blocked(map: &TileMap) -> Vec<bool> {
+    let mut blocked: Vec<bool> = map.tiles.iter().map(|tile| !tile.walkable).collect();
+
+    for object in &map.objects {
+        if !is_blocking(object.object_type) {
+            continue;
+        }
+        let (tx, ty) = object.position.tile_coords(TILE_SIZE);
+        if tx < 0 || ty < 0 || tx as u32 >= map.width || ty as u32 >= map.height {
+            continue;
+        }
+        blocked[map.xy_idx(tx as usize, ty as usize)] = true;
+    }
+
+    blocked
+}
+
+fn is_blocking(object_type: ObjectType) -> bool {
+    matches!(object_type, ObjectType::Door | ObjectType::Npc)
+}
+
+/// A `blocked` bitmap plus a per-tile list of `super::map::MapObject`
+/// indices, both
+/// row-major and addressed like `TileMap::xy_idx`. See
+/// [`TileMap::rebuild_spatial_index`] for how this is kept in sync.
+#[derive(Debug, Clone, Default)]
+pub struct SpatialIndex {
+    width: usize,
+    pub blocked: Vec<bool>,
+    pub tile_contents: Vec<Vec<usize>>,
+}
+
+impl SpatialIndex {
+    pub fn build(map: &TileMap) -> Self {
+        let blocked = populate_blocked(map);
+        let mut tile_contents = vec![Vec::new(); (map.width * map.height) as usize];
+
+        for (i, object) in map.objects.iter().enumerate() {
+            let (tx, ty) = object.position.tile_coords(TILE_SIZE);
+            if tx < 0 || ty < 0 || tx as u32 >= map.width || ty as u32 >= map.height {
+                continue;
+            }
+            tile_contents[map.xy_idx(tx as usize, ty as usize)].push(i);
+        }
+
+        Self {
+            width: map.width as usize,
+            blocked,
+            tile_contents,
+        }
+    }
+
+    pub fn is_blocked(&self, x: usize, y: usize) -> bool {
+        self.blocked
+            .get(y * self.width + x)
+            .copied()
+            .unwrap_or(true)
+    }
+}
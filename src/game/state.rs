@@ -2,12 +2,12 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Duration;
 
-use super::constants::TILE_SIZE;
+use super::constants::{FOV_RANGE, TILE_SIZE};
 use super::map::{ObjectRender, TileMapRender};
 use super::physics;
 use super::player::{Direction, Player};
 use super::progression::{LevelPrerequisites, ProgressionState};
-use super::world::{Tile, TileType, World};
+use super::world::{Tile, TileType, TileVisibility, World};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub struct Position {
@@ -35,6 +35,13 @@ pub enum GamePhase {
     LevelComplete,
 }
 
+/// Fixed simulation timestep used by `GameState::update`. Movement and any
+/// future tick-based mechanics run in whole steps accumulated from the
+/// real-time `delta` passed in, so replaying an `ActionLog` (see
+/// `super::replay`) reproduces the exact same number of ticks regardless of
+/// the frame rate that originally drove it.
+pub const FIXED_TIMESTEP: Duration = Duration::from_millis(16);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameState {
     pub player: Player,
@@ -50,6 +57,10 @@ pub struct GameState {
     pub total_xp: u32,
     #[serde(skip)]
     pub levels_completed: Vec<String>,
+    /// Leftover time not yet consumed by a fixed tick. Not part of the save
+    /// format — it's sub-tick real-time jitter, not game state.
+    #[serde(skip)]
+    pub tick_accumulator: Duration,
 }
 
 impl Default for GameState {
@@ -63,6 +74,7 @@ impl Default for GameState {
             active_quest_id: None,
             total_xp: 0,
             levels_completed: Vec::new(),
+            tick_accumulator: Duration::ZERO,
         }
     }
 }
@@ -77,12 +89,36 @@ impl GameState {
         self.world = world;
         self.player.position = self.world.spawn_point;
         self.game_phase = GamePhase::Playing;
+        self.recompute_fov();
+    }
+
+    /// Swap in a `World` reloaded from disk (see `levels::hot_reload`)
+    /// without resetting progression or the player's position like
+    /// `start_level` does - the player keeps standing where they are if
+    /// that spot is still walkable on the new map, and only snaps back to
+    /// `spawn_point` if the edit put something solid underfoot.
+    pub fn apply_reloaded_world(&mut self, new_world: World) {
+        let position_still_valid = physics::is_position_walkable(&new_world, self.player.position);
+        let spawn_point = new_world.spawn_point;
+        self.world = new_world;
+        if !position_still_valid {
+            self.player.position = spawn_point;
+        }
+        self.recompute_fov();
+    }
+
+    /// Recompute the world's field of view around the player's current
+    /// position - see [`physics::compute_fov`]. Called after anything that
+    /// moves the player or swaps the world out from under them.
+    fn recompute_fov(&mut self) {
+        physics::compute_fov(&mut self.world, self.player.position, FOV_RANGE);
     }
 
     /// Complete the current level, award XP, and unlock doors
     /// Returns the XP earned (0 if already completed)
     pub fn complete_level(&mut self, xp_reward: u32) -> u32 {
         let xp_earned = if let Some(ref level_id) = self.current_level_id {
+            let xp_reward = self.progression.apply_hint_penalty(level_id, xp_reward);
             self.progression.complete_level(level_id, xp_reward)
         } else {
             0
@@ -99,6 +135,48 @@ impl GameState {
         xp_earned
     }
 
+    /// Award partial credit XP for the current level, scaled from
+    /// `passed_count` of `total` test cases passing. Only the amount above
+    /// any previously-granted partial credit is newly earned. Does not
+    /// change `game_phase` — unlike [`Self::complete_level`], a partial
+    /// score never completes the level.
+    pub fn award_partial_xp(&mut self, xp_reward: u32, passed_count: usize, total: usize) -> u32 {
+        let xp_earned = if let Some(ref level_id) = self.current_level_id {
+            let already_completed = self.progression.is_completed(level_id);
+            let xp_reward = self.progression.apply_hint_penalty(level_id, xp_reward);
+            self.progression
+                .award_partial_xp(level_id, xp_reward, passed_count, total, already_completed)
+        } else {
+            0
+        };
+
+        self.player.xp += xp_earned;
+        self.total_xp = self.progression.total_xp;
+        xp_earned
+    }
+
+    /// Same as [`Self::award_partial_xp`], scoped to a single quest within a
+    /// multi-quest level instead of the level as a whole.
+    pub fn award_quest_partial_xp(
+        &mut self,
+        level_id: &str,
+        quest_id: &str,
+        xp_reward: u32,
+        passed_count: usize,
+        total: usize,
+    ) -> u32 {
+        let key = ProgressionState::quest_partial_key(level_id, quest_id);
+        let already_completed = self.progression.is_quest_completed(level_id, quest_id);
+        let xp_reward = self.progression.apply_hint_penalty(&key, xp_reward);
+        let xp_earned = self
+            .progression
+            .award_partial_xp(&key, xp_reward, passed_count, total, already_completed);
+
+        self.player.xp += xp_earned;
+        self.total_xp = self.progression.total_xp;
+        xp_earned
+    }
+
     /// Update which levels are unlocked based on prerequisites
     pub fn update_unlocked_levels(&mut self, prerequisites: &HashMap<String, LevelPrerequisites>) {
         self.progression.update_unlocks(prerequisites);
@@ -121,12 +199,28 @@ impl GameState {
     /// Complete a quest and award XP
     /// Returns the XP earned (0 if already completed)
     pub fn complete_quest(&mut self, level_id: &str, quest_id: &str, xp_reward: u32) -> u32 {
+        let key = ProgressionState::quest_partial_key(level_id, quest_id);
+        let xp_reward = self.progression.apply_hint_penalty(&key, xp_reward);
         let xp_earned = self.progression.complete_quest(level_id, quest_id, xp_reward);
         self.player.xp += xp_earned;
         self.total_xp = self.progression.total_xp;
         xp_earned
     }
 
+    /// Record an unsuccessful submission against `key` (a level id, or
+    /// [`ProgressionState::quest_partial_key`] for a quest), counting towards
+    /// unlocking that key's final hint.
+    pub fn record_failed_attempt(&mut self, key: &str) {
+        self.progression.record_failed_attempt(key);
+    }
+
+    /// Reveal hint `index` of `total_hints` for `key`, applying the
+    /// progressive-unlock and failed-attempt gating in
+    /// [`ProgressionState::reveal_hint`].
+    pub fn reveal_hint(&mut self, key: &str, index: usize, total_hints: usize) -> Result<(), String> {
+        self.progression.reveal_hint(key, index, total_hints)
+    }
+
     /// Check if a specific quest is completed
     pub fn is_quest_completed(&self, level_id: &str, quest_id: &str) -> bool {
         self.progression.is_quest_completed(level_id, quest_id)
@@ -174,17 +268,31 @@ impl GameState {
             return false;
         }
 
-        // Calculate new position
+        // Calculate new position, riding any slope the player lands on
         let new_position = physics::calculate_movement(self.player.position, direction, distance);
+        let new_position = physics::resolve_slope(&self.world, new_position);
 
-        // Check for collision
-        if physics::check_collision(&self.world, self.player.position, new_position) {
-            return false; // Movement blocked
+        // Check for collision, resolving each axis independently so a
+        // diagonal-feeling wall bump still lets the player slide along it
+        let collision = physics::check_collision(&self.world, self.player.position, new_position);
+        if collision.blocked_x && collision.blocked_y {
+            return false; // Movement blocked on both axes
         }
 
-        // Update player position and facing direction
-        self.player.position = new_position;
+        self.player.position = Position::new(
+            if collision.blocked_x {
+                self.player.position.x
+            } else {
+                new_position.x
+            },
+            if collision.blocked_y {
+                self.player.position.y
+            } else {
+                new_position.y
+            },
+        );
         self.player.facing = direction;
+        self.recompute_fov();
 
         true
     }
@@ -226,23 +334,59 @@ impl GameState {
         None
     }
 
-    /// Update game state for a single tick
+    /// Advance game state by `delta` real time, in fixed-size steps.
+    ///
+    /// Accumulating `delta` and draining it in `FIXED_TIMESTEP` chunks (rather
+    /// than running tick logic once per call with a variable `delta`) means
+    /// the number and size of simulation steps depends only on elapsed time,
+    /// not on frame rate — so `apply_replay` reproduces identical results
+    /// regardless of how the log was originally recorded.
     pub fn update(&mut self, delta: Duration) {
-        // Update logic that runs every tick
-        // This is where time-based game mechanics would go
+        self.tick_accumulator += delta;
 
-        // For now, this is a placeholder for future game tick logic
-        // Examples of what could go here:
-        // - Animation updates
-        // - Particle effects
-        // - Enemy AI (future)
-        // - Environmental effects
-        // - Status effect timers
-
-        let _delta_secs = delta.as_secs_f32();
+        while self.tick_accumulator >= FIXED_TIMESTEP {
+            self.tick_accumulator -= FIXED_TIMESTEP;
+            self.fixed_tick();
+        }
+    }
 
-        // Currently no tick-based logic needed
-        // Game state is updated through player actions only
+    /// Run a single fixed-size simulation step.
+    ///
+    /// This is where time-based game mechanics would go. For now it's a
+    /// placeholder:
+    /// - Animation updates
+    /// - Particle effects
+    /// - Enemy AI (future)
+    /// - Environmental effects
+    /// - Status effect timers
+    ///
+    /// Game state is otherwise updated through player actions only.
+    fn fixed_tick(&mut self) {}
+
+    /// Apply a single `PlayerAction` to this state.
+    ///
+    /// This is the shared dispatch used by both live input handling and
+    /// `apply_replay` (see `super::replay`), so replaying a log runs through
+    /// the exact same code path a live session would.
+    pub fn apply_action(&mut self, action: PlayerAction) {
+        match action {
+            PlayerAction::Move { direction } => {
+                self.move_player(direction, TILE_SIZE);
+            }
+            PlayerAction::Interact => {
+                self.interact_with_nearest();
+            }
+            PlayerAction::SubmitCode { .. } => {
+                // Code submission is graded through a dedicated command, not
+                // replayed here — replay only reconstructs movement/world state.
+            }
+            PlayerAction::Pause => {
+                self.game_phase = GamePhase::Paused;
+            }
+            PlayerAction::Resume => {
+                self.game_phase = GamePhase::Playing;
+            }
+        }
     }
 
     /// Generate render state for frontend (20x15 viewport centered on player)
@@ -266,6 +410,7 @@ impl GameState {
 
         // Extract visible tiles
         let mut visible_tiles = Vec::new();
+        let mut tile_visibility = Vec::new();
         for y in offset_y..(offset_y + viewport_height).min(self.world.height) {
             let row: Vec<Tile> = self.world.tiles[y]
                 .iter()
@@ -274,11 +419,18 @@ impl GameState {
                 .cloned()
                 .collect();
             visible_tiles.push(row);
+
+            let visibility_row: Vec<TileVisibility> = (offset_x
+                ..(offset_x + viewport_width).min(self.world.width))
+                .map(|x| self.world.visibility_at(x, y))
+                .collect();
+            tile_visibility.push(visibility_row);
         }
 
         RenderState {
             player: self.player.clone(),
             visible_tiles,
+            tile_visibility,
             viewport_offset: Position::new(offset_x as f32, offset_y as f32),
             game_phase: self.game_phase,
             current_level_id: self.current_level_id.clone(),
@@ -295,6 +447,7 @@ impl GameState {
 pub struct RenderState {
     pub player: Player,
     pub visible_tiles: Vec<Vec<Tile>>,
+    pub tile_visibility: Vec<Vec<TileVisibility>>,
     pub viewport_offset: Position,
     pub game_phase: GamePhase,
     pub current_level_id: Option<String>,
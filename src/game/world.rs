@@ -23,6 +23,111 @@ pub enum TileType {
     Pit,      // Dark hole/void (walkable=false)
 }
 
+/// How a tile blocks movement, for [`super::physics::check_collision`] to
+/// resolve the X and Y axes of motion independently instead of treating a
+/// tile as uniformly solid or passable.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionShape {
+    /// Blocks entry from whichever of the four sides are `true` - a tile
+    /// with only `from_top` set can be walked into from below or the
+    /// sides but not fallen into from above, like a one-way platform.
+    Directional {
+        from_top: bool,
+        from_left: bool,
+        from_right: bool,
+        from_bottom: bool,
+    },
+    /// Floor rises from the bottom-right corner to the top-left corner of
+    /// the tile; the player's Y is clamped to the slope surface at their
+    /// X offset within the tile instead of colliding.
+    SlopeUpLeft,
+    /// Floor rises from the bottom-left corner to the top-right corner.
+    SlopeUpRight,
+}
+
+impl CollisionShape {
+    pub fn full() -> Self {
+        Self::Directional {
+            from_top: true,
+            from_left: true,
+            from_right: true,
+            from_bottom: true,
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self::Directional {
+            from_top: false,
+            from_left: false,
+            from_right: false,
+            from_bottom: false,
+        }
+    }
+
+    fn from_walkable(walkable: bool) -> Self {
+        if walkable {
+            Self::empty()
+        } else {
+            Self::full()
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        matches!(
+            self,
+            Self::Directional {
+                from_top: true,
+                from_left: true,
+                from_right: true,
+                from_bottom: true,
+            }
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(
+            self,
+            Self::Directional {
+                from_top: false,
+                from_left: false,
+                from_right: false,
+                from_bottom: false,
+            }
+        )
+    }
+
+    pub fn is_slope(&self) -> bool {
+        matches!(self, Self::SlopeUpLeft | Self::SlopeUpRight)
+    }
+}
+
+/// Fog-of-war state of a single tile, derived from a `World`/`TileMap`'s
+/// `revealed_tiles`/`visible_tiles` - see [`super::physics::compute_fov`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TileVisibility {
+    /// Never seen - the frontend renders this as blank/hidden.
+    Unseen,
+    /// Revealed by a past viewshed but outside the current one - render
+    /// dimmed.
+    Seen,
+    /// Inside the current viewshed.
+    Visible,
+}
+
+impl TileVisibility {
+    pub fn from_flags(revealed: bool, visible: bool) -> Self {
+        if visible {
+            Self::Visible
+        } else if revealed {
+            Self::Seen
+        } else {
+            Self::Unseen
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tile {
     pub tile_type: TileType,
@@ -31,15 +136,53 @@ pub struct Tile {
     /// Quest ID for terminals (links terminal to specific quest)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quest_id: Option<String>,
+    /// Mirror flags carried from the source tileset GID (Tiled's top three
+    /// flip bits, stripped off before resolving the tile type), for the
+    /// renderer to apply when blitting this tile's sprite.
+    #[serde(default)]
+    pub flipped_h: bool,
+    #[serde(default)]
+    pub flipped_v: bool,
+    #[serde(default)]
+    pub flipped_d: bool,
+    /// Overrides the plain walkable/solid collision derived from
+    /// `walkable` with directional blocking or a slope - e.g. a half-wall
+    /// that can be jumped onto from below but not walked through. `None`
+    /// (the common case) falls back to `CollisionShape::full()`/`empty()`
+    /// based on `walkable` - see [`Tile::collision_shape`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collision: Option<CollisionShape>,
 }
 
 impl Tile {
+    /// The tile's effective collision shape: its explicit `collision` if
+    /// set, otherwise a plain full/empty shape derived from `walkable`.
+    pub fn collision_shape(&self) -> CollisionShape {
+        self.collision
+            .unwrap_or_else(|| CollisionShape::from_walkable(self.walkable))
+    }
+
+    /// Whether this tile blocks line of sight for
+    /// [`super::physics::compute_fov`]. Derived from `tile_type` rather
+    /// than stored, so every existing hand-authored or Tiled-imported
+    /// level gets sensible opacity for free.
+    pub fn is_opaque(&self) -> bool {
+        matches!(
+            self.tile_type,
+            TileType::Wall | TileType::Void | TileType::Tree | TileType::Rock
+        )
+    }
+
     pub fn floor() -> Self {
         Self {
             tile_type: TileType::Floor,
             walkable: true,
             interactable: false,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -49,6 +192,10 @@ impl Tile {
             walkable: false,
             interactable: false,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -58,6 +205,10 @@ impl Tile {
             walkable: true,
             interactable: true,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -68,6 +219,10 @@ impl Tile {
             walkable: true,
             interactable: true,
             quest_id: Some(quest_id),
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -77,6 +232,10 @@ impl Tile {
             walkable: false,
             interactable: true,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -86,6 +245,10 @@ impl Tile {
             walkable: false,
             interactable: false,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -96,6 +259,10 @@ impl Tile {
             walkable: false,
             interactable: false,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -105,6 +272,10 @@ impl Tile {
             walkable: false,
             interactable: false,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -114,6 +285,10 @@ impl Tile {
             walkable: false,
             interactable: false,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -123,6 +298,10 @@ impl Tile {
             walkable: true,
             interactable: false,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -132,6 +311,10 @@ impl Tile {
             walkable: true,
             interactable: false,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -141,6 +324,10 @@ impl Tile {
             walkable: true,
             interactable: false,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -150,6 +337,10 @@ impl Tile {
             walkable: true,
             interactable: false,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 
@@ -159,6 +350,10 @@ impl Tile {
             walkable: false,
             interactable: false,
             quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         }
     }
 }
@@ -169,9 +364,24 @@ pub struct World {
     pub height: usize,
     pub tiles: Vec<Vec<Tile>>,
     pub spawn_point: Position,
+    /// Tiles ever seen by [`super::physics::compute_fov`] - row-major,
+    /// `width * height` long. Never cleared, unlike `visible_tiles`: once
+    /// revealed a tile stays revealed (dimmed fog-of-war), for the
+    /// frontend to distinguish from tiles that are currently in view.
+    #[serde(default)]
+    pub revealed_tiles: Vec<bool>,
+    /// Tiles inside the most recent [`super::physics::compute_fov`] call -
+    /// row-major, `width * height` long. Cleared and recomputed every
+    /// call, unlike `revealed_tiles`.
+    #[serde(default)]
+    pub visible_tiles: Vec<bool>,
 }
 
 impl World {
+    fn blank_fov(width: usize, height: usize) -> (Vec<bool>, Vec<bool>) {
+        (vec![false; width * height], vec![false; width * height])
+    }
+
     pub fn new(width: usize, height: usize) -> Self {
         let mut tiles = vec![vec![Tile::floor(); width]; height];
 
@@ -205,11 +415,47 @@ impl World {
             tiles[4][5] = Tile::water();
         }
 
+        let (revealed_tiles, visible_tiles) = Self::blank_fov(width, height);
         Self {
             width,
             height,
             tiles,
             spawn_point,
+            revealed_tiles,
+            visible_tiles,
+        }
+    }
+
+    /// An empty canvas of `Void` tiles with no border, decorations, or
+    /// spawn point — unlike `new`, which hand-places those for the default
+    /// hub map. Intended for builders (see `levels::map_builders`) that lay
+    /// out their own floors, walls, and spawn from scratch.
+    pub fn blank(width: usize, height: usize) -> Self {
+        let tiles = vec![
+            vec![
+                Tile {
+                    tile_type: TileType::Void,
+                    walkable: false,
+                    interactable: false,
+                    quest_id: None,
+                    flipped_h: false,
+                    flipped_v: false,
+                    flipped_d: false,
+                    collision: None,
+                };
+                width
+            ];
+            height
+        ];
+
+        let (revealed_tiles, visible_tiles) = Self::blank_fov(width, height);
+        Self {
+            width,
+            height,
+            tiles,
+            spawn_point: Position::new(0.0, 0.0),
+            revealed_tiles,
+            visible_tiles,
         }
     }
 
@@ -221,6 +467,15 @@ impl World {
         self.get_tile(x, y).map(|t| t.walkable).unwrap_or(false)
     }
 
+    /// Fog-of-war state of the tile at `(x, y)` - see `compute_fov`.
+    pub fn visibility_at(&self, x: usize, y: usize) -> TileVisibility {
+        let index = y * self.width + x;
+        TileVisibility::from_flags(
+            self.revealed_tiles.get(index).copied().unwrap_or(false),
+            self.visible_tiles.get(index).copied().unwrap_or(false),
+        )
+    }
+
     pub fn from_config(config: &WorldConfig) -> Self {
         let width = config.width;
         let height = config.height;
@@ -279,11 +534,14 @@ impl World {
 
         let spawn_point = Position::new(config.spawn_x, config.spawn_y);
 
+        let (revealed_tiles, visible_tiles) = Self::blank_fov(width, height);
         Self {
             width,
             height,
             tiles,
             spawn_point,
+            revealed_tiles,
+            visible_tiles,
         }
     }
 
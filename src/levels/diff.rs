@@ -0,0 +1,190 @@
+//! Character/line diff between a test case's `expected` and `actual`
+//! output, so a failing case's feedback can point at exactly what's wrong
+//! instead of leaving the learner to eyeball two blobs. See
+//! [`super::validator::TestCaseResult::diff`].
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// One run of an edit script: `kind` says whether `text` is present in both
+/// (`Equal`), only in `actual` (`Insert`), or only in `expected` (`Delete`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffSpan {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+/// Diff `expected` against `actual`: align them line by line first, then
+/// re-diff the first differing line pair character by character so e.g. a
+/// single stray space surfaces as a one-character `Insert` instead of the
+/// whole line reading as different.
+pub fn diff_output(expected: &str, actual: &str) -> Vec<DiffSpan> {
+    let expected_lines: Vec<String> = expected.lines().map(str::to_string).collect();
+    let actual_lines: Vec<String> = actual.lines().map(str::to_string).collect();
+    let line_ops = lcs_align(&expected_lines, &actual_lines);
+
+    let mut spans: Vec<DiffSpan> = Vec::new();
+    let mut refined = false;
+    let mut i = 0;
+
+    while i < line_ops.len() {
+        let substitution = !refined
+            && matches!(
+                (line_ops.get(i), line_ops.get(i + 1)),
+                (Some(Op::Delete(_)), Some(Op::Insert(_)))
+                    | (Some(Op::Insert(_)), Some(Op::Delete(_)))
+            );
+
+        if substitution {
+            let (deleted, inserted) = match (&line_ops[i], &line_ops[i + 1]) {
+                (Op::Delete(d), Op::Insert(ins)) => (d, ins),
+                (Op::Insert(ins), Op::Delete(d)) => (d, ins),
+                _ => unreachable!("substitution only set true for a Delete/Insert pair"),
+            };
+            spans.extend(diff_chars(deleted, inserted));
+            refined = true;
+            i += 2;
+            continue;
+        }
+
+        let (kind, line) = match &line_ops[i] {
+            Op::Equal(l) => (DiffKind::Equal, l),
+            Op::Delete(l) => (DiffKind::Delete, l),
+            Op::Insert(l) => (DiffKind::Insert, l),
+        };
+        push_span(&mut spans, kind, line.clone(), "\n");
+        i += 1;
+    }
+
+    spans
+}
+
+/// Character-level diff of a single differing line pair.
+fn diff_chars(expected_line: &str, actual_line: &str) -> Vec<DiffSpan> {
+    let expected_chars: Vec<char> = expected_line.chars().collect();
+    let actual_chars: Vec<char> = actual_line.chars().collect();
+    let char_ops = lcs_align(&expected_chars, &actual_chars);
+
+    let mut spans = Vec::new();
+    for op in char_ops {
+        let (kind, ch) = match op {
+            Op::Equal(c) => (DiffKind::Equal, c),
+            Op::Delete(c) => (DiffKind::Delete, c),
+            Op::Insert(c) => (DiffKind::Insert, c),
+        };
+        push_span(&mut spans, kind, ch.to_string(), "");
+    }
+    spans
+}
+
+/// Append `text` as a new span, or fold it into the last span if it's the
+/// same kind - joined by `sep` - to keep the edit script compact.
+fn push_span(spans: &mut Vec<DiffSpan>, kind: DiffKind, text: String, sep: &str) {
+    if let Some(last) = spans.last_mut() {
+        if last.kind == kind {
+            last.text.push_str(sep);
+            last.text.push_str(&text);
+            return;
+        }
+    }
+    spans.push(DiffSpan { kind, text });
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Op<T> {
+    Equal(T),
+    Delete(T),
+    Insert(T),
+}
+
+/// Classic DP longest-common-subsequence table: `table[i][j]` is the LCS
+/// length of `a[..i]` and `b[..j]`.
+fn lcs_table<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Vec<usize>> {
+    let (n, m) = (a.len(), b.len());
+    let mut table = vec![vec![0usize; m + 1]; n + 1];
+    for i in 1..=n {
+        for j in 1..=m {
+            table[i][j] = if a[i - 1] == b[j - 1] {
+                table[i - 1][j - 1] + 1
+            } else {
+                table[i - 1][j].max(table[i][j - 1])
+            };
+        }
+    }
+    table
+}
+
+/// Backtrack an LCS table into an edit script, in original (not reversed)
+/// order. Ties between an insert and a delete favor the insert, which just
+/// picks a consistent side to group a same-length substitution on.
+fn lcs_align<T: PartialEq + Clone>(a: &[T], b: &[T]) -> Vec<Op<T>> {
+    let table = lcs_table(a, b);
+    let (mut i, mut j) = (a.len(), b.len());
+    let mut ops = Vec::new();
+
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && a[i - 1] == b[j - 1] {
+            ops.push(Op::Equal(a[i - 1].clone()));
+            i -= 1;
+            j -= 1;
+        } else if j > 0 && (i == 0 || table[i][j - 1] >= table[i - 1][j]) {
+            ops.push(Op::Insert(b[j - 1].clone()));
+            j -= 1;
+        } else {
+            ops.push(Op::Delete(a[i - 1].clone()));
+            i -= 1;
+        }
+    }
+
+    ops.reverse();
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_output_diffs_as_one_equal_span() {
+        let spans = diff_output("hello\nworld", "hello\nworld");
+        assert_eq!(
+            spans,
+            vec![DiffSpan {
+                kind: DiffKind::Equal,
+                text: "hello\nworld".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn extra_space_on_a_line_is_a_single_char_insert() {
+        let spans = diff_output("sum: 5", "sum:  5");
+        assert_eq!(
+            spans,
+            vec![
+                DiffSpan { kind: DiffKind::Equal, text: "sum:".to_string() },
+                DiffSpan { kind: DiffKind::Insert, text: " ".to_string() },
+                DiffSpan { kind: DiffKind::Equal, text: " 5".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn extra_trailing_line_is_a_whole_line_insert() {
+        let spans = diff_output("a\nb", "a\nb\nc");
+        assert_eq!(
+            spans,
+            vec![
+                DiffSpan { kind: DiffKind::Equal, text: "a\nb".to_string() },
+                DiffSpan { kind: DiffKind::Insert, text: "c".to_string() },
+            ]
+        );
+    }
+}
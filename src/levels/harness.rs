@@ -3,16 +3,61 @@
 //! Wraps user-written functions with a main() that calls them with test inputs
 //! and prints the result for validation.
 
-use super::loader::{FunctionSignature, TestCase};
+use super::loader::{FunctionSignature, MatchMode, OutputLength, OutputParam, StructDef, TestCase};
+use super::signature::{parse_signature, signatures_agree};
 
-/// Generate a complete C program that wraps the user's function with a test harness
+/// Generate a complete C program that wraps the user's function with a test harness.
+///
+/// `signature` is optional: when a challenge doesn't supply one, it's inferred
+/// from `user_code` instead. When both are present, the declared signature is
+/// used (it may carry `structs` the inferred one can't know about), but it
+/// must agree with what's actually in `user_code` or this errors - catching a
+/// level author's signature drifting out of sync with the stub/solution.
 pub fn generate_harness(
     user_code: &str,
-    signature: &FunctionSignature,
+    signature: Option<&FunctionSignature>,
     test_case: &TestCase,
 ) -> Result<String, String> {
-    let call_args = format_call_args(&signature.parameters, &test_case.input)?;
-    let print_format = get_print_format(&signature.return_type)?;
+    let inferred = parse_signature(user_code);
+    let signature: &FunctionSignature = match (signature, &inferred) {
+        (Some(declared), Ok(inferred)) => {
+            if !signatures_agree(declared, inferred) {
+                return Err(format!(
+                    "Declared function signature ({} {}(...)) does not match user code ({} {}(...))",
+                    declared.return_type, declared.name, inferred.return_type, inferred.name
+                ));
+            }
+            declared
+        }
+        (Some(declared), Err(_)) => declared,
+        (None, Ok(inferred)) => inferred,
+        (None, Err(e)) => {
+            return Err(format!(
+                "No function signature provided and none could be inferred: {}",
+                e
+            ))
+        }
+    };
+    let (setup_decls, call_args) = format_call_args(
+        &signature.parameters,
+        &test_case.input,
+        &signature.structs,
+        &test_case.output_params,
+    )?;
+
+    // Out-params take over the printed output: a void-returning, out-param-only
+    // challenge has nothing else worth printing, and a normal return value
+    // would otherwise be conflated with the mutated buffers.
+    let print_stmt = if signature.return_type == "void" && !test_case.output_params.is_empty() {
+        String::new()
+    } else {
+        generate_print_stmt(signature, &test_case.match_mode)?
+    };
+    let output_dumps = generate_output_param_dumps(
+        &signature.parameters,
+        &test_case.input,
+        &test_case.output_params,
+    )?;
 
     let harness = format!(
         r#"#include <stdio.h>
@@ -23,24 +68,33 @@ pub fn generate_harness(
 {user_code}
 
 int main() {{
+    {setup_decls}
     {result_decl}
     {print_stmt}
+    {output_dumps}
     return 0;
 }}
 "#,
         user_code = user_code,
-        result_decl = generate_result_decl(&signature, &call_args),
-        print_stmt = generate_print_stmt(&signature.return_type, &print_format),
+        setup_decls = setup_decls.join("\n    "),
+        result_decl = generate_result_decl(signature, &call_args),
+        print_stmt = print_stmt,
+        output_dumps = output_dumps.join("\n    "),
     );
 
     Ok(harness)
 }
 
-/// Format the arguments for the function call based on parameter types and test input
+/// Format the arguments for the function call based on parameter types and test input.
+/// Returns the `int argN[] = { ... };`-style declarations that out-params need hoisted
+/// into named locals (so they can be read back after the call), plus the comma-joined
+/// call argument list itself (which references those locals by name where applicable).
 fn format_call_args(
     params: &[super::loader::FunctionParameter],
     input: &[serde_json::Value],
-) -> Result<String, String> {
+    structs: &[StructDef],
+    output_params: &[OutputParam],
+) -> Result<(Vec<String>, String), String> {
     if params.len() != input.len() {
         return Err(format!(
             "Parameter count mismatch: expected {}, got {}",
@@ -49,17 +103,105 @@ fn format_call_args(
         ));
     }
 
-    let args: Result<Vec<String>, String> = params
-        .iter()
-        .zip(input.iter())
-        .map(|(param, value)| format_single_arg(&param.param_type, value))
-        .collect();
+    let mut setup_decls = Vec::new();
+    let mut args = Vec::new();
+    for (index, (param, value)) in params.iter().zip(input.iter()).enumerate() {
+        if output_params.iter().any(|op| op.param_index == index) {
+            let arr = value.as_array().ok_or_else(|| {
+                format!("Out-param '{}' expects an array input, got {:?}", param.name, value)
+            })?;
+            let elements: Result<Vec<String>, String> = arr
+                .iter()
+                .map(|v| {
+                    v.as_i64()
+                        .map(|n| n.to_string())
+                        .ok_or_else(|| format!("Array element must be integer: {:?}", v))
+                })
+                .collect();
+            let var_name = format!("arg{}", index);
+            setup_decls.push(format!("int {}[] = {{ {} }};", var_name, elements?.join(", ")));
+            args.push(var_name);
+        } else {
+            args.push(format_single_arg(&param.param_type, value, structs)?);
+        }
+    }
+
+    Ok((setup_decls, args.join(", ")))
+}
+
+/// Resolve how many elements an out-param's buffer holds, for test cases generated
+/// ahead of time: a `Literal` is used directly, and a `Param` is read from the sibling
+/// parameter's test-case input (not a runtime C variable, since the harness is
+/// generated fresh per test case and already knows every input value).
+fn resolve_output_length(
+    length: &OutputLength,
+    params: &[super::loader::FunctionParameter],
+    input: &[serde_json::Value],
+) -> Result<usize, String> {
+    match length {
+        OutputLength::Literal(n) => Ok(*n),
+        OutputLength::Param(name) => {
+            let index = params
+                .iter()
+                .position(|p| &p.name == name)
+                .ok_or_else(|| format!("Output length references unknown parameter '{}'", name))?;
+            let value = input
+                .get(index)
+                .ok_or_else(|| format!("Missing input for length parameter '{}'", name))?;
+            value
+                .as_i64()
+                .map(|n| n as usize)
+                .ok_or_else(|| format!("Length parameter '{}' must be an integer, got {:?}", name, value))
+        }
+    }
+}
 
-    Ok(args?.join(", "))
+/// Generate the `for` loops that print each out-param's post-call contents, one
+/// space-separated line per out-param, so the validator can keep comparing plain text.
+fn generate_output_param_dumps(
+    params: &[super::loader::FunctionParameter],
+    input: &[serde_json::Value],
+    output_params: &[OutputParam],
+) -> Result<Vec<String>, String> {
+    output_params
+        .iter()
+        .map(|op| {
+            let var_name = format!("arg{}", op.param_index);
+            let len = resolve_output_length(&op.length, params, input)?;
+            Ok(format!(
+                "for (int i = 0; i < {}; i++) printf(\"%d \", {}[i]); printf(\"\\n\");",
+                len, var_name
+            ))
+        })
+        .collect()
 }
 
 /// Format a single argument value based on its type
-fn format_single_arg(param_type: &str, value: &serde_json::Value) -> Result<String, String> {
+fn format_single_arg(
+    param_type: &str,
+    value: &serde_json::Value,
+    structs: &[StructDef],
+) -> Result<String, String> {
+    // Struct types (scalar or array-of-struct) take priority over the
+    // pointer/scalar checks below, since a struct name can itself end in
+    // `*`/`[]` to mean "array of this struct".
+    if let Some(def) = structs.iter().find(|s| {
+        let base = param_type.trim_end_matches("[]").trim_end_matches('*').trim();
+        s.name == base
+    }) {
+        if param_type.ends_with("[]") || param_type.ends_with('*') {
+            let arr = value
+                .as_array()
+                .ok_or_else(|| format!("Expected array of struct {}, got {:?}", def.name, value))?;
+            let elements: Result<Vec<String>, String> = arr
+                .iter()
+                .map(|v| format_struct_literal(def, v, structs))
+                .collect();
+            return Ok(format!("(struct {}[]){{ {} }}", def.name, elements?.join(", ")));
+        }
+        return format_struct_literal(def, value, structs);
+    }
+
     // Check for pointer types first
     if param_type.contains("int*") || param_type.contains("int *") {
         // Special handling for NULL
@@ -126,6 +268,44 @@ fn format_single_arg(param_type: &str, value: &serde_json::Value) -> Result<Stri
     }
 }
 
+/// Build a `(struct Name){ .field = value, ... }` compound literal, recursing
+/// through [`format_single_arg`] per field so nested structs (and arrays of
+/// structs) are formatted the same way a top-level argument would be. A
+/// missing or extra JSON key is a hard error - mirrors the parameter-count
+/// check in [`format_call_args`], since silently zero-initializing a field
+/// would hide a broken test case instead of failing loudly.
+fn format_struct_literal(
+    def: &StructDef,
+    value: &serde_json::Value,
+    structs: &[StructDef],
+) -> Result<String, String> {
+    let obj = value
+        .as_object()
+        .ok_or_else(|| format!("Expected object for struct {}, got {:?}", def.name, value))?;
+    if obj.len() != def.fields.len() {
+        return Err(format!(
+            "Struct {} expects {} fields, got {}",
+            def.name,
+            def.fields.len(),
+            obj.len()
+        ));
+    }
+
+    let inits: Result<Vec<String>, String> = def
+        .fields
+        .iter()
+        .map(|field| {
+            let field_value = obj
+                .get(&field.name)
+                .ok_or_else(|| format!("Struct {} missing field '{}'", def.name, field.name))?;
+            let formatted = format_single_arg(&field.field_type, field_value, structs)?;
+            Ok(format!(".{} = {}", field.name, formatted))
+        })
+        .collect();
+
+    Ok(format!("(struct {}){{ {} }}", def.name, inits?.join(", ")))
+}
+
 /// Get the printf format specifier for a return type
 fn get_print_format(return_type: &str) -> Result<&'static str, String> {
     match return_type {
@@ -146,6 +326,11 @@ fn get_print_format(return_type: &str) -> Result<&'static str, String> {
 fn generate_result_decl(signature: &FunctionSignature, call_args: &str) -> String {
     if signature.return_type == "void" {
         format!("{}({});", signature.name, call_args)
+    } else if let Some(def) = signature.find_struct(&signature.return_type) {
+        format!(
+            "struct {} result = {}({});",
+            def.name, signature.name, call_args
+        )
     } else {
         format!(
             "{} result = {}({});",
@@ -154,13 +339,66 @@ fn generate_result_decl(signature: &FunctionSignature, call_args: &str) -> Strin
     }
 }
 
-/// Generate the print statement for the result
-fn generate_print_stmt(return_type: &str, format: &str) -> String {
-    if return_type == "void" {
-        String::from("printf(\"done\\n\");")
-    } else {
-        format!("printf(\"{}\\n\", result);", format)
+/// Generate the print statement for the result. A struct return prints one
+/// field per declaration order, space-separated, so the validator can keep
+/// doing a plain string compare instead of needing to understand structs.
+///
+/// A `float`/`double` return under [`MatchMode::FloatTolerance`] prints with
+/// full precision (`%.17g`) instead of the return type's usual format
+/// specifier, since the comparison step re-parses the printed text as an
+/// `f64` anyway - the lossy `%f`/`%lf` rounding would just add its own error
+/// on top of whatever tolerance the test case already allows.
+fn generate_print_stmt(signature: &FunctionSignature, match_mode: &MatchMode) -> Result<String, String> {
+    if signature.return_type == "void" {
+        return Ok(String::from("printf(\"done\\n\");"));
     }
+
+    if let Some(def) = signature.find_struct(&signature.return_type) {
+        let formats: Result<Vec<&str>, String> = def
+            .fields
+            .iter()
+            .map(|f| get_print_format(&f.field_type))
+            .collect();
+        let args: Vec<String> = def.fields.iter().map(|f| format!("result.{}", f.name)).collect();
+        return Ok(format!(
+            "printf(\"{}\\n\", {});",
+            formats?.join(" "),
+            args.join(", ")
+        ));
+    }
+
+    if matches!(match_mode, MatchMode::FloatTolerance { .. })
+        && (signature.return_type == "float" || signature.return_type == "double")
+    {
+        return Ok(String::from("printf(\"%.17g\\n\", result);"));
+    }
+
+    // Pointer returns can legitimately be NULL (e.g. "return NULL on bad
+    // input"), and printing through that pointer unconditionally would
+    // segfault the harness itself. `char*`/`string` already prints the
+    // pointer's text directly; any other pointer return is dereferenced
+    // once the NULL case is ruled out.
+    if is_pointer_return(&signature.return_type) {
+        let (format, expr) = if matches!(signature.return_type.as_str(), "char*" | "char *" | "string") {
+            ("%s", "result".to_string())
+        } else {
+            let base = signature.return_type.trim_end_matches('*').trim();
+            (get_print_format(base)?, "(*result)".to_string())
+        };
+        return Ok(format!(
+            "if (result == NULL) printf(\"null\\n\"); else printf(\"{}\\n\", {});",
+            format, expr
+        ));
+    }
+
+    let format = get_print_format(&signature.return_type)?;
+    Ok(format!("printf(\"{}\\n\", result);", format))
+}
+
+/// Whether a return type is a C pointer that could come back NULL - either
+/// spelled with a trailing `*`, or the `string` alias for `char*`.
+fn is_pointer_return(return_type: &str) -> bool {
+    return_type.ends_with('*') || return_type == "string"
 }
 
 /// Escape special characters in a C string
@@ -175,7 +413,7 @@ fn escape_c_string(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::levels::loader::FunctionParameter;
+    use crate::levels::loader::{FunctionParameter, StructField};
 
     #[test]
     fn test_generate_simple_harness() {
@@ -193,14 +431,17 @@ mod tests {
                     param_type: "int".to_string(),
                 },
             ],
+            structs: vec![],
         };
         let test_case = TestCase {
             input: vec![serde_json::json!(2), serde_json::json!(3)],
             expected: "5".to_string(),
             sample: true,
+            output_params: vec![],
+            match_mode: MatchMode::default(),
         };
 
-        let harness = generate_harness(user_code, &signature, &test_case).unwrap();
+        let harness = generate_harness(user_code, Some(&signature), &test_case).unwrap();
         assert!(harness.contains("int result = add(2, 3);"));
         assert!(harness.contains("printf(\"%d\\n\", result);"));
     }
@@ -211,14 +452,17 @@ mod tests {
             name: "hello".to_string(),
             return_type: "void".to_string(),
             parameters: vec![],
+            structs: vec![],
         };
         let test_case = TestCase {
             input: vec![],
             expected: "Hello, World!".to_string(),
             sample: true,
+            output_params: vec![],
+            match_mode: MatchMode::default(),
         };
 
-        let harness = generate_harness("void hello() { printf(\"Hello, World!\\n\"); }", &signature, &test_case).unwrap();
+        let harness = generate_harness("void hello() { printf(\"Hello, World!\\n\"); }", Some(&signature), &test_case).unwrap();
         assert!(harness.contains("hello();"));
         assert!(harness.contains("printf(\"done\\n\");"));
     }
@@ -235,6 +479,7 @@ mod tests {
                     param_type: "int*".to_string(),
                 },
             ],
+            structs: vec![],
         };
 
         // Test with NULL string
@@ -242,8 +487,10 @@ mod tests {
             input: vec![serde_json::json!("NULL")],
             expected: "-1".to_string(),
             sample: true,
+            output_params: vec![],
+            match_mode: MatchMode::default(),
         };
-        let harness_null = generate_harness(user_code, &signature, &test_null).unwrap();
+        let harness_null = generate_harness(user_code, Some(&signature), &test_null).unwrap();
         assert!(harness_null.contains("safeRead(NULL)"));
 
         // Test with integer value (creates compound literal pointer)
@@ -251,8 +498,10 @@ mod tests {
             input: vec![serde_json::json!(42)],
             expected: "42".to_string(),
             sample: true,
+            output_params: vec![],
+            match_mode: MatchMode::default(),
         };
-        let harness_value = generate_harness(user_code, &signature, &test_value).unwrap();
+        let harness_value = generate_harness(user_code, Some(&signature), &test_value).unwrap();
         assert!(harness_value.contains("safeRead(&(int){42})"));
     }
 
@@ -272,6 +521,7 @@ mod tests {
                     param_type: "int".to_string(),
                 },
             ],
+            structs: vec![],
         };
 
         // Test with array input (creates compound literal array)
@@ -279,8 +529,231 @@ mod tests {
             input: vec![serde_json::json!([10, 20, 30, 40, 50]), serde_json::json!(2)],
             expected: "30".to_string(),
             sample: true,
+            output_params: vec![],
+            match_mode: MatchMode::default(),
         };
-        let harness = generate_harness(user_code, &signature, &test_case).unwrap();
+        let harness = generate_harness(user_code, Some(&signature), &test_case).unwrap();
         assert!(harness.contains("getAt((int[]){ 10, 20, 30, 40, 50 }, 2)"));
     }
+
+    fn point_struct() -> StructDef {
+        StructDef {
+            name: "Point".to_string(),
+            fields: vec![
+                StructField { name: "x".to_string(), field_type: "int".to_string() },
+                StructField { name: "y".to_string(), field_type: "int".to_string() },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_struct_argument_and_return() {
+        let user_code = "struct Point shift(struct Point p) { p.x += 1; return p; }";
+        let signature = FunctionSignature {
+            name: "shift".to_string(),
+            return_type: "Point".to_string(),
+            parameters: vec![FunctionParameter {
+                name: "p".to_string(),
+                param_type: "Point".to_string(),
+            }],
+            structs: vec![point_struct()],
+        };
+        let test_case = TestCase {
+            input: vec![serde_json::json!({"x": 1, "y": 2})],
+            expected: "2 2".to_string(),
+            sample: true,
+            output_params: vec![],
+            match_mode: MatchMode::default(),
+        };
+
+        let harness = generate_harness(user_code, Some(&signature), &test_case).unwrap();
+        assert!(harness.contains("shift((struct Point){ .x = 1, .y = 2 })"));
+        assert!(harness.contains("struct Point result = shift"));
+        assert!(harness.contains("printf(\"%d %d\\n\", result.x, result.y);"));
+    }
+
+    #[test]
+    fn test_struct_argument_rejects_missing_field() {
+        let signature = FunctionSignature {
+            name: "shift".to_string(),
+            return_type: "int".to_string(),
+            parameters: vec![FunctionParameter {
+                name: "p".to_string(),
+                param_type: "Point".to_string(),
+            }],
+            structs: vec![point_struct()],
+        };
+        let test_case = TestCase {
+            input: vec![serde_json::json!({"x": 1})],
+            expected: "0".to_string(),
+            sample: true,
+            output_params: vec![],
+            match_mode: MatchMode::default(),
+        };
+
+        assert!(generate_harness("int shift(struct Point p) { return p.x; }", Some(&signature), &test_case).is_err());
+    }
+
+    #[test]
+    fn test_output_param_with_literal_length_and_void_return() {
+        let user_code = "void doubleAll(int *arr, int n) { for (int i = 0; i < n; i++) arr[i] *= 2; }";
+        let signature = FunctionSignature {
+            name: "doubleAll".to_string(),
+            return_type: "void".to_string(),
+            parameters: vec![
+                FunctionParameter {
+                    name: "arr".to_string(),
+                    param_type: "int*".to_string(),
+                },
+                FunctionParameter {
+                    name: "n".to_string(),
+                    param_type: "int".to_string(),
+                },
+            ],
+            structs: vec![],
+        };
+        let test_case = TestCase {
+            input: vec![serde_json::json!([1, 2, 3]), serde_json::json!(3)],
+            expected: "2 4 6".to_string(),
+            sample: true,
+            output_params: vec![OutputParam {
+                param_index: 0,
+                length: OutputLength::Literal(3),
+            }],
+            match_mode: MatchMode::default(),
+        };
+
+        let harness = generate_harness(user_code, Some(&signature), &test_case).unwrap();
+        assert!(harness.contains("int arg0[] = { 1, 2, 3 };"));
+        assert!(harness.contains("doubleAll(arg0, 3);"));
+        assert!(!harness.contains("printf(\"done\\n\");"));
+        assert!(harness.contains("for (int i = 0; i < 3; i++) printf(\"%d \", arg0[i]); printf(\"\\n\");"));
+    }
+
+    #[test]
+    fn test_output_param_length_from_sibling_parameter() {
+        let user_code = "int sumAndFill(int *arr, int n) { int s = 0; for (int i = 0; i < n; i++) { s += arr[i]; arr[i] = 0; } return s; }";
+        let signature = FunctionSignature {
+            name: "sumAndFill".to_string(),
+            return_type: "int".to_string(),
+            parameters: vec![
+                FunctionParameter {
+                    name: "arr".to_string(),
+                    param_type: "int*".to_string(),
+                },
+                FunctionParameter {
+                    name: "n".to_string(),
+                    param_type: "int".to_string(),
+                },
+            ],
+            structs: vec![],
+        };
+        let test_case = TestCase {
+            input: vec![serde_json::json!([5, 10]), serde_json::json!(2)],
+            expected: "15\n0 0".to_string(),
+            sample: true,
+            output_params: vec![OutputParam {
+                param_index: 0,
+                length: OutputLength::Param("n".to_string()),
+            }],
+            match_mode: MatchMode::default(),
+        };
+
+        let harness = generate_harness(user_code, Some(&signature), &test_case).unwrap();
+        assert!(harness.contains("int arg0[] = { 5, 10 };"));
+        assert!(harness.contains("sumAndFill(arg0, 2);"));
+        assert!(harness.contains("printf(\"%d\\n\", result);"));
+        assert!(harness.contains("for (int i = 0; i < 2; i++) printf(\"%d \", arg0[i]); printf(\"\\n\");"));
+    }
+
+    #[test]
+    fn test_float_tolerance_prints_full_precision() {
+        let user_code = "double average(int a, int b) { return (a + b) / 2.0; }";
+        let signature = FunctionSignature {
+            name: "average".to_string(),
+            return_type: "double".to_string(),
+            parameters: vec![
+                FunctionParameter { name: "a".to_string(), param_type: "int".to_string() },
+                FunctionParameter { name: "b".to_string(), param_type: "int".to_string() },
+            ],
+            structs: vec![],
+        };
+        let test_case = TestCase {
+            input: vec![serde_json::json!(1), serde_json::json!(2)],
+            expected: "1.5".to_string(),
+            sample: true,
+            output_params: vec![],
+            match_mode: MatchMode::FloatTolerance { epsilon: 0.0001 },
+        };
+
+        let harness = generate_harness(user_code, Some(&signature), &test_case).unwrap();
+        assert!(harness.contains("printf(\"%.17g\\n\", result);"));
+        assert!(!harness.contains("printf(\"%lf\\n\", result);"));
+    }
+
+    #[test]
+    fn test_exact_mode_keeps_default_float_format() {
+        let user_code = "double average(int a, int b) { return (a + b) / 2.0; }";
+        let signature = FunctionSignature {
+            name: "average".to_string(),
+            return_type: "double".to_string(),
+            parameters: vec![
+                FunctionParameter { name: "a".to_string(), param_type: "int".to_string() },
+                FunctionParameter { name: "b".to_string(), param_type: "int".to_string() },
+            ],
+            structs: vec![],
+        };
+        let test_case = TestCase {
+            input: vec![serde_json::json!(1), serde_json::json!(2)],
+            expected: "1.500000".to_string(),
+            sample: true,
+            output_params: vec![],
+            match_mode: MatchMode::default(),
+        };
+
+        let harness = generate_harness(user_code, Some(&signature), &test_case).unwrap();
+        assert!(harness.contains("printf(\"%lf\\n\", result);"));
+    }
+
+    #[test]
+    fn test_pointer_return_guards_against_null_deref() {
+        let user_code = "int* find(int x) { if (x < 0) return NULL; static int v; v = x; return &v; }";
+        let signature = FunctionSignature {
+            name: "find".to_string(),
+            return_type: "int*".to_string(),
+            parameters: vec![FunctionParameter { name: "x".to_string(), param_type: "int".to_string() }],
+            structs: vec![],
+        };
+        let test_case = TestCase {
+            input: vec![serde_json::json!(-1)],
+            expected: "null".to_string(),
+            sample: true,
+            output_params: vec![],
+            match_mode: MatchMode::default(),
+        };
+
+        let harness = generate_harness(user_code, Some(&signature), &test_case).unwrap();
+        assert!(harness.contains("if (result == NULL) printf(\"null\\n\"); else printf(\"%d\\n\", (*result));"));
+    }
+
+    #[test]
+    fn test_char_pointer_return_distinguishes_null_from_empty_string() {
+        let user_code = "char* maybeEmpty(int x) { if (x < 0) return NULL; return \"\"; }";
+        let signature = FunctionSignature {
+            name: "maybeEmpty".to_string(),
+            return_type: "char*".to_string(),
+            parameters: vec![FunctionParameter { name: "x".to_string(), param_type: "int".to_string() }],
+            structs: vec![],
+        };
+        let test_case = TestCase {
+            input: vec![serde_json::json!(1)],
+            expected: "".to_string(),
+            sample: true,
+            output_params: vec![],
+            match_mode: MatchMode::default(),
+        };
+
+        let harness = generate_harness(user_code, Some(&signature), &test_case).unwrap();
+        assert!(harness.contains("if (result == NULL) printf(\"null\\n\"); else printf(\"%s\\n\", result);"));
+    }
 }
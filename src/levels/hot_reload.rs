@@ -0,0 +1,67 @@
+//! Runtime hot-reloading of a single level's JSON file, for a level
+//! designer iterating on one level without restarting the game.
+//!
+//! `LevelRegistry::load_from_json` bakes the bundled `assets/levels.json`
+//! into the binary via `include_str!`, so there's no on-disk path to watch
+//! for it. This targets the other case: a level exported to its own loose
+//! JSON file on disk, read and re-validated fresh on every change via
+//! [`reload_from_path`], with [`watch`] wrapping that in a debounced
+//! background watcher. A level that fails to parse or fails
+//! [`LevelData::validate`] is only logged - it never reaches the channel, so
+//! whatever level the caller already has stays live.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+use super::loader::LevelData;
+
+/// Rapid-fire filesystem events (most editors emit several writes per save)
+/// are coalesced into a single reload this far after the last one.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Parse and validate a level JSON file from disk. Does not touch any
+/// currently-live level - call sites decide whether/how to apply the result.
+pub fn reload_from_path(path: &Path) -> Result<LevelData, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    let json: serde_json::Value = serde_json::from_str(&contents)
+        .map_err(|e| format!("invalid JSON in {}: {}", path.display(), e))?;
+    let level = LevelData::from_json(&json)?;
+    level.validate()?;
+    Ok(level)
+}
+
+/// Watch `path` for changes, sending a freshly reloaded, already-validated
+/// [`LevelData`] over the returned channel each time it changes and still
+/// passes [`reload_from_path`]. A rejected edit is only logged to stderr -
+/// nothing is sent, so the caller's existing level is left untouched.
+pub fn watch(path: PathBuf) -> Result<Receiver<LevelData>, notify::Error> {
+    use notify::{RecursiveMode, Watcher};
+
+    let (tx, rx) = channel();
+    let (fs_tx, fs_rx) = channel();
+    let mut watcher = notify::recommended_watcher(fs_tx)?;
+    watcher.watch(&path, RecursiveMode::NonRecursive)?;
+
+    std::thread::spawn(move || {
+        // Keep the watcher alive for as long as this thread runs.
+        let _watcher = watcher;
+        while fs_rx.recv().is_ok() {
+            // Coalesce any further events already queued within the
+            // debounce window into this one reload.
+            while fs_rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+            match reload_from_path(&path) {
+                Ok(level) => {
+                    if tx.send(level).is_err() {
+                        break; // receiver gone - nothing left to reload into
+                    }
+                }
+                Err(e) => eprintln!("rejected level reload for {}: {}", path.display(), e),
+            }
+        }
+    });
+
+    Ok(rx)
+}
@@ -1,14 +1,16 @@
 use super::validator::SuccessCriteria;
 use crate::game::progression::LevelPrerequisites;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use utoipa::ToSchema;
 
 // ============================================================================
 // Function-Based Challenge System
 // ============================================================================
 
 /// Lesson content shown to player before the challenge
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Lesson {
     pub title: String,
     pub content: Vec<String>,
@@ -16,34 +18,147 @@ pub struct Lesson {
     pub examples: Vec<LessonExample>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LessonExample {
     pub code: String,
     pub explanation: String,
 }
 
 /// Function signature that player must implement
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FunctionSignature {
     pub name: String,
     pub return_type: String,
     pub parameters: Vec<FunctionParameter>,
+    /// C struct definitions referenced by `parameters`/`return_type`, keyed
+    /// by name via [`Self::find_struct`] - lets the harness generator build
+    /// and print compound literals for record-typed arguments and returns.
+    #[serde(default)]
+    pub structs: Vec<StructDef>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct FunctionParameter {
     pub name: String,
     #[serde(rename = "type")]
     pub param_type: String,
 }
 
+/// One field of a [`StructDef`], in declaration order.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StructField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+}
+
+/// A named C struct, as an ordered list of fields. Order matters: it drives
+/// both the `.field = value` compound-literal initializer list and the
+/// per-field `printf` the harness emits for a struct-typed return.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StructDef {
+    pub name: String,
+    pub fields: Vec<StructField>,
+}
+
+impl FunctionSignature {
+    /// Look up a struct definition by name, stripping a trailing `*`/`[]` so
+    /// callers can pass a raw parameter/return type string directly.
+    pub fn find_struct(&self, type_name: &str) -> Option<&StructDef> {
+        let base = type_name.trim_end_matches("[]").trim_end_matches('*').trim();
+        self.structs.iter().find(|s| s.name == base)
+    }
+}
+
+/// How many elements an [`OutputParam`]'s buffer holds.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputLength {
+    /// A fixed element count.
+    Literal(usize),
+    /// The name of another parameter in the same signature whose test-case
+    /// input value (known at harness-generation time, not runtime) gives the
+    /// count.
+    Param(String),
+}
+
+/// Marks one of a test case's array/pointer arguments as mutated in place by
+/// the call, so the harness should capture and print its post-call contents
+/// instead of (or alongside) the return value.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OutputParam {
+    /// Index into `FunctionSignature::parameters`/`TestCase::input`.
+    pub param_index: usize,
+    pub length: OutputLength,
+}
+
+/// How a test case's `expected` should be judged against a harness run's
+/// actual stdout. Exact string comparison breaks down for floating-point
+/// returns (rounding), output whose exact whitespace doesn't matter, and
+/// line-oriented/set-valued answers that are correct in a different order.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MatchMode {
+    /// Byte-for-byte comparison (after trimming), as today.
+    #[default]
+    Exact,
+    /// Parse both sides as `f64` and accept them within `epsilon`. The
+    /// harness prints the full-precision value (`%.17g`) rather than the
+    /// return type's usual format specifier so rounding in the printed
+    /// representation doesn't itself cause a false mismatch.
+    ///
+    /// For multi-value output, both sides are tokenized on whitespace first:
+    /// token counts must match, and each pair is accepted if it parses as
+    /// `f64` and is within `epsilon` (absolute or relative), else compared
+    /// as plain text.
+    FloatTolerance { epsilon: f64 },
+    /// Compare with runs of whitespace collapsed, so extra spaces or a
+    /// differently-wrapped line don't fail an otherwise-correct answer.
+    NormalizedWhitespace,
+    /// Compare line-by-line after trimming each line and dropping trailing
+    /// blank lines, for output that's correct but wrapped or indented
+    /// differently than `expected`.
+    TrimmedLines,
+    /// Sort the non-empty, trimmed lines of both sides before comparing, for
+    /// answers that are a set/multiset where order isn't meaningful.
+    Unordered,
+}
+
 /// Test case for function-based challenges
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TestCase {
     pub input: Vec<serde_json::Value>,
+    /// For a pointer-returning function, the literal text `"null"` is a
+    /// sentinel meaning "the function must return NULL", distinct from an
+    /// empty `char*` result (which prints as an empty line, not `null`).
     pub expected: String,
     #[serde(default)]
     pub sample: bool,
+    /// Pointer parameters whose post-call contents should be captured and
+    /// printed, for challenges whose point is mutating memory (in-place
+    /// sort, swap, fill) rather than returning a value.
+    #[serde(default)]
+    pub output_params: Vec<OutputParam>,
+    /// How `expected` should be compared against the harness's actual
+    /// output. Defaults to exact string comparison.
+    #[serde(default)]
+    pub match_mode: MatchMode,
+}
+
+impl TestCase {
+    /// Stable identifier derived from this case's input and expected output,
+    /// so the frontend and a later submission can both refer to the same
+    /// case without relying on its position in `test_cases` — which shifts
+    /// once TEST-mode sample filtering removes some entries.
+    pub fn stable_id(&self) -> String {
+        let mut hasher = Sha256::new();
+        for value in &self.input {
+            hasher.update(value.to_string().as_bytes());
+            hasher.update(b"\0");
+        }
+        hasher.update(self.expected.as_bytes());
+        hex::encode(hasher.finalize())
+    }
 }
 
 // ============================================================================
@@ -55,7 +170,7 @@ fn default_quest_xp() -> u32 {
 }
 
 /// Progressive teaching content for each quest
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct QuestTeaching {
     pub concept: String,
     pub explanation: String,
@@ -65,7 +180,7 @@ pub struct QuestTeaching {
 
 /// A quest is a single challenge within a level
 /// Each level can have multiple quests that must all be completed
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Quest {
     pub id: String,
     #[serde(default)]
@@ -87,7 +202,7 @@ pub struct Quest {
 }
 
 /// Quest info for frontend display (includes completion status)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct QuestInfo {
     pub id: String,
     pub order: u32,
@@ -105,7 +220,7 @@ pub struct QuestInfo {
 // World Configuration
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum WorldPreset {
     Tutorial,
@@ -115,7 +230,7 @@ pub enum WorldPreset {
 }
 
 /// Terminal placement with optional quest link
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TerminalConfig {
     pub x: f32,
     pub y: f32,
@@ -124,7 +239,7 @@ pub struct TerminalConfig {
 }
 
 /// Individual tile configuration for custom layouts
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TileConfig {
     pub x: usize,
     pub y: usize,
@@ -132,7 +247,7 @@ pub struct TileConfig {
     pub tile_type: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct WorldConfig {
     pub width: usize,
     pub height: usize,
@@ -174,7 +289,7 @@ fn default_xp() -> u32 {
     50
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Challenge {
     pub id: String,
     pub prompt: String,
@@ -182,7 +297,7 @@ pub struct Challenge {
     pub starter_code: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LevelData {
     pub id: String,
     pub title: String,
@@ -297,6 +412,38 @@ impl LevelData {
         self.user_template.as_deref().unwrap_or(&self.code_template)
     }
 
+    /// Validate a level before letting it replace a currently-live one via
+    /// `levels::hot_reload`: the world has sane bounds, the world it builds
+    /// has at least one walkable tile, and every terminal's `quest_id` (if
+    /// set) names a quest this level actually defines. `load_from_json`
+    /// never calls this - the bundled `assets/levels.json` is trusted at
+    /// compile time - it only guards levels re-parsed at runtime.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.world_config.width == 0 || self.world_config.height == 0 {
+            return Err("world_config width/height must be non-zero".to_string());
+        }
+
+        let world = crate::game::world::World::from_config(&self.world_config);
+        if !world.tiles.iter().flatten().any(|tile| tile.walkable) {
+            return Err("world has no walkable tiles".to_string());
+        }
+
+        let quest_ids: std::collections::HashSet<&str> =
+            self.get_quests().iter().map(|q| q.id.as_str()).collect();
+        for terminal in &self.world_config.terminals {
+            if let Some(ref quest_id) = terminal.quest_id {
+                if !quest_ids.contains(quest_id.as_str()) {
+                    return Err(format!(
+                        "terminal at ({}, {}) references unknown quest_id '{}'",
+                        terminal.x, terminal.y, quest_id
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Validate execution output against level criteria (legacy, only with compiler feature)
     #[cfg(feature = "compiler")]
     pub fn validate_output(&self, output: &crate::compiler::ExecutionOutput) -> bool {
@@ -316,7 +463,7 @@ pub struct LevelRegistry {
     prerequisites: HashMap<String, LevelPrerequisites>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LevelInfo {
     pub id: String,
     pub title: String,
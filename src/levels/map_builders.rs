@@ -0,0 +1,324 @@
+//! Procedural map generation via a composable builder chain, so levels
+//! aren't limited to the hand-authored JSON files `load_map_file` embeds.
+//!
+//! A [`BuilderChain`] runs one [`InitialMapBuilder`] to lay down the first
+//! draft of a [`World`] in a shared [`BuildData`], then an ordered list of
+//! [`MetaMapBuilder`]s that each mutate it in turn (culling unreachable
+//! area, stamping a prefab, placing spawn). Call [`generate`] for the
+//! default chain this module ships.
+
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::game::state::Position;
+use crate::game::world::{Tile, TileType, World};
+
+/// A rectangular room carved into the map by an [`InitialMapBuilder`],
+/// handed to later builders (e.g. to pick a prefab location inside one).
+#[derive(Debug, Clone, Copy)]
+pub struct Room {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Room {
+    pub fn center(&self) -> (usize, usize) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+}
+
+/// Shared, mutable state threaded through every builder in a
+/// [`BuilderChain`].
+pub struct BuildData {
+    pub world: World,
+    pub spawn: Option<Position>,
+    pub rooms: Vec<Room>,
+    /// A `World` clone captured after each builder step, so the generation
+    /// can later be visualized/debugged step by step.
+    pub snapshot_history: Vec<World>,
+}
+
+impl BuildData {
+    fn new(world: World) -> Self {
+        Self {
+            world,
+            spawn: None,
+            rooms: Vec::new(),
+            snapshot_history: Vec::new(),
+        }
+    }
+
+    fn snapshot(&mut self) {
+        self.snapshot_history.push(self.world.clone());
+    }
+}
+
+/// Produces the first draft of a [`World`] a [`BuilderChain`] then refines.
+pub trait InitialMapBuilder {
+    fn build_initial(&self, data: &mut BuildData, rng: &mut StdRng);
+}
+
+/// Mutates an already-initialized [`BuildData`] in place.
+pub trait MetaMapBuilder {
+    fn build_meta(&self, data: &mut BuildData, rng: &mut StdRng);
+}
+
+/// Runs one [`InitialMapBuilder`] followed by an ordered list of
+/// [`MetaMapBuilder`]s against a seeded RNG, recording a `snapshot_history`
+/// entry after each step.
+pub struct BuilderChain {
+    width: usize,
+    height: usize,
+    initial: Box<dyn InitialMapBuilder>,
+    meta: Vec<Box<dyn MetaMapBuilder>>,
+}
+
+impl BuilderChain {
+    pub fn new(width: usize, height: usize, initial: Box<dyn InitialMapBuilder>) -> Self {
+        Self {
+            width,
+            height,
+            initial,
+            meta: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, builder: Box<dyn MetaMapBuilder>) -> Self {
+        self.meta.push(builder);
+        self
+    }
+
+    pub fn build(&self, seed: u64) -> BuildData {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut data = BuildData::new(World::blank(self.width, self.height));
+
+        self.initial.build_initial(&mut data, &mut rng);
+        data.snapshot();
+
+        for builder in &self.meta {
+            builder.build_meta(&mut data, &mut rng);
+            data.snapshot();
+        }
+
+        data
+    }
+}
+
+/// Carves `room_count` random rectangular rooms into a blank canvas and
+/// connects each to the previous one with an L-shaped corridor.
+pub struct RoomsAndCorridors {
+    pub room_count: usize,
+    pub min_size: usize,
+    pub max_size: usize,
+}
+
+impl Default for RoomsAndCorridors {
+    fn default() -> Self {
+        Self {
+            room_count: 6,
+            min_size: 4,
+            max_size: 8,
+        }
+    }
+}
+
+impl InitialMapBuilder for RoomsAndCorridors {
+    fn build_initial(&self, data: &mut BuildData, rng: &mut StdRng) {
+        let (width, height) = (data.world.width, data.world.height);
+
+        for _ in 0..self.room_count {
+            if self.max_size + 2 >= width.min(height) {
+                break; // canvas too small for any room of this size
+            }
+            let w = rng.gen_range(self.min_size..=self.max_size);
+            let h = rng.gen_range(self.min_size..=self.max_size);
+            if w + 2 >= width || h + 2 >= height {
+                continue;
+            }
+            let x = rng.gen_range(1..width - w - 1);
+            let y = rng.gen_range(1..height - h - 1);
+            let room = Room { x, y, width: w, height: h };
+
+            carve_room(&mut data.world, &room);
+            if let Some(prev) = data.rooms.last() {
+                carve_corridor(&mut data.world, prev.center(), room.center());
+            }
+            data.rooms.push(room);
+        }
+    }
+}
+
+fn carve_room(world: &mut World, room: &Room) {
+    for y in room.y..room.y + room.height {
+        for x in room.x..room.x + room.width {
+            world.tiles[y][x] = Tile::floor();
+        }
+    }
+}
+
+/// Carve an L-shaped corridor: horizontal from `from` to `to`'s x, then
+/// vertical from `from`'s y to `to`.
+fn carve_corridor(world: &mut World, from: (usize, usize), to: (usize, usize)) {
+    let (x0, y0) = from;
+    let (x1, y1) = to;
+
+    let (min_x, max_x) = (x0.min(x1), x0.max(x1));
+    for x in min_x..=max_x {
+        world.tiles[y0][x] = Tile::floor();
+    }
+
+    let (min_y, max_y) = (y0.min(y1), y0.max(y1));
+    for y in min_y..=max_y {
+        world.tiles[y][x1] = Tile::floor();
+    }
+}
+
+/// Picks the first reachable floor tile (scanning top-left to bottom-right)
+/// as the spawn point. Run this before [`CullUnreachable`] so the latter has
+/// a spawn to flood-fill from.
+pub struct PlaceSpawn;
+
+impl MetaMapBuilder for PlaceSpawn {
+    fn build_meta(&self, data: &mut BuildData, _rng: &mut StdRng) {
+        let (width, height) = (data.world.width, data.world.height);
+        for y in 0..height {
+            for x in 0..width {
+                if data.world.tiles[y][x].walkable {
+                    let spawn = Position::new(x as f32 * 32.0 + 16.0, y as f32 * 32.0 + 16.0);
+                    data.spawn = Some(spawn);
+                    data.world.spawn_point = spawn;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Flood-fills from `data.spawn` and converts any tile the player can't
+/// reach into [`TileType::Void`] — a disconnected room generated by
+/// [`RoomsAndCorridors`] (or left over from a prefab) is dead weight
+/// otherwise. No-ops if no spawn has been placed yet.
+pub struct CullUnreachable;
+
+impl MetaMapBuilder for CullUnreachable {
+    fn build_meta(&self, data: &mut BuildData, _rng: &mut StdRng) {
+        let Some(spawn) = data.spawn else {
+            return;
+        };
+        let (width, height) = (data.world.width, data.world.height);
+        let start = ((spawn.x / 32.0) as usize, (spawn.y / 32.0) as usize);
+        if start.0 >= width || start.1 >= height || !data.world.tiles[start.1][start.0].walkable {
+            return;
+        }
+
+        let mut reached = vec![vec![false; width]; height];
+        let mut queue = VecDeque::new();
+        reached[start.1][start.0] = true;
+        queue.push_back(start);
+
+        while let Some((x, y)) = queue.pop_front() {
+            for (dx, dy) in [(1i32, 0i32), (-1, 0), (0, 1), (0, -1)] {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                    continue;
+                }
+                let (nx, ny) = (nx as usize, ny as usize);
+                if !reached[ny][nx] && data.world.tiles[ny][nx].walkable {
+                    reached[ny][nx] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        for y in 0..height {
+            for x in 0..width {
+                if data.world.tiles[y][x].walkable && !reached[y][x] {
+                    data.world.tiles[y][x] = Tile {
+                        tile_type: TileType::Void,
+                        walkable: false,
+                        interactable: false,
+                        quest_id: None,
+                        flipped_h: false,
+                        flipped_v: false,
+                        flipped_d: false,
+                        collision: None,
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Stamps a small fixed ASCII template into a random valid (all-floor)
+/// region of the map. `'D'` becomes a locked door, `'T'` a terminal, `'#'`
+/// a wall, anything else floor - e.g. the default template is a terminal
+/// surrounded by locked doors.
+pub struct PrefabVault {
+    pub template: Vec<&'static str>,
+}
+
+impl Default for PrefabVault {
+    fn default() -> Self {
+        Self {
+            template: vec!["#D#", "DTD", "#D#"],
+        }
+    }
+}
+
+impl MetaMapBuilder for PrefabVault {
+    fn build_meta(&self, data: &mut BuildData, rng: &mut StdRng) {
+        let vault_h = self.template.len();
+        let vault_w = self.template.iter().map(|row| row.len()).max().unwrap_or(0);
+        if vault_w == 0 || vault_h == 0 {
+            return;
+        }
+        let (width, height) = (data.world.width, data.world.height);
+        if vault_w >= width || vault_h >= height {
+            return;
+        }
+
+        let mut candidates = Vec::new();
+        for y in 0..=(height - vault_h) {
+            for x in 0..=(width - vault_w) {
+                if region_is_floor(&data.world, x, y, vault_w, vault_h) {
+                    candidates.push((x, y));
+                }
+            }
+        }
+        if candidates.is_empty() {
+            return; // no valid empty region found - leave the map as-is
+        }
+
+        let (origin_x, origin_y) = candidates[rng.gen_range(0..candidates.len())];
+        for (row_idx, row) in self.template.iter().enumerate() {
+            for (col_idx, ch) in row.chars().enumerate() {
+                let (x, y) = (origin_x + col_idx, origin_y + row_idx);
+                data.world.tiles[y][x] = match ch {
+                    'D' => Tile::door(),
+                    'T' => Tile::terminal(),
+                    '#' => Tile::wall(),
+                    _ => Tile::floor(),
+                };
+            }
+        }
+    }
+}
+
+fn region_is_floor(world: &World, x: usize, y: usize, w: usize, h: usize) -> bool {
+    (y..y + h).all(|row| (x..x + w).all(|col| world.tiles[row][col].walkable))
+}
+
+/// Run the default builder chain - rooms and corridors, spawn placement, a
+/// prefab vault, then culling anything unreachable - deterministically from
+/// `seed`.
+pub fn generate(seed: u64) -> World {
+    let chain = BuilderChain::new(50, 30, Box::new(RoomsAndCorridors::default()))
+        .with(Box::new(PlaceSpawn))
+        .with(Box::new(PrefabVault::default()))
+        .with(Box::new(CullUnreachable));
+
+    chain.build(seed).world
+}
@@ -15,6 +15,29 @@ pub struct TiledMap {
     pub layers: Vec<TiledLayer>,
     #[serde(default)]
     pub properties: Vec<TiledProperty>,
+    /// Embedded tileset definitions. When present, these drive GID→`Tile`
+    /// resolution via each tile's custom `properties` instead of the
+    /// hardcoded numbering in `tile_id_to_tile` — see `resolve_gid`.
+    #[serde(default)]
+    pub tilesets: Vec<Tileset>,
+}
+
+/// One `tilesets` entry from Tiled JSON: the GID range it owns
+/// (`firstgid..`) and any per-tile custom properties declared on it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Tileset {
+    pub firstgid: u32,
+    #[serde(default)]
+    pub tiles: Vec<TilesetTile>,
+}
+
+/// A single tile definition within a `Tileset`, keyed by its *local* id
+/// (i.e. GID minus the owning tileset's `firstgid`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct TilesetTile {
+    pub id: u32,
+    #[serde(default)]
+    pub properties: Vec<TiledProperty>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -23,7 +46,14 @@ pub enum TiledLayer {
     #[serde(rename = "tilelayer")]
     TileLayer {
         name: String,
-        data: Vec<u32>,
+        /// Either a plain GID array (default/`"csv"` encoding) or a base64
+        /// string, optionally zlib/gzip/zstd-compressed — see
+        /// [`decode_tile_layer_data`].
+        data: serde_json::Value,
+        #[serde(default)]
+        encoding: Option<String>,
+        #[serde(default)]
+        compression: Option<String>,
         width: usize,
         height: usize,
     },
@@ -34,6 +64,78 @@ pub enum TiledLayer {
     },
 }
 
+/// Decode a Tiled tile layer's `data` into raw GIDs (flip flags still set —
+/// callers mask those off via [`gid_to_tile`]), handling the encodings Tiled
+/// can export: a plain JSON array (no `encoding`, or `"csv"`), or a base64
+/// string optionally compressed with zlib, gzip, or zstd.
+pub fn decode_tile_layer_data(
+    data: &serde_json::Value,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u32>, String> {
+    match encoding {
+        None | Some("csv") => data
+            .as_array()
+            .ok_or_else(|| "tile layer data must be an array for csv/default encoding".to_string())?
+            .iter()
+            .map(|v| {
+                v.as_u64()
+                    .map(|n| n as u32)
+                    .ok_or_else(|| "tile layer data entries must be integers".to_string())
+            })
+            .collect(),
+        Some("base64") => {
+            use base64::{engine::general_purpose::STANDARD, Engine};
+
+            let encoded = data
+                .as_str()
+                .ok_or_else(|| "base64-encoded tile layer data must be a string".to_string())?;
+            let raw = STANDARD
+                .decode(encoded.trim())
+                .map_err(|e| format!("invalid base64 tile layer data: {}", e))?;
+
+            let bytes = match compression {
+                None => raw,
+                Some("zlib") => inflate_zlib(&raw)?,
+                Some("gzip") => inflate_gzip(&raw)?,
+                Some("zstd") => {
+                    zstd::stream::decode_all(&raw[..]).map_err(|e| format!("failed to decompress zstd tile layer data: {}", e))?
+                }
+                Some(other) => return Err(format!("unsupported tile layer compression: {}", other)),
+            };
+
+            if bytes.len() % 4 != 0 {
+                return Err(
+                    "decoded tile layer byte stream isn't a whole number of u32 GIDs".to_string(),
+                );
+            }
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .collect())
+        }
+        Some(other) => Err(format!("unsupported tile layer encoding: {}", other)),
+    }
+}
+
+fn inflate_zlib(raw: &[u8]) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+    let mut out = Vec::new();
+    flate2::read::ZlibDecoder::new(raw)
+        .read_to_end(&mut out)
+        .map_err(|e| format!("failed to inflate zlib tile layer data: {}", e))?;
+    Ok(out)
+}
+
+fn inflate_gzip(raw: &[u8]) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+    let mut out = Vec::new();
+    flate2::read::GzDecoder::new(raw)
+        .read_to_end(&mut out)
+        .map_err(|e| format!("failed to inflate gzip tile layer data: {}", e))?;
+    Ok(out)
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct TiledObject {
     #[serde(default)]
@@ -93,6 +195,9 @@ pub struct LegacyObject {
 pub enum MapFormat {
     Tiled(TiledMap),
     Legacy(LegacyMap),
+    /// Produced algorithmically by `levels::map_builders::generate` rather
+    /// than parsed from a file.
+    Generated(World),
 }
 
 impl MapFormat {
@@ -100,32 +205,79 @@ impl MapFormat {
         match self {
             MapFormat::Tiled(map) => map.to_world(),
             MapFormat::Legacy(map) => map.to_world(),
+            MapFormat::Generated(world) => world.clone(),
         }
     }
 }
 
 impl TiledMap {
+    /// Find the tileset owning local tile id `gid` (flip bits already
+    /// masked off): the one with the largest `firstgid` that's still
+    /// `<= gid`, matching how Tiled itself resolves a GID to a tileset.
+    fn tileset_for_gid(&self, gid: u32) -> Option<&Tileset> {
+        self.tilesets
+            .iter()
+            .filter(|ts| ts.firstgid <= gid)
+            .max_by_key(|ts| ts.firstgid)
+    }
+
+    /// Resolve a raw GID (flip flags still set) into a [`Tile`]. Prefers the
+    /// owning tileset's declared per-tile `properties`; falls back to the
+    /// legacy hardcoded numbering in [`tile_id_to_tile`] when no tileset
+    /// covers the GID, or the tileset doesn't define that particular tile.
+    fn resolve_gid(&self, gid: u32) -> Tile {
+        let masked = gid & GID_MASK;
+        let mut tile = match self.tileset_for_gid(masked) {
+            Some(tileset) => {
+                let local_id = masked - tileset.firstgid;
+                match tileset.tiles.iter().find(|t| t.id == local_id) {
+                    Some(tile_def) => tile_from_properties(&tile_def.properties),
+                    None => tile_id_to_tile(masked),
+                }
+            }
+            None => tile_id_to_tile(masked),
+        };
+        tile.flipped_h = gid & GID_FLIP_H != 0;
+        tile.flipped_v = gid & GID_FLIP_V != 0;
+        tile.flipped_d = gid & GID_FLIP_D != 0;
+        tile
+    }
+
     pub fn to_world(&self) -> World {
         let mut world = World::new(self.width, self.height);
 
         for layer in &self.layers {
             match layer {
                 TiledLayer::TileLayer {
-                    name, data, width, ..
+                    name,
+                    data,
+                    encoding,
+                    compression,
+                    width,
+                    ..
                 } => {
+                    let gids = match decode_tile_layer_data(
+                        data,
+                        encoding.as_deref(),
+                        compression.as_deref(),
+                    ) {
+                        Ok(gids) => gids,
+                        Err(_) => continue, // malformed layer - skip, leave tiles at defaults
+                    };
+
                     if name == "floor" || name == "tiles" {
-                        for (i, &tile_id) in data.iter().enumerate() {
+                        for (i, &gid) in gids.iter().enumerate() {
                             let x = i % width;
                             let y = i / width;
                             if y < self.height && x < self.width {
-                                world.tiles[y][x] = tile_id_to_tile(tile_id);
+                                world.tiles[y][x] = self.resolve_gid(gid);
                             }
                         }
                     } else if name == "collision" {
-                        for (i, &tile_id) in data.iter().enumerate() {
+                        for (i, &gid) in gids.iter().enumerate() {
                             let x = i % width;
                             let y = i / width;
-                            if y < self.height && x < self.width && tile_id != 0 {
+                            if y < self.height && x < self.width && (gid & GID_MASK) != 0 {
                                 // Assuming non-zero in collision layer means blocked
                                 world.tiles[y][x].walkable = false;
                             }
@@ -165,6 +317,11 @@ impl TiledMap {
                                         tile_type: TileType::Door,
                                         walkable: !is_locked,
                                         interactable: true,
+                                        quest_id: None,
+                                        flipped_h: false,
+                                        flipped_v: false,
+                                        flipped_d: false,
+                                        collision: None,
                                     };
                                 }
                             }
@@ -209,6 +366,11 @@ impl LegacyMap {
                             tile_type: TileType::Door,
                             walkable: !obj.locked,
                             interactable: true,
+                            quest_id: None,
+                            flipped_h: false,
+                            flipped_v: false,
+                            flipped_d: false,
+                            collision: None,
                         };
                     }
                     _ => {}
@@ -227,6 +389,11 @@ fn tile_id_to_tile(id: u32) -> Tile {
             tile_type: TileType::Void,
             walkable: false,
             interactable: false,
+            quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         },
         1 => Tile::floor(),
         2 => Tile::floor(), // Tech floor is still floor
@@ -239,12 +406,88 @@ fn tile_id_to_tile(id: u32) -> Tile {
             tile_type: TileType::Door,
             walkable: true,
             interactable: true,
+            quest_id: None,
+            flipped_h: false,
+            flipped_v: false,
+            flipped_d: false,
+            collision: None,
         },
         _ => Tile::floor(),
     }
 }
 
+/// Top three bits of a Tiled GID are horizontal/vertical/diagonal flip
+/// flags; the remaining 29 bits are the actual tile id.
+const GID_FLIP_H: u32 = 0x8000_0000;
+const GID_FLIP_V: u32 = 0x4000_0000;
+const GID_FLIP_D: u32 = 0x2000_0000;
+const GID_MASK: u32 = 0x1FFF_FFFF;
+
+/// Build a [`Tile`] from a tileset tile's custom `properties`
+/// (`"walkable"`, `"interactable"`, `"tile_type"`, `"locked"`), defaulting
+/// anything unspecified to a plain walkable floor tile.
+fn tile_from_properties(properties: &[TiledProperty]) -> Tile {
+    let mut tile = Tile::floor();
+    for prop in properties {
+        match prop.name.as_str() {
+            "walkable" => {
+                if let Some(v) = prop.value.as_bool() {
+                    tile.walkable = v;
+                }
+            }
+            "interactable" => {
+                if let Some(v) = prop.value.as_bool() {
+                    tile.interactable = v;
+                }
+            }
+            "locked" => {
+                if let Some(v) = prop.value.as_bool() {
+                    tile.walkable = !v;
+                }
+            }
+            "tile_type" => {
+                if let Some(name) = prop.value.as_str() {
+                    if let Some(tile_type) = parse_tile_type(name) {
+                        tile.tile_type = tile_type;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    tile
+}
+
+fn parse_tile_type(name: &str) -> Option<TileType> {
+    Some(match name {
+        "floor" => TileType::Floor,
+        "wall" => TileType::Wall,
+        "water" => TileType::Water,
+        "void" => TileType::Void,
+        "door" => TileType::Door,
+        "terminal" => TileType::Terminal,
+        "tree" => TileType::Tree,
+        "rock" => TileType::Rock,
+        "lava" => TileType::Lava,
+        "ice" => TileType::Ice,
+        "bridge" => TileType::Bridge,
+        "grass" => TileType::Grass,
+        "path" => TileType::Path,
+        "pit" => TileType::Pit,
+        _ => return None,
+    })
+}
+
 pub fn load_map_file(map_path: &str) -> Result<MapFormat, String> {
+    // "generated:<seed>" selects a procedurally generated map instead of a
+    // bundled JSON file - see `levels::map_builders::generate`.
+    if let Some(seed_str) = map_path.strip_prefix("generated:") {
+        let seed: u64 = seed_str
+            .parse()
+            .map_err(|_| format!("invalid seed in generated map path: {}", map_path))?;
+        return Ok(MapFormat::Generated(super::map_builders::generate(seed)));
+    }
+
     let json_str = match map_path {
         // Themed maps (new unique layouts)
         "maps/L01_first_spell.json" => include_str!("../assets/maps/L01_first_spell.json"),
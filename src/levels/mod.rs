@@ -1,14 +1,28 @@
+pub mod diff;
 pub mod harness;
+pub mod hot_reload;
 pub mod loader;
+pub mod map_builders;
 pub mod map_loader;
 pub mod puzzle;
+pub mod runner;
+pub mod signature;
 pub mod validator;
 
 pub use harness::generate_harness;
+pub use signature::parse_signature;
+pub use hot_reload::{reload_from_path as reload_level_from_path, watch as watch_level};
 pub use loader::{
     Challenge, FunctionParameter, FunctionSignature, Lesson, LessonExample, LevelData, LevelInfo,
     LevelRegistry, TestCase, WorldConfig, WorldPreset,
 };
+pub use map_builders::{
+    generate as generate_map, BuildData, BuilderChain, CullUnreachable, InitialMapBuilder,
+    MetaMapBuilder, PlaceSpawn, PrefabVault, Room, RoomsAndCorridors,
+};
+pub use diff::{diff_output, DiffKind, DiffSpan};
 pub use map_loader::{load_map_file, MapFormat};
 pub use puzzle::PuzzleState;
-pub use validator::{SuccessCriteria, TestCaseResult, TestSuiteResult};
+#[cfg(feature = "compiler")]
+pub use runner::{diagnose_failure, run_test_suite, run_test_suite_with_coverage, TestSuiteRun};
+pub use validator::{Outcome, SuccessCriteria, TestCaseResult, TestSuiteResult};
@@ -0,0 +1,206 @@
+//! Concurrent test-suite execution, shared by the desktop (Tauri) and web
+//! (axum) frontends so both get the same parallelism, ordering, and
+//! early-exit behavior instead of duplicating the run loop.
+
+#[cfg(feature = "compiler")]
+use futures::stream::{self, StreamExt};
+
+use super::diff::diff_output;
+use super::harness::generate_harness;
+use super::loader::{FunctionSignature, TestCase};
+use super::validator::{classify_outcome, Outcome, TestCaseResult};
+
+#[cfg(feature = "compiler")]
+use crate::compiler::{CCompiler, CoverageReport};
+
+/// Outcome of running a test suite.
+#[cfg(feature = "compiler")]
+pub enum TestSuiteRun {
+    /// Every harness compiled; results are in the same order as the input
+    /// `test_cases`.
+    Ran {
+        results: Vec<TestCaseResult>,
+        total_time_ms: u64,
+    },
+    /// At least one harness failed to compile; the rest were cancelled.
+    CompileError {
+        message: String,
+        stderr: String,
+        /// Time spent on harnesses that finished before the failing one.
+        total_time_ms: u64,
+    },
+}
+
+/// Compile and run `test_cases` against `code`, up to `compiler.parallelism()`
+/// at a time, preserving input order in the returned results.
+///
+/// Harnesses are generated up front so a bad one surfaces as an `Err` before
+/// any process is spawned. If any harness fails to compile, the remaining
+/// in-flight and not-yet-started runs are cancelled (by dropping the
+/// underlying stream) and `TestSuiteRun::CompileError` is returned instead of
+/// partial results.
+#[cfg(feature = "compiler")]
+pub async fn run_test_suite(
+    compiler: &CCompiler,
+    code: &str,
+    signature: &FunctionSignature,
+    test_cases: &[&TestCase],
+) -> Result<TestSuiteRun, String> {
+    let harnesses: Vec<(usize, String)> = test_cases
+        .iter()
+        .enumerate()
+        .map(|(i, tc)| generate_harness(code, Some(signature), tc).map(|h| (i, h)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to generate test harness: {}", e))?;
+
+    let mut runs = stream::iter(harnesses.into_iter().map(|(i, harness)| async move {
+        let result = compiler.compile_and_run(&harness).await;
+        (i, result)
+    }))
+    .buffer_unordered(compiler.parallelism());
+
+    let mut total_time_ms = 0u64;
+    let mut indexed: Vec<(usize, TestCaseResult)> = Vec::with_capacity(test_cases.len());
+
+    while let Some((i, execution_result)) = runs.next().await {
+        let execution_result = execution_result?;
+        total_time_ms += execution_result.execution_time_ms;
+
+        if let Some(message) = execution_result.compile_error {
+            // Dropping `runs` cancels every other in-flight/queued harness.
+            drop(runs);
+            return Ok(TestSuiteRun::CompileError {
+                message,
+                stderr: execution_result.stderr,
+                total_time_ms,
+            });
+        }
+
+        let actual = execution_result.stdout.trim().to_string();
+        let expected = test_cases[i].expected.trim().to_string();
+        let outcome = classify_outcome(&execution_result, &actual, &expected, &test_cases[i].match_mode);
+        let passed = matches!(outcome, Outcome::Passed);
+        let diff = (!passed).then(|| diff_output(&expected, &actual));
+
+        indexed.push((
+            i,
+            TestCaseResult {
+                id: test_cases[i].stable_id(),
+                input: test_cases[i].input.clone(),
+                expected,
+                actual,
+                passed,
+                outcome,
+                diff,
+            },
+        ));
+    }
+
+    indexed.sort_by_key(|(i, _)| *i);
+    let results = indexed.into_iter().map(|(_, r)| r).collect();
+
+    Ok(TestSuiteRun::Ran {
+        results,
+        total_time_ms,
+    })
+}
+
+/// Like [`run_test_suite`], but compiles every harness with gcov
+/// instrumentation and merges their per-line hit counts into one report.
+///
+/// Each test case already compiles to its own harness (the test input is
+/// baked into the generated source, not passed via stdin), but every
+/// harness shares the same `user_code` at the same line offsets - so
+/// summing hit counts across per-harness reports gives an accurate picture
+/// of which lines of the *learner's* code any test reached. Runs
+/// sequentially rather than through `compiler.parallelism()`: coverage
+/// mode is already the deliberately-slower opt-in path, and it avoids
+/// piling up concurrent `gcc`/`gcov` invocations on top of each other.
+#[cfg(feature = "compiler")]
+pub async fn run_test_suite_with_coverage(
+    compiler: &CCompiler,
+    code: &str,
+    signature: &FunctionSignature,
+    test_cases: &[&TestCase],
+) -> Result<(TestSuiteRun, Option<CoverageReport>), String> {
+    let harnesses: Vec<(usize, String)> = test_cases
+        .iter()
+        .enumerate()
+        .map(|(i, tc)| generate_harness(code, Some(signature), tc).map(|h| (i, h)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to generate test harness: {}", e))?;
+
+    let mut total_time_ms = 0u64;
+    let mut indexed: Vec<(usize, TestCaseResult)> = Vec::with_capacity(test_cases.len());
+    let mut coverage: Option<CoverageReport> = None;
+
+    for (i, harness) in harnesses {
+        let (execution_result, run_coverage) =
+            compiler.compile_and_run_with_coverage(&harness, None).await?;
+        total_time_ms += execution_result.execution_time_ms;
+
+        if let Some(message) = execution_result.compile_error {
+            return Ok((
+                TestSuiteRun::CompileError {
+                    message,
+                    stderr: execution_result.stderr,
+                    total_time_ms,
+                },
+                coverage,
+            ));
+        }
+
+        if let Some(run_coverage) = run_coverage {
+            match &mut coverage {
+                Some(acc) => acc.merge(&run_coverage),
+                None => coverage = Some(run_coverage),
+            }
+        }
+
+        let actual = execution_result.stdout.trim().to_string();
+        let expected = test_cases[i].expected.trim().to_string();
+        let outcome = classify_outcome(&execution_result, &actual, &expected, &test_cases[i].match_mode);
+        let passed = matches!(outcome, Outcome::Passed);
+        let diff = (!passed).then(|| diff_output(&expected, &actual));
+
+        indexed.push((
+            i,
+            TestCaseResult {
+                id: test_cases[i].stable_id(),
+                input: test_cases[i].input.clone(),
+                expected,
+                actual,
+                passed,
+                outcome,
+                diff,
+            },
+        ));
+    }
+
+    indexed.sort_by_key(|(i, _)| *i);
+    let results = indexed.into_iter().map(|(_, r)| r).collect();
+
+    Ok((
+        TestSuiteRun::Ran {
+            results,
+            total_time_ms,
+        },
+        coverage,
+    ))
+}
+
+/// First actionable diagnostic among `results`, if any test crashed, timed
+/// out, or produced no comparable output — lets a caller show e.g. "Test 3
+/// timed out" instead of a generic "N/M tests passed" when something more
+/// specific than a wrong answer happened.
+#[cfg(feature = "compiler")]
+pub fn diagnose_failure(results: &[TestCaseResult]) -> Option<String> {
+    results.iter().enumerate().find_map(|(i, r)| match &r.outcome {
+        Outcome::TimedOut => Some(format!("Test {} timed out", i + 1)),
+        Outcome::RuntimeError { signal_or_code } => {
+            Some(format!("Test {} crashed ({})", i + 1, signal_or_code))
+        }
+        Outcome::Inconclusive => Some(format!("Test {} produced no output to check", i + 1)),
+        Outcome::Passed | Outcome::Failed => None,
+    })
+}
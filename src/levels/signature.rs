@@ -0,0 +1,274 @@
+//! Infers a [`FunctionSignature`] by scanning a user's C source for its
+//! first top-level function definition, so callers don't have to keep a
+//! hand-maintained signature in lockstep with code that already says the
+//! same thing.
+
+use super::loader::{FunctionParameter, FunctionSignature};
+
+/// Parse the first top-level function definition out of `user_code` and
+/// return its inferred [`FunctionSignature`].
+///
+/// Only a *definition* (parameter list followed by a `{`) counts - a bare
+/// prototype declaration (`int add(int a, int b);`) is skipped and scanning
+/// continues, since it carries no body to harness against.
+pub fn parse_signature(user_code: &str) -> Result<FunctionSignature, String> {
+    let chars: Vec<char> = user_code.chars().collect();
+    let len = chars.len();
+    let mut search_from = 0usize;
+
+    loop {
+        let open_paren = chars[search_from..]
+            .iter()
+            .position(|&c| c == '(')
+            .map(|p| p + search_from);
+        let open_paren = match open_paren {
+            Some(p) => p,
+            None => return Err("No function definition found in user code".to_string()),
+        };
+
+        let mut depth = 1i32;
+        let mut idx = open_paren + 1;
+        let mut close_paren = None;
+        while idx < len {
+            match chars[idx] {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close_paren = Some(idx);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            idx += 1;
+        }
+        let close_paren = match close_paren {
+            Some(p) => p,
+            None => return Err("Unbalanced parentheses in user code".to_string()),
+        };
+
+        // A definition's parameter list is followed by '{'; a prototype's by
+        // ';'. Anything else (a call expression, for instance) isn't a
+        // function header at all, so keep scanning past it either way.
+        let mut after = close_paren + 1;
+        while after < len && chars[after].is_whitespace() {
+            after += 1;
+        }
+        if after >= len || chars[after] != '{' {
+            search_from = close_paren + 1;
+            continue;
+        }
+
+        let before: String = chars[..open_paren].iter().collect();
+        // Only consider text since the previous statement/block boundary, so
+        // an earlier unrelated declaration on the same line isn't dragged in
+        // as part of the return type.
+        let before = before
+            .rsplit(|c| c == ';' || c == '}')
+            .next()
+            .unwrap_or(&before)
+            .trim();
+
+        let (return_type, name) = split_type_and_ident(before)?;
+
+        let params_text: String = chars[open_paren + 1..close_paren].iter().collect();
+        let params_text = params_text.trim();
+
+        let parameters = if params_text.is_empty() || params_text == "void" {
+            Vec::new()
+        } else {
+            split_top_level_commas(params_text)
+                .iter()
+                .map(|p| classify_param(p))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        return Ok(FunctionSignature {
+            name,
+            return_type,
+            parameters,
+            structs: Vec::new(),
+        });
+    }
+}
+
+/// Split a parameter list on commas at paren-depth zero, so a nested
+/// parenthesized type (e.g. a function-pointer parameter) isn't split apart.
+fn split_top_level_commas(text: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut current = String::new();
+    for c in text.chars() {
+        match c {
+            '(' => {
+                depth += 1;
+                current.push(c);
+            }
+            ')' => {
+                depth -= 1;
+                current.push(c);
+            }
+            ',' if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current = String::new();
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+/// Split `"<type tokens> <*...>ident"` into `(type, ident)`, normalizing
+/// pointer stars (whether attached to the type or the identifier) onto the
+/// type, and stripping a leading `struct` keyword to match the bare-name
+/// convention [`FunctionSignature::find_struct`] expects.
+fn split_type_and_ident(raw: &str) -> Result<(String, String), String> {
+    let star_count = raw.matches('*').count();
+    let without_stars = raw.replace('*', " ");
+    let mut tokens: Vec<&str> = without_stars.split_whitespace().collect();
+    if tokens.is_empty() {
+        return Err(format!("Could not parse declaration: '{}'", raw));
+    }
+    let ident = tokens.pop().unwrap().to_string();
+    if tokens.is_empty() {
+        return Err(format!("Missing type in declaration: '{}'", raw));
+    }
+    let mut base_type = tokens.join(" ");
+    if let Some(stripped) = base_type.strip_prefix("struct ") {
+        base_type = stripped.to_string();
+    }
+    let type_str = if star_count > 0 {
+        format!("{}{}", base_type, "*".repeat(star_count))
+    } else {
+        base_type
+    };
+    Ok((type_str, ident))
+}
+
+/// Classify a single parameter declaration into a [`FunctionParameter`],
+/// stripping `const` and normalizing `int arr[]`-style array params to the
+/// `int*` spelling [`super::harness::format_single_arg`] already keys on.
+fn classify_param(raw: &str) -> Result<FunctionParameter, String> {
+    let mut raw = raw.trim().to_string();
+    if let Some(stripped) = raw.strip_prefix("const ") {
+        raw = stripped.trim().to_string();
+    }
+
+    let mut is_array = false;
+    if let Some(stripped) = raw.strip_suffix("[]") {
+        raw = stripped.trim().to_string();
+        is_array = true;
+    }
+
+    let (mut param_type, name) = split_type_and_ident(&raw)?;
+    if is_array && !param_type.ends_with('*') {
+        param_type.push('*');
+    }
+
+    Ok(FunctionParameter { name, param_type })
+}
+
+/// Whether two signatures describe the same callable shape: same name,
+/// return type, and parameter types in order. Parameter *names* are allowed
+/// to differ, since an author-supplied signature may use more descriptive
+/// names than the ones in the player-facing stub.
+pub fn signatures_agree(a: &FunctionSignature, b: &FunctionSignature) -> bool {
+    a.name == b.name
+        && a.return_type == b.return_type
+        && a.parameters.len() == b.parameters.len()
+        && a.parameters
+            .iter()
+            .zip(b.parameters.iter())
+            .all(|(pa, pb)| pa.param_type == pb.param_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_simple_signature() {
+        let sig = parse_signature("int add(int a, int b) { return a + b; }").unwrap();
+        assert_eq!(sig.name, "add");
+        assert_eq!(sig.return_type, "int");
+        assert_eq!(sig.parameters.len(), 2);
+        assert_eq!(sig.parameters[0].name, "a");
+        assert_eq!(sig.parameters[0].param_type, "int");
+        assert_eq!(sig.parameters[1].name, "b");
+    }
+
+    #[test]
+    fn test_parses_void_parameter_list() {
+        let sig = parse_signature("void greet(void) { printf(\"hi\\n\"); }").unwrap();
+        assert_eq!(sig.name, "greet");
+        assert!(sig.parameters.is_empty());
+
+        let sig = parse_signature("void greet() { printf(\"hi\\n\"); }").unwrap();
+        assert!(sig.parameters.is_empty());
+    }
+
+    #[test]
+    fn test_normalizes_pointer_and_array_params() {
+        let sig = parse_signature(
+            "int sumArr(int *arr, int n) { int s = 0; for (int i = 0; i < n; i++) s += arr[i]; return s; }",
+        )
+        .unwrap();
+        assert_eq!(sig.parameters[0].param_type, "int*");
+
+        let sig = parse_signature(
+            "int sumArr(int arr[], int n) { int s = 0; for (int i = 0; i < n; i++) s += arr[i]; return s; }",
+        )
+        .unwrap();
+        assert_eq!(sig.parameters[0].param_type, "int*");
+
+        let sig = parse_signature("int headOf(int* arr) { return arr[0]; }").unwrap();
+        assert_eq!(sig.parameters[0].param_type, "int*");
+    }
+
+    #[test]
+    fn test_strips_const_and_struct_keyword() {
+        let sig = parse_signature(
+            "struct Point shift(const struct Point p) { return p; }",
+        )
+        .unwrap();
+        assert_eq!(sig.return_type, "Point");
+        assert_eq!(sig.parameters[0].param_type, "Point");
+    }
+
+    #[test]
+    fn test_skips_prototype_and_finds_definition() {
+        let sig = parse_signature(
+            "int add(int a, int b);\nint add(int a, int b) { return a + b; }",
+        )
+        .unwrap();
+        assert_eq!(sig.name, "add");
+    }
+
+    #[test]
+    fn test_errors_when_no_definition_found() {
+        assert!(parse_signature("int add(int a, int b);").is_err());
+        assert!(parse_signature("// just a comment, no code").is_err());
+    }
+
+    #[test]
+    fn test_signatures_agree() {
+        let declared = FunctionSignature {
+            name: "add".to_string(),
+            return_type: "int".to_string(),
+            parameters: vec![
+                FunctionParameter { name: "x".to_string(), param_type: "int".to_string() },
+                FunctionParameter { name: "y".to_string(), param_type: "int".to_string() },
+            ],
+            structs: vec![],
+        };
+        let inferred = parse_signature("int add(int a, int b) { return a + b; }").unwrap();
+        assert!(signatures_agree(&declared, &inferred));
+
+        let mismatched = parse_signature("int add(int a, double b) { return 0; }").unwrap();
+        assert!(!signatures_agree(&declared, &mismatched));
+    }
+}
@@ -3,12 +3,16 @@ use crate::compiler::ExecutionOutput;
 #[cfg(feature = "compiler")]
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[cfg(feature = "compiler")]
+use super::loader::MatchMode;
 
 // ============================================================================
 // Legacy Success Criteria (for backward compatibility)
 // ============================================================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SuccessCriteria {
     /// Output must match exactly
@@ -23,6 +27,20 @@ pub enum SuccessCriteria {
     /// Code must compile without errors (no output check)
     CompileOnly,
 
+    /// Execution must finish within a wall-clock budget
+    WithinTime { max_ms: u64 },
+
+    /// Peak resident set size must stay under a budget. Passes vacuously if
+    /// the sandbox backend didn't report `peak_memory_kb` (not every backend
+    /// does), since there's nothing to compare against.
+    MaxMemory { kib: u64 },
+
+    /// Program must not have written anything to stderr
+    NoStderr,
+
+    /// Program must exit with a specific status code
+    ExitCode { code: i32 },
+
     /// Multiple criteria must all pass
     All { criteria: Vec<SuccessCriteria> },
 
@@ -49,6 +67,16 @@ impl SuccessCriteria {
 
             SuccessCriteria::CompileOnly => output.compile_success(),
 
+            SuccessCriteria::WithinTime { max_ms } => output.execution_time_ms <= *max_ms,
+
+            SuccessCriteria::MaxMemory { kib } => {
+                output.peak_memory_kb.map(|peak| peak <= *kib).unwrap_or(true)
+            }
+
+            SuccessCriteria::NoStderr => output.stderr.is_empty(),
+
+            SuccessCriteria::ExitCode { code } => output.exit_code == Some(*code),
+
             SuccessCriteria::All { criteria } => criteria.iter().all(|c| c.validate(output)),
 
             SuccessCriteria::Any { criteria } => criteria.iter().any(|c| c.validate(output)),
@@ -60,13 +88,166 @@ impl SuccessCriteria {
 // New Test Suite Results (for function-based challenges)
 // ============================================================================
 
+/// How a single test case finished, beyond a bare pass/fail bool.
+///
+/// `passed` on [`TestCaseResult`] stays as the quick summary consumers
+/// already branch on; `outcome` is what lets callers tell a wrong answer
+/// apart from a crash, a timeout, or a harness that never printed anything
+/// comparable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Outcome {
+    /// Program exited cleanly and stdout matched `expected`.
+    Passed,
+    /// Program exited cleanly but stdout didn't match `expected`.
+    Failed,
+    /// Killed by the execution timeout before it could finish.
+    TimedOut,
+    /// Terminated by a signal, or exited non-zero without printing
+    /// anything comparable (e.g. crashed before reaching the `printf`).
+    RuntimeError { signal_or_code: String },
+    /// Exited cleanly but produced no output to compare against `expected`.
+    Inconclusive,
+}
+
+/// Classify how a harness run finished, from its raw execution result.
+///
+/// Only reached for test cases that compiled; a harness that fails to
+/// compile surfaces via [`super::runner::TestSuiteRun::CompileError`]
+/// instead and never gets a [`TestCaseResult`].
+#[cfg(feature = "compiler")]
+pub fn classify_outcome(
+    execution_result: &ExecutionOutput,
+    actual: &str,
+    expected: &str,
+    match_mode: &MatchMode,
+) -> Outcome {
+    if execution_result.timed_out {
+        return Outcome::TimedOut;
+    }
+
+    if let Some(signal) = execution_result.term_signal {
+        return Outcome::RuntimeError {
+            signal_or_code: describe_signal(signal),
+        };
+    }
+
+    match execution_result.exit_code {
+        Some(0) => {
+            if actual.is_empty() && !expected.is_empty() {
+                Outcome::Inconclusive
+            } else if judge(expected, actual, match_mode) {
+                Outcome::Passed
+            } else {
+                Outcome::Failed
+            }
+        }
+        Some(code) if actual.is_empty() => Outcome::RuntimeError {
+            signal_or_code: format!("exited with code {}", code),
+        },
+        Some(_) => {
+            if judge(expected, actual, match_mode) {
+                Outcome::Passed
+            } else {
+                Outcome::Failed
+            }
+        }
+        None => Outcome::Inconclusive,
+    }
+}
+
+/// Judge a harness's actual output against the expected string under the
+/// test case's chosen [`MatchMode`], so a solution isn't failed over
+/// formatting the problem statement never asked it to get exactly right.
+#[cfg(feature = "compiler")]
+pub fn judge(expected: &str, actual: &str, mode: &MatchMode) -> bool {
+    match mode {
+        MatchMode::Exact => actual == expected,
+
+        MatchMode::FloatTolerance { epsilon } => {
+            let actual_tokens: Vec<&str> = actual.split_whitespace().collect();
+            let expected_tokens: Vec<&str> = expected.split_whitespace().collect();
+            actual_tokens.len() == expected_tokens.len()
+                && actual_tokens.iter().zip(expected_tokens.iter()).all(|(a, e)| {
+                    match (a.parse::<f64>(), e.parse::<f64>()) {
+                        (Ok(a), Ok(e)) => {
+                            let diff = (a - e).abs();
+                            diff <= *epsilon || (e != 0.0 && diff / e.abs() <= *epsilon)
+                        }
+                        _ => a == e,
+                    }
+                })
+        }
+
+        MatchMode::NormalizedWhitespace => actual.split_whitespace().eq(expected.split_whitespace()),
+
+        MatchMode::TrimmedLines => trimmed_nonblank_tail(actual) == trimmed_nonblank_tail(expected),
+
+        MatchMode::Unordered => {
+            let mut actual_lines = trimmed_nonblank_lines(actual);
+            let mut expected_lines = trimmed_nonblank_lines(expected);
+            actual_lines.sort();
+            expected_lines.sort();
+            actual_lines == expected_lines
+        }
+    }
+}
+
+/// Every non-empty line of `s`, trimmed.
+fn trimmed_nonblank_lines(s: &str) -> Vec<&str> {
+    s.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).collect()
+}
+
+/// `s` split into trimmed lines with trailing blank lines dropped, but
+/// interior blank lines kept - unlike [`trimmed_nonblank_lines`], this
+/// preserves line order and count for everything but the trailing padding.
+fn trimmed_nonblank_tail(s: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = s.lines().map(|l| l.trim()).collect();
+    while lines.last().map_or(false, |l| l.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Turn a raw signal number into the kind of phrase a learner would
+/// recognize (`"segmentation fault"` rather than `"signal 11"`).
+///
+/// The sandbox backends that populate `term_signal` only ever run on Linux
+/// (seccomp is Linux-only; the bwrap/fallback backends used elsewhere run on
+/// whatever OS the server is on, but production is Linux), so these are the
+/// standard Linux `signal(7)` numbers rather than `libc` constants — no
+/// point pulling in a platform-gated dependency for a handful of numbers
+/// that don't change.
+#[cfg(feature = "compiler")]
+fn describe_signal(signal: i32) -> String {
+    match signal {
+        6 => "aborted (assertion failure or abort())".to_string(),
+        7 => "bus error (misaligned or invalid memory access)".to_string(),
+        8 => "floating point exception (likely divide-by-zero)".to_string(),
+        9 => "killed (out of memory or resource limit)".to_string(),
+        11 => "segmentation fault".to_string(),
+        other => format!("terminated by signal {}", other),
+    }
+}
+
 /// Result of running a single test case
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestCaseResult {
+    /// Stable ID (see [`super::loader::TestCase::stable_id`]) so the
+    /// frontend can track a case's expand/collapse state and diff its
+    /// output across submissions instead of relying on array position.
+    pub id: String,
     pub input: Vec<serde_json::Value>,
     pub expected: String,
     pub actual: String,
     pub passed: bool,
+    pub outcome: Outcome,
+    /// Edit script between `expected` and `actual` (see
+    /// [`super::diff::diff_output`]), populated only when the case failed -
+    /// a passing case has nothing to diff and this just keeps the payload
+    /// small.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<Vec<super::diff::DiffSpan>>,
 }
 
 /// Result of running all (or sample) test cases for a level
@@ -82,4 +263,115 @@ pub struct TestSuiteResult {
     pub results: Vec<TestCaseResult>,
     /// Compilation error if any
     pub compilation_error: Option<String>,
+    /// Human-readable label (derived from each case's input) for every test
+    /// case that was executed, in order — lets the frontend render a
+    /// progress bar without re-deriving it from `results`.
+    pub executed: Vec<String>,
+    /// Same labels as `executed`, filtered to the cases that passed.
+    pub passed_cases: Vec<String>,
+    /// Suite-level summary of `results`: `Passed` only if every case did,
+    /// `Inconclusive` if nothing produced a usable pass/fail signal,
+    /// `Failed` otherwise (including a partial pass).
+    pub outcome: Outcome,
+}
+
+impl TestSuiteResult {
+    /// Build a suite result from per-case outcomes, deriving the summary
+    /// fields instead of leaving every caller recompute them.
+    pub fn from_results(results: Vec<TestCaseResult>) -> Self {
+        let total = results.len();
+        let passed_count = results.iter().filter(|r| r.passed).count();
+        let executed = results.iter().map(|r| describe_input(&r.input)).collect();
+        let passed_cases = results
+            .iter()
+            .filter(|r| r.passed)
+            .map(|r| describe_input(&r.input))
+            .collect();
+        let outcome = suite_outcome(&results);
+
+        Self {
+            passed: total > 0 && passed_count == total,
+            total,
+            passed_count,
+            results,
+            compilation_error: None,
+            executed,
+            passed_cases,
+            outcome,
+        }
+    }
+
+    /// Build a suite result for a harness that failed to compile — no test
+    /// case ever ran.
+    pub fn from_compile_error(total: usize, message: String) -> Self {
+        Self {
+            passed: false,
+            total,
+            passed_count: 0,
+            results: vec![],
+            compilation_error: Some(message),
+            executed: vec![],
+            passed_cases: vec![],
+            outcome: Outcome::Inconclusive,
+        }
+    }
+}
+
+/// Render a test case's input values as a compact, human-readable label
+/// (e.g. `"1, 2"` for `[1, 2]`).
+fn describe_input(input: &[serde_json::Value]) -> String {
+    input.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Collapse per-case outcomes into a single suite-level verdict.
+fn suite_outcome(results: &[TestCaseResult]) -> Outcome {
+    if results.is_empty() {
+        return Outcome::Inconclusive;
+    }
+    if results.iter().all(|r| r.passed) {
+        return Outcome::Passed;
+    }
+    if results.iter().all(|r| matches!(r.outcome, Outcome::Inconclusive)) {
+        return Outcome::Inconclusive;
+    }
+    Outcome::Failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "compiler")]
+    #[test]
+    fn test_judge_exact() {
+        assert!(judge("5", "5", &MatchMode::Exact));
+        assert!(!judge("5", "6", &MatchMode::Exact));
+    }
+
+    #[cfg(feature = "compiler")]
+    #[test]
+    fn test_judge_float_tolerance_accepts_rounding_and_relative_error() {
+        let mode = MatchMode::FloatTolerance { epsilon: 0.001 };
+        assert!(judge("3.14", "3.1400001", &mode));
+        assert!(judge("1000.0", "1000.5", &MatchMode::FloatTolerance { epsilon: 0.001 }));
+        assert!(!judge("3.14", "3.20", &mode));
+        assert!(judge("1 2.5", "1 2.5000001", &mode));
+        assert!(!judge("1 2.5", "1", &mode));
+    }
+
+    #[cfg(feature = "compiler")]
+    #[test]
+    fn test_judge_trimmed_lines_ignores_trailing_blank_lines_and_padding() {
+        let mode = MatchMode::TrimmedLines;
+        assert!(judge("a\nb", "a \n b \n\n", &mode));
+        assert!(!judge("a\nb", "a\nc", &mode));
+    }
+
+    #[cfg(feature = "compiler")]
+    #[test]
+    fn test_judge_unordered_ignores_line_order() {
+        let mode = MatchMode::Unordered;
+        assert!(judge("1\n2\n3", "3\n1\n2", &mode));
+        assert!(!judge("1\n2\n3", "1\n2\n2", &mode));
+    }
 }
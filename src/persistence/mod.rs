@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::game::progression::ProgressionState;
 use crate::game::state::Position;
@@ -16,6 +16,54 @@ pub struct SaveData {
     pub timestamp: u64,
 }
 
+/// Migrations keyed by the `version` they upgrade *from*, applied in
+/// sequence by [`migrate_to_current`] until a loaded blob reaches
+/// [`SaveData::CURRENT_VERSION`]. Empty today - version 1 is still the
+/// only schema - so add a `(1, migrate_v1_to_v2)`-shaped row here the
+/// next time `CURRENT_VERSION` bumps, filling in any newly-added fields
+/// with defaults rather than rejecting the old save outright.
+const MIGRATIONS: &[(u32, fn(serde_json::Value) -> serde_json::Value)] = &[];
+
+/// Upgrades a raw save blob through [`MIGRATIONS`] until it reaches
+/// `SaveData::CURRENT_VERSION`, so a save from an older build loads
+/// cleanly instead of only newer-than-supported saves being rejected.
+fn migrate_to_current(mut value: serde_json::Value) -> Result<serde_json::Value, String> {
+    let mut version = value
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| "Save data is missing a version field".to_string())? as u32;
+
+    if version > SaveData::CURRENT_VERSION {
+        return Err(format!(
+            "Save file version {} is newer than supported version {}",
+            version,
+            SaveData::CURRENT_VERSION
+        ));
+    }
+
+    while version < SaveData::CURRENT_VERSION {
+        let migrate = MIGRATIONS
+            .iter()
+            .find(|(from, _)| *from == version)
+            .map(|(_, migrate)| migrate)
+            .ok_or_else(|| {
+                format!(
+                    "No migration available from save version {} to {}",
+                    version,
+                    SaveData::CURRENT_VERSION
+                )
+            })?;
+
+        value = migrate(value);
+        version += 1;
+        if let Some(object) = value.as_object_mut() {
+            object.insert("version".to_string(), serde_json::Value::from(version));
+        }
+    }
+
+    Ok(value)
+}
+
 impl SaveData {
     pub const CURRENT_VERSION: u32 = 1;
 
@@ -86,33 +134,72 @@ impl SaveManager {
         self.save_dir.join(format!("{}.json", slot_name))
     }
 
-    /// Save game data to a slot
+    /// Path of the previous-contents backup kept alongside a save slot.
+    fn backup_path(path: &Path) -> PathBuf {
+        path.with_extension("json.bak")
+    }
+
+    /// Path of the temporary file a save is written to before it's
+    /// atomically renamed over the real slot file.
+    fn tmp_path(path: &Path) -> PathBuf {
+        path.with_extension("json.tmp")
+    }
+
+    /// Parses and migrates a raw save file's contents - shared by
+    /// [`Self::load`] and [`Self::list_saves`] so both fall back to the
+    /// `.bak` copy the same way on corruption.
+    fn parse_save_data(json: &str) -> Result<SaveData, String> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| format!("Failed to parse save data: {}", e))?;
+        let migrated = migrate_to_current(value)?;
+        serde_json::from_value(migrated).map_err(|e| format!("Failed to parse save data: {}", e))
+    }
+
+    /// Save game data to a slot.
+    ///
+    /// Writes to a temporary file in the same directory and atomically
+    /// `rename`s it over the target, so a crash mid-write can never leave
+    /// a half-written slot file behind. The previous contents (if any)
+    /// are kept as a `.bak` copy for [`Self::load`] to fall back to if
+    /// the new save somehow turns out corrupt.
     pub fn save(&self, data: &SaveData) -> Result<(), String> {
         let path = self.get_save_path(&data.slot_name);
         let json = serde_json::to_string_pretty(data)
             .map_err(|e| format!("Failed to serialize save data: {}", e))?;
-        fs::write(&path, json)
-            .map_err(|e| format!("Failed to write save file: {}", e))?;
+
+        if path.exists() {
+            fs::copy(&path, Self::backup_path(&path))
+                .map_err(|e| format!("Failed to back up previous save: {}", e))?;
+        }
+
+        let tmp_path = Self::tmp_path(&path);
+        fs::write(&tmp_path, json).map_err(|e| format!("Failed to write save file: {}", e))?;
+        fs::rename(&tmp_path, &path)
+            .map_err(|e| format!("Failed to finalize save file: {}", e))?;
+
         Ok(())
     }
 
-    /// Load game data from a slot
+    /// Load game data from a slot.
+    ///
+    /// Old saves are upgraded to `SaveData::CURRENT_VERSION` via
+    /// [`migrate_to_current`] rather than rejected outright. If the slot
+    /// file fails to parse (corruption, not simply being absent), falls
+    /// back to the `.bak` copy kept by [`Self::save`] before surfacing
+    /// the original error.
     pub fn load(&self, slot_name: &str) -> Result<SaveData, String> {
         let path = self.get_save_path(slot_name);
         let json = fs::read_to_string(&path)
             .map_err(|e| format!("Failed to read save file: {}", e))?;
-        let data: SaveData = serde_json::from_str(&json)
-            .map_err(|e| format!("Failed to parse save data: {}", e))?;
 
-        // Check version compatibility
-        if data.version > SaveData::CURRENT_VERSION {
-            return Err(format!(
-                "Save file version {} is newer than supported version {}",
-                data.version, SaveData::CURRENT_VERSION
-            ));
+        match Self::parse_save_data(&json) {
+            Ok(data) => Ok(data),
+            Err(primary_err) => {
+                let backup_json = fs::read_to_string(Self::backup_path(&path))
+                    .map_err(|_| primary_err.clone())?;
+                Self::parse_save_data(&backup_json).map_err(|_| primary_err)
+            }
         }
-
-        Ok(data)
     }
 
     /// Delete a save slot
@@ -136,7 +223,12 @@ impl SaveManager {
             let path = entry.path();
             if path.extension().map(|e| e == "json").unwrap_or(false) {
                 if let Ok(json) = fs::read_to_string(&path) {
-                    if let Ok(data) = serde_json::from_str::<SaveData>(&json) {
+                    let data = Self::parse_save_data(&json).or_else(|_| {
+                        fs::read_to_string(Self::backup_path(&path))
+                            .map_err(|e| e.to_string())
+                            .and_then(|backup_json| Self::parse_save_data(&backup_json))
+                    });
+                    if let Ok(data) = data {
                         saves.push(SaveSlotInfo {
                             slot_name: data.slot_name.clone(),
                             timestamp: data.timestamp,
@@ -226,4 +318,83 @@ mod tests {
         manager.delete("to_delete").unwrap();
         assert!(!manager.exists("to_delete"));
     }
+
+    #[test]
+    fn test_save_does_not_leave_a_temp_file_behind() {
+        let manager = create_test_manager();
+        let data = SaveData::new("test_slot".to_string());
+        manager.save(&data).unwrap();
+
+        let path = manager.get_save_path("test_slot");
+        assert!(path.exists());
+        assert!(!SaveManager::tmp_path(&path).exists());
+    }
+
+    #[test]
+    fn test_save_keeps_a_backup_of_the_previous_contents() {
+        let manager = create_test_manager();
+        let mut data = SaveData::new("test_slot".to_string());
+        manager.save(&data).unwrap();
+
+        data.progression.complete_level("L01", 50);
+        manager.save(&data).unwrap();
+
+        let path = manager.get_save_path("test_slot");
+        let backup_json = fs::read_to_string(SaveManager::backup_path(&path)).unwrap();
+        let backup: SaveData = serde_json::from_str(&backup_json).unwrap();
+        assert!(!backup.progression.is_completed("L01"));
+    }
+
+    #[test]
+    fn test_load_falls_back_to_backup_on_corruption() {
+        let manager = create_test_manager();
+        let mut data = SaveData::new("test_slot".to_string());
+        data.progression.complete_level("L01", 50);
+        manager.save(&data).unwrap();
+        manager.save(&data).unwrap(); // second save seeds a valid .bak
+
+        let path = manager.get_save_path("test_slot");
+        fs::write(&path, "{ not valid json").unwrap();
+
+        let loaded = manager.load("test_slot").unwrap();
+        assert!(loaded.progression.is_completed("L01"));
+    }
+
+    #[test]
+    fn test_load_rejects_version_with_no_migration_path() {
+        let manager = create_test_manager();
+        let path = manager.get_save_path("legacy_slot");
+        let legacy_json = serde_json::json!({
+            "version": 0,
+            "slot_name": "legacy_slot",
+            "progression": ProgressionState::new(),
+            "current_level_id": null,
+            "player_position": { "x": 0.0, "y": 0.0 },
+            "timestamp": 0,
+        })
+        .to_string();
+        fs::write(&path, legacy_json).unwrap();
+
+        let err = manager.load("legacy_slot").unwrap_err();
+        assert!(err.contains("No migration available"));
+    }
+
+    #[test]
+    fn test_load_rejects_newer_version() {
+        let manager = create_test_manager();
+        let path = manager.get_save_path("future_slot");
+        let future_json = serde_json::json!({
+            "version": SaveData::CURRENT_VERSION + 1,
+            "slot_name": "future_slot",
+            "progression": ProgressionState::new(),
+            "current_level_id": null,
+            "player_position": { "x": 0.0, "y": 0.0 },
+            "timestamp": 0,
+        })
+        .to_string();
+        fs::write(&path, future_json).unwrap();
+
+        let err = manager.load("future_slot").unwrap_err();
+        assert!(err.contains("newer than supported"));
+    }
 }